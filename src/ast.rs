@@ -1,11 +1,12 @@
 //! Public document model used by parser and compiler APIs.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 use crate::error::SyamlError;
+use crate::source_map::{SourceMap, SourceSpan};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Parsed `.syaml` document before expression and constraint resolution.
@@ -21,6 +22,35 @@ pub struct ParsedDocument {
     /// Optional parsed contracts section.
     #[serde(default)]
     pub contracts: Option<ContractsDoc>,
+    /// Optional parsed functions section.
+    #[serde(default)]
+    pub functions: Option<FunctionsDoc>,
+    /// Source spans of data keys/values keyed by normalized data path.
+    #[serde(skip)]
+    pub source_map: SourceMap,
+    /// Base document path when this document is an overlay (`meta.overlay`
+    /// or an `---overlay` section); `data` then holds the patch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A non-fatal compile diagnostic.
+pub struct Warning {
+    /// Human-readable message.
+    pub message: String,
+    /// Normalized data path (`$.a.b[0]`) the warning refers to, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_path: Option<String>,
+    /// Source span of the data path, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,9 +58,13 @@ pub struct ParsedDocument {
 pub struct CompiledDocument {
     /// Resolved JSON value after env + expression resolution and validation.
     pub value: JsonValue,
-    /// Non-fatal diagnostic messages collected during compilation (e.g. deprecation warnings).
+    /// Non-fatal diagnostics collected during compilation (e.g. deprecation warnings).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub warnings: Vec<String>,
+    pub warnings: Vec<Warning>,
+    /// Data paths (`$.db.password`) holding sensitive values: values marked
+    /// `sensitive: true` and everything derived from them.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub sensitive_paths: BTreeSet<String>,
 }
 
 impl CompiledDocument {
@@ -51,6 +85,12 @@ impl CompiledDocument {
     pub fn to_yaml_string(&self) -> String {
         crate::yaml_writer::to_yaml_string(&self.value)
     }
+
+    /// Replaces every value at a sensitive path with
+    /// [`REDACTED`](crate::sensitive::REDACTED).
+    pub fn redact(&mut self) {
+        crate::sensitive::redact(&mut self.value, &self.sensitive_paths);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,12 +110,79 @@ pub struct Meta {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// Single environment binding definition from `meta.env`.
 pub struct EnvBinding {
-    /// Environment variable key to read from process/provider.
+    /// What to read: the environment variable name, the file path for file
+    /// sources, or the entry name for `from: dotenv`.
     pub key: String,
+    /// Where the value is read from.
+    #[serde(default)]
+    pub source: EnvSource,
     /// Whether missing env input is an error when no default is provided.
     pub required: bool,
     /// Default value used when env input is missing.
     pub default: Option<JsonValue>,
+    /// Schema type the value must match (`type:`): a type name or an inline
+    /// schema.
+    #[serde(default)]
+    pub type_ref: Option<JsonValue>,
+    /// How the raw text becomes a value (`parse:`). When unset, verbatim
+    /// file sources and `string`-typed bindings keep the text as a string and
+    /// everything else is parsed as a scalar.
+    #[serde(default)]
+    pub parse: Option<EnvParse>,
+    /// Whether the value is secret (`sensitive: true`). Secret values and
+    /// everything derived from them are redactable and never printed in
+    /// error messages or generated docs.
+    #[serde(default)]
+    pub sensitive: bool,
+}
+
+impl EnvBinding {
+    /// Human-readable origin of the binding's value, e.g. `environment
+    /// variable DB_HOST` or `file /run/secrets/db_password`.
+    pub fn describe_source(&self) -> String {
+        match &self.source {
+            EnvSource::Env => format!("environment variable {}", self.key),
+            EnvSource::File { .. } | EnvSource::LiteralFile => format!("file {}", self.key),
+            EnvSource::Dotenv { file } => format!("{} in dotenv file {}", self.key, file),
+        }
+    }
+}
+
+/// Source of a `meta.env` binding (`from:`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvSource {
+    /// Process environment variable (`from: env`).
+    #[default]
+    Env,
+    /// File contents parsed as a scalar like an env value (`from: file`).
+    File {
+        /// Strip leading and trailing whitespace before parsing.
+        trim: bool,
+    },
+    /// File contents kept verbatim as a string (`from: literal_file`).
+    LiteralFile,
+    /// Entry of a `KEY=value` dotenv file (`from: dotenv`).
+    Dotenv {
+        /// Path of the dotenv file.
+        file: String,
+    },
+}
+
+/// Parse mode of a `meta.env` binding (`parse:`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvParse {
+    /// A YAML scalar: `5` is an integer, `true` a boolean, anything else a
+    /// string.
+    Scalar,
+    /// The text as a string.
+    String,
+    /// A JSON document.
+    Json,
+    /// Comma-separated list; items are trimmed and parsed as scalars, or kept
+    /// as strings when the binding's type is a list of strings.
+    Csv,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,7 +200,7 @@ pub struct ImportBinding {
     #[serde(default)]
     pub version: Option<String>,
     /// Sections to import from the document. `None` (or omitted) means all sections.
-    /// Valid names: `"schema"`, `"data"`, `"contracts"`.
+    /// Valid names: `"schema"`, `"data"`, `"contracts"`, `"functions"`.
     /// Example: `sections: [schema]` imports only schema types; data is not available.
     #[serde(default)]
     pub sections: Option<Vec<String>>,
@@ -154,6 +261,10 @@ pub struct ParameterDef {
     pub type_ref: serde_json::Value,
     /// Whether this parameter is mutable.
     pub mutable: bool,
+    /// Whether the parameter carries a secret: declared `sensitive: true` or
+    /// typed with a sensitive schema type.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sensitive: bool,
 }
 
 /// Capability-scoped permission block.
@@ -228,6 +339,36 @@ pub struct ContractsDoc {
     pub functions: BTreeMap<String, FunctionDef>,
 }
 
+/// Single parameter of a pure function in the `---functions` section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PureFunctionParam {
+    /// Parameter name, bound in the function body.
+    pub name: String,
+    /// Parameter type as a schema fragment (a built-in or schema type name).
+    pub type_ref: JsonValue,
+}
+
+/// Single pure function in the `---functions` section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PureFunctionDef {
+    /// Parameters in call order.
+    pub params: Vec<PureFunctionParam>,
+    /// Declared result type as a schema fragment (optional).
+    #[serde(default)]
+    pub returns: Option<JsonValue>,
+    /// Expression evaluated with only the parameters in scope.
+    pub body: String,
+    /// Human-readable description (optional).
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Parsed `---functions` section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FunctionsDoc {
+    pub functions: BTreeMap<String, PureFunctionDef>,
+}
+
 /// Import policy from a module manifest restricting what module members can import.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportPolicy {
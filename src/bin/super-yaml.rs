@@ -5,14 +5,23 @@ use std::{
     process::ExitCode,
 };
 
+use super_yaml::ast::Warning;
+use super_yaml::contracts::mark_sensitive_parameters;
+use super_yaml::formatter::{format_document, FormatOptions};
+use super_yaml::overlay::resolve_profile;
+use super_yaml::overrides::{normalize_override_path, parse_override};
+use super_yaml::resolve::query_json_path;
+use super_yaml::watch::{watch_set, PollWatcher, DEFAULT_POLL_INTERVAL};
 use super_yaml::{
-    collect_import_graph, compile_document_from_path_with_fetch, discover_module_members,
-    from_json_schema_path, generate_html_docs_from_path, generate_html_docs_site,
-    generate_proto_types_from_path, generate_rust_types_and_data_from_path,
+    collect_import_graph, compile_document_from_path_with_options,
+    compile_document_with_provenance_from_path, discover_module_members, from_json_schema_path,
+    generate_html_docs_from_path, generate_html_docs_site, generate_proto_types_from_path,
+    generate_rust_types_and_data_from_path, generate_rust_types_and_redacted_data_from_path,
     generate_rust_types_from_path, generate_typescript_types_and_data_from_path,
-    generate_typescript_types_from_path, EnvProvider, ProcessEnvProvider,
+    generate_typescript_types_and_redacted_data_from_path, generate_typescript_types_from_path,
+    EnvProvider, ProcessEnvProvider, SyamlError,
 };
-use super_yaml::{parse_document, to_json_schema};
+use super_yaml::{diagnostics::Diagnostic, parse_document, to_json_schema};
 
 #[derive(Clone, Copy, Debug)]
 enum OutputFormat {
@@ -26,32 +35,124 @@ enum OutputFormat {
     HtmlDocs,
 }
 
+/// How errors and warnings are written to stderr.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MessageFormat {
+    /// Rendered text with source snippets.
+    Human,
+    /// One JSON diagnostic record per line.
+    Json,
+}
+
+#[derive(Debug)]
+struct ValidateOptions {
+    allowed_env_keys: HashSet<String>,
+    allowed_files: Vec<PathBuf>,
+    message_format: MessageFormat,
+    watch: bool,
+    profile: Option<String>,
+}
+
 #[derive(Debug)]
 struct CompileOptions {
     pretty: bool,
     format: OutputFormat,
     allowed_env_keys: HashSet<String>,
+    allowed_files: Vec<PathBuf>,
     cache_dir: Option<PathBuf>,
     update_imports: bool,
     skip_data: bool,
+    message_format: MessageFormat,
+    output: Option<PathBuf>,
+    watch: bool,
+    overrides: Vec<(String, OverrideValue)>,
+    /// Replace sensitive values with `[redacted]` in json/yaml output.
+    redact: bool,
+    /// Compile with the `<stem>.<profile>.syaml` overlay applied.
+    profile: Option<String>,
+}
+
+/// Value given to `--set` or `--set-file`.
+#[derive(Debug, Clone, PartialEq)]
+enum OverrideValue {
+    Value(serde_json::Value),
+    /// Read as a string on every build, so `--watch` picks up changes.
+    File(PathBuf),
+}
+
+#[derive(Debug)]
+struct ExplainOptions {
+    path: String,
+    allowed_env_keys: HashSet<String>,
+    allowed_files: Vec<PathBuf>,
+    profile: Option<String>,
+}
+
+/// How `get` reports the values it finds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GetOutput {
+    /// Strings unquoted, other values as compact JSON, one match per line.
+    Raw,
+    /// JSON; a wildcard query prints an array of the matched values.
+    Json,
+    /// Nothing; the exit status tells whether the path matched.
+    Exists,
+}
+
+#[derive(Debug)]
+struct GetOptions {
+    path: String,
+    output: GetOutput,
+    pretty: bool,
+    redact: bool,
+    allowed_env_keys: HashSet<String>,
+    allowed_files: Vec<PathBuf>,
+    profile: Option<String>,
+}
+
+/// Failure returned from [`run`].
+#[derive(Debug)]
+enum CliError {
+    /// Message to print, followed by usage.
+    Message(String),
+    /// Diagnostics were already written in the requested message format.
+    Reported,
+    /// `get` matched no value, or `explain` found no such path; exits with status 2.
+    NotFound,
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Message(message)
+    }
+}
+
+#[derive(Debug)]
+struct FmtOptions {
+    check: bool,
+    sort_properties: bool,
 }
 
 #[derive(Debug)]
 struct DocsOptions {
     output_dir: PathBuf,
     follow_imports: bool,
+    watch: bool,
 }
 
-/// Env provider that allows only explicitly listed process env keys.
+/// Env provider that allows only explicitly listed process env keys and
+/// files (a listed directory allows every file under it).
 struct AllowListEnvProvider {
     allowed_env_keys: HashSet<String>,
+    allowed_files: Vec<PathBuf>,
     process_env: ProcessEnvProvider,
 }
 
 impl AllowListEnvProvider {
-    fn new(allowed_env_keys: HashSet<String>) -> Self {
+    fn new(allowed_env_keys: HashSet<String>, allowed_files: Vec<PathBuf>) -> Self {
         Self {
             allowed_env_keys,
+            allowed_files,
             process_env: ProcessEnvProvider,
         }
     }
@@ -65,108 +166,257 @@ impl EnvProvider for AllowListEnvProvider {
             None
         }
     }
+
+    fn read_file(&self, path: &Path) -> Option<String> {
+        // Canonical paths, so `..` and symlinks cannot escape an allowed directory.
+        let path = path.canonicalize().ok()?;
+        let allowed = self.allowed_files.iter().any(|allowed| {
+            allowed
+                .canonicalize()
+                .is_ok_and(|allowed| path.starts_with(allowed))
+        });
+        if allowed {
+            self.process_env.read_file(&path)
+        } else {
+            None
+        }
+    }
 }
 
 fn main() -> ExitCode {
     match run(env::args().collect()) {
         Ok(()) => ExitCode::SUCCESS,
-        Err(err) => {
+        Err(CliError::Message(err)) => {
             eprintln!("{err}");
             print_usage();
             ExitCode::FAILURE
         }
+        Err(CliError::Reported) => ExitCode::FAILURE,
+        Err(CliError::NotFound) => ExitCode::from(2),
     }
 }
 
-fn run(args: Vec<String>) -> Result<(), String> {
+fn run(args: Vec<String>) -> Result<(), CliError> {
     if args.len() < 2 {
-        return Err("not enough arguments".to_string());
+        return Err("not enough arguments".to_string().into());
     }
 
     let command = args[1].as_str();
 
     if command == "from-json-schema" {
         if args.len() < 3 {
-            return Err("from-json-schema requires an input file".to_string());
+            return Err("from-json-schema requires an input file".to_string().into());
         }
         let file = PathBuf::from(&args[2]);
         let output_path = parse_from_json_schema_options(&args[3..])?;
-        return run_from_json_schema(&file, output_path.as_deref());
+        return Ok(run_from_json_schema(&file, output_path.as_deref())?);
+    }
+
+    if command == "lsp" {
+        if let Some(extra) = args.get(2) {
+            return Err(format!("unknown option for lsp: {extra}").into());
+        }
+        return super_yaml::lsp::run_stdio()
+            .map_err(|e| CliError::Message(format!("lsp server failed: {e}")));
     }
 
     if args.len() < 3 {
-        return Err("not enough arguments".to_string());
+        return Err("not enough arguments".to_string().into());
     }
 
     let file = PathBuf::from(&args[2]);
 
     match command {
         "validate" => {
-            let allowed_env_keys = parse_validate_options(&args[3..])?;
-            let env_provider = AllowListEnvProvider::new(allowed_env_keys);
-            run_validate(&file, &env_provider)
+            let mut options = parse_validate_options(&args[3..])?;
+            let env_provider = AllowListEnvProvider::new(
+                std::mem::take(&mut options.allowed_env_keys),
+                std::mem::take(&mut options.allowed_files),
+            );
+            let file = profile_file(&file, options.profile.as_deref(), options.message_format)?;
+            if options.watch {
+                return run_watch(&file, || {
+                    run_validate(&file, &env_provider, options.message_format)
+                });
+            }
+            run_validate(&file, &env_provider, options.message_format)
         }
         "compile" => {
-            let options = parse_compile_options(&args[3..])?;
-            let env_provider = AllowListEnvProvider::new(options.allowed_env_keys);
-            run_compile(
-                &file,
-                &env_provider,
-                options.pretty,
-                options.format,
-                options.cache_dir,
-                options.update_imports,
-                options.skip_data,
-            )
+            let mut options = parse_compile_options(&args[3..])?;
+            let env_provider = AllowListEnvProvider::new(
+                std::mem::take(&mut options.allowed_env_keys),
+                std::mem::take(&mut options.allowed_files),
+            );
+            if options.watch {
+                let watched =
+                    profile_file(&file, options.profile.as_deref(), options.message_format)?;
+                return run_watch(&watched, || run_compile(&file, &env_provider, &options));
+            }
+            run_compile(&file, &env_provider, &options)
+        }
+        "get" => {
+            let options = parse_get_options(&args[3..])?;
+            let env_provider = AllowListEnvProvider::new(
+                options.allowed_env_keys.clone(),
+                options.allowed_files.clone(),
+            );
+            let file = profile_file(&file, options.profile.as_deref(), MessageFormat::Human)?;
+            run_get(&file, &env_provider, &options)
+        }
+        "explain" => {
+            let options = parse_explain_options(&args[3..])?;
+            let file = profile_file(&file, options.profile.as_deref(), MessageFormat::Human)?;
+            let env_provider =
+                AllowListEnvProvider::new(options.allowed_env_keys, options.allowed_files);
+            run_explain(&file, &env_provider, &options.path)
+        }
+        "fmt" => {
+            let options = parse_fmt_options(&args[3..])?;
+            run_fmt(&file, &options)
         }
         "docs" => {
             let parsed_options = parse_docs_options(&args[3..])?;
-            run_docs(&file, &parsed_options)
+            if parsed_options.watch {
+                return run_watch(&file, || Ok(run_docs(&file, &parsed_options)?));
+            }
+            Ok(run_docs(&file, &parsed_options)?)
         }
-        _ => Err(format!("unknown command '{command}'")),
+        _ => Err(format!("unknown command '{command}'").into()),
     }
 }
 
-fn run_validate(file: &PathBuf, env: &dyn EnvProvider) -> Result<(), String> {
-    let compiled = super_yaml::compile_document_from_path(file, env).map_err(|e| e.to_string())?;
-    for warning in &compiled.warnings {
-        eprintln!("warning: {warning}");
+/// The file to compile for `--profile`: the profile overlay, which leads to
+/// `file` through its base chain. Without a profile, `file` itself.
+fn profile_file(
+    file: &Path,
+    profile: Option<&str>,
+    message_format: MessageFormat,
+) -> Result<PathBuf, CliError> {
+    match profile {
+        Some(profile) => {
+            resolve_profile(file, profile).map_err(|e| report_error(&e, file, message_format))
+        }
+        None => Ok(file.to_path_buf()),
     }
+}
+
+fn run_validate(
+    file: &PathBuf,
+    env: &dyn EnvProvider,
+    message_format: MessageFormat,
+) -> Result<(), CliError> {
+    let compiled = super_yaml::compile_document_from_path(file, env)
+        .map_err(|e| report_error(&e, file, message_format))?;
+    report_warnings(&compiled.warnings, file, message_format);
     println!("OK");
     Ok(())
 }
 
+fn run_get(file: &PathBuf, env: &dyn EnvProvider, options: &GetOptions) -> Result<(), CliError> {
+    let mut compiled = super_yaml::compile_document_from_path(file, env)
+        .map_err(|e| report_error(&e, file, MessageFormat::Human))?;
+    report_warnings(&compiled.warnings, file, MessageFormat::Human);
+    if options.redact {
+        compiled.redact();
+    }
+
+    let path = match options.path.as_str() {
+        "$" => "$".to_string(),
+        p if p.starts_with("$.") => p.to_string(),
+        p if p.starts_with('[') => format!("${p}"),
+        p => format!("$.{p}"),
+    };
+    let matches = query_json_path(&compiled.value, &path)
+        .map_err(|e| report_error(&e, file, MessageFormat::Human))?;
+    if matches.is_empty() {
+        if options.output != GetOutput::Exists {
+            eprintln!("path '{}' not found", options.path);
+        }
+        return Err(CliError::NotFound);
+    }
+
+    match options.output {
+        GetOutput::Exists => {}
+        GetOutput::Raw => {
+            for (_, value) in matches {
+                match value {
+                    serde_json::Value::String(s) => println!("{s}"),
+                    other => println!("{other}"),
+                }
+            }
+        }
+        GetOutput::Json => {
+            let value = if path.contains('*') {
+                serde_json::Value::Array(matches.into_iter().map(|(_, v)| v.clone()).collect())
+            } else {
+                matches[0].1.clone()
+            };
+            let rendered = if options.pretty {
+                serde_json::to_string_pretty(&value)
+            } else {
+                serde_json::to_string(&value)
+            }
+            .map_err(|e| CliError::Message(format!("failed to serialize value: {e}")))?;
+            println!("{rendered}");
+        }
+    }
+    Ok(())
+}
+
+fn run_explain(file: &PathBuf, env: &dyn EnvProvider, path: &str) -> Result<(), CliError> {
+    let (_, provenance) = compile_document_with_provenance_from_path(file, env)
+        .map_err(|e| report_error(&e, file, MessageFormat::Human))?;
+    let explanation = provenance.explain(path).map_err(|e| match e {
+        SyamlError::PathNotFound(_) => {
+            eprintln!("{e}");
+            CliError::NotFound
+        }
+        other => report_error(&other, file, MessageFormat::Human),
+    })?;
+    print!("{explanation}");
+    Ok(())
+}
+
 fn run_compile(
     file: &PathBuf,
     env: &dyn EnvProvider,
-    pretty: bool,
-    format: OutputFormat,
-    cache_dir: Option<PathBuf>,
-    update_imports: bool,
-    skip_data: bool,
-) -> Result<(), String> {
-    let output = match format {
+    options: &CompileOptions,
+) -> Result<(), CliError> {
+    let pretty = options.pretty;
+    let skip_data = options.skip_data;
+    let message_format = options.message_format;
+    let output = match options.format {
         OutputFormat::Json => {
-            let compiled =
-                compile_document_from_path_with_fetch(file, env, cache_dir, update_imports)
-                    .map_err(|e| e.to_string())?;
-            for warning in &compiled.warnings {
-                eprintln!("warning: {warning}");
+            let mut compiled = compile_document_from_path_with_options(
+                file,
+                env,
+                &library_compile_options(options)?,
+            )
+            .map_err(|e| report_error(&e, file, message_format))?;
+            report_warnings(&compiled.warnings, file, message_format);
+            if options.redact {
+                compiled.redact();
             }
             compiled.to_json_string(pretty)
         }
         OutputFormat::Yaml => {
-            let compiled =
-                compile_document_from_path_with_fetch(file, env, cache_dir, update_imports)
-                    .map_err(|e| e.to_string())?;
-            for warning in &compiled.warnings {
-                eprintln!("warning: {warning}");
+            let mut compiled = compile_document_from_path_with_options(
+                file,
+                env,
+                &library_compile_options(options)?,
+            )
+            .map_err(|e| report_error(&e, file, message_format))?;
+            report_warnings(&compiled.warnings, file, message_format);
+            if options.redact {
+                compiled.redact();
             }
             Ok(compiled.to_yaml_string())
         }
         OutputFormat::Rust => {
             if skip_data {
                 generate_rust_types_from_path(file)
+            } else if options.redact {
+                generate_rust_types_and_redacted_data_from_path(file, env)
             } else {
                 generate_rust_types_and_data_from_path(file, env)
             }
@@ -174,6 +424,8 @@ fn run_compile(
         OutputFormat::TypeScript => {
             if skip_data {
                 generate_typescript_types_from_path(file)
+            } else if options.redact {
+                generate_typescript_types_and_redacted_data_from_path(file, env)
             } else {
                 generate_typescript_types_and_data_from_path(file, env)
             }
@@ -182,23 +434,210 @@ fn run_compile(
         OutputFormat::ContractsJson => {
             let input = std::fs::read_to_string(file)
                 .map_err(|e| format!("failed to read '{}': {e}", file.display()))?;
-            let parsed = parse_document(&input).map_err(|e| e.to_string())?;
+            let parsed =
+                parse_document(&input).map_err(|e| report_error(&e, file, message_format))?;
             match parsed.contracts {
-                Some(ref func_doc) => super_yaml::contracts::contracts_to_json(func_doc, pretty),
+                Some(mut func_doc) => {
+                    mark_sensitive_parameters(&mut func_doc, &parsed.schema.types);
+                    super_yaml::contracts::contracts_to_json(&func_doc, pretty)
+                }
                 None => Ok("{}".to_string()),
             }
         }
         OutputFormat::JsonSchema => {
             let input = std::fs::read_to_string(file)
                 .map_err(|e| format!("failed to read '{}': {e}", file.display()))?;
-            let parsed = parse_document(&input).map_err(|e| e.to_string())?;
+            let parsed =
+                parse_document(&input).map_err(|e| report_error(&e, file, message_format))?;
             to_json_schema(&parsed.schema, pretty)
         }
         OutputFormat::HtmlDocs => generate_html_docs_from_path(file),
     }
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| report_error(&e, file, message_format))?;
+
+    match &options.output {
+        Some(path) => {
+            std::fs::write(path, format!("{output}\n"))
+                .map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+            eprintln!("wrote: {}", path.display());
+        }
+        None => println!("{output}"),
+    }
+    Ok(())
+}
+
+/// Library options for a JSON or YAML build, with `--set-file` values read.
+fn library_compile_options(
+    options: &CompileOptions,
+) -> Result<super_yaml::CompileOptions, CliError> {
+    let mut overrides = Vec::with_capacity(options.overrides.len());
+    for (path, value) in &options.overrides {
+        let value = match value {
+            OverrideValue::Value(value) => value.clone(),
+            OverrideValue::File(file) => serde_json::Value::String(
+                std::fs::read_to_string(file)
+                    .map_err(|e| format!("failed to read '{}': {e}", file.display()))?,
+            ),
+        };
+        overrides.push((path.clone(), value));
+    }
+    Ok(super_yaml::CompileOptions {
+        overrides,
+        cache_dir: options.cache_dir.clone(),
+        update_imports: options.update_imports,
+        profile: options.profile.clone(),
+    })
+}
+
+/// Runs `build`, then rebuilds whenever `file` or anything it depends on changes.
+///
+/// Build failures are reported and watching continues; only failing to list the
+/// watched files ends the loop.
+fn run_watch(file: &Path, mut build: impl FnMut() -> Result<(), CliError>) -> Result<(), CliError> {
+    let mut watcher = PollWatcher::new(DEFAULT_POLL_INTERVAL);
+    loop {
+        let paths = watch_set(file).map_err(|e| render_error(&e, file))?;
+        watcher.set_paths(paths);
+        match build() {
+            Ok(()) | Err(CliError::Reported) | Err(CliError::NotFound) => {}
+            Err(CliError::Message(message)) => eprintln!("{message}"),
+        }
+        eprintln!(
+            "watching {} file(s) for changes (press Ctrl-C to stop)",
+            watcher.len()
+        );
+        let changed = watcher.wait();
+        for path in &changed {
+            eprintln!("changed: {}", path.display());
+        }
+    }
+}
+
+/// Writes `err` in the requested format. Human output is returned for `main`
+/// to print; JSON records are written to stderr immediately.
+fn report_error(err: &SyamlError, file: &Path, message_format: MessageFormat) -> CliError {
+    match message_format {
+        MessageFormat::Human => CliError::Message(render_error(err, file)),
+        MessageFormat::Json => {
+            for mut diagnostic in Diagnostic::from_error(err) {
+                diagnostic
+                    .file
+                    .get_or_insert_with(|| file.display().to_string());
+                eprintln!("{}", diagnostic.to_json_line());
+            }
+            CliError::Reported
+        }
+    }
+}
+
+fn report_warnings(warnings: &[Warning], file: &Path, message_format: MessageFormat) {
+    for warning in warnings {
+        match message_format {
+            MessageFormat::Human => eprintln!("warning: {warning}"),
+            MessageFormat::Json => {
+                let mut diagnostic = Diagnostic::warning(warning);
+                diagnostic
+                    .file
+                    .get_or_insert_with(|| file.display().to_string());
+                eprintln!("{}", diagnostic.to_json_line());
+            }
+        }
+    }
+}
+
+/// Formats an error, with a source snippet when it carries a span.
+///
+/// Spans without a file refer to `file`, the document named on the command line.
+/// Collected errors are rendered one after another with a count at the end.
+fn render_error(err: &SyamlError, file: &Path) -> String {
+    let errors = err.errors();
+    if errors.len() > 1 {
+        let rendered: Vec<String> = errors
+            .iter()
+            .map(|e| render_single_error(e, file))
+            .collect();
+        return format!("{}\n\n{} errors found", rendered.join("\n\n"), errors.len());
+    }
+    render_single_error(err, file)
+}
+
+fn render_single_error(err: &SyamlError, file: &Path) -> String {
+    let Some(span) = err.span() else {
+        return err.to_string();
+    };
+    let mut span = span.clone();
+    let source_path = span
+        .file
+        .get_or_insert_with(|| file.display().to_string())
+        .clone();
+    match std::fs::read_to_string(&source_path) {
+        Ok(source) => super_yaml::source_map::render_snippet(&err.to_string(), &span, &source),
+        Err(_) => err.to_string(),
+    }
+}
+
+/// Formats `path` (a file, or every `.syaml` file under a directory) in place.
+///
+/// With `--check`, files are left untouched and the command fails if any file
+/// is not already formatted.
+fn run_fmt(path: &Path, options: &FmtOptions) -> Result<(), CliError> {
+    let files = if path.is_dir() {
+        let mut files = Vec::new();
+        collect_syaml_files(path, &mut files)?;
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let format_options = FormatOptions {
+        sort_schema_properties: options.sort_properties,
+    };
+    let mut failed = false;
+    for file in &files {
+        let input = std::fs::read_to_string(file)
+            .map_err(|e| format!("failed to read '{}': {e}", file.display()))?;
+        let formatted = match format_document(&input, &format_options) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                eprintln!("{}", render_error(&e, file));
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == input {
+            continue;
+        }
+        if options.check {
+            println!("would reformat: {}", file.display());
+            failed = true;
+        } else {
+            std::fs::write(file, formatted)
+                .map_err(|e| format!("failed to write '{}': {e}", file.display()))?;
+            eprintln!("formatted: {}", file.display());
+        }
+    }
 
-    println!("{output}");
+    if failed {
+        Err(CliError::Reported)
+    } else {
+        Ok(())
+    }
+}
+
+fn collect_syaml_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("failed to read directory '{}': {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("failed to read directory entry: {e}"))?
+            .path();
+        if path.is_dir() {
+            collect_syaml_files(&path, out)?;
+        } else if path.extension().and_then(|e| e.to_str()) == Some("syaml") {
+            out.push(path);
+        }
+    }
     Ok(())
 }
 
@@ -298,33 +737,157 @@ fn run_docs(input_path: &PathBuf, options: &DocsOptions) -> Result<(), String> {
     Ok(())
 }
 
-fn parse_validate_options(args: &[String]) -> Result<HashSet<String>, String> {
+fn parse_validate_options(args: &[String]) -> Result<ValidateOptions, String> {
     let mut allowed_env_keys = HashSet::new();
+    let mut allowed_files = Vec::new();
+    let mut message_format = MessageFormat::Human;
+    let mut watch = false;
+    let mut profile = None;
     let mut i = 0usize;
     while i < args.len() {
         match args[i].as_str() {
+            "--profile" => profile = Some(parse_profile_option(args, &mut i)?),
             "--allow-env" => parse_allow_env_option(args, &mut i, &mut allowed_env_keys)?,
+            "--allow-file" => allowed_files.push(parse_allow_file_option(args, &mut i)?),
+            "--message-format" => message_format = parse_message_format_option(args, &mut i)?,
+            "--watch" => {
+                watch = true;
+                i += 1;
+            }
             other => return Err(format!("unknown option '{other}'")),
         }
     }
-    Ok(allowed_env_keys)
+    Ok(ValidateOptions {
+        allowed_env_keys,
+        allowed_files,
+        message_format,
+        watch,
+        profile,
+    })
+}
+
+fn parse_get_options(args: &[String]) -> Result<GetOptions, String> {
+    let Some(path) = args.first().filter(|a| !a.starts_with("--")) else {
+        return Err("get requires a data path (for example server.port)".to_string());
+    };
+    let mut output = GetOutput::Raw;
+    let mut pretty = false;
+    let mut redact = false;
+    let mut allowed_env_keys = HashSet::new();
+    let mut allowed_files = Vec::new();
+    let mut profile = None;
+    let mut i = 1usize;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--raw" => {
+                output = GetOutput::Raw;
+                i += 1;
+            }
+            "--json" => {
+                output = GetOutput::Json;
+                i += 1;
+            }
+            "--exists" => {
+                output = GetOutput::Exists;
+                i += 1;
+            }
+            "--pretty" => {
+                pretty = true;
+                i += 1;
+            }
+            "--redact" => {
+                redact = true;
+                i += 1;
+            }
+            "--profile" => profile = Some(parse_profile_option(args, &mut i)?),
+            "--allow-env" => parse_allow_env_option(args, &mut i, &mut allowed_env_keys)?,
+            "--allow-file" => allowed_files.push(parse_allow_file_option(args, &mut i)?),
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+    Ok(GetOptions {
+        path: path.clone(),
+        output,
+        pretty,
+        redact,
+        allowed_env_keys,
+        allowed_files,
+        profile,
+    })
+}
+
+fn parse_explain_options(args: &[String]) -> Result<ExplainOptions, String> {
+    let Some(path) = args.first().filter(|a| !a.starts_with("--")) else {
+        return Err("explain requires a data path (for example server.port)".to_string());
+    };
+    let mut allowed_env_keys = HashSet::new();
+    let mut allowed_files = Vec::new();
+    let mut profile = None;
+    let mut i = 1usize;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--profile" => profile = Some(parse_profile_option(args, &mut i)?),
+            "--allow-env" => parse_allow_env_option(args, &mut i, &mut allowed_env_keys)?,
+            "--allow-file" => allowed_files.push(parse_allow_file_option(args, &mut i)?),
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+    Ok(ExplainOptions {
+        path: path.clone(),
+        allowed_env_keys,
+        allowed_files,
+        profile,
+    })
 }
 
 fn parse_compile_options(args: &[String]) -> Result<CompileOptions, String> {
     let mut pretty = false;
     let mut format = OutputFormat::Json;
     let mut allowed_env_keys = HashSet::new();
+    let mut allowed_files = Vec::new();
     let mut cache_dir: Option<PathBuf> = None;
     let mut update_imports = false;
     let mut skip_data = false;
+    let mut message_format = MessageFormat::Human;
+    let mut output: Option<PathBuf> = None;
+    let mut watch = false;
+    let mut overrides = Vec::new();
+    let mut redact = false;
+    let mut profile: Option<String> = None;
     let mut i = 0usize;
 
     while i < args.len() {
         match args[i].as_str() {
+            "--profile" => profile = Some(parse_profile_option(args, &mut i)?),
+            "--set" => {
+                let spec = args
+                    .get(i + 1)
+                    .ok_or("missing value for --set (expected path=value)")?;
+                let (path, value) = parse_override(spec).map_err(|e| e.to_string())?;
+                overrides.push((path, OverrideValue::Value(value)));
+                i += 2;
+            }
+            "--set-file" => {
+                let spec = args
+                    .get(i + 1)
+                    .ok_or("missing value for --set-file (expected path=file)")?;
+                let Some((path, file)) = spec.split_once('=') else {
+                    return Err(format!(
+                        "invalid --set-file value '{spec}' (expected path=file)"
+                    ));
+                };
+                let path = normalize_override_path(path).map_err(|e| e.to_string())?;
+                overrides.push((path, OverrideValue::File(PathBuf::from(file))));
+                i += 2;
+            }
             "--pretty" => {
                 pretty = true;
                 i += 1;
             }
+            "--redact" => {
+                redact = true;
+                i += 1;
+            }
             "--yaml" => {
                 format = OutputFormat::Yaml;
                 i += 1;
@@ -382,6 +945,8 @@ fn parse_compile_options(args: &[String]) -> Result<CompileOptions, String> {
                 i += 2;
             }
             "--allow-env" => parse_allow_env_option(args, &mut i, &mut allowed_env_keys)?,
+            "--allow-file" => allowed_files.push(parse_allow_file_option(args, &mut i)?),
+            "--message-format" => message_format = parse_message_format_option(args, &mut i)?,
             "--update-imports" => {
                 update_imports = true;
                 i += 1;
@@ -397,25 +962,75 @@ fn parse_compile_options(args: &[String]) -> Result<CompileOptions, String> {
                 cache_dir = Some(PathBuf::from(&args[i + 1]));
                 i += 2;
             }
+            "--output" => {
+                if i + 1 >= args.len() {
+                    return Err("missing value for --output".to_string());
+                }
+                output = Some(PathBuf::from(&args[i + 1]));
+                i += 2;
+            }
+            "--watch" => {
+                watch = true;
+                i += 1;
+            }
             other => {
                 return Err(format!("unknown option '{other}'"));
             }
         }
     }
 
+    if !overrides.is_empty() && !matches!(format, OutputFormat::Json | OutputFormat::Yaml) {
+        return Err("--set and --set-file apply only to json and yaml output".to_string());
+    }
+    if redact
+        && !matches!(
+            format,
+            OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Rust | OutputFormat::TypeScript
+        )
+    {
+        return Err("--redact applies only to json, yaml, rust and ts output".to_string());
+    }
+    if profile.is_some() && !matches!(format, OutputFormat::Json | OutputFormat::Yaml) {
+        return Err("--profile applies only to json and yaml output".to_string());
+    }
+
     Ok(CompileOptions {
         pretty,
         format,
         allowed_env_keys,
+        allowed_files,
         cache_dir,
         update_imports,
         skip_data,
+        message_format,
+        output,
+        watch,
+        overrides,
+        redact,
+        profile,
+    })
+}
+
+fn parse_fmt_options(args: &[String]) -> Result<FmtOptions, String> {
+    let mut check = false;
+    let mut sort_properties = false;
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--sort-properties" => sort_properties = true,
+            other => return Err(format!("unknown option '{other}'")),
+        }
+    }
+    Ok(FmtOptions {
+        check,
+        sort_properties,
     })
 }
 
 fn parse_docs_options(args: &[String]) -> Result<DocsOptions, String> {
     let mut output_dir: Option<PathBuf> = None;
     let mut follow_imports = false;
+    let mut watch = false;
     let mut i = 0usize;
 
     while i < args.len() {
@@ -431,6 +1046,10 @@ fn parse_docs_options(args: &[String]) -> Result<DocsOptions, String> {
                 follow_imports = true;
                 i += 1;
             }
+            "--watch" => {
+                watch = true;
+                i += 1;
+            }
             other => return Err(format!("unknown option '{other}'")),
         }
     }
@@ -441,6 +1060,7 @@ fn parse_docs_options(args: &[String]) -> Result<DocsOptions, String> {
     Ok(DocsOptions {
         output_dir,
         follow_imports,
+        watch,
     })
 }
 
@@ -465,6 +1085,39 @@ fn parse_allow_env_option(
     Ok(())
 }
 
+fn parse_allow_file_option(args: &[String], i: &mut usize) -> Result<PathBuf, String> {
+    let Some(path) = args.get(*i + 1).filter(|path| !path.trim().is_empty()) else {
+        return Err("missing value for --allow-file (expected file or directory path)".to_string());
+    };
+    *i += 2;
+    Ok(PathBuf::from(path))
+}
+
+fn parse_profile_option(args: &[String], i: &mut usize) -> Result<String, String> {
+    let Some(profile) = args.get(*i + 1) else {
+        return Err("missing value for --profile".to_string());
+    };
+    *i += 2;
+    Ok(profile.clone())
+}
+
+fn parse_message_format_option(args: &[String], i: &mut usize) -> Result<MessageFormat, String> {
+    if *i + 1 >= args.len() {
+        return Err("missing value for --message-format (expected human or json)".to_string());
+    }
+    let format = match args[*i + 1].as_str() {
+        "human" => MessageFormat::Human,
+        "json" => MessageFormat::Json,
+        other => {
+            return Err(format!(
+                "invalid --message-format value '{other}' (expected human or json)"
+            ))
+        }
+    };
+    *i += 2;
+    Ok(format)
+}
+
 fn parse_from_json_schema_options(args: &[String]) -> Result<Option<PathBuf>, String> {
     let mut output: Option<PathBuf> = None;
     let mut i = 0usize;
@@ -499,14 +1152,28 @@ fn run_from_json_schema(file: &PathBuf, output: Option<&Path>) -> Result<(), Str
 fn print_usage() {
     eprintln!("usage:");
     eprintln!("  super-yaml from-json-schema <schema.json> [--output <file.syaml>]");
-    eprintln!("  super-yaml validate <file> [--allow-env KEY]...");
     eprintln!(
-        "  super-yaml compile <file> [--pretty] [--format json|yaml|rust|ts|typescript|proto|contracts-json|html] [--allow-env KEY]..."
+        "  super-yaml validate <file> [--profile NAME] [--allow-env KEY]... [--allow-file PATH]... [--message-format human|json] [--watch]"
+    );
+    eprintln!(
+        "  super-yaml compile <file> [--pretty] [--format json|yaml|rust|ts|typescript|proto|contracts-json|html] [--allow-env KEY]... [--allow-file PATH]..."
     );
     eprintln!(
-        "  super-yaml compile <file> [--yaml|--json|--rust|--ts|--proto|--contracts-json|--html] [--allow-env KEY]..."
+        "  super-yaml compile <file> [--yaml|--json|--rust|--ts|--proto|--contracts-json|--html] [--allow-env KEY]... [--allow-file PATH]..."
     );
-    eprintln!("  super-yaml docs <path> --output <dir> [--follow-imports]");
+    eprintln!(
+        "  super-yaml compile <file> [--profile NAME] [--set path=value]... [--set-file path=file]... (json/yaml output)"
+    );
+    eprintln!("  super-yaml compile <file> [--redact] (json/yaml/rust/ts output)");
+    eprintln!(
+        "  super-yaml get <file> <data.path> [--raw|--json|--exists] [--pretty] [--redact] [--profile NAME] [--allow-env KEY]... [--allow-file PATH]..."
+    );
+    eprintln!(
+        "  super-yaml explain <file> <data.path> [--profile NAME] [--allow-env KEY]... [--allow-file PATH]..."
+    );
+    eprintln!("  super-yaml docs <path> --output <dir> [--follow-imports] [--watch]");
+    eprintln!("  super-yaml fmt <path> [--check] [--sort-properties]");
+    eprintln!("  super-yaml lsp");
     eprintln!();
     eprintln!("codegen options (--rust / --ts):");
     eprintln!("  --skip-data            emit type definitions only; omit data constants/fns");
@@ -515,6 +1182,25 @@ fn print_usage() {
     eprintln!("  --update-imports       force re-fetch of all URL imports (bypass lockfile cache)");
     eprintln!("  --cache-dir <path>     override default URL import cache directory");
     eprintln!();
+    eprintln!("output options (compile):");
+    eprintln!("  --output <file>        write output to a file instead of stdout");
+    eprintln!("  --profile <name>       apply the <file stem>.<name>.syaml overlay (also for validate/get/explain)");
+    eprintln!("  --set <path=value>     replace a data value before derived values resolve");
+    eprintln!("  --set-file <path=file> replace a data value with the contents of a file");
+    eprintln!("  --redact               replace sensitive values with [redacted] (json/yaml/rust/ts and get)");
+    eprintln!();
+    eprintln!("watch options (validate / compile / docs):");
+    eprintln!("  --watch                rebuild whenever the file, its imports, or module.syaml/syaml.syaml change");
+    eprintln!();
+    eprintln!("diagnostics options (validate / compile):");
+    eprintln!("  --message-format json  write errors and warnings to stderr as JSON lines");
+    eprintln!();
+    eprintln!("fmt options:");
+    eprintln!(
+        "  --check                report files that are not formatted instead of rewriting them"
+    );
+    eprintln!("  --sort-properties      sort schema `properties` entries by name");
+    eprintln!();
     eprintln!("docs options:");
     eprintln!("  --output <dir>         directory to write generated HTML files into");
     eprintln!("  --follow-imports       also generate docs for all transitively imported files");
@@ -522,11 +1208,146 @@ fn print_usage() {
     eprintln!(
         "note: environment access is disabled by default; use --allow-env to permit specific keys."
     );
+    eprintln!(
+        "note: meta.env file and dotenv sources read only paths given with --allow-file; a directory allows every file under it."
+    );
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_compile_options, parse_docs_options, parse_validate_options, OutputFormat};
+    use super::{
+        parse_compile_options, parse_docs_options, parse_explain_options, parse_fmt_options,
+        parse_get_options, parse_validate_options, GetOutput, MessageFormat, OutputFormat,
+        OverrideValue, PathBuf,
+    };
+
+    #[test]
+    fn parse_compile_set_and_set_file() {
+        let args = vec![
+            "--set".to_string(),
+            "service.replicas=5".to_string(),
+            "--set-file".to_string(),
+            "tls.cert=./cert.pem".to_string(),
+        ];
+        let options = parse_compile_options(&args).unwrap();
+        assert_eq!(
+            options.overrides,
+            vec![
+                (
+                    "$.service.replicas".to_string(),
+                    OverrideValue::Value(serde_json::json!(5))
+                ),
+                (
+                    "$.tls.cert".to_string(),
+                    OverrideValue::File("./cert.pem".into())
+                ),
+            ]
+        );
+
+        let err =
+            parse_compile_options(&["--set".to_string(), "replicas".to_string()]).unwrap_err();
+        assert!(err.contains("expected path=value"), "{err}");
+
+        let args = vec!["--rust".to_string(), "--set".to_string(), "a=1".to_string()];
+        let err = parse_compile_options(&args).unwrap_err();
+        assert!(err.contains("only to json and yaml output"), "{err}");
+    }
+
+    #[test]
+    fn parse_redact_for_compile_and_get() {
+        assert!(!parse_compile_options(&[]).unwrap().redact);
+        let args = vec!["--yaml".to_string(), "--redact".to_string()];
+        assert!(parse_compile_options(&args).unwrap().redact);
+        let args = vec!["--rust".to_string(), "--redact".to_string()];
+        assert!(parse_compile_options(&args).unwrap().redact);
+
+        let args = vec!["--html".to_string(), "--redact".to_string()];
+        let err = parse_compile_options(&args).unwrap_err();
+        assert!(
+            err.contains("--redact applies only to json, yaml, rust and ts output"),
+            "{err}"
+        );
+
+        let args = vec!["db.password".to_string(), "--redact".to_string()];
+        assert!(parse_get_options(&args).unwrap().redact);
+    }
+
+    #[test]
+    fn parse_compile_profile() {
+        assert_eq!(parse_compile_options(&[]).unwrap().profile, None);
+        let args = vec!["--profile".to_string(), "prod".to_string()];
+        assert_eq!(
+            parse_compile_options(&args).unwrap().profile.as_deref(),
+            Some("prod")
+        );
+
+        let err = parse_compile_options(&["--profile".to_string()]).unwrap_err();
+        assert!(err.contains("missing value for --profile"), "{err}");
+
+        let args = vec![
+            "--rust".to_string(),
+            "--profile".to_string(),
+            "prod".to_string(),
+        ];
+        let err = parse_compile_options(&args).unwrap_err();
+        assert!(
+            err.contains("--profile applies only to json and yaml output"),
+            "{err}"
+        );
+
+        let profile = vec!["--profile".to_string(), "prod".to_string()];
+        assert_eq!(
+            parse_validate_options(&profile).unwrap().profile.as_deref(),
+            Some("prod")
+        );
+        let args = [vec!["port".to_string()], profile].concat();
+        assert_eq!(
+            parse_get_options(&args).unwrap().profile.as_deref(),
+            Some("prod")
+        );
+        assert_eq!(
+            parse_explain_options(&args).unwrap().profile.as_deref(),
+            Some("prod")
+        );
+    }
+
+    #[test]
+    fn parse_get_output_modes() {
+        let options = parse_get_options(&["service.port".to_string()]).unwrap();
+        assert_eq!(options.path, "service.port");
+        assert_eq!(options.output, GetOutput::Raw);
+        assert!(!options.pretty);
+
+        let args = vec![
+            "services.*.port".to_string(),
+            "--json".to_string(),
+            "--pretty".to_string(),
+        ];
+        let options = parse_get_options(&args).unwrap();
+        assert_eq!(options.output, GetOutput::Json);
+        assert!(options.pretty);
+
+        let args = vec!["service.port".to_string(), "--exists".to_string()];
+        assert_eq!(parse_get_options(&args).unwrap().output, GetOutput::Exists);
+
+        let err = parse_get_options(&["--json".to_string()]).unwrap_err();
+        assert!(err.contains("get requires a data path"));
+    }
+
+    #[test]
+    fn parse_explain_path_and_env_keys() {
+        let args = vec![
+            "server.port".to_string(),
+            "--allow-env".to_string(),
+            "PORT".to_string(),
+        ];
+        let options = parse_explain_options(&args).unwrap();
+        assert_eq!(options.path, "server.port");
+        assert!(options.allowed_env_keys.contains("PORT"));
+
+        let err = parse_explain_options(&["--allow-env".to_string()]).unwrap_err();
+        assert!(err.contains("explain requires a data path"));
+    }
 
     #[test]
     fn parse_compile_yaml_format() {
@@ -612,9 +1433,47 @@ mod tests {
             "--allow-env".to_string(),
             "DB_HOST".to_string(),
         ];
-        let allowed = parse_validate_options(&args).unwrap();
-        assert!(allowed.contains("CPU_CORES"));
-        assert!(allowed.contains("DB_HOST"));
+        let options = parse_validate_options(&args).unwrap();
+        assert!(options.allowed_env_keys.contains("CPU_CORES"));
+        assert!(options.allowed_env_keys.contains("DB_HOST"));
+        assert_eq!(options.message_format, MessageFormat::Human);
+    }
+
+    #[test]
+    fn parse_allow_file_repeatable_and_requires_value() {
+        let args = vec![
+            "--allow-file".to_string(),
+            "secrets/db_password".to_string(),
+            "--allow-file".to_string(),
+            "/run/secrets".to_string(),
+        ];
+        let options = parse_compile_options(&args).unwrap();
+        assert_eq!(
+            options.allowed_files,
+            vec![
+                PathBuf::from("secrets/db_password"),
+                PathBuf::from("/run/secrets")
+            ]
+        );
+
+        let err = parse_get_options(&["port".to_string(), "--allow-file".to_string()]).unwrap_err();
+        assert!(err.contains("missing value for --allow-file"));
+    }
+
+    #[test]
+    fn parse_message_format_json() {
+        let args = vec!["--message-format".to_string(), "json".to_string()];
+        let options = parse_validate_options(&args).unwrap();
+        assert_eq!(options.message_format, MessageFormat::Json);
+        let options = parse_compile_options(&args).unwrap();
+        assert_eq!(options.message_format, MessageFormat::Json);
+    }
+
+    #[test]
+    fn parse_message_format_rejects_unknown_value() {
+        let args = vec!["--message-format".to_string(), "xml".to_string()];
+        let err = parse_validate_options(&args).unwrap_err();
+        assert!(err.contains("expected human or json"));
     }
 
     #[test]
@@ -658,4 +1517,37 @@ mod tests {
         assert_eq!(opts.output_dir.to_str().unwrap(), "/tmp/docs");
         assert!(!opts.follow_imports);
     }
+
+    #[test]
+    fn parse_watch_flag_for_each_command() {
+        let watch = vec!["--watch".to_string()];
+        assert!(parse_validate_options(&watch).unwrap().watch);
+        assert!(!parse_validate_options(&[]).unwrap().watch);
+
+        let args = vec![
+            "--watch".to_string(),
+            "--output".to_string(),
+            "out.json".to_string(),
+        ];
+        let opts = parse_compile_options(&args).unwrap();
+        assert!(opts.watch);
+        assert_eq!(opts.output.unwrap().to_str().unwrap(), "out.json");
+
+        let args = vec![
+            "--output".to_string(),
+            "/tmp/docs".to_string(),
+            "--watch".to_string(),
+        ];
+        assert!(parse_docs_options(&args).unwrap().watch);
+    }
+
+    #[test]
+    fn parse_fmt_flags() {
+        let opts = parse_fmt_options(&[]).unwrap();
+        assert!(!opts.check && !opts.sort_properties);
+        let args = vec!["--check".to_string(), "--sort-properties".to_string()];
+        let opts = parse_fmt_options(&args).unwrap();
+        assert!(opts.check && opts.sort_properties);
+        assert!(parse_fmt_options(&["--bogus".to_string()]).is_err());
+    }
 }
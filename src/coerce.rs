@@ -28,7 +28,9 @@ pub fn coerce_string_constructors_for_type_hints(
             continue;
         };
 
-        let constructed = constructors.construct(source, path, type_name)?;
+        let constructed = constructors
+            .construct(source, path, type_name)
+            .map_err(|e| e.at_path(path))?;
         set_json_path(data, path, constructed)?;
     }
 
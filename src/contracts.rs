@@ -10,6 +10,9 @@ use crate::ast::{
     PermissionsDef, SpecificationDef,
 };
 use crate::error::SyamlError;
+use crate::expr::parse_expression;
+use crate::expr::render::{render, Target};
+use crate::expr::typecheck::{check_condition, Type, TypeEnv};
 
 /// Parses a `---contracts` section value into a [`ContractsDoc`].
 pub fn parse_contracts(value: &JsonValue) -> Result<ContractsDoc, SyamlError> {
@@ -82,6 +85,7 @@ fn parse_parameter_def(
         return Ok(ParameterDef {
             type_ref: serde_json::json!({ "type": type_str }),
             mutable: false,
+            sensitive: false,
         });
     }
 
@@ -106,8 +110,16 @@ fn parse_parameter_def(
         .get("mutable")
         .and_then(JsonValue::as_bool)
         .unwrap_or(false);
+    let sensitive = map
+        .get("sensitive")
+        .and_then(JsonValue::as_bool)
+        .unwrap_or(false);
 
-    Ok(ParameterDef { type_ref, mutable })
+    Ok(ParameterDef {
+        type_ref,
+        mutable,
+        sensitive,
+    })
 }
 
 fn parse_permissions_def(func_name: &str, value: &JsonValue) -> Result<PermissionsDef, SyamlError> {
@@ -242,17 +254,24 @@ fn parse_specification_def(
     })
 }
 
-/// Validates strict conditions in specification blocks: syntax check + scope check.
+/// Validates strict conditions in specification blocks: syntax, scope and type checks.
 ///
 /// For each function with a specification containing strict conditions:
 /// - Parses each expression (syntax error → ContractsError)
 /// - Walks the AST for variable references
 /// - Verifies roots are in-scope (input/data for preconditions; input/data/output for postconditions)
 /// - Verifies input params exist, data paths are covered by permissions.data.read, output is declared
-pub fn validate_specification_strict_conditions(doc: &ContractsDoc) -> Result<(), SyamlError> {
-    use crate::expr::parse_expression;
+/// - Type-checks the expression against input/output types and the data, and requires a boolean
+pub fn validate_specification_strict_conditions(
+    doc: &ContractsDoc,
+    types: &BTreeMap<String, JsonValue>,
+    data: &JsonValue,
+    type_hints: &BTreeMap<String, String>,
+) -> Result<(), SyamlError> {
     use crate::schema::collect_var_paths;
 
+    let data_type = TypeEnv::new(types).with_data(data, type_hints).data_type();
+
     for (func_name, func_def) in &doc.functions {
         let Some(spec) = &func_def.specification else {
             continue;
@@ -270,6 +289,28 @@ pub fn validate_specification_strict_conditions(doc: &ContractsDoc) -> Result<()
 
         let has_output = func_def.output.is_some();
 
+        let input_type = Type::Object(
+            func_def
+                .inputs
+                .iter()
+                .map(|(name, param)| {
+                    (
+                        name.clone(),
+                        TypeEnv::new(types).schema_type(&param.type_ref),
+                    )
+                })
+                .collect(),
+        );
+        let pre_env = TypeEnv::new(types)
+            .with_root("input", input_type.clone())
+            .with_root("data", data_type.clone());
+        let mut post_env = TypeEnv::new(types)
+            .with_root("input", input_type)
+            .with_root("data", data_type.clone());
+        if let Some(output) = &func_def.output {
+            post_env = post_env.with_root("output", TypeEnv::new(types).schema_type(output));
+        }
+
         // Validate preconditions (strict)
         if let Some(cond_set) = &spec.preconditions {
             for expr_str in &cond_set.strict {
@@ -325,6 +366,13 @@ pub fn validate_specification_strict_conditions(doc: &ContractsDoc) -> Result<()
                         }
                     }
                 }
+
+                if let Some(issue) = check_condition(&pre_env, &ast).into_iter().next() {
+                    return Err(SyamlError::ContractsError(format!(
+                        "contracts.{}: strict precondition '{}': {}",
+                        func_name, expr_str, issue
+                    )));
+                }
             }
         }
 
@@ -386,6 +434,13 @@ pub fn validate_specification_strict_conditions(doc: &ContractsDoc) -> Result<()
                         }
                     }
                 }
+
+                if let Some(issue) = check_condition(&post_env, &ast).into_iter().next() {
+                    return Err(SyamlError::ContractsError(format!(
+                        "contracts.{}: strict postcondition '{}': {}",
+                        func_name, expr_str, issue
+                    )));
+                }
             }
         }
     }
@@ -412,16 +467,19 @@ fn read_path_covers_segments(read_paths: &[String], data_segs: &[String]) -> boo
 }
 
 /// Validates that all type references in contracts definitions exist in the type registry.
+///
+/// Every unknown reference is reported.
 pub fn validate_contracts_type_references(
     doc: &ContractsDoc,
     types: &BTreeMap<String, JsonValue>,
 ) -> Result<(), SyamlError> {
+    let mut errors = Vec::new();
     for (func_name, func_def) in &doc.functions {
         for (param_name, param_def) in &func_def.inputs {
             let type_ref = &param_def.type_ref;
             if let Some(type_name) = type_ref.get("type").and_then(JsonValue::as_str) {
                 if !is_builtin_type(type_name) && !types.contains_key(type_name) {
-                    return Err(SyamlError::ContractsError(format!(
+                    errors.push(SyamlError::ContractsError(format!(
                         "contracts.{}.inputs.{}: unknown type '{}'",
                         func_name, param_name, type_name
                     )));
@@ -432,7 +490,7 @@ pub fn validate_contracts_type_references(
         if let Some(output) = &func_def.output {
             if let Some(type_name) = output.get("type").and_then(JsonValue::as_str) {
                 if !is_builtin_type(type_name) && !types.contains_key(type_name) {
-                    return Err(SyamlError::ContractsError(format!(
+                    errors.push(SyamlError::ContractsError(format!(
                         "contracts.{}.output: unknown type '{}'",
                         func_name, type_name
                     )));
@@ -440,10 +498,10 @@ pub fn validate_contracts_type_references(
             }
         }
 
-        if let Some(errors) = &func_def.errors {
-            if let Some(type_name) = errors.get("type").and_then(JsonValue::as_str) {
+        if let Some(error_type) = &func_def.errors {
+            if let Some(type_name) = error_type.get("type").and_then(JsonValue::as_str) {
                 if !is_builtin_type(type_name) && !types.contains_key(type_name) {
-                    return Err(SyamlError::ContractsError(format!(
+                    errors.push(SyamlError::ContractsError(format!(
                         "contracts.{}.errors: unknown type '{}'",
                         func_name, type_name
                     )));
@@ -451,22 +509,25 @@ pub fn validate_contracts_type_references(
             }
         }
     }
-    Ok(())
+    SyamlError::from_errors(errors)
 }
 
-fn is_builtin_type(name: &str) -> bool {
+pub(crate) fn is_builtin_type(name: &str) -> bool {
     matches!(
         name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || crate::quantity::QUANTITY_TYPE_NAMES.contains(&name)
 }
 
 /// Validates permission data paths against the actual data structure.
+///
+/// Every invalid path is reported.
 pub fn validate_permission_data_paths(
     doc: &ContractsDoc,
     data: &JsonValue,
     import_aliases: &BTreeSet<String>,
 ) -> Result<(), SyamlError> {
+    let mut errors = Vec::new();
     for (func_name, func_def) in &doc.functions {
         let Some(perms) = &func_def.permissions else {
             continue;
@@ -488,10 +549,11 @@ pub fn validate_permission_data_paths(
             let root_stripped = root_segment.trim_start_matches('$').trim_start_matches('.');
             let first_key = root_stripped.split('.').next().unwrap_or(root_stripped);
             if import_aliases.contains(first_key) {
-                return Err(SyamlError::ContractsError(format!(
+                errors.push(SyamlError::ContractsError(format!(
                     "contracts.{}: permission path '{}' rooted at import alias '{}' is not allowed",
                     func_name, path, first_key
                 )));
+                continue;
             }
 
             // Verify path exists in data (handle wildcard '*')
@@ -501,14 +563,14 @@ pub fn validate_permission_data_paths(
 
             let normalized = normalize_path(path);
             if !path_exists_in_data(&normalized, data) {
-                return Err(SyamlError::ContractsError(format!(
+                errors.push(SyamlError::ContractsError(format!(
                     "contracts.{}: permission path '{}' does not exist in data",
                     func_name, path
                 )));
             }
         }
     }
-    Ok(())
+    SyamlError::from_errors(errors)
 }
 
 fn normalize_path(path: &str) -> Vec<String> {
@@ -623,6 +685,17 @@ fn is_path_frozen(path: &str, freeze_markers: &FreezeMarkers) -> bool {
     false
 }
 
+/// Marks every input typed with a sensitive schema type as sensitive, so the
+/// serialized contracts tell which parameters carry secrets.
+pub fn mark_sensitive_parameters(doc: &mut ContractsDoc, types: &BTreeMap<String, JsonValue>) {
+    for func_def in doc.functions.values_mut() {
+        for param_def in func_def.inputs.values_mut() {
+            param_def.sensitive |=
+                crate::sensitive::schema_is_sensitive(&param_def.type_ref, types);
+        }
+    }
+}
+
 /// Serializes a `ContractsDoc` to JSON text.
 pub fn contracts_to_json(doc: &ContractsDoc, pretty: bool) -> Result<String, SyamlError> {
     if pretty {
@@ -670,43 +743,29 @@ fn collect_condition_refs(expr: &str) -> (BTreeSet<String>, BTreeSet<String>, bo
 }
 
 /// Translates a syaml condition expression to target-language source by substituting
-/// `input.X`, `data.X`, and `output` with the provided name functions.
-/// All other tokens (operators, numbers, booleans, function names) pass through unchanged.
+/// `input.X`, `data.X`, and `output` with the provided names. Conditionals and
+/// `let` bindings become the target's own syntax; other operators, literals and
+/// function names pass through unchanged.
 fn translate_condition_expr(
     expr: &str,
+    target: Target,
     input_fn: &dyn Fn(&str) -> String,
     data_fn: &dyn Fn(&str) -> String,
     output_var: &str,
 ) -> String {
-    let mut result = String::new();
-    let bytes = expr.as_bytes();
-    let mut i = 0;
-
-    while i < bytes.len() {
-        if bytes[i].is_ascii_alphabetic() || bytes[i] == b'_' {
-            let start = i;
-            while i < bytes.len()
-                && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.')
-            {
-                i += 1;
-            }
-            let token = &expr[start..i];
-            if let Some(param) = token.strip_prefix("input.") {
-                result.push_str(&input_fn(param));
-            } else if let Some(field) = token.strip_prefix("data.") {
-                result.push_str(&data_fn(field));
-            } else if token == "output" {
-                result.push_str(output_var);
-            } else {
-                result.push_str(token);
-            }
-        } else {
-            result.push(bytes[i] as char);
-            i += 1;
-        }
-    }
-
-    result
+    // Strict conditions are parsed during validation; keep the source otherwise.
+    let Ok(ast) = parse_expression(expr) else {
+        return expr.to_string();
+    };
+    render(&ast, target, &|path: &[String]| match path[0].as_str() {
+        "input" if path.len() > 1 => input_fn(&path[1..].join(".")),
+        "data" if path.len() > 1 => data_fn(&path[1..].join(".")),
+        "output" => std::iter::once(output_var)
+            .chain(path[1..].iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("."),
+        _ => path.join("."),
+    })
 }
 
 /// Resolves a type_ref to its primitive base kind: "integer", "number", "string",
@@ -998,7 +1057,8 @@ fn build_preconditions_fn_rust(
     let input_fn = |p: &str| to_snake_case(p);
     let data_fn = |f: &str| format!("data_{}", to_snake_case(f));
     for expr in &cond_set.strict {
-        let translated = translate_condition_expr(expr, &input_fn, &data_fn, "output");
+        let translated =
+            translate_condition_expr(expr, Target::Rust, &input_fn, &data_fn, "output");
         body.push_str(&format!(
             "    if !({translated}) {{\n        return Err(\"precondition violated: {expr}\".to_string());\n    }}\n"
         ));
@@ -1081,7 +1141,8 @@ fn build_postconditions_fn_rust(
     let input_fn = |p: &str| to_snake_case(p);
     let data_fn = |f: &str| format!("data_{}", to_snake_case(f));
     for expr in &cond_set.strict {
-        let translated = translate_condition_expr(expr, &input_fn, &data_fn, "output");
+        let translated =
+            translate_condition_expr(expr, Target::Rust, &input_fn, &data_fn, "output");
         body.push_str(&format!(
             "    if !({translated}) {{\n        return Err(\"postcondition violated: {expr}\".to_string());\n    }}\n"
         ));
@@ -1171,7 +1232,8 @@ fn build_preconditions_fn_ts(
     let input_fn = |p: &str| to_camel_case(p);
     let data_fn = |f: &str| format!("data{}", to_pascal_case(f));
     for expr in &cond_set.strict {
-        let translated = translate_condition_expr(expr, &input_fn, &data_fn, "output");
+        let translated =
+            translate_condition_expr(expr, Target::TypeScript, &input_fn, &data_fn, "output");
         body.push_str(&format!(
             "  if (!({translated})) throw new Error(\"precondition violated: {expr}\");\n"
         ));
@@ -1251,7 +1313,8 @@ fn build_postconditions_fn_ts(
     let input_fn = |p: &str| to_camel_case(p);
     let data_fn = |f: &str| format!("data{}", to_pascal_case(f));
     for expr in &cond_set.strict {
-        let translated = translate_condition_expr(expr, &input_fn, &data_fn, "output");
+        let translated =
+            translate_condition_expr(expr, Target::TypeScript, &input_fn, &data_fn, "output");
         body.push_str(&format!(
             "  if (!({translated})) throw new Error(\"postcondition violated: {expr}\");\n"
         ));
@@ -1893,6 +1956,9 @@ fn schema_to_rust_type(schema: &JsonValue, _types: &BTreeMap<String, JsonValue>)
             "null" => "()".to_string(),
             "array" => "Vec<serde_json::Value>".to_string(),
             "object" => "std::collections::BTreeMap<String, serde_json::Value>".to_string(),
+            "duration" => "super_yaml::quantity::Duration".to_string(),
+            "timestamp" => "super_yaml::quantity::Timestamp".to_string(),
+            "bytesize" => "super_yaml::quantity::ByteSize".to_string(),
             other => {
                 // Named type reference — convert to PascalCase
                 to_pascal_case(other)
@@ -1911,6 +1977,8 @@ fn schema_to_ts_type(schema: &JsonValue, _types: &BTreeMap<String, JsonValue>) -
             "null" => "null".to_string(),
             "array" => "unknown[]".to_string(),
             "object" => "Record<string, unknown>".to_string(),
+            "duration" | "timestamp" => "string".to_string(),
+            "bytesize" => "string | number".to_string(),
             other => to_pascal_case(other),
         };
     }
@@ -0,0 +1,973 @@
+//! Lossless concrete syntax tree (CST) for `.syaml` files.
+//!
+//! [`CstDocument::parse`] keeps every source line verbatim — indentation,
+//! quoting, comments, blank lines and line terminators — so printing the tree
+//! with [`CstDocument::to_source`] reproduces the input byte for byte. Lines are
+//! arranged by indentation into sections, blocks and entries following the
+//! `mini_yaml` grammar, which lets tools find and rewrite individual entries
+//! without reformatting the rest of the file. [`CstSection::to_json_value`]
+//! lowers a section to the same [`JsonValue`] that `mini_yaml` produces.
+//!
+//! [`CstDocument::parse`]: crate::cst::CstDocument::parse
+//! [`CstDocument::to_source`]: crate::cst::CstDocument::to_source
+//! [`CstSection::to_json_value`]: crate::cst::CstSection::to_json_value
+
+use std::fmt;
+
+use regex::Regex;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::error::SyamlError;
+use crate::mini_yaml::{
+    apply_merges, find_unquoted_colon, is_ignorable, is_merge_key, leading_spaces, merge_sources,
+    parse_block_scalar_header, parse_inline_value_with, parse_key, render_block_scalar,
+    sequence_item_rest, split_anchor, strip_inline_comment, yaml_parse_error, Anchors,
+    BlockScalarHeader, MAX_COLLECTION_ITEMS, MAX_CONTAINER_DEPTH,
+};
+use crate::section_scanner::scan_sections;
+use crate::type_hints::canonical_data_key;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One source line.
+pub struct CstLine {
+    /// Line content without its terminator.
+    pub text: String,
+    /// Terminator as written: `"\n"`, `"\r\n"`, or `""` for a final unterminated line.
+    pub newline: String,
+}
+
+impl CstLine {
+    /// Creates a line terminated by `\n`.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            newline: "\n".to_string(),
+        }
+    }
+
+    /// Number of leading spaces.
+    pub fn indent(&self) -> usize {
+        leading_spaces(&self.text)
+    }
+
+    /// Returns `true` for whitespace-only lines.
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// Returns `true` for lines holding only a `#` comment.
+    pub fn is_comment(&self) -> bool {
+        self.text.trim_start().starts_with('#')
+    }
+
+    fn write_to(&self, out: &mut String) {
+        out.push_str(&self.text);
+        out.push_str(&self.newline);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A parsed `.syaml` file.
+pub struct CstDocument {
+    /// Lines before the first section fence: blank lines and the `---!syaml/v0` marker.
+    pub header: Vec<CstLine>,
+    /// Sections in source order.
+    pub sections: Vec<CstSection>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A `---name` section and its body.
+pub struct CstSection {
+    /// Section name (for example `schema` or `data`).
+    pub name: String,
+    /// The fence line as written.
+    pub fence: CstLine,
+    /// Section body.
+    pub body: CstBlock,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// Lines at one indentation level: entries plus the blank and comment lines
+/// between them.
+pub struct CstBlock {
+    /// Items in source order.
+    pub items: Vec<CstItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Element of a [`CstBlock`].
+pub enum CstItem {
+    /// Blank or comment-only line.
+    Trivia(CstLine),
+    /// Mapping entry, sequence item, or bare scalar.
+    Entry(CstEntry),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Kind of entry line.
+pub enum CstEntryKind {
+    /// `key: value`
+    Mapping,
+    /// `- value`
+    SequenceItem,
+    /// A value on its own line.
+    Scalar,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// One entry line with everything nested under it.
+pub struct CstEntry {
+    /// Entry kind.
+    pub kind: CstEntryKind,
+    /// The entry line as written.
+    pub line: CstLine,
+    /// Content lines of a `|` / `>` block scalar value, as written.
+    pub block_scalar: Vec<CstLine>,
+    /// Nested block for entries whose value starts on the next line.
+    pub children: Option<CstBlock>,
+}
+
+impl CstDocument {
+    /// Parses `input` into a lossless syntax tree.
+    ///
+    /// Section names and the document marker are validated like
+    /// [`crate::parse_document`]; section bodies must follow the `mini_yaml`
+    /// indentation rules.
+    pub fn parse(input: &str) -> Result<Self, SyamlError> {
+        scan_sections(input)?;
+        let fence_re = Regex::new(r"^---([a-z_]+)\s*$").expect("valid regex");
+
+        let mut header = Vec::new();
+        let mut sections: Vec<(String, CstLine, Vec<CstLine>)> = Vec::new();
+        for line in split_lines(input) {
+            if let Some(cap) = fence_re.captures(line.text.trim()) {
+                sections.push((cap[1].to_string(), line, Vec::new()));
+            } else if let Some((_, _, body)) = sections.last_mut() {
+                body.push(line);
+            } else {
+                header.push(line);
+            }
+        }
+
+        let sections = sections
+            .into_iter()
+            .map(|(name, fence, lines)| {
+                let body = parse_body(lines).map_err(|e| name_section(e, &name))?;
+                Ok(CstSection { name, fence, body })
+            })
+            .collect::<Result<_, SyamlError>>()?;
+        Ok(Self { header, sections })
+    }
+
+    /// Prints the tree back to source text.
+    pub fn to_source(&self) -> String {
+        let mut out = String::new();
+        for line in &self.header {
+            line.write_to(&mut out);
+        }
+        for section in &self.sections {
+            section.fence.write_to(&mut out);
+            section.body.write_to(&mut out);
+        }
+        out
+    }
+
+    /// Returns the section named `name`.
+    pub fn section(&self, name: &str) -> Option<&CstSection> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Returns the section named `name` for editing.
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut CstSection> {
+        self.sections
+            .iter_mut()
+            .find(|section| section.name == name)
+    }
+}
+
+impl fmt::Display for CstDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_source())
+    }
+}
+
+impl CstSection {
+    /// Lowers the section body to JSON, exactly as `mini_yaml` parses it.
+    pub fn to_json_value(&self) -> Result<JsonValue, SyamlError> {
+        self.body
+            .to_json_value(0)
+            .map_err(|e| name_section(e, &self.name))
+    }
+
+    /// Returns the entry at a normalized data path such as `$.service.ports[0]`.
+    ///
+    /// Mapping keys match either as written or by their canonical data key, so
+    /// `$.port` finds `port^ <Port>`.
+    pub fn entry(&self, path: &str) -> Option<&CstEntry> {
+        let segments = parse_path(path)?;
+        let (first, rest) = segments.split_first()?;
+        let mut entry = self.body.lookup(first)?;
+        for segment in rest {
+            entry = entry.children.as_ref()?.lookup(segment)?;
+        }
+        Some(entry)
+    }
+
+    /// Returns the entry at a normalized data path for editing.
+    pub fn entry_mut(&mut self, path: &str) -> Option<&mut CstEntry> {
+        let segments = parse_path(path)?;
+        let (first, rest) = segments.split_first()?;
+        let mut entry = self.body.lookup_mut(first)?;
+        for segment in rest {
+            entry = entry.children.as_mut()?.lookup_mut(segment)?;
+        }
+        Some(entry)
+    }
+}
+
+impl CstBlock {
+    /// Iterates the entries of this block, skipping trivia.
+    pub fn entries(&self) -> impl Iterator<Item = &CstEntry> {
+        self.items.iter().filter_map(|item| match item {
+            CstItem::Entry(entry) => Some(entry),
+            CstItem::Trivia(_) => None,
+        })
+    }
+
+    /// Iterates the entries of this block for editing.
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut CstEntry> {
+        self.items.iter_mut().filter_map(|item| match item {
+            CstItem::Entry(entry) => Some(entry),
+            CstItem::Trivia(_) => None,
+        })
+    }
+
+    /// Column of the entries in this block, or `None` when it has none.
+    pub fn indent(&self) -> Option<usize> {
+        self.entries().next().map(CstEntry::indent)
+    }
+
+    /// Returns the mapping entry whose key (as written, or canonicalized) is `key`.
+    pub fn get(&self, key: &str) -> Option<&CstEntry> {
+        self.entries().find(|entry| entry.matches_key(key))
+    }
+
+    /// Returns the mapping entry whose key is `key` for editing.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut CstEntry> {
+        self.entries_mut().find(|entry| entry.matches_key(key))
+    }
+
+    /// Appends `key: value` after the last entry, at the block's indentation.
+    ///
+    /// `indent` is used when the block is empty.
+    pub fn push_mapping(&mut self, key: &str, value: &str, indent: usize) -> &mut CstEntry {
+        let indent = self.indent().unwrap_or(indent);
+        let text = if value.is_empty() {
+            format!("{}{key}:", " ".repeat(indent))
+        } else {
+            format!("{}{key}: {value}", " ".repeat(indent))
+        };
+        let position = self
+            .items
+            .iter()
+            .rposition(|item| matches!(item, CstItem::Entry(_)))
+            .map_or(self.items.len(), |i| i + 1);
+        if let Some(CstItem::Entry(previous)) = position.checked_sub(1).map(|i| &mut self.items[i])
+        {
+            previous.ensure_terminated();
+        }
+        self.items.insert(
+            position,
+            CstItem::Entry(CstEntry {
+                kind: CstEntryKind::Mapping,
+                line: CstLine::new(text),
+                block_scalar: Vec::new(),
+                children: None,
+            }),
+        );
+        match &mut self.items[position] {
+            CstItem::Entry(entry) => entry,
+            CstItem::Trivia(_) => unreachable!("entry was just inserted"),
+        }
+    }
+
+    /// Removes the mapping entry `key` and returns it.
+    pub fn remove(&mut self, key: &str) -> Option<CstEntry> {
+        let position = self
+            .items
+            .iter()
+            .position(|item| matches!(item, CstItem::Entry(entry) if entry.matches_key(key)))?;
+        match self.items.remove(position) {
+            CstItem::Entry(entry) => Some(entry),
+            CstItem::Trivia(_) => None,
+        }
+    }
+
+    /// Lowers the block to JSON.
+    ///
+    /// Anchors are scoped to this block; lower a whole section with
+    /// [`CstSection::to_json_value`] to resolve aliases across it.
+    pub fn to_json_value(&self, depth: usize) -> Result<JsonValue, SyamlError> {
+        self.lower(depth, &mut Anchors::default())
+    }
+
+    fn lower(&self, depth: usize, anchors: &mut Anchors) -> Result<JsonValue, SyamlError> {
+        if depth > MAX_CONTAINER_DEPTH {
+            return Err(yaml_parse_error(format!(
+                "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
+            )));
+        }
+        let entries: Vec<&CstEntry> = self.entries().collect();
+        let Some(first) = entries.first() else {
+            return Ok(JsonValue::Object(JsonMap::new()));
+        };
+
+        match first.kind {
+            CstEntryKind::Scalar => {
+                if entries.len() > 1 {
+                    return Err(yaml_parse_error(format!(
+                        "unexpected content after scalar value '{}'",
+                        first.content()
+                    )));
+                }
+                first.lower_value(depth, anchors)
+            }
+            CstEntryKind::SequenceItem => {
+                let mut items = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    if entry.kind != CstEntryKind::SequenceItem {
+                        return Err(yaml_parse_error(format!(
+                            "mixed sequence/mapping at '{}'",
+                            entry.content()
+                        )));
+                    }
+                    items.push(entry.lower_value(depth, anchors)?);
+                    if items.len() > MAX_COLLECTION_ITEMS {
+                        return Err(yaml_parse_error(format!(
+                            "sequence exceeds max item count ({MAX_COLLECTION_ITEMS})"
+                        )));
+                    }
+                }
+                Ok(JsonValue::Array(items))
+            }
+            CstEntryKind::Mapping => {
+                let mut map = JsonMap::new();
+                let mut merges = Vec::new();
+                for entry in entries {
+                    let key = match entry.kind {
+                        CstEntryKind::Mapping => entry.key().unwrap_or(""),
+                        CstEntryKind::SequenceItem => {
+                            return Err(yaml_parse_error(format!(
+                                "mixed sequence/mapping at '{}'",
+                                entry.content()
+                            )))
+                        }
+                        CstEntryKind::Scalar => {
+                            return Err(yaml_parse_error(format!(
+                                "expected key:value at '{}'",
+                                entry.content()
+                            )))
+                        }
+                    };
+                    if is_merge_key(key) {
+                        merges.extend(merge_sources(entry.lower_value(depth, anchors)?)?);
+                        continue;
+                    }
+                    let key = parse_key(key)?;
+                    if map.contains_key(&key) {
+                        return Err(yaml_parse_error(format!("duplicate key '{key}'")));
+                    }
+                    map.insert(key, entry.lower_value(depth, anchors)?);
+                    if map.len() > MAX_COLLECTION_ITEMS {
+                        return Err(yaml_parse_error(format!(
+                            "mapping exceeds max item count ({MAX_COLLECTION_ITEMS})"
+                        )));
+                    }
+                }
+                apply_merges(&mut map, merges)?;
+                Ok(JsonValue::Object(map))
+            }
+        }
+    }
+
+    fn lookup(&self, segment: &PathSegment) -> Option<&CstEntry> {
+        match segment {
+            PathSegment::Key(key) => self.get(key),
+            PathSegment::Index(index) => self
+                .entries()
+                .filter(|entry| entry.kind == CstEntryKind::SequenceItem)
+                .nth(*index),
+        }
+    }
+
+    fn lookup_mut(&mut self, segment: &PathSegment) -> Option<&mut CstEntry> {
+        match segment {
+            PathSegment::Key(key) => self.get_mut(key),
+            PathSegment::Index(index) => self
+                .entries_mut()
+                .filter(|entry| entry.kind == CstEntryKind::SequenceItem)
+                .nth(*index),
+        }
+    }
+
+    fn write_to(&self, out: &mut String) {
+        for item in &self.items {
+            match item {
+                CstItem::Trivia(line) => line.write_to(out),
+                CstItem::Entry(entry) => entry.write_to(out),
+            }
+        }
+    }
+}
+
+impl CstEntry {
+    /// Column of the entry line.
+    pub fn indent(&self) -> usize {
+        self.line.indent()
+    }
+
+    /// Entry text without indentation or trailing whitespace.
+    pub fn content(&self) -> &str {
+        self.line.text.trim()
+    }
+
+    /// Mapping key exactly as written (quotes, `<Type>` hints and suffixes included).
+    pub fn key(&self) -> Option<&str> {
+        if self.kind != CstEntryKind::Mapping {
+            return None;
+        }
+        let content = self.content();
+        find_unquoted_colon(content).map(|colon| content[..colon].trim())
+    }
+
+    /// Raw inline value text including any trailing comment; empty when the value
+    /// is nested on following lines.
+    pub fn raw_value(&self) -> &str {
+        let content = self.content();
+        match self.kind {
+            CstEntryKind::Mapping => find_unquoted_colon(content)
+                .map(|colon| content[colon + 1..].trim_start())
+                .unwrap_or(""),
+            CstEntryKind::SequenceItem => sequence_item_rest(content).unwrap_or(""),
+            CstEntryKind::Scalar => content,
+        }
+    }
+
+    /// Name of the `&anchor` on this entry's value, if any.
+    pub fn anchor(&self) -> Option<&str> {
+        if self.kind == CstEntryKind::Scalar {
+            return None;
+        }
+        split_anchor(self.raw_value())
+            .ok()
+            .flatten()
+            .map(|(name, _)| name)
+    }
+
+    /// Raw value text after any `&anchor`; empty when only the anchor (and a
+    /// comment) is written inline.
+    fn anchored_value(&self) -> &str {
+        let raw = self.raw_value();
+        if self.kind == CstEntryKind::Scalar {
+            return raw;
+        }
+        match split_anchor(raw) {
+            Ok(Some((_, rest))) => rest,
+            _ => raw,
+        }
+    }
+
+    /// Header of the `|` / `>` block scalar started on this entry line.
+    pub(crate) fn block_scalar_header(&self) -> Option<BlockScalarHeader> {
+        if self.kind == CstEntryKind::Scalar {
+            return None;
+        }
+        parse_block_scalar_header(self.anchored_value())
+            .ok()
+            .flatten()
+    }
+
+    /// Inline value text without a trailing comment.
+    pub fn value(&self) -> &str {
+        strip_inline_comment(self.raw_value())
+    }
+
+    /// Trailing `# ...` comment on the entry line.
+    pub fn comment(&self) -> Option<&str> {
+        let raw = self.raw_value();
+        let value = strip_inline_comment(raw);
+        let comment = raw[value.len()..].trim();
+        (!comment.is_empty()).then_some(comment)
+    }
+
+    /// Replaces the inline value, keeping the key, indentation and trailing comment.
+    ///
+    /// Nested children and block scalar lines are removed.
+    pub fn set_value(&mut self, value: &str) {
+        let indent = " ".repeat(self.indent());
+        let comment = self.comment().map(str::to_string);
+        let prefix = match self.kind {
+            CstEntryKind::Mapping => format!("{}:", self.key().unwrap_or_default()),
+            CstEntryKind::SequenceItem => "-".to_string(),
+            CstEntryKind::Scalar => String::new(),
+        };
+        let mut text = indent + &prefix;
+        if !value.is_empty() {
+            if !prefix.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(value);
+        }
+        if let Some(comment) = comment {
+            text.push(' ');
+            text.push_str(&comment);
+        }
+        self.line.text = text;
+        self.block_scalar.clear();
+        self.children = None;
+    }
+
+    /// Replaces the mapping key, keeping the rest of the line unchanged.
+    pub fn set_key(&mut self, key: &str) {
+        let Some(old) = self.key() else {
+            return;
+        };
+        let start = old.as_ptr() as usize - self.line.text.as_ptr() as usize;
+        let end = start + old.len();
+        self.line.text.replace_range(start..end, key);
+    }
+
+    /// Lowers the entry's value to JSON.
+    ///
+    /// Aliases can only refer to anchors defined inside the value itself.
+    pub fn value_to_json(&self, depth: usize) -> Result<JsonValue, SyamlError> {
+        self.lower_value(depth, &mut Anchors::default())
+    }
+
+    fn lower_value(&self, depth: usize, anchors: &mut Anchors) -> Result<JsonValue, SyamlError> {
+        let raw = self.raw_value();
+        let (anchor, raw) = match self.kind {
+            CstEntryKind::Scalar => (None, raw),
+            _ => match split_anchor(raw)? {
+                Some((name, rest)) => (Some(name), rest),
+                None => (None, raw),
+            },
+        };
+        let value = if let Some(children) = &self.children {
+            children.lower(depth + 1, anchors)?
+        } else if self.kind == CstEntryKind::Scalar {
+            parse_inline_value_with(raw, depth + 1, anchors)?
+        } else if raw.is_empty() {
+            JsonValue::Null
+        } else if let Some(header) = parse_block_scalar_header(raw)? {
+            JsonValue::String(self.render_block(header))
+        } else {
+            parse_inline_value_with(raw, depth + 1, anchors)?
+        };
+        if let Some(name) = anchor {
+            anchors.define(name, &value);
+        }
+        Ok(value)
+    }
+
+    fn render_block(&self, header: BlockScalarHeader) -> String {
+        let content_indent = header
+            .explicit_indent
+            .map(|v| self.indent() + v)
+            .or_else(|| {
+                self.block_scalar
+                    .iter()
+                    .find(|line| !line.is_blank())
+                    .map(CstLine::indent)
+            })
+            .unwrap_or(0);
+        let content: Vec<String> = self
+            .block_scalar
+            .iter()
+            .map(|line| {
+                if line.is_blank() {
+                    String::new()
+                } else {
+                    line.text[content_indent..].to_string()
+                }
+            })
+            .collect();
+        render_block_scalar(&content, header)
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        let Some(raw) = self.key() else {
+            return false;
+        };
+        raw == key
+            || parse_key(raw)
+                .is_ok_and(|parsed| parsed == key || canonical_data_key(&parsed) == key)
+    }
+
+    /// Makes sure the last line of this entry ends with a newline before content
+    /// is added after it.
+    fn ensure_terminated(&mut self) {
+        let last = match self.children.as_mut() {
+            Some(children) => children.last_line_mut(),
+            None => self.block_scalar.last_mut(),
+        };
+        let line = last.unwrap_or(&mut self.line);
+        if line.newline.is_empty() {
+            line.newline = "\n".to_string();
+        }
+    }
+
+    fn write_to(&self, out: &mut String) {
+        self.line.write_to(out);
+        for line in &self.block_scalar {
+            line.write_to(out);
+        }
+        if let Some(children) = &self.children {
+            children.write_to(out);
+        }
+    }
+}
+
+impl CstBlock {
+    fn last_line_mut(&mut self) -> Option<&mut CstLine> {
+        match self.items.last_mut()? {
+            CstItem::Trivia(line) => Some(line),
+            CstItem::Entry(entry) => {
+                if let Some(children) = entry.children.as_mut() {
+                    if children.items.is_empty() {
+                        return Some(&mut entry.line);
+                    }
+                    return children.last_line_mut();
+                }
+                Some(entry.block_scalar.last_mut().unwrap_or(&mut entry.line))
+            }
+        }
+    }
+}
+
+fn split_lines(input: &str) -> Vec<CstLine> {
+    input
+        .split_inclusive('\n')
+        .map(|raw| {
+            let (text, newline) = if let Some(text) = raw.strip_suffix("\r\n") {
+                (text, "\r\n")
+            } else if let Some(text) = raw.strip_suffix('\n') {
+                (text, "\n")
+            } else {
+                (raw, "")
+            };
+            CstLine {
+                text: text.to_string(),
+                newline: newline.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn name_section(e: SyamlError, section: &str) -> SyamlError {
+    e.map_inner(|inner| match inner {
+        SyamlError::YamlParseError { message, .. } => SyamlError::YamlParseError {
+            section: section.to_string(),
+            message,
+        },
+        other => other,
+    })
+}
+
+fn parse_body(lines: Vec<CstLine>) -> Result<CstBlock, SyamlError> {
+    let indent = lines
+        .iter()
+        .find(|line| !is_ignorable(&line.text))
+        .map(CstLine::indent)
+        .unwrap_or(0);
+    let mut lines = lines.into_iter().peekable();
+    let block = parse_block(&mut lines, indent, true, 0)?;
+    if let Some(line) = lines.next() {
+        return Err(yaml_parse_error(format!(
+            "unexpected indentation at '{}': expected {indent}, found {}",
+            line.text.trim(),
+            line.indent()
+        )));
+    }
+    Ok(block)
+}
+
+type Lines = std::iter::Peekable<std::vec::IntoIter<CstLine>>;
+
+fn parse_block(
+    lines: &mut Lines,
+    indent: usize,
+    root: bool,
+    depth: usize,
+) -> Result<CstBlock, SyamlError> {
+    if depth > MAX_CONTAINER_DEPTH {
+        return Err(yaml_parse_error(format!(
+            "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
+        )));
+    }
+
+    let mut block = CstBlock::default();
+    while let Some(line) = lines.peek() {
+        if line.is_blank() {
+            block
+                .items
+                .push(CstItem::Trivia(lines.next().expect("peeked")));
+            continue;
+        }
+        let line_indent = line.indent();
+        if line.is_comment() {
+            // A dedented comment stays in this block when the block continues after it.
+            let continues = next_content_indent(lines).is_some_and(|next| next >= indent);
+            if line_indent < indent && !root && !continues {
+                break;
+            }
+            block
+                .items
+                .push(CstItem::Trivia(lines.next().expect("peeked")));
+            continue;
+        }
+        if line_indent < indent {
+            break;
+        }
+        if line_indent > indent {
+            return Err(yaml_parse_error(format!(
+                "unexpected indentation at '{}': expected {indent}, found {line_indent}",
+                line.text.trim()
+            )));
+        }
+
+        let line = lines.next().expect("peeked");
+        let content = line.text[indent..].trim_start();
+        let kind = if sequence_item_rest(content).is_some() {
+            CstEntryKind::SequenceItem
+        } else if find_unquoted_colon(content).is_some() {
+            CstEntryKind::Mapping
+        } else {
+            CstEntryKind::Scalar
+        };
+        let mut entry = CstEntry {
+            kind,
+            line,
+            block_scalar: Vec::new(),
+            children: None,
+        };
+
+        if kind != CstEntryKind::Scalar {
+            let raw = match split_anchor(entry.raw_value())? {
+                Some((_, rest)) => rest,
+                None => entry.raw_value(),
+            };
+            if raw.is_empty() {
+                if let Some(next_indent) = next_content_indent(lines) {
+                    if next_indent > indent {
+                        entry.children = Some(parse_block(lines, next_indent, false, depth + 1)?);
+                    }
+                }
+            } else if let Some(header) = parse_block_scalar_header(raw)? {
+                entry.block_scalar = read_block_scalar(lines, indent, header);
+            }
+        }
+        block.items.push(CstItem::Entry(entry));
+    }
+    Ok(block)
+}
+
+/// Indentation of the next non-blank, non-comment line, without consuming anything.
+fn next_content_indent(lines: &Lines) -> Option<usize> {
+    lines
+        .clone()
+        .find(|line| !is_ignorable(&line.text))
+        .map(|line| line.indent())
+}
+
+/// Consumes block scalar lines using the same rules as `mini_yaml`.
+fn read_block_scalar(
+    lines: &mut Lines,
+    parent_indent: usize,
+    header: BlockScalarHeader,
+) -> Vec<CstLine> {
+    let mut content_indent = header.explicit_indent.map(|v| parent_indent + v);
+    let mut out = Vec::new();
+    while let Some(line) = lines.peek() {
+        if !line.is_blank() {
+            let indent = line.indent();
+            let effective = match content_indent {
+                Some(v) => v,
+                None if indent <= parent_indent => break,
+                None => {
+                    content_indent = Some(indent);
+                    indent
+                }
+            };
+            if indent < effective {
+                break;
+            }
+        }
+        out.push(lines.next().expect("peeked"));
+    }
+    out
+}
+
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Splits `$.a.b[0]` into segments.
+fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
+    let rest = path.strip_prefix('$')?;
+    let mut segments = Vec::new();
+    let mut chars = rest.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '.' => {
+                let start = i + 1;
+                let mut end = rest.len();
+                while let Some((j, c)) = chars.peek().copied() {
+                    if c == '.' || c == '[' {
+                        end = j;
+                        break;
+                    }
+                    chars.next();
+                }
+                let key = &rest[start..end];
+                if key.is_empty() {
+                    return None;
+                }
+                segments.push(PathSegment::Key(key.to_string()));
+            }
+            '[' => {
+                let start = i + 1;
+                let close = rest[start..].find(']')? + start;
+                segments.push(PathSegment::Index(rest[start..close].parse().ok()?));
+                while chars.peek().is_some_and(|(j, _)| *j <= close) {
+                    chars.next();
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::CstDocument;
+
+    const SOURCE: &str = "\n---!syaml/v0\n---meta\nfile:\n  owner: 'team'   # who\n---data\r\n# leading comment\nport^ <Port>: 8080\n\nservice:\n    hosts:\n      - a\n      -\n        name: b\n    script: |\n      echo hi\n\n    tail: end\nlast: 1";
+
+    #[test]
+    fn round_trips_source_exactly() {
+        let doc = CstDocument::parse(SOURCE).unwrap();
+        assert_eq!(doc.to_source(), SOURCE);
+        assert_eq!(doc.sections.len(), 2);
+    }
+
+    #[test]
+    fn lowers_sections_like_mini_yaml() {
+        let doc = CstDocument::parse(SOURCE).unwrap();
+        for section in &doc.sections {
+            let mut body = String::new();
+            section.body.write_to(&mut body);
+            assert_eq!(
+                section.to_json_value().unwrap(),
+                crate::mini_yaml::parse_document(&body).unwrap()
+            );
+        }
+        assert_eq!(
+            doc.section("data").unwrap().to_json_value().unwrap()["service"]["hosts"],
+            json!(["a", {"name": "b"}])
+        );
+    }
+
+    #[test]
+    fn finds_entries_by_data_path() {
+        let doc = CstDocument::parse(SOURCE).unwrap();
+        let data = doc.section("data").unwrap();
+        assert_eq!(data.entry("$.port").unwrap().value(), "8080");
+        assert_eq!(data.entry("$.service.hosts[0]").unwrap().value(), "a");
+        assert_eq!(data.entry("$.service.hosts[1].name").unwrap().value(), "b");
+        assert!(data.entry("$.missing").is_none());
+    }
+
+    #[test]
+    fn edits_preserve_surrounding_text() {
+        let mut doc = CstDocument::parse(SOURCE).unwrap();
+        let meta = doc.section_mut("meta").unwrap();
+        meta.entry_mut("$.file.owner")
+            .unwrap()
+            .set_value("\"platform\"");
+        let data = doc.section_mut("data").unwrap();
+        data.entry_mut("$.port").unwrap().set_key("port <Port>");
+        data.body.push_mapping("added", "true", 0);
+
+        let expected = SOURCE
+            .replace("owner: 'team'   # who", "owner: \"platform\" # who")
+            .replace("port^ <Port>:", "port <Port>:")
+            + "\nadded: true\n";
+        assert_eq!(doc.to_source(), expected);
+    }
+
+    #[test]
+    fn round_trips_and_lowers_bundled_examples() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut dirs = vec![root.join("examples"), root.join("library")];
+        let mut checked = 0;
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(&dir).unwrap().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().and_then(|e| e.to_str()) != Some("syaml") {
+                    continue;
+                }
+                let source = std::fs::read_to_string(&path).unwrap();
+                let doc = CstDocument::parse(&source).unwrap();
+                assert_eq!(doc.to_source(), source, "{}", path.display());
+                let (_, sections) = crate::section_scanner::scan_sections(&source).unwrap();
+                for (section, scanned) in doc.sections.iter().zip(&sections) {
+                    assert_eq!(
+                        section.to_json_value().unwrap(),
+                        crate::mini_yaml::parse_document(&scanned.body).unwrap(),
+                        "{} section {}",
+                        path.display(),
+                        section.name
+                    );
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn lowers_anchors_aliases_and_merge_keys() {
+        let source = "---!syaml/v0\n---data\nbase: &base # shared\n  retries: 3\nscript: &script |\n  echo hi\nservice:\n  <<: *base\n  run: *script\n  tags: [&t a, *t]\n";
+        let doc = CstDocument::parse(source).unwrap();
+        assert_eq!(doc.to_source(), source);
+        let data = doc.section("data").unwrap();
+        assert_eq!(data.entry("$.base").unwrap().anchor(), Some("base"));
+        let lowered = data.to_json_value().unwrap();
+        let (_, sections) = crate::section_scanner::scan_sections(source).unwrap();
+        assert_eq!(
+            lowered,
+            crate::mini_yaml::parse_document(&sections[0].body).unwrap()
+        );
+        assert_eq!(
+            lowered["service"],
+            json!({"retries": 3, "run": "echo hi\n", "tags": ["a", "a"]})
+        );
+    }
+
+    #[test]
+    fn reports_structural_errors() {
+        let err = CstDocument::parse("---!syaml/v0\n---data\na: 1\n   b: 2\n").unwrap_err();
+        assert!(err.to_string().contains("section 'data'"), "{err}");
+        let doc = CstDocument::parse("---!syaml/v0\n---data\na: 1\na: 2\n").unwrap();
+        assert!(doc.section("data").unwrap().to_json_value().is_err());
+    }
+}
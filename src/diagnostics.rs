@@ -0,0 +1,124 @@
+//! Structured diagnostics for machine-readable tool output.
+//!
+//! A [`Diagnostic`] is a flat, serializable record of one error or warning. The
+//! CLI emits these as JSON lines with `--message-format json`.
+//!
+//! [`Diagnostic`]: crate::diagnostics::Diagnostic
+
+use serde::Serialize;
+
+use crate::ast::Warning;
+use crate::error::SyamlError;
+use crate::source_map::SourceSpan;
+
+/// Code used for deprecation warnings from versioned schema fields.
+pub const DEPRECATED_FIELD_CODE: &str = "deprecated_field";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// Diagnostic severity.
+pub enum Severity {
+    /// Compilation failed.
+    Error,
+    /// Compilation succeeded but something should be looked at.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// One error or warning in a stable, serializable shape.
+pub struct Diagnostic {
+    /// Stable code, see [`SyamlError::code`] and [`DEPRECATED_FIELD_CODE`].
+    pub code: String,
+    /// Error or warning.
+    pub severity: Severity,
+    /// Human-readable message without location decoration.
+    pub message: String,
+    /// File the diagnostic refers to, when known.
+    pub file: Option<String>,
+    /// Line/column/byte range in `file`, when known. The span's own `file` is
+    /// moved to [`Diagnostic::file`].
+    pub span: Option<SourceSpan>,
+    /// Normalized data path (`$.a.b[0]`), when known.
+    pub data_path: Option<String>,
+}
+
+impl Diagnostic {
+    /// Builds one error diagnostic per failure in `err`.
+    pub fn from_error(err: &SyamlError) -> Vec<Diagnostic> {
+        err.errors()
+            .into_iter()
+            .map(|e| {
+                let mut span = e.span().cloned();
+                let file = span.as_mut().and_then(|s| s.file.take());
+                Diagnostic {
+                    code: e.code().to_string(),
+                    severity: Severity::Error,
+                    message: e.inner().to_string(),
+                    file,
+                    span,
+                    data_path: e.data_path().map(str::to_string),
+                }
+            })
+            .collect()
+    }
+
+    /// Builds a warning diagnostic for a `CompiledDocument::warnings` entry.
+    pub fn warning(warning: &Warning) -> Diagnostic {
+        let mut span = warning.span.clone();
+        let file = span.as_mut().and_then(|s| s.file.take());
+        Diagnostic {
+            code: DEPRECATED_FIELD_CODE.to_string(),
+            severity: Severity::Warning,
+            message: warning.message.clone(),
+            file,
+            span,
+            data_path: warning.data_path.clone(),
+        }
+    }
+
+    /// Serializes the diagnostic as a single-line JSON object.
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("diagnostic serialization cannot fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Severity};
+    use crate::error::SyamlError;
+    use crate::source_map::SourceSpan;
+
+    #[test]
+    fn error_diagnostics_carry_code_location_and_plain_message() {
+        let span = SourceSpan {
+            file: Some("a.syaml".to_string()),
+            line: 3,
+            column: 7,
+            start: 20,
+            end: 25,
+            sensitive: false,
+        };
+        let err = SyamlError::from_errors(vec![
+            SyamlError::ConstraintError("too small".to_string())
+                .at_path("$.port")
+                .with_span(span),
+            SyamlError::EnvError("missing".to_string()),
+        ])
+        .unwrap_err();
+
+        let diagnostics = Diagnostic::from_error(&err);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code, "constraint_error");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].message, "constraint error: too small");
+        assert_eq!(diagnostics[0].file.as_deref(), Some("a.syaml"));
+        assert_eq!(diagnostics[0].data_path.as_deref(), Some("$.port"));
+        assert!(diagnostics[0].span.as_ref().unwrap().file.is_none());
+        assert_eq!(diagnostics[1].code, "env_error");
+        assert!(diagnostics[1].span.is_none());
+
+        let line = diagnostics[0].to_json_line();
+        assert!(line.contains(r#""severity":"error""#), "{line}");
+        assert!(line.contains(r#""line":3"#), "{line}");
+    }
+}
@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::source_map::SourceSpan;
+
 #[derive(Debug, Error)]
 /// Top-level error type returned by public APIs.
 pub enum SyamlError {
@@ -59,6 +61,9 @@ pub enum SyamlError {
     /// Parse or validation error in the `---contracts` section.
     #[error("contracts error: {0}")]
     ContractsError(String),
+    /// Parse or validation error in the `---functions` section.
+    #[error("functions error: {0}")]
+    FunctionsError(String),
     /// Invalid mutability keyword usage in `---schema`.
     #[error("mutability error: {0}")]
     MutabilityError(String),
@@ -77,4 +82,339 @@ pub enum SyamlError {
     /// `@module_name` import attempted but no `syaml.toml` registry was found.
     #[error("no project registry: syaml.syaml not found; required for @module imports")]
     NoProjectRegistry,
+    /// The formatter could not reprint a document without changing its values.
+    #[error("format error: {0}")]
+    FormatError(String),
+    /// Invalid or rejected `--set` value override.
+    #[error("override error: {0}")]
+    OverrideError(String),
+    /// Invalid overlay document, missing profile or rejected overlay patch.
+    #[error("overlay error: {0}")]
+    OverlayError(String),
+    /// A data path asked about (for example by `explain`) is not in the compiled data.
+    #[error("path '{0}' not found in compiled data")]
+    PathNotFound(String),
+    /// Any other error annotated with the data path and/or source span it refers to.
+    ///
+    /// Displays exactly like the wrapped error; use [`SyamlError::location`] to read
+    /// the attached location.
+    #[error("{error}")]
+    Located {
+        error: Box<SyamlError>,
+        location: ErrorLocation,
+    },
+    /// Several independent failures found in one compilation, one per line.
+    ///
+    /// Built by [`SyamlError::from_errors`]; never empty and never nested.
+    #[error("{}", display_multiple(.0))]
+    Multiple(Vec<SyamlError>),
+}
+
+fn display_multiple(errors: &[SyamlError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Where in a document an error occurred.
+pub struct ErrorLocation {
+    /// Normalized data path (`$.a.b[0]`) the error refers to, if known.
+    pub data_path: Option<String>,
+    /// Source span of the offending key or value, if known.
+    pub span: Option<SourceSpan>,
+}
+
+impl SyamlError {
+    /// Combines collected errors: `Ok` when empty, the error itself when there is
+    /// one, otherwise [`SyamlError::Multiple`]. Nested `Multiple`s are flattened.
+    pub fn from_errors(errors: Vec<SyamlError>) -> Result<(), SyamlError> {
+        let mut flat = Vec::with_capacity(errors.len());
+        for error in errors {
+            match error {
+                SyamlError::Multiple(children) => flat.extend(children),
+                other => flat.push(other),
+            }
+        }
+        match flat.len() {
+            0 => Ok(()),
+            1 => Err(flat.pop().expect("one error")),
+            _ => Err(SyamlError::Multiple(flat)),
+        }
+    }
+
+    /// Stable machine-readable code for the error kind, e.g. `"constraint_error"`.
+    ///
+    /// Codes are part of the CLI's JSON diagnostics contract and do not change
+    /// when messages are reworded.
+    pub fn code(&self) -> &'static str {
+        match self {
+            SyamlError::MarkerError(_) => "marker_error",
+            SyamlError::SectionError(_) => "section_error",
+            SyamlError::YamlParseError { .. } => "yaml_parse_error",
+            SyamlError::SchemaError(_) => "schema_error",
+            SyamlError::TypeHintError(_) => "type_hint_error",
+            SyamlError::ExpressionError(_) => "expression_error",
+            SyamlError::ConstraintError(_) => "constraint_error",
+            SyamlError::EnvError(_) => "env_error",
+            SyamlError::CycleError(_) => "cycle_error",
+            SyamlError::ImportError(_) => "import_error",
+            SyamlError::TemplateError(_) => "template_error",
+            SyamlError::SerializationError(_) => "serialization_error",
+            SyamlError::HashError(_) => "hash_error",
+            SyamlError::SignatureError(_) => "signature_error",
+            SyamlError::VersionError(_) => "version_error",
+            SyamlError::VersionFieldError(_) => "version_field_error",
+            SyamlError::FetchError(_) => "fetch_error",
+            SyamlError::ContractsError(_) => "contracts_error",
+            SyamlError::FunctionsError(_) => "functions_error",
+            SyamlError::MutabilityError(_) => "mutability_error",
+            SyamlError::Io(_) => "io_error",
+            SyamlError::ModuleManifestError(_) => "module_manifest_error",
+            SyamlError::ImportPolicyViolation { .. } => "import_policy_violation",
+            SyamlError::ModuleNotFound(_) => "module_not_found",
+            SyamlError::NoProjectRegistry => "no_project_registry",
+            SyamlError::FormatError(_) => "format_error",
+            SyamlError::OverrideError(_) => "override_error",
+            SyamlError::OverlayError(_) => "overlay_error",
+            SyamlError::PathNotFound(_) => "path_not_found",
+            SyamlError::Located { error, .. } => error.code(),
+            SyamlError::Multiple(_) => "multiple_errors",
+        }
+    }
+
+    /// Returns every individual failure: the children of [`SyamlError::Multiple`],
+    /// or just `self`.
+    pub fn errors(&self) -> Vec<&SyamlError> {
+        match self {
+            SyamlError::Multiple(errors) => errors.iter().collect(),
+            other => vec![other],
+        }
+    }
+
+    /// Returns the underlying error, looking through any [`SyamlError::Located`] wrapper.
+    pub fn inner(&self) -> &SyamlError {
+        match self {
+            SyamlError::Located { error, .. } => error.inner(),
+            other => other,
+        }
+    }
+
+    /// Consumes the error and returns it without its location wrapper.
+    pub fn into_inner(self) -> SyamlError {
+        match self {
+            SyamlError::Located { error, .. } => error.into_inner(),
+            other => other,
+        }
+    }
+
+    /// Returns the attached location, if any.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            SyamlError::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Returns the attached data path, if any.
+    pub fn data_path(&self) -> Option<&str> {
+        self.location().and_then(|l| l.data_path.as_deref())
+    }
+
+    /// Returns the attached source span, if any.
+    pub fn span(&self) -> Option<&SourceSpan> {
+        self.location().and_then(|l| l.span.as_ref())
+    }
+
+    /// Annotates the error with the data path it refers to.
+    ///
+    /// An existing data path is kept, so the innermost (most specific) path wins.
+    pub fn at_path(self, path: impl Into<String>) -> SyamlError {
+        let path = path.into();
+        self.map_location(&|location| {
+            if location.data_path.is_none() {
+                location.data_path = Some(path.clone());
+            }
+        })
+    }
+
+    /// Annotates the error with a source span. An existing span is kept.
+    pub fn with_span(self, span: SourceSpan) -> SyamlError {
+        self.map_location(&|location| {
+            if location.span.is_none() {
+                location.span = Some(span.clone());
+            }
+        })
+    }
+
+    /// Sets the file on an attached span that does not name one yet.
+    pub fn with_file(self, file: impl Into<String>) -> SyamlError {
+        let file = file.into();
+        match self {
+            SyamlError::Located {
+                error,
+                mut location,
+            } => {
+                if let Some(span) = location.span.as_mut() {
+                    if span.file.is_none() {
+                        span.file = Some(file);
+                    }
+                }
+                SyamlError::Located { error, location }
+            }
+            SyamlError::Multiple(errors) => SyamlError::Multiple(
+                errors
+                    .into_iter()
+                    .map(|e| e.with_file(file.clone()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Copies the location of `self` onto `other`, used when an error is re-wrapped
+    /// into a different variant (for example an import failure).
+    pub fn relocate(&self, other: SyamlError) -> SyamlError {
+        match self.location() {
+            Some(location) => SyamlError::Located {
+                error: Box::new(other.into_inner()),
+                location: location.clone(),
+            },
+            None => other,
+        }
+    }
+
+    /// Rewrites the underlying error while keeping any attached location.
+    pub fn map_inner(self, f: impl FnOnce(SyamlError) -> SyamlError) -> SyamlError {
+        match self {
+            SyamlError::Located { error, location } => SyamlError::Located {
+                error: Box::new(error.map_inner(f)),
+                location,
+            },
+            other => f(other),
+        }
+    }
+
+    /// Rewrites the text of every message, keeping error kinds and locations.
+    pub(crate) fn map_messages(self, f: &dyn Fn(String) -> String) -> SyamlError {
+        match self {
+            SyamlError::MarkerError(m) => SyamlError::MarkerError(f(m)),
+            SyamlError::SectionError(m) => SyamlError::SectionError(f(m)),
+            SyamlError::SchemaError(m) => SyamlError::SchemaError(f(m)),
+            SyamlError::TypeHintError(m) => SyamlError::TypeHintError(f(m)),
+            SyamlError::ExpressionError(m) => SyamlError::ExpressionError(f(m)),
+            SyamlError::ConstraintError(m) => SyamlError::ConstraintError(f(m)),
+            SyamlError::EnvError(m) => SyamlError::EnvError(f(m)),
+            SyamlError::CycleError(m) => SyamlError::CycleError(f(m)),
+            SyamlError::ImportError(m) => SyamlError::ImportError(f(m)),
+            SyamlError::TemplateError(m) => SyamlError::TemplateError(f(m)),
+            SyamlError::SerializationError(m) => SyamlError::SerializationError(f(m)),
+            SyamlError::HashError(m) => SyamlError::HashError(f(m)),
+            SyamlError::SignatureError(m) => SyamlError::SignatureError(f(m)),
+            SyamlError::VersionError(m) => SyamlError::VersionError(f(m)),
+            SyamlError::VersionFieldError(m) => SyamlError::VersionFieldError(f(m)),
+            SyamlError::FetchError(m) => SyamlError::FetchError(f(m)),
+            SyamlError::ContractsError(m) => SyamlError::ContractsError(f(m)),
+            SyamlError::FunctionsError(m) => SyamlError::FunctionsError(f(m)),
+            SyamlError::MutabilityError(m) => SyamlError::MutabilityError(f(m)),
+            SyamlError::ModuleManifestError(m) => SyamlError::ModuleManifestError(f(m)),
+            SyamlError::ModuleNotFound(m) => SyamlError::ModuleNotFound(f(m)),
+            SyamlError::FormatError(m) => SyamlError::FormatError(f(m)),
+            SyamlError::OverrideError(m) => SyamlError::OverrideError(f(m)),
+            SyamlError::OverlayError(m) => SyamlError::OverlayError(f(m)),
+            SyamlError::YamlParseError { section, message } => SyamlError::YamlParseError {
+                section,
+                message: f(message),
+            },
+            SyamlError::ImportPolicyViolation { file, reason } => {
+                SyamlError::ImportPolicyViolation {
+                    file,
+                    reason: f(reason),
+                }
+            }
+            SyamlError::Located { error, location } => SyamlError::Located {
+                error: Box::new(error.map_messages(f)),
+                location,
+            },
+            SyamlError::Multiple(errors) => {
+                SyamlError::Multiple(errors.into_iter().map(|e| e.map_messages(f)).collect())
+            }
+            other @ (SyamlError::Io(_)
+            | SyamlError::NoProjectRegistry
+            | SyamlError::PathNotFound(_)) => other,
+        }
+    }
+
+    /// Marks the spans of located errors whose data path `is_sensitive`, so
+    /// snippets do not print the value.
+    pub(crate) fn mark_sensitive_spans(self, is_sensitive: &dyn Fn(&str) -> bool) -> SyamlError {
+        match self {
+            SyamlError::Located {
+                error,
+                mut location,
+            } => {
+                if let (Some(path), Some(span)) =
+                    (location.data_path.as_deref(), location.span.as_mut())
+                {
+                    span.sensitive |= is_sensitive(path);
+                }
+                SyamlError::Located { error, location }
+            }
+            SyamlError::Multiple(errors) => SyamlError::Multiple(
+                errors
+                    .into_iter()
+                    .map(|e| e.mark_sensitive_spans(is_sensitive))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Replaces the message of each located error whose data path
+    /// `is_sensitive`, since it may quote the value there.
+    pub(crate) fn hide_sensitive_messages(self, is_sensitive: &dyn Fn(&str) -> bool) -> SyamlError {
+        match self {
+            SyamlError::Located { error, location } => {
+                let error = match location.data_path.as_deref() {
+                    Some(path) if is_sensitive(path) => Box::new(error.map_messages(&|_| {
+                        format!("details hidden because the value at {path} is sensitive")
+                    })),
+                    _ => error,
+                };
+                SyamlError::Located { error, location }
+            }
+            SyamlError::Multiple(errors) => SyamlError::Multiple(
+                errors
+                    .into_iter()
+                    .map(|e| e.hide_sensitive_messages(is_sensitive))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    fn map_location(self, f: &dyn Fn(&mut ErrorLocation)) -> SyamlError {
+        match self {
+            SyamlError::Multiple(errors) => {
+                SyamlError::Multiple(errors.into_iter().map(|e| e.map_location(f)).collect())
+            }
+            SyamlError::Located {
+                error,
+                mut location,
+            } => {
+                f(&mut location);
+                SyamlError::Located { error, location }
+            }
+            other => {
+                let mut location = ErrorLocation::default();
+                f(&mut location);
+                SyamlError::Located {
+                    error: Box::new(other),
+                    location,
+                }
+            }
+        }
+    }
 }
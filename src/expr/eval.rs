@@ -1,14 +1,67 @@
 //! Runtime evaluator for expression AST values.
 
+use std::cmp::Ordering;
 use std::collections::HashSet;
 
 use serde_json::{Number as JsonNumber, Value as JsonValue};
 use std::collections::BTreeMap;
 
+use regex::RegexBuilder;
+
+use crate::ast::PureFunctionDef;
 use crate::error::SyamlError;
+use crate::quantity::{ByteSize, Duration, Quantity, Timestamp};
 
+use super::number::Number;
 use super::parser::{BinaryOp, Expr, UnaryOp};
 
+/// Upper bound on the length of strings built by string functions.
+const MAX_STRING_RESULT_LEN: usize = 1024 * 1024;
+/// Compiled size limit for `matches()` patterns.
+const MAX_REGEX_SIZE: usize = 1024 * 1024;
+
+/// Names of the built-in functions; user-defined functions may not reuse them.
+pub const BUILTIN_FUNCTIONS: [&str; 38] = [
+    "min",
+    "max",
+    "abs",
+    "floor",
+    "ceil",
+    "round",
+    "len",
+    "coalesce",
+    "keys",
+    "values",
+    "contains",
+    "duration",
+    "timestamp",
+    "bytesize",
+    "seconds",
+    "milliseconds",
+    "bytes",
+    "unix_seconds",
+    "lower",
+    "upper",
+    "trim",
+    "starts_with",
+    "ends_with",
+    "matches",
+    "replace",
+    "split",
+    "join",
+    "substr",
+    "pad_left",
+    "format",
+    "map",
+    "filter",
+    "all",
+    "any",
+    "sum",
+    "count",
+    "unique",
+    "sort",
+];
+
 #[derive(Debug)]
 /// Evaluation-time error classification.
 pub enum EvalError {
@@ -40,45 +93,260 @@ pub struct EvalContext<'a> {
     pub current_scope: Option<&'a JsonValue>,
     /// Extra named roots: "input" → input object, "output" → output value, "data" → data snapshot.
     pub named_scopes: BTreeMap<String, JsonValue>,
+    /// User-defined functions by call name (`alias.name` when imported).
+    pub functions: &'a BTreeMap<String, PureFunctionDef>,
+    /// Data paths (`$.a.b`) holding a typed `duration`, `timestamp` or
+    /// `bytesize` string, with its type name. Operators read them as that
+    /// quantity.
+    pub quantities: &'a BTreeMap<String, &'static str>,
+}
+
+/// Names bound by enclosing lambdas and `let` bindings, innermost first.
+struct Locals<'s> {
+    name: &'s str,
+    value: &'s JsonValue,
+    parent: Option<&'s Locals<'s>>,
+}
+
+impl Locals<'_> {
+    fn get(&self, name: &str) -> Option<&JsonValue> {
+        if self.name == name {
+            Some(self.value)
+        } else {
+            self.parent?.get(name)
+        }
+    }
 }
 
 /// Evaluates an expression AST node into a JSON value.
 pub fn evaluate(expr: &Expr, ctx: &EvalContext<'_>) -> Result<JsonValue, EvalError> {
+    eval_expr(expr, ctx, None)
+}
+
+fn eval_expr(
+    expr: &Expr,
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
     match expr {
-        Expr::Number(n) => number(*n),
+        Expr::Number(n) => Ok(n.to_json()?),
+        Expr::Quantity(q) => Ok(q.to_json()),
         Expr::String(s) => Ok(JsonValue::String(s.clone())),
         Expr::Bool(b) => Ok(JsonValue::Bool(*b)),
         Expr::Null => Ok(JsonValue::Null),
-        Expr::Var(path) => resolve_var(path, ctx),
-        Expr::Unary { op, expr } => {
-            let value = evaluate(expr, ctx)?;
-            match op {
-                UnaryOp::Neg => Ok(number(-as_f64(&value)?)?),
-                UnaryOp::Not => Ok(JsonValue::Bool(!as_bool(&value)?)),
+        Expr::Var(path) => resolve_var(path, &[], ctx, locals),
+        Expr::Unary {
+            op: UnaryOp::Not,
+            expr,
+        } => Ok(JsonValue::Bool(!as_bool(&eval_expr(expr, ctx, locals)?)?)),
+        Expr::Unary { .. } | Expr::Binary { .. } => eval_operand(expr, ctx, locals)?.into_json(),
+        Expr::Call { name, args } => eval_call(name, args, ctx, locals),
+        Expr::Index {
+            target,
+            index,
+            optional,
+        } => {
+            // Null-safe chains resolve as one path, so `cfg?.port` inside
+            // `cfg` does not wait on `cfg` itself.
+            if let Some((path, flags)) = expr.null_safe_path() {
+                return resolve_var(&path, &flags, ctx, locals);
+            }
+            let target = eval_expr(target, ctx, locals)?;
+            if *optional && target.is_null() {
+                return Ok(JsonValue::Null);
+            }
+            let index = eval_expr(index, ctx, locals)?;
+            match index_value(&target, &index) {
+                Err(_) if *optional && is_missing(&target, &index) => Ok(JsonValue::Null),
+                result => result,
+            }
+        }
+        Expr::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => {
+            let cond = eval_expr(cond, ctx, locals)?;
+            if as_bool(&cond)? {
+                eval_expr(then_branch, ctx, locals)
+            } else {
+                eval_expr(else_branch, ctx, locals)
             }
         }
+        Expr::Let { name, value, body } => {
+            let value = eval_expr(value, ctx, locals)?;
+            let scope = Locals {
+                name,
+                value: &value,
+                parent: locals,
+            };
+            eval_expr(body, ctx, Some(&scope))
+        }
+        Expr::Lambda { .. } => Err(SyamlError::ExpressionError(
+            "lambda expressions are only allowed as arguments to collection functions".to_string(),
+        )
+        .into()),
+    }
+}
+
+/// Intermediate result of an operator. Arithmetic keeps its [`Number`] until
+/// a JSON value is needed, so `i128` intermediates that would not fit in the
+/// output survive nested operations. Duration, timestamp and byte-size
+/// literals and constructor calls stay typed, so `"1h" + "1h"` concatenates
+/// while `1h + 1h` adds.
+enum Operand {
+    Number(Number),
+    Quantity(Quantity),
+    Value(JsonValue),
+}
+
+impl Operand {
+    fn into_json(self) -> Result<JsonValue, EvalError> {
+        match self {
+            Operand::Number(n) => Ok(n.to_json()?),
+            Operand::Quantity(q) => Ok(q.to_json()),
+            Operand::Value(v) => Ok(v),
+        }
+    }
+
+    fn number(&self) -> Result<Number, EvalError> {
+        match self {
+            Operand::Number(n) => Ok(*n),
+            Operand::Quantity(q) => Err(SyamlError::ExpressionError(format!(
+                "expected number, got {}",
+                q.type_name()
+            ))
+            .into()),
+            Operand::Value(v) => as_number(v),
+        }
+    }
+
+    /// Typed quantity, or a string that spells one.
+    fn quantity(&self) -> Option<Quantity> {
+        match self {
+            Operand::Quantity(q) => Some(*q),
+            Operand::Value(JsonValue::String(s)) => Quantity::parse(s),
+            _ => None,
+        }
+    }
+
+    fn scalar(&self) -> Result<Scalar, EvalError> {
+        match self {
+            Operand::Number(n) => Ok(Scalar::Number(*n)),
+            Operand::Quantity(q) => Ok(Scalar::Quantity(*q)),
+            Operand::Value(v) => scalar_value(v),
+        }
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(self, Operand::Value(JsonValue::String(_)))
+    }
+}
+
+fn eval_operand(
+    expr: &Expr,
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<Operand, EvalError> {
+    match expr {
+        Expr::Number(n) => Ok(Operand::Number(*n)),
+        Expr::Quantity(q) => Ok(Operand::Quantity(*q)),
+        Expr::Call { name, .. } if QUANTITY_CONSTRUCTORS.contains(&name.as_str()) => {
+            let value = eval_expr(expr, ctx, locals)?;
+            Ok(Operand::Quantity(quantity_arg(name, name, &value, 0)?))
+        }
+        Expr::Var(path) => {
+            let value = eval_expr(expr, ctx, locals)?;
+            let typed = data_quantity_kind(path, ctx, locals)
+                .and_then(|kind| Quantity::from_json_as(kind, &value))
+                .and_then(Result::ok);
+            Ok(typed.map_or(Operand::Value(value), Operand::Quantity))
+        }
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            expr,
+        } => match eval_operand(expr, ctx, locals)? {
+            Operand::Quantity(Quantity::Duration(d)) => {
+                let nanos = d.as_nanos().checked_neg().ok_or_else(|| {
+                    SyamlError::ExpressionError("duration overflow in '-'".to_string())
+                })?;
+                Ok(Operand::Quantity(Quantity::Duration(Duration::from_nanos(
+                    nanos,
+                ))))
+            }
+            operand => Ok(Operand::Number(operand.number()?.checked_neg()?)),
+        },
+        // `??` only evaluates its right operand when the left one is null.
+        Expr::Binary {
+            op: BinaryOp::Coalesce,
+            left,
+            right,
+        } => match eval_operand(left, ctx, locals)? {
+            Operand::Value(JsonValue::Null) => eval_operand(right, ctx, locals),
+            left => Ok(left),
+        },
         Expr::Binary { op, left, right } => {
-            let l = evaluate(left, ctx)?;
-            let r = evaluate(right, ctx)?;
+            let l = eval_operand(left, ctx, locals)?;
+            let r = eval_operand(right, ctx, locals)?;
             eval_binary(*op, l, r)
         }
-        Expr::Call { name, args } => eval_call(name, args, ctx),
+        _ => Ok(Operand::Value(eval_expr(expr, ctx, locals)?)),
+    }
+}
+
+/// Quantity type of the data value `path` reads, when it names a typed
+/// `duration`, `timestamp` or `bytesize` in [`EvalContext::quantities`]
+/// rather than a local, scope, env, `value` or import binding.
+fn data_quantity_kind(
+    path: &[String],
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Option<&'static str> {
+    let head = path.first()?;
+    let shadowed = locals.is_some_and(|l| l.get(head).is_some())
+        || ctx.named_scopes.contains_key(head)
+        || head == "env"
+        || head == "value"
+        || ctx.imports.contains_key(head);
+    if shadowed {
+        return None;
     }
+    ctx.quantities
+        .get(&format!("$.{}", path.join(".")))
+        .copied()
 }
 
-fn resolve_var(path: &[String], ctx: &EvalContext<'_>) -> Result<JsonValue, EvalError> {
+/// Resolves a variable path. `optional[i]` marks segment `i` as null-safe
+/// (`a?.b`): the path yields `null` when the value before it is `null` or lacks
+/// it. An empty `optional` makes every segment required.
+fn resolve_var(
+    path: &[String],
+    optional: &[bool],
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
     if path.is_empty() {
         return Err(SyamlError::ExpressionError("empty variable path".to_string()).into());
     }
+    let rest_optional = optional.get(1..).unwrap_or(&[]);
+    let not_found = || {
+        EvalError::Fatal(SyamlError::ExpressionError(format!(
+            "path '{}' not found",
+            path.join(".")
+        )))
+    };
+
+    if let Some(bound) = locals.and_then(|l| l.get(&path[0])) {
+        return lookup_path(bound, &path[1..], rest_optional)
+            .cloned()
+            .ok_or_else(not_found);
+    }
 
     // Named scopes: input.*, output.*, data.*, etc.
     if let Some(root) = ctx.named_scopes.get(&path[0]) {
-        if path.len() == 1 {
-            return Ok(root.clone());
-        }
-        return lookup_path(root, &path[1..])
+        return lookup_path(root, &path[1..], rest_optional)
             .cloned()
-            .ok_or_else(|| EvalError::Unresolved(path.join(".")));
+            .ok_or_else(not_found);
     }
 
     if path[0] == "env" {
@@ -103,154 +371,397 @@ fn resolve_var(path: &[String], ctx: &EvalContext<'_>) -> Result<JsonValue, Eval
                 "'value' is only available in constraint expressions".to_string(),
             ))
         })?;
-        return if path.len() == 1 {
-            Ok(base.clone())
-        } else {
-            lookup_path(base, &path[1..]).cloned().ok_or_else(|| {
+        return lookup_path(base, &path[1..], rest_optional)
+            .cloned()
+            .ok_or_else(|| {
                 EvalError::Fatal(SyamlError::ExpressionError(format!(
                     "path '{}' not found under value",
                     path.join(".")
                 )))
-            })
-        };
+            });
     }
 
     if let Some(import_root) = ctx.imports.get(&path[0]) {
-        return if path.len() == 1 {
-            Ok(import_root.clone())
-        } else {
-            lookup_path(import_root, &path[1..])
-                .cloned()
-                .ok_or_else(|| {
-                    EvalError::Fatal(SyamlError::ExpressionError(format!(
-                        "unknown reference '{}'",
-                        path.join(".")
-                    )))
-                })
-        };
+        return lookup_path(import_root, &path[1..], rest_optional)
+            .cloned()
+            .ok_or_else(|| {
+                EvalError::Fatal(SyamlError::ExpressionError(format!(
+                    "unknown reference '{}'",
+                    path.join(".")
+                )))
+            });
     }
 
     let full_path = format!("$.{}", path.join("."));
-    if ctx.unresolved_paths.contains(&full_path) {
+    if is_pending(ctx.unresolved_paths, &full_path) {
         return Err(EvalError::Unresolved(full_path));
     }
 
-    if let Some(found) = lookup_path(ctx.data, path) {
-        return Ok(found.clone());
-    }
-
-    if let Some(scope) = ctx.current_scope {
-        if let Some(found) = lookup_path(scope, path) {
+    let roots = [Some(ctx.data), ctx.current_scope, ctx.current_value];
+    for root in roots.into_iter().flatten() {
+        if let Some(found) = lookup_path(root, path, optional) {
             return Ok(found.clone());
         }
     }
 
-    if let Some(current) = ctx.current_value {
-        if let Some(found) = lookup_path(current, path) {
-            return Ok(found.clone());
-        }
+    // A known head with a missing field is a lookup miss, not a bad name.
+    let head_found = roots
+        .into_iter()
+        .flatten()
+        .any(|root| lookup_path(root, &path[..1], &[]).is_some());
+    if path.len() > 1 && head_found {
+        return Err(not_found());
     }
-
     Err(EvalError::Fatal(SyamlError::ExpressionError(format!(
         "unknown reference '{}'",
         path.join(".")
     ))))
 }
 
-fn lookup_path<'a>(root: &'a JsonValue, path: &[String]) -> Option<&'a JsonValue> {
+/// Returns `true` when `path`, one of its ancestors, or one of its descendants
+/// is still waiting to be resolved.
+fn is_pending(unresolved: &HashSet<String>, path: &str) -> bool {
+    if unresolved.is_empty() {
+        return false;
+    }
+    if unresolved.contains(path) {
+        return true;
+    }
+    let ancestor_pending = path
+        .char_indices()
+        .filter(|(_, c)| *c == '.')
+        .skip(1)
+        .any(|(i, _)| unresolved.contains(&path[..i]));
+    ancestor_pending
+        || unresolved.iter().any(|p| {
+            p.strip_prefix(path)
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with('['))
+        })
+}
+
+/// Whether `target[index]` fails only because the key or index is absent.
+fn is_missing(target: &JsonValue, index: &JsonValue) -> bool {
+    match (target, index) {
+        (JsonValue::Object(map), JsonValue::String(key)) => !map.contains_key(key),
+        (JsonValue::Array(items), JsonValue::Number(n)) => n
+            .as_u64()
+            .is_some_and(|i| usize::try_from(i).map_or(true, |i| i >= items.len())),
+        _ => false,
+    }
+}
+
+fn index_value(target: &JsonValue, index: &JsonValue) -> Result<JsonValue, EvalError> {
+    let error = |message: String| EvalError::Fatal(SyamlError::ExpressionError(message));
+    match (target, index) {
+        (JsonValue::Array(items), JsonValue::Number(n)) => {
+            let i = n.as_u64().ok_or_else(|| {
+                error(format!(
+                    "array index must be a non-negative integer, got {n}"
+                ))
+            })?;
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .cloned()
+                .ok_or_else(|| {
+                    error(format!(
+                        "index {i} out of bounds for array of length {}",
+                        items.len()
+                    ))
+                })
+        }
+        (JsonValue::Object(map), JsonValue::String(key)) => map
+            .get(key)
+            .cloned()
+            .ok_or_else(|| error(format!("key '{key}' not found"))),
+        (JsonValue::Array(_), other) => Err(error(format!(
+            "array index must be a number, got {}",
+            json_type_name(other)
+        ))),
+        (JsonValue::Object(_), other) => Err(error(format!(
+            "object key must be a string, got {}",
+            json_type_name(other)
+        ))),
+        (other, _) => Err(error(format!(
+            "cannot index into {}",
+            json_type_name(other)
+        ))),
+    }
+}
+
+/// Walks `path` below `root`; segments flagged in `optional` turn a `null`
+/// parent or a missing key into `null` instead of a miss.
+fn lookup_path<'a>(
+    root: &'a JsonValue,
+    path: &[String],
+    optional: &[bool],
+) -> Option<&'a JsonValue> {
     let mut cur = root;
-    for segment in path {
-        cur = cur.as_object()?.get(segment)?;
+    for (i, segment) in path.iter().enumerate() {
+        let null_safe = optional.get(i).copied().unwrap_or(false);
+        if null_safe && cur.is_null() {
+            return Some(&JsonValue::Null);
+        }
+        cur = match cur.as_object().and_then(|map| map.get(segment)) {
+            Some(next) => next,
+            None if null_safe && cur.is_object() => return Some(&JsonValue::Null),
+            None => return None,
+        };
     }
     Some(cur)
 }
 
-fn eval_binary(op: BinaryOp, left: JsonValue, right: JsonValue) -> Result<JsonValue, EvalError> {
+fn eval_binary(op: BinaryOp, left: Operand, right: Operand) -> Result<Operand, EvalError> {
+    if uses_quantities(op, &left, &right) {
+        return eval_quantity_binary(op, left.scalar()?, right.scalar()?);
+    }
+    let number = |n: Number| Ok(Operand::Number(n));
+    let bool = |b: bool| Ok(Operand::Value(JsonValue::Bool(b)));
     match op {
         BinaryOp::Add => {
             if left.is_string() || right.is_string() {
-                Ok(JsonValue::String(format!(
+                Ok(Operand::Value(JsonValue::String(format!(
                     "{}{}",
-                    json_to_string(&left),
-                    json_to_string(&right)
-                )))
+                    json_to_string(&left.into_json()?),
+                    json_to_string(&right.into_json()?)
+                ))))
             } else {
-                Ok(number(as_f64(&left)? + as_f64(&right)?)?)
+                number(left.number()?.checked_add(right.number()?)?)
             }
         }
-        BinaryOp::Sub => Ok(number(as_f64(&left)? - as_f64(&right)?)?),
-        BinaryOp::Mul => Ok(number(as_f64(&left)? * as_f64(&right)?)?),
-        BinaryOp::Div => {
-            let rhs = as_f64(&right)?;
-            if rhs == 0.0 {
-                return Err(SyamlError::ExpressionError("division by zero".to_string()).into());
-            }
-            Ok(number(as_f64(&left)? / rhs)?)
+        BinaryOp::Sub => number(left.number()?.checked_sub(right.number()?)?),
+        BinaryOp::Mul => number(left.number()?.checked_mul(right.number()?)?),
+        BinaryOp::Div => number(left.number()?.checked_div(right.number()?)?),
+        BinaryOp::Mod => number(left.number()?.checked_rem(right.number()?)?),
+        BinaryOp::Eq => bool(operands_equal(left, right)?),
+        BinaryOp::NotEq => bool(!operands_equal(left, right)?),
+        BinaryOp::Lt => bool(compare(&left, &right)?.is_some_and(Ordering::is_lt)),
+        BinaryOp::Lte => bool(compare(&left, &right)?.is_some_and(Ordering::is_le)),
+        BinaryOp::Gt => bool(compare(&left, &right)?.is_some_and(Ordering::is_gt)),
+        BinaryOp::Gte => bool(compare(&left, &right)?.is_some_and(Ordering::is_ge)),
+        BinaryOp::And => bool(as_bool(&left.into_json()?)? && as_bool(&right.into_json()?)?),
+        BinaryOp::Or => bool(as_bool(&left.into_json()?)? || as_bool(&right.into_json()?)?),
+        BinaryOp::Coalesce => Ok(match left {
+            Operand::Value(JsonValue::Null) => right,
+            left => left,
+        }),
+    }
+}
+
+/// Numbers compare by value (`0.1 + 0.2 == 0.3`, `2 == 2.0`); everything
+/// else compares as JSON.
+fn operands_equal(left: Operand, right: Operand) -> Result<bool, EvalError> {
+    let is_number =
+        |o: &Operand| matches!(o, Operand::Number(_) | Operand::Value(JsonValue::Number(_)));
+    if is_number(&left) && is_number(&right) {
+        return Ok(left.number()?.compare(right.number()?) == Some(Ordering::Equal));
+    }
+    Ok(left.into_json()? == right.into_json()?)
+}
+
+fn compare(left: &Operand, right: &Operand) -> Result<Option<Ordering>, EvalError> {
+    Ok(left.number()?.compare(right.number()?))
+}
+
+/// Function names that build a typed duration, timestamp or byte size.
+const QUANTITY_CONSTRUCTORS: [&str; 3] = ["duration", "timestamp", "bytesize"];
+
+/// Number or quantity operand of quantity arithmetic and comparisons.
+#[derive(Clone, Copy)]
+enum Scalar {
+    Number(Number),
+    Quantity(Quantity),
+}
+
+impl Scalar {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Scalar::Number(_) => "number",
+            Scalar::Quantity(q) => q.type_name(),
         }
-        BinaryOp::Mod => {
-            let l = as_i64(&left)?;
-            let r = as_i64(&right)?;
-            if r == 0 {
-                return Err(SyamlError::ExpressionError("modulo by zero".to_string()).into());
-            }
-            Ok(JsonValue::Number(JsonNumber::from(l % r)))
+    }
+
+    fn compare(&self, other: &Scalar) -> Option<Ordering> {
+        match (self, other) {
+            (Scalar::Number(a), Scalar::Number(b)) => a.compare(*b),
+            (Scalar::Quantity(a), Scalar::Quantity(b)) => a.compare(b),
+            _ => None,
+        }
+    }
+
+    fn into_json(self) -> Result<JsonValue, EvalError> {
+        match self {
+            Scalar::Number(n) => Ok(n.to_json()?),
+            Scalar::Quantity(q) => Ok(q.to_json()),
         }
-        BinaryOp::Eq => Ok(JsonValue::Bool(left == right)),
-        BinaryOp::NotEq => Ok(JsonValue::Bool(left != right)),
-        BinaryOp::Lt => compare(left, right, |a, b| a < b),
-        BinaryOp::Lte => compare(left, right, |a, b| a <= b),
-        BinaryOp::Gt => compare(left, right, |a, b| a > b),
-        BinaryOp::Gte => compare(left, right, |a, b| a >= b),
-        BinaryOp::And => Ok(JsonValue::Bool(as_bool(&left)? && as_bool(&right)?)),
-        BinaryOp::Or => Ok(JsonValue::Bool(as_bool(&left)? || as_bool(&right)?)),
     }
 }
 
-fn compare<F>(left: JsonValue, right: JsonValue, cmp: F) -> Result<JsonValue, EvalError>
-where
-    F: Fn(f64, f64) -> bool,
-{
-    Ok(JsonValue::Bool(cmp(as_f64(&left)?, as_f64(&right)?)))
+/// Whether `op` works on durations, timestamps or byte sizes: always when an
+/// operand is a typed quantity, and for operators other than `+`, `==` and
+/// `!=` also when an operand is a string spelling one (such as a data value
+/// `"30s"`).
+fn uses_quantities(op: BinaryOp, left: &Operand, right: &Operand) -> bool {
+    if matches!(op, BinaryOp::And | BinaryOp::Or | BinaryOp::Coalesce) {
+        return false;
+    }
+    let typed = |o: &Operand| matches!(o, Operand::Quantity(_));
+    if typed(left) || typed(right) {
+        return true;
+    }
+    !matches!(op, BinaryOp::Add | BinaryOp::Eq | BinaryOp::NotEq)
+        && (left.quantity().is_some() || right.quantity().is_some())
 }
 
-fn eval_call(name: &str, args: &[Expr], ctx: &EvalContext<'_>) -> Result<JsonValue, EvalError> {
+fn eval_quantity_binary(op: BinaryOp, left: Scalar, right: Scalar) -> Result<Operand, EvalError> {
+    use Quantity::{ByteSize as B, Duration as D, Timestamp as T};
+
+    let mismatch = || {
+        EvalError::Fatal(SyamlError::ExpressionError(format!(
+            "cannot apply '{}' to {} and {}",
+            op.symbol(),
+            left.type_name(),
+            right.type_name()
+        )))
+    };
+    let overflow = || {
+        EvalError::Fatal(SyamlError::ExpressionError(format!(
+            "{} overflow in '{}'",
+            left.type_name(),
+            op.symbol()
+        )))
+    };
+    let duration = |nanos: Option<i128>| {
+        Ok(Operand::Quantity(D(Duration::from_nanos(
+            nanos.ok_or_else(overflow)?,
+        ))))
+    };
+    let bytes = |n: Option<i128>| {
+        Ok(Operand::Quantity(B(ByteSize::from_bytes(
+            n.ok_or_else(overflow)?,
+        ))))
+    };
+    let timestamp = |nanos: Option<i128>| {
+        let timestamp = Timestamp::from_unix_nanos(nanos.ok_or_else(overflow)?);
+        if !timestamp.is_in_range() {
+            return Err(EvalError::Fatal(SyamlError::ExpressionError(format!(
+                "timestamp out of range in '{}': UTC years run from 0000 to 9999",
+                op.symbol()
+            ))));
+        }
+        Ok(Operand::Quantity(T(timestamp)))
+    };
+    let ordering = || left.compare(&right).ok_or_else(mismatch);
+    let bool = |b: bool| Ok(Operand::Value(JsonValue::Bool(b)));
+
+    match (op, left, right) {
+        (BinaryOp::Eq, ..) => bool(left.compare(&right) == Some(Ordering::Equal)),
+        (BinaryOp::NotEq, ..) => bool(left.compare(&right) != Some(Ordering::Equal)),
+        (BinaryOp::Lt, ..) => bool(ordering()?.is_lt()),
+        (BinaryOp::Lte, ..) => bool(ordering()?.is_le()),
+        (BinaryOp::Gt, ..) => bool(ordering()?.is_gt()),
+        (BinaryOp::Gte, ..) => bool(ordering()?.is_ge()),
+        (op, Scalar::Quantity(a), Scalar::Quantity(b)) => match (op, a, b) {
+            (BinaryOp::Add, D(a), D(b)) => duration(a.as_nanos().checked_add(b.as_nanos())),
+            (BinaryOp::Sub, D(a), D(b)) => duration(a.as_nanos().checked_sub(b.as_nanos())),
+            (BinaryOp::Mod, D(a), D(b)) => duration(a.as_nanos().checked_rem(b.as_nanos())),
+            (BinaryOp::Add, T(t), D(d)) | (BinaryOp::Add, D(d), T(t)) => {
+                timestamp(t.unix_nanos().checked_add(d.as_nanos()))
+            }
+            (BinaryOp::Sub, T(t), D(d)) => timestamp(t.unix_nanos().checked_sub(d.as_nanos())),
+            (BinaryOp::Sub, T(a), T(b)) => duration(a.unix_nanos().checked_sub(b.unix_nanos())),
+            (BinaryOp::Add, B(a), B(b)) => bytes(a.bytes().checked_add(b.bytes())),
+            (BinaryOp::Sub, B(a), B(b)) => bytes(a.bytes().checked_sub(b.bytes())),
+            (BinaryOp::Mod, B(a), B(b)) => bytes(a.bytes().checked_rem(b.bytes())),
+            (BinaryOp::Div, D(a), D(b)) => Ok(Operand::Number(
+                Number::Int(a.as_nanos()).checked_div(Number::Int(b.as_nanos()))?,
+            )),
+            (BinaryOp::Div, B(a), B(b)) => Ok(Operand::Number(
+                Number::Int(a.bytes()).checked_div(Number::Int(b.bytes()))?,
+            )),
+            _ => Err(mismatch()),
+        },
+        (BinaryOp::Mul, Scalar::Quantity(q), Scalar::Number(n))
+        | (BinaryOp::Mul, Scalar::Number(n), Scalar::Quantity(q)) => match q {
+            D(d) => duration(scale(d.as_nanos(), n, Number::checked_mul)?),
+            B(b) => bytes(scale(b.bytes(), n, Number::checked_mul)?),
+            T(_) => Err(mismatch()),
+        },
+        (BinaryOp::Div, Scalar::Quantity(q), Scalar::Number(n)) => match q {
+            D(d) => duration(scale(d.as_nanos(), n, Number::checked_div)?),
+            B(b) => bytes(scale(b.bytes(), n, Number::checked_div)?),
+            T(_) => Err(mismatch()),
+        },
+        _ => Err(mismatch()),
+    }
+}
+
+/// Applies `op(amount, factor)` and rounds to a whole nanosecond or byte.
+fn scale(
+    amount: i128,
+    factor: Number,
+    op: fn(Number, Number) -> Result<Number, SyamlError>,
+) -> Result<Option<i128>, EvalError> {
+    Ok(op(Number::Int(amount), factor)?.round().as_i128())
+}
+
+fn eval_call(
+    name: &str,
+    args: &[Expr],
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
+    if let Some(function) = ctx.functions.get(name) {
+        return eval_user_call(name, function, args, ctx, locals);
+    }
+    if let Some(result) = eval_higher_order_call(name, args, ctx, locals) {
+        return result;
+    }
+
     let mut evaluated = Vec::with_capacity(args.len());
     for arg in args {
-        evaluated.push(evaluate(arg, ctx)?);
+        evaluated.push(eval_expr(arg, ctx, locals)?);
     }
 
     match name {
-        "min" => {
-            require_arity_at_least(name, &evaluated, 1)?;
-            let mut m = f64::INFINITY;
-            for value in &evaluated {
-                m = m.min(as_f64(value)?);
-            }
-            number(m)
-        }
-        "max" => {
+        "min" | "max" => {
             require_arity_at_least(name, &evaluated, 1)?;
-            let mut m = f64::NEG_INFINITY;
-            for value in &evaluated {
-                m = m.max(as_f64(value)?);
+            let wanted = if name == "min" {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            let mut best = scalar_value(&evaluated[0])?;
+            for value in &evaluated[1..] {
+                let candidate = scalar_value(value)?;
+                let ordering = candidate.compare(&best).ok_or_else(|| {
+                    SyamlError::ExpressionError(format!(
+                        "{name}() cannot compare {} and {}",
+                        best.type_name(),
+                        candidate.type_name()
+                    ))
+                })?;
+                if ordering == wanted {
+                    best = candidate;
+                }
             }
-            number(m)
+            best.into_json()
         }
         "abs" => {
             require_arity(name, &evaluated, 1)?;
-            number(as_f64(&evaluated[0])?.abs())
+            Ok(as_number(&evaluated[0])?.checked_abs()?.to_json()?)
         }
         "floor" => {
             require_arity(name, &evaluated, 1)?;
-            number(as_f64(&evaluated[0])?.floor())
+            Ok(as_number(&evaluated[0])?.floor().to_json()?)
         }
         "ceil" => {
             require_arity(name, &evaluated, 1)?;
-            number(as_f64(&evaluated[0])?.ceil())
+            Ok(as_number(&evaluated[0])?.ceil().to_json()?)
         }
         "round" => {
             require_arity(name, &evaluated, 1)?;
-            number(as_f64(&evaluated[0])?.round())
+            Ok(as_number(&evaluated[0])?.round().to_json()?)
         }
         "len" => {
             require_arity(name, &evaluated, 1)?;
@@ -276,10 +787,582 @@ fn eval_call(name: &str, args: &[Expr], ctx: &EvalContext<'_>) -> Result<JsonVal
             }
             Ok(JsonValue::Null)
         }
-        _ => Err(SyamlError::ExpressionError(format!("unknown function '{name}'")).into()),
+        "keys" => {
+            require_arity(name, &evaluated, 1)?;
+            let map = evaluated[0]
+                .as_object()
+                .ok_or_else(|| arg_type_error(name, 0, "an object", &evaluated[0]))?;
+            Ok(JsonValue::Array(
+                map.keys().map(|k| JsonValue::String(k.clone())).collect(),
+            ))
+        }
+        "values" => {
+            require_arity(name, &evaluated, 1)?;
+            let map = evaluated[0]
+                .as_object()
+                .ok_or_else(|| arg_type_error(name, 0, "an object", &evaluated[0]))?;
+            Ok(JsonValue::Array(map.values().cloned().collect()))
+        }
+        "contains" if !evaluated.first().is_some_and(JsonValue::is_string) => {
+            require_arity(name, &evaluated, 2)?;
+            match &evaluated[0] {
+                JsonValue::Array(items) => Ok(JsonValue::Bool(items.contains(&evaluated[1]))),
+                JsonValue::Object(map) => {
+                    let key = string_arg(name, &evaluated, 1)?;
+                    Ok(JsonValue::Bool(map.contains_key(key)))
+                }
+                other => Err(arg_type_error(name, 0, "a string, array, or object", other)),
+            }
+        }
+        _ if QUANTITY_CONSTRUCTORS.contains(&name) => {
+            require_arity(name, &evaluated, 1)?;
+            Ok(quantity_arg(name, name, &evaluated[0], 0)?.to_json())
+        }
+        "seconds" | "milliseconds" => {
+            require_arity(name, &evaluated, 1)?;
+            let Quantity::Duration(d) = quantity_arg(name, "duration", &evaluated[0], 0)? else {
+                unreachable!("quantity_arg returns the requested type")
+            };
+            let per_unit = if name == "seconds" {
+                1_000_000_000
+            } else {
+                1_000_000
+            };
+            Ok(Number::Int(d.as_nanos())
+                .checked_div(Number::Int(per_unit))?
+                .to_json()?)
+        }
+        "bytes" => {
+            require_arity(name, &evaluated, 1)?;
+            let Quantity::ByteSize(b) = quantity_arg(name, "bytesize", &evaluated[0], 0)? else {
+                unreachable!("quantity_arg returns the requested type")
+            };
+            Ok(Number::Int(b.bytes()).to_json()?)
+        }
+        "unix_seconds" => {
+            require_arity(name, &evaluated, 1)?;
+            let Quantity::Timestamp(t) = quantity_arg(name, "timestamp", &evaluated[0], 0)? else {
+                unreachable!("quantity_arg returns the requested type")
+            };
+            Ok(Number::Int(t.unix_nanos())
+                .checked_div(Number::Int(1_000_000_000))?
+                .to_json()?)
+        }
+        _ => eval_string_call(name, &evaluated).unwrap_or_else(|| {
+            Err(SyamlError::ExpressionError(format!("unknown function '{name}'")).into())
+        }),
     }
 }
 
+/// Calls a user-defined function. The body sees only its parameters (and
+/// other functions), never data, imports or environment bindings.
+fn eval_user_call(
+    name: &str,
+    function: &PureFunctionDef,
+    args: &[Expr],
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
+    if args.len() != function.params.len() {
+        return Err(SyamlError::ExpressionError(format!(
+            "{name} expects {} arguments, got {}",
+            function.params.len(),
+            args.len()
+        ))
+        .into());
+    }
+    let mut values = Vec::with_capacity(args.len());
+    for arg in args {
+        values.push(eval_expr(arg, ctx, locals)?);
+    }
+
+    let body = super::parse_expression(&function.body)?;
+    let no_values = BTreeMap::new();
+    let nothing_pending = HashSet::new();
+    let body_ctx = EvalContext {
+        data: &JsonValue::Null,
+        imports: &no_values,
+        env: &no_values,
+        unresolved_paths: &nothing_pending,
+        current_value: None,
+        current_scope: None,
+        named_scopes: BTreeMap::new(),
+        functions: ctx.functions,
+        quantities: &BTreeMap::new(),
+    };
+    let names: Vec<&str> = function.params.iter().map(|p| p.name.as_str()).collect();
+    eval_with_params(&body, &body_ctx, &names, &values, None).map_err(|e| match e {
+        EvalError::Fatal(SyamlError::ExpressionError(message)) => EvalError::Fatal(
+            SyamlError::ExpressionError(format!("{message} (in function '{name}')")),
+        ),
+        other => other,
+    })
+}
+
+/// Evaluates `body` with `names[i]` bound to `values[i]`.
+fn eval_with_params(
+    body: &Expr,
+    ctx: &EvalContext<'_>,
+    names: &[&str],
+    values: &[JsonValue],
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
+    match (names.split_first(), values.split_first()) {
+        (Some((name, names)), Some((value, values))) => {
+            let scope = Locals {
+                name,
+                value,
+                parent: locals,
+            };
+            eval_with_params(body, ctx, names, values, Some(&scope))
+        }
+        _ => eval_expr(body, ctx, locals),
+    }
+}
+
+/// Evaluates the collection built-ins that take an optional lambda; returns
+/// `None` for other function names.
+///
+/// Arrays are processed item by item. `map`, `filter`, `all`, `any`, `sum`
+/// and `count` also accept objects and work on their values (`map` and
+/// `filter` keep the keys).
+fn eval_higher_order_call(
+    name: &str,
+    args: &[Expr],
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Option<Result<JsonValue, EvalError>> {
+    let lambda_required = match name {
+        "map" | "filter" | "all" | "any" => true,
+        "sum" | "count" | "unique" | "sort" => false,
+        _ => return None,
+    };
+    Some(eval_higher_order(name, args, lambda_required, ctx, locals))
+}
+
+fn eval_higher_order(
+    name: &str,
+    args: &[Expr],
+    lambda_required: bool,
+    ctx: &EvalContext<'_>,
+    locals: Option<&Locals<'_>>,
+) -> Result<JsonValue, EvalError> {
+    let (min, expected) = if lambda_required {
+        (2, "2")
+    } else {
+        (1, "1 or 2")
+    };
+    if !(min..=2).contains(&args.len()) {
+        return Err(SyamlError::ExpressionError(format!(
+            "{name} expects {expected} arguments, got {}",
+            args.len()
+        ))
+        .into());
+    }
+
+    let collection = eval_expr(&args[0], ctx, locals)?;
+    let lambda = match args.get(1) {
+        Some(Expr::Lambda { param, body }) => Some((param.as_str(), body.as_ref())),
+        Some(_) => {
+            return Err(SyamlError::ExpressionError(format!(
+                "{name}() expects argument 2 to be a lambda such as 'x => x.port'"
+            ))
+            .into())
+        }
+        None => None,
+    };
+    let apply = |item: &JsonValue| -> Result<JsonValue, EvalError> {
+        match lambda {
+            Some((param, body)) => {
+                let scope = Locals {
+                    name: param,
+                    value: item,
+                    parent: locals,
+                };
+                eval_expr(body, ctx, Some(&scope))
+            }
+            None => Ok(item.clone()),
+        }
+    };
+    let test = |item: &JsonValue| -> Result<bool, EvalError> { as_bool(&apply(item)?) };
+
+    match name {
+        "map" => match &collection {
+            JsonValue::Object(map) => {
+                let mut out = serde_json::Map::new();
+                for (key, value) in map {
+                    out.insert(key.clone(), apply(value)?);
+                }
+                Ok(JsonValue::Object(out))
+            }
+            _ => {
+                let items = collection_items(name, &collection)?;
+                Ok(JsonValue::Array(
+                    items.into_iter().map(apply).collect::<Result<_, _>>()?,
+                ))
+            }
+        },
+        "filter" => match &collection {
+            JsonValue::Object(map) => {
+                let mut out = serde_json::Map::new();
+                for (key, value) in map {
+                    if test(value)? {
+                        out.insert(key.clone(), value.clone());
+                    }
+                }
+                Ok(JsonValue::Object(out))
+            }
+            _ => {
+                let mut out = Vec::new();
+                for item in collection_items(name, &collection)? {
+                    if test(item)? {
+                        out.push(item.clone());
+                    }
+                }
+                Ok(JsonValue::Array(out))
+            }
+        },
+        "all" => {
+            for item in collection_items(name, &collection)? {
+                if !test(item)? {
+                    return Ok(JsonValue::Bool(false));
+                }
+            }
+            Ok(JsonValue::Bool(true))
+        }
+        "any" => {
+            for item in collection_items(name, &collection)? {
+                if test(item)? {
+                    return Ok(JsonValue::Bool(true));
+                }
+            }
+            Ok(JsonValue::Bool(false))
+        }
+        "sum" => {
+            let mut total = Number::Int(0);
+            for item in collection_items(name, &collection)? {
+                total = total.checked_add(as_number(&apply(item)?)?)?;
+            }
+            Ok(total.to_json()?)
+        }
+        "count" => {
+            let items = collection_items(name, &collection)?;
+            let mut n = 0i64;
+            for item in items {
+                if lambda.is_none() || test(item)? {
+                    n += 1;
+                }
+            }
+            Ok(JsonValue::Number(JsonNumber::from(n)))
+        }
+        "unique" => {
+            let items = array_arg(name, &collection)?;
+            let mut seen: Vec<JsonValue> = Vec::new();
+            let mut out = Vec::new();
+            for item in items {
+                let key = apply(item)?;
+                if !seen.contains(&key) {
+                    seen.push(key);
+                    out.push(item.clone());
+                }
+            }
+            Ok(JsonValue::Array(out))
+        }
+        _ => {
+            let items = array_arg(name, &collection)?;
+            let mut keyed = Vec::with_capacity(items.len());
+            for item in items {
+                keyed.push((apply(item)?, item.clone()));
+            }
+            if keyed.iter().all(|(key, _)| key.is_number()) {
+                keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+                    (JsonValue::Number(a), JsonValue::Number(b)) => Number::from_json(a)
+                        .compare(Number::from_json(b))
+                        .unwrap_or(Ordering::Equal),
+                    _ => Ordering::Equal,
+                });
+            } else if keyed.iter().all(|(key, _)| key.is_string()) {
+                keyed.sort_by(|(a, _), (b, _)| a.as_str().cmp(&b.as_str()));
+            } else {
+                return Err(SyamlError::ExpressionError(
+                    "sort() keys must be all numbers or all strings".to_string(),
+                )
+                .into());
+            }
+            Ok(JsonValue::Array(
+                keyed.into_iter().map(|(_, item)| item).collect(),
+            ))
+        }
+    }
+}
+
+/// Items of an array, or values of an object.
+fn collection_items<'v>(name: &str, value: &'v JsonValue) -> Result<Vec<&'v JsonValue>, EvalError> {
+    match value {
+        JsonValue::Array(items) => Ok(items.iter().collect()),
+        JsonValue::Object(map) => Ok(map.values().collect()),
+        other => Err(arg_type_error(name, 0, "an array or object", other)),
+    }
+}
+
+fn array_arg<'v>(name: &str, value: &'v JsonValue) -> Result<&'v [JsonValue], EvalError> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| arg_type_error(name, 0, "an array", value))
+}
+
+/// Evaluates the string built-ins; returns `None` for other function names.
+fn eval_string_call(name: &str, args: &[JsonValue]) -> Option<Result<JsonValue, EvalError>> {
+    let result = match name {
+        "lower" => unary_string(name, args, |s| s.to_lowercase()),
+        "upper" => unary_string(name, args, |s| s.to_uppercase()),
+        "trim" => unary_string(name, args, |s| s.trim().to_string()),
+        "starts_with" => binary_string(name, args, |s, prefix| s.starts_with(prefix)),
+        "ends_with" => binary_string(name, args, |s, suffix| s.ends_with(suffix)),
+        "contains" => binary_string(name, args, |s, needle| s.contains(needle)),
+        "replace" => eval_replace(args),
+        "split" => eval_split(args),
+        "join" => eval_join(args),
+        "substr" => eval_substr(args),
+        "pad_left" => eval_pad_left(args),
+        "format" => eval_format(args),
+        "matches" => eval_matches(args),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn unary_string(
+    name: &str,
+    args: &[JsonValue],
+    f: impl Fn(&str) -> String,
+) -> Result<JsonValue, EvalError> {
+    require_arity(name, args, 1)?;
+    Ok(JsonValue::String(f(string_arg(name, args, 0)?)))
+}
+
+fn binary_string(
+    name: &str,
+    args: &[JsonValue],
+    f: impl Fn(&str, &str) -> bool,
+) -> Result<JsonValue, EvalError> {
+    require_arity(name, args, 2)?;
+    Ok(JsonValue::Bool(f(
+        string_arg(name, args, 0)?,
+        string_arg(name, args, 1)?,
+    )))
+}
+
+fn eval_replace(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    require_arity("replace", args, 3)?;
+    let input = string_arg("replace", args, 0)?;
+    let from = string_arg("replace", args, 1)?;
+    let to = string_arg("replace", args, 2)?;
+    if from.is_empty() {
+        return Err(
+            SyamlError::ExpressionError("replace() pattern must not be empty".to_string()).into(),
+        );
+    }
+    let matches = input.matches(from).count();
+    check_string_len("replace", input.len() + matches * to.len())?;
+    Ok(JsonValue::String(input.replace(from, to)))
+}
+
+fn eval_split(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    require_arity("split", args, 2)?;
+    let input = string_arg("split", args, 0)?;
+    let separator = string_arg("split", args, 1)?;
+    if separator.is_empty() {
+        return Err(
+            SyamlError::ExpressionError("split() separator must not be empty".to_string()).into(),
+        );
+    }
+    Ok(JsonValue::Array(
+        input
+            .split(separator)
+            .map(|part| JsonValue::String(part.to_string()))
+            .collect(),
+    ))
+}
+
+fn eval_join(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    require_arity("join", args, 2)?;
+    let items = args[0]
+        .as_array()
+        .ok_or_else(|| arg_type_error("join", 0, "an array", &args[0]))?;
+    let separator = string_arg("join", args, 1)?;
+    let mut parts = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Bool(_) => {
+                parts.push(json_to_string(item))
+            }
+            other => {
+                return Err(SyamlError::ExpressionError(format!(
+                    "join() expects array items to be strings, numbers, or booleans, got {}",
+                    json_type_name(other)
+                ))
+                .into())
+            }
+        }
+    }
+    let len = parts.iter().map(String::len).sum::<usize>()
+        + separator.len() * parts.len().saturating_sub(1);
+    check_string_len("join", len)?;
+    Ok(JsonValue::String(parts.join(separator)))
+}
+
+fn eval_substr(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    if !(2..=3).contains(&args.len()) {
+        return Err(SyamlError::ExpressionError(format!(
+            "substr expects 2 or 3 arguments, got {}",
+            args.len()
+        ))
+        .into());
+    }
+    let input = string_arg("substr", args, 0)?;
+    let start = index_arg("substr", args, 1)?;
+    let chars = input.chars().skip(start);
+    let out: String = match args.get(2) {
+        Some(_) => chars.take(index_arg("substr", args, 2)?).collect(),
+        None => chars.collect(),
+    };
+    Ok(JsonValue::String(out))
+}
+
+fn eval_pad_left(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    if !(2..=3).contains(&args.len()) {
+        return Err(SyamlError::ExpressionError(format!(
+            "pad_left expects 2 or 3 arguments, got {}",
+            args.len()
+        ))
+        .into());
+    }
+    let input = string_arg("pad_left", args, 0)?;
+    let width = index_arg("pad_left", args, 1)?;
+    let fill = match args.get(2) {
+        Some(_) => {
+            let fill = string_arg("pad_left", args, 2)?;
+            let mut chars = fill.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => {
+                    return Err(SyamlError::ExpressionError(format!(
+                        "pad_left() fill must be a single character, got '{fill}'"
+                    ))
+                    .into())
+                }
+            }
+        }
+        None => ' ',
+    };
+    let missing = width.saturating_sub(input.chars().count());
+    check_string_len("pad_left", input.len() + missing * fill.len_utf8())?;
+    let mut out: String = std::iter::repeat_n(fill, missing).collect();
+    out.push_str(input);
+    Ok(JsonValue::String(out))
+}
+
+/// `format("{}:{}", host, port)`: `{}` takes the next argument, `{N}` the N-th
+/// (0-based) argument after the template, and `{{` / `}}` are literal braces.
+fn eval_format(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    require_arity_at_least("format", args, 1)?;
+    let template = string_arg("format", args, 0)?;
+    let values = &args[1..];
+    let error = |message: String| EvalError::Fatal(SyamlError::ExpressionError(message));
+
+    let mut out = String::new();
+    let mut next = 0usize;
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(error("format() has an unclosed '{'".to_string())),
+                    }
+                }
+                let index = if spec.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    spec.trim().parse::<usize>().map_err(|_| {
+                        error(format!("format() placeholder '{{{spec}}}' is not an index"))
+                    })?
+                };
+                let value = values.get(index).ok_or_else(|| {
+                    error(format!(
+                        "format() placeholder {index} has no argument ({} given)",
+                        values.len()
+                    ))
+                })?;
+                out.push_str(&json_to_string(value));
+                check_string_len("format", out.len())?;
+            }
+            '}' => return Err(error("format() has an unmatched '}'".to_string())),
+            c => out.push(c),
+        }
+    }
+    Ok(JsonValue::String(out))
+}
+
+fn eval_matches(args: &[JsonValue]) -> Result<JsonValue, EvalError> {
+    require_arity("matches", args, 2)?;
+    let input = string_arg("matches", args, 0)?;
+    let pattern = string_arg("matches", args, 1)?;
+    let re = RegexBuilder::new(pattern)
+        .size_limit(MAX_REGEX_SIZE)
+        .build()
+        .map_err(|e| {
+            SyamlError::ExpressionError(format!("matches() invalid regex '{pattern}': {e}"))
+        })?;
+    Ok(JsonValue::Bool(re.is_match(input)))
+}
+
+fn string_arg<'v>(name: &str, args: &'v [JsonValue], index: usize) -> Result<&'v str, EvalError> {
+    args[index]
+        .as_str()
+        .ok_or_else(|| arg_type_error(name, index, "a string", &args[index]))
+}
+
+/// Non-negative integer argument (a character count or position).
+fn index_arg(name: &str, args: &[JsonValue], index: usize) -> Result<usize, EvalError> {
+    args[index]
+        .as_u64()
+        .and_then(|v| usize::try_from(v).ok())
+        .ok_or_else(|| arg_type_error(name, index, "a non-negative integer", &args[index]))
+}
+
+fn arg_type_error(name: &str, index: usize, expected: &str, got: &JsonValue) -> EvalError {
+    EvalError::Fatal(SyamlError::ExpressionError(format!(
+        "{name}() expects argument {} to be {expected}, got {}",
+        index + 1,
+        json_type_name(got)
+    )))
+}
+
+fn check_string_len(name: &str, len: usize) -> Result<(), EvalError> {
+    if len > MAX_STRING_RESULT_LEN {
+        return Err(SyamlError::ExpressionError(format!(
+            "{name}() result exceeds max string length ({MAX_STRING_RESULT_LEN})"
+        ))
+        .into());
+    }
+    Ok(())
+}
+
 fn require_arity(name: &str, args: &[JsonValue], expected: usize) -> Result<(), EvalError> {
     if args.len() != expected {
         return Err(SyamlError::ExpressionError(format!(
@@ -302,29 +1385,43 @@ fn require_arity_at_least(name: &str, args: &[JsonValue], min: usize) -> Result<
     Ok(())
 }
 
-fn as_f64(value: &JsonValue) -> Result<f64, EvalError> {
-    value.as_f64().ok_or_else(|| {
-        EvalError::Fatal(SyamlError::ExpressionError(format!(
-            "expected number, got {}",
-            json_type_name(value)
-        )))
-    })
-}
-
-fn as_i64(value: &JsonValue) -> Result<i64, EvalError> {
-    if let Some(v) = value.as_i64() {
-        Ok(v)
-    } else if let Some(v) = value.as_u64() {
-        i64::try_from(v).map_err(|_| {
+/// Number, or a string spelling a duration, timestamp or byte size.
+fn scalar_value(value: &JsonValue) -> Result<Scalar, EvalError> {
+    match value {
+        JsonValue::String(s) => Quantity::parse(s).map(Scalar::Quantity).ok_or_else(|| {
             EvalError::Fatal(SyamlError::ExpressionError(format!(
-                "integer value out of range: {v}"
+                "expected number, duration, timestamp or byte size, got '{s}'"
             )))
-        })
-    } else {
-        Err(EvalError::Fatal(SyamlError::ExpressionError(format!(
-            "expected integer, got {}",
-            json_type_name(value)
-        ))))
+        }),
+        other => as_number(other).map(Scalar::Number),
+    }
+}
+
+/// Argument parsed as the quantity type `type_name`.
+fn quantity_arg(
+    name: &str,
+    type_name: &str,
+    value: &JsonValue,
+    index: usize,
+) -> Result<Quantity, EvalError> {
+    match Quantity::from_json_as(type_name, value) {
+        Some(Ok(q)) => Ok(q),
+        Some(Err(reason)) => Err(SyamlError::ExpressionError(format!(
+            "{name}() argument {}: {reason}",
+            index + 1
+        ))
+        .into()),
+        None => unreachable!("'{type_name}' is a quantity type"),
+    }
+}
+
+fn as_number(value: &JsonValue) -> Result<Number, EvalError> {
+    match value {
+        JsonValue::Number(n) => Ok(Number::from_json(n)),
+        other => Err(EvalError::Fatal(SyamlError::ExpressionError(format!(
+            "expected number, got {}",
+            json_type_name(other)
+        )))),
     }
 }
 
@@ -337,22 +1434,6 @@ fn as_bool(value: &JsonValue) -> Result<bool, EvalError> {
     })
 }
 
-fn number(value: f64) -> Result<JsonValue, EvalError> {
-    let num = JsonNumber::from_f64(value).ok_or_else(|| {
-        EvalError::Fatal(SyamlError::ExpressionError(format!(
-            "invalid numeric result {value}"
-        )))
-    })?;
-
-    if value.fract() == 0.0 {
-        if value >= i64::MIN as f64 && value <= i64::MAX as f64 {
-            return Ok(JsonValue::Number(JsonNumber::from(value as i64)));
-        }
-    }
-
-    Ok(JsonValue::Number(num))
-}
-
 fn json_type_name(value: &JsonValue) -> &'static str {
     if value.is_null() {
         "null"
@@ -1,12 +1,17 @@
 //! Lexer for the `super_yaml` expression language.
 
 use crate::error::SyamlError;
+use crate::quantity::Quantity;
+
+use super::number::Number;
 
 #[derive(Debug, Clone, PartialEq)]
 /// Token variants emitted by [`tokenize`].
 pub enum TokenKind {
     /// Numeric literal.
-    Number(f64),
+    Number(Number),
+    /// Duration or byte-size literal (`30s`, `512MiB`).
+    Quantity(Quantity),
     /// Quoted string literal.
     String(String),
     /// Boolean literal.
@@ -29,6 +34,10 @@ pub enum TokenKind {
     LParen,
     /// `)`
     RParen,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
     /// `,`
     Comma,
     /// `.`
@@ -51,6 +60,18 @@ pub enum TokenKind {
     AndAnd,
     /// `||`
     OrOr,
+    /// `=>` (lambda arrow)
+    Arrow,
+    /// `=` (in `let` bindings)
+    Assign,
+    /// `?`
+    Question,
+    /// `?.` (null-safe access)
+    QuestionDot,
+    /// `??` (default operator)
+    QuestionQuestion,
+    /// `:`
+    Colon,
     /// End-of-input sentinel.
     Eof,
 }
@@ -65,6 +86,10 @@ pub struct Token {
 }
 
 /// Tokenizes expression source code.
+///
+/// In string literals `\"`, `\\`, `\n`, `\t` and `\r` are escapes. Any other
+/// backslash sequence is kept as written, backslash included, so `"\d+"` is the
+/// regex `\d+`. Earlier versions dropped the backslash and read it as `d+`.
 pub fn tokenize(input: &str) -> Result<Vec<Token>, SyamlError> {
     let mut chars = input.char_indices().peekable();
     let mut tokens = Vec::new();
@@ -92,8 +117,34 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, SyamlError> {
                         break;
                     }
                 }
+                if chars.peek().is_some_and(|(_, c)| c.is_alphabetic()) {
+                    // A unit suffix makes a duration (`30s`, `1h30m`) or byte-size
+                    // (`512MiB`) literal.
+                    let mut stop = input.len();
+                    while let Some((i, c)) = chars.peek().copied() {
+                        if c.is_alphanumeric() || c == '.' {
+                            chars.next();
+                        } else {
+                            stop = i;
+                            break;
+                        }
+                    }
+                    let raw = &input[start..stop];
+                    let quantity = Quantity::parse(raw)
+                        .filter(|q| !matches!(q, Quantity::Timestamp(_)))
+                        .ok_or_else(|| {
+                            SyamlError::ExpressionError(format!(
+                                "invalid duration or byte size literal '{raw}' at {start}"
+                            ))
+                        })?;
+                    tokens.push(Token {
+                        kind: TokenKind::Quantity(quantity),
+                        pos: start,
+                    });
+                    continue;
+                }
                 let raw = &input[start..=end];
-                let n: f64 = raw.parse().map_err(|e| {
+                let n = Number::parse_literal(raw).map_err(|e| {
                     SyamlError::ExpressionError(format!(
                         "invalid number literal '{raw}' at {start}: {e}"
                     ))
@@ -121,7 +172,12 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, SyamlError> {
                                 'n' => out.push('\n'),
                                 't' => out.push('\t'),
                                 'r' => out.push('\r'),
-                                other => out.push(other),
+                                // Other escapes are kept as written so regex
+                                // patterns such as "\d+" need no doubling.
+                                other => {
+                                    out.push('\\');
+                                    out.push(other);
+                                }
                             }
                         } else {
                             return Err(SyamlError::ExpressionError(
@@ -193,6 +249,20 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, SyamlError> {
                     pos: idx,
                 });
             }
+            '[' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::LBracket,
+                    pos: idx,
+                });
+            }
+            ']' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::RBracket,
+                    pos: idx,
+                });
+            }
             ',' => {
                 chars.next();
                 tokens.push(Token {
@@ -230,12 +300,38 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>, SyamlError> {
                         kind: TokenKind::EqEq,
                         pos: idx,
                     });
+                } else if matches!(chars.peek(), Some((_, '>'))) {
+                    chars.next();
+                    tokens.push(Token {
+                        kind: TokenKind::Arrow,
+                        pos: idx,
+                    });
                 } else {
-                    return Err(SyamlError::ExpressionError(format!(
-                        "unexpected '=' at {idx}; use '==' for equality"
-                    )));
+                    tokens.push(Token {
+                        kind: TokenKind::Assign,
+                        pos: idx,
+                    });
                 }
             }
+            '?' => {
+                chars.next();
+                let kind = match chars.peek() {
+                    Some((_, '.')) => TokenKind::QuestionDot,
+                    Some((_, '?')) => TokenKind::QuestionQuestion,
+                    _ => TokenKind::Question,
+                };
+                if !matches!(kind, TokenKind::Question) {
+                    chars.next();
+                }
+                tokens.push(Token { kind, pos: idx });
+            }
+            ':' => {
+                chars.next();
+                tokens.push(Token {
+                    kind: TokenKind::Colon,
+                    pos: idx,
+                });
+            }
             '<' => {
                 chars.next();
                 if matches!(chars.peek(), Some((_, '='))) {
@@ -337,3 +433,23 @@ fn is_ident_start(c: char) -> bool {
 fn is_ident_continue(c: char) -> bool {
     c.is_ascii_alphanumeric() || c == '_'
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, TokenKind};
+
+    fn string_literal(source: &str) -> String {
+        match tokenize(source).unwrap().remove(0).kind {
+            TokenKind::String(s) => s,
+            other => panic!("expected a string literal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn string_escapes_keep_unknown_sequences() {
+        assert_eq!(string_literal(r#""a\"b\\c\nd\te\rf""#), "a\"b\\c\nd\te\rf");
+        assert_eq!(string_literal(r#""^v\d+\.\w*$""#), r"^v\d+\.\w*$");
+        assert_eq!(string_literal(r#""\\d""#), r"\d");
+        assert!(tokenize(r#""abc\"#).is_err());
+    }
+}
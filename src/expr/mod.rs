@@ -4,8 +4,14 @@
 pub mod eval;
 /// Tokenizer for expression source text.
 pub mod lexer;
+/// Exact integer/decimal numeric tower.
+pub mod number;
 /// Parser and expression AST definitions.
 pub mod parser;
+/// Rendering of expressions as Rust or TypeScript source.
+pub(crate) mod render;
+/// Static type inference and checking.
+pub mod typecheck;
 
 use crate::error::SyamlError;
 
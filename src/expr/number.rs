@@ -0,0 +1,327 @@
+//! Exact numeric tower used by the expression evaluator.
+//!
+//! Integers are kept as `i128` and fail loudly on overflow, decimal literals and
+//! JSON floats are kept as base-10 decimals so money arithmetic is exact, and
+//! `f64` is only used for JSON floats that no decimal can represent.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde_json::{Number as JsonNumber, Value as JsonValue};
+
+use crate::error::SyamlError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Numeric value in the evaluator.
+pub enum Number {
+    /// Exact integer.
+    Int(i128),
+    /// Exact base-10 decimal (28 significant digits).
+    Decimal(Decimal),
+    /// Binary float, for values outside the decimal range.
+    Float(f64),
+}
+
+/// Operand pair after promotion to the wider of both representations.
+enum Promoted {
+    Ints(i128, i128),
+    Decimals(Decimal, Decimal),
+    Floats(f64, f64),
+}
+
+impl Number {
+    /// Parses an expression literal: digits become an integer, digits with a
+    /// `.` become a decimal. Errors carry the parser's reason.
+    pub fn parse_literal(raw: &str) -> Result<Self, String> {
+        if !raw.contains('.') {
+            return raw
+                .parse::<i128>()
+                .map(Number::Int)
+                .map_err(|e| e.to_string());
+        }
+        if let Ok(d) = Decimal::from_str_exact(raw) {
+            return Ok(Number::Decimal(d));
+        }
+        raw.parse::<f64>()
+            .map(Number::Float)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Converts a JSON number without losing integer precision.
+    ///
+    /// JSON floats are read through their shortest decimal spelling, so `0.1`
+    /// in the data becomes exactly one tenth.
+    pub fn from_json(n: &JsonNumber) -> Self {
+        if let Some(v) = n.as_i64() {
+            Number::Int(v.into())
+        } else if let Some(v) = n.as_u64() {
+            Number::Int(v.into())
+        } else {
+            let v = n.as_f64().unwrap_or(f64::NAN);
+            Decimal::from_str_exact(&v.to_string())
+                .map(Number::Decimal)
+                .unwrap_or(Number::Float(v))
+        }
+    }
+
+    /// Converts back to JSON. Integral results are written as integers, which
+    /// must fit in 64 bits; fractional results become the nearest float.
+    pub fn to_json(self) -> Result<JsonValue, SyamlError> {
+        match self {
+            Number::Int(v) => {
+                let n = if let Ok(v) = i64::try_from(v) {
+                    JsonNumber::from(v)
+                } else if let Ok(v) = u64::try_from(v) {
+                    JsonNumber::from(v)
+                } else {
+                    return Err(SyamlError::ExpressionError(format!(
+                        "integer result {v} does not fit in a 64-bit integer"
+                    )));
+                };
+                Ok(JsonValue::Number(n))
+            }
+            Number::Decimal(d) => {
+                let d = d.normalize();
+                if d.scale() == 0 {
+                    return Number::Int(d.mantissa()).to_json();
+                }
+                let v: f64 = d.to_string().parse().map_err(|_| {
+                    SyamlError::ExpressionError(format!("invalid numeric result {d}"))
+                })?;
+                Number::Float(v).to_json()
+            }
+            Number::Float(v) => {
+                if v.fract() == 0.0 && v >= i64::MIN as f64 && v <= i64::MAX as f64 {
+                    return Ok(JsonValue::Number(JsonNumber::from(v as i64)));
+                }
+                JsonNumber::from_f64(v)
+                    .map(JsonValue::Number)
+                    .ok_or_else(|| {
+                        SyamlError::ExpressionError(format!("invalid numeric result {v}"))
+                    })
+            }
+        }
+    }
+
+    /// Nearest `f64` value.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(v) => v as f64,
+            Number::Decimal(d) => d.to_string().parse().unwrap_or(f64::NAN),
+            Number::Float(v) => v,
+        }
+    }
+
+    /// Integer value, if this number is integral.
+    pub fn as_i128(self) -> Option<i128> {
+        match self {
+            Number::Int(v) => Some(v),
+            Number::Decimal(d) => {
+                let d = d.normalize();
+                (d.scale() == 0).then(|| d.mantissa())
+            }
+            Number::Float(v) => {
+                (v.fract() == 0.0 && v.abs() < i128::MAX as f64).then_some(v as i128)
+            }
+        }
+    }
+
+    fn promote(self, other: Number) -> Result<Promoted, SyamlError> {
+        Ok(match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Promoted::Ints(a, b),
+            (Number::Float(a), b) => Promoted::Floats(a, b.as_f64()),
+            (a, Number::Float(b)) => Promoted::Floats(a.as_f64(), b),
+            (a, b) => Promoted::Decimals(a.to_decimal()?, b.to_decimal()?),
+        })
+    }
+
+    fn to_decimal(self) -> Result<Decimal, SyamlError> {
+        match self {
+            Number::Int(v) => Decimal::try_from_i128_with_scale(v, 0).map_err(|_| {
+                SyamlError::ExpressionError(format!(
+                    "integer {v} is too large to combine with a decimal"
+                ))
+            }),
+            Number::Decimal(d) => Ok(d),
+            Number::Float(v) => Decimal::from_str_exact(&v.to_string()).map_err(|_| {
+                SyamlError::ExpressionError(format!("float {v} cannot be used as a decimal"))
+            }),
+        }
+    }
+
+    /// `self + other`.
+    pub fn checked_add(self, other: Number) -> Result<Number, SyamlError> {
+        match self.promote(other)? {
+            Promoted::Ints(a, b) => a
+                .checked_add(b)
+                .map(Number::Int)
+                .ok_or_else(|| overflow("+")),
+            Promoted::Decimals(a, b) => a
+                .checked_add(b)
+                .map(Number::Decimal)
+                .ok_or_else(|| overflow("+")),
+            Promoted::Floats(a, b) => Ok(Number::Float(a + b)),
+        }
+    }
+
+    /// `self - other`.
+    pub fn checked_sub(self, other: Number) -> Result<Number, SyamlError> {
+        match self.promote(other)? {
+            Promoted::Ints(a, b) => a
+                .checked_sub(b)
+                .map(Number::Int)
+                .ok_or_else(|| overflow("-")),
+            Promoted::Decimals(a, b) => a
+                .checked_sub(b)
+                .map(Number::Decimal)
+                .ok_or_else(|| overflow("-")),
+            Promoted::Floats(a, b) => Ok(Number::Float(a - b)),
+        }
+    }
+
+    /// `self * other`.
+    pub fn checked_mul(self, other: Number) -> Result<Number, SyamlError> {
+        match self.promote(other)? {
+            Promoted::Ints(a, b) => a
+                .checked_mul(b)
+                .map(Number::Int)
+                .ok_or_else(|| overflow("*")),
+            Promoted::Decimals(a, b) => a
+                .checked_mul(b)
+                .map(Number::Decimal)
+                .ok_or_else(|| overflow("*")),
+            Promoted::Floats(a, b) => Ok(Number::Float(a * b)),
+        }
+    }
+
+    /// `self / other`. Integer division stays integral only when exact.
+    pub fn checked_div(self, other: Number) -> Result<Number, SyamlError> {
+        if other.is_zero() {
+            return Err(SyamlError::ExpressionError("division by zero".to_string()));
+        }
+        match self.promote(other)? {
+            Promoted::Ints(a, b) if a.checked_rem(b) == Some(0) => a
+                .checked_div(b)
+                .map(Number::Int)
+                .ok_or_else(|| overflow("/")),
+            Promoted::Ints(..) => Number::Decimal(self.to_decimal()?)
+                .checked_div(Number::Decimal(other.to_decimal()?)),
+            Promoted::Decimals(a, b) => a
+                .checked_div(b)
+                .map(Number::Decimal)
+                .ok_or_else(|| overflow("/")),
+            Promoted::Floats(a, b) => Ok(Number::Float(a / b)),
+        }
+    }
+
+    /// Integer remainder; both operands must be integral.
+    pub fn checked_rem(self, other: Number) -> Result<Number, SyamlError> {
+        let (Some(a), Some(b)) = (self.as_i128(), other.as_i128()) else {
+            let bad = if self.as_i128().is_none() {
+                self
+            } else {
+                other
+            };
+            return Err(SyamlError::ExpressionError(format!(
+                "expected integer, got {bad}"
+            )));
+        };
+        if b == 0 {
+            return Err(SyamlError::ExpressionError("modulo by zero".to_string()));
+        }
+        a.checked_rem(b)
+            .map(Number::Int)
+            .ok_or_else(|| overflow("%"))
+    }
+
+    /// `-self`.
+    pub fn checked_neg(self) -> Result<Number, SyamlError> {
+        match self {
+            Number::Int(v) => v
+                .checked_neg()
+                .map(Number::Int)
+                .ok_or_else(|| overflow("-")),
+            Number::Decimal(d) => Ok(Number::Decimal(-d)),
+            Number::Float(v) => Ok(Number::Float(-v)),
+        }
+    }
+
+    /// Absolute value.
+    pub fn checked_abs(self) -> Result<Number, SyamlError> {
+        if self.is_negative() {
+            self.checked_neg()
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Largest integer not greater than `self`.
+    pub fn floor(self) -> Number {
+        match self {
+            Number::Decimal(d) => Number::Decimal(d.floor()),
+            Number::Float(v) => Number::Float(v.floor()),
+            int => int,
+        }
+    }
+
+    /// Smallest integer not less than `self`.
+    pub fn ceil(self) -> Number {
+        match self {
+            Number::Decimal(d) => Number::Decimal(d.ceil()),
+            Number::Float(v) => Number::Float(v.ceil()),
+            int => int,
+        }
+    }
+
+    /// Nearest integer, rounding half away from zero.
+    pub fn round(self) -> Number {
+        match self {
+            Number::Decimal(d) => {
+                Number::Decimal(d.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero))
+            }
+            Number::Float(v) => Number::Float(v.round()),
+            int => int,
+        }
+    }
+
+    fn is_zero(self) -> bool {
+        match self {
+            Number::Int(v) => v == 0,
+            Number::Decimal(d) => d.is_zero(),
+            Number::Float(v) => v == 0.0,
+        }
+    }
+
+    fn is_negative(self) -> bool {
+        match self {
+            Number::Int(v) => v < 0,
+            Number::Decimal(d) => d.is_sign_negative() && !d.is_zero(),
+            Number::Float(v) => v < 0.0,
+        }
+    }
+
+    /// Exact numeric comparison; `None` only when a float is NaN.
+    pub fn compare(self, other: Number) -> Option<Ordering> {
+        match self.promote(other) {
+            Ok(Promoted::Ints(a, b)) => Some(a.cmp(&b)),
+            Ok(Promoted::Decimals(a, b)) => Some(a.cmp(&b)),
+            Ok(Promoted::Floats(a, b)) => a.partial_cmp(&b),
+            Err(_) => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(v) => write!(f, "{v}"),
+            Number::Decimal(d) => write!(f, "{d}"),
+            Number::Float(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+fn overflow(op: &str) -> SyamlError {
+    SyamlError::ExpressionError(format!("numeric overflow in '{op}'"))
+}
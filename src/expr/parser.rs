@@ -1,14 +1,20 @@
 //! Recursive-descent parser and AST for expressions.
 
+use std::fmt;
+
 use crate::error::SyamlError;
+use crate::quantity::Quantity;
 
 use super::lexer::{Token, TokenKind};
+use super::number::Number;
 
 #[derive(Debug, Clone)]
 /// Expression AST node.
 pub enum Expr {
     /// Number literal.
-    Number(f64),
+    Number(Number),
+    /// Duration or byte-size literal (`30s`, `512MiB`).
+    Quantity(Quantity),
     /// String literal.
     String(String),
     /// Boolean literal.
@@ -35,13 +41,52 @@ pub enum Expr {
     },
     /// Function call expression.
     Call {
-        /// Function name.
+        /// Function name (`alias.name` for imported functions).
         name: String,
         /// Call argument expressions.
         args: Vec<Expr>,
     },
+    /// Array index or object key access (`items[0]`, `map["key"]`, `f(x).field`).
+    Index {
+        /// Indexed value.
+        target: Box<Expr>,
+        /// Index (number) or key (string) expression.
+        index: Box<Expr>,
+        /// Null-safe access (`a?.b`, `a?.[0]`): yields `null` instead of an
+        /// error when the target is `null` or lacks the key or index.
+        optional: bool,
+    },
+    /// Single-parameter lambda (`x => x.port`), only valid as a call argument.
+    Lambda {
+        /// Parameter name.
+        param: String,
+        /// Lambda body.
+        body: Box<Expr>,
+    },
+    /// Conditional (`if c then a else b` or `c ? a : b`); only the chosen
+    /// branch is evaluated.
+    Conditional {
+        /// Condition expression.
+        cond: Box<Expr>,
+        /// Value when the condition is true.
+        then_branch: Box<Expr>,
+        /// Value when the condition is false.
+        else_branch: Box<Expr>,
+    },
+    /// Local binding (`let x = value in body`).
+    Let {
+        /// Bound name.
+        name: String,
+        /// Bound value.
+        value: Box<Expr>,
+        /// Expression evaluated with the binding in scope.
+        body: Box<Expr>,
+    },
 }
 
+/// Words with a fixed meaning in expressions; they cannot be used as names.
+const KEYWORDS: [&str; 5] = ["if", "then", "else", "let", "in"];
+
 #[derive(Debug, Clone, Copy)]
 /// Unary operators.
 pub enum UnaryOp {
@@ -80,12 +125,202 @@ pub enum BinaryOp {
     And,
     /// `||`
     Or,
+    /// `??` (right operand when the left one is `null`)
+    Coalesce,
+}
+
+impl BinaryOp {
+    /// Source spelling of the operator.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Lte => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Gte => ">=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Coalesce => "??",
+        }
+    }
+}
+
+impl BinaryOp {
+    /// Binding strength; higher binds tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Coalesce => 1,
+            BinaryOp::Or => 2,
+            BinaryOp::And => 3,
+            BinaryOp::Eq | BinaryOp::NotEq => 4,
+            BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte => 5,
+            BinaryOp::Add | BinaryOp::Sub => 6,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 7,
+        }
+    }
+}
+
+impl Expr {
+    /// Variable path and per-segment null-safety flags of a chain of
+    /// literal-key accesses containing `?.` (`a?.b.c`); such chains are
+    /// resolved as one path.
+    pub(crate) fn null_safe_path(&self) -> Option<(Vec<String>, Vec<bool>)> {
+        fn flatten(expr: &Expr) -> Option<(Vec<String>, Vec<bool>)> {
+            match expr {
+                Expr::Var(path) => Some((path.clone(), vec![false; path.len()])),
+                Expr::Index {
+                    target,
+                    index,
+                    optional,
+                } => {
+                    let Expr::String(key) = &**index else {
+                        return None;
+                    };
+                    let (mut path, mut flags) = flatten(target)?;
+                    path.push(key.clone());
+                    flags.push(*optional);
+                    Some((path, flags))
+                }
+                _ => None,
+            }
+        }
+        flatten(self).filter(|(_, flags)| flags.contains(&true))
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Lambda { .. } | Expr::Conditional { .. } | Expr::Let { .. } => 0,
+            Expr::Binary { op, .. } => op.precedence(),
+            Expr::Unary { .. } => 8,
+            _ => 9,
+        }
+    }
+
+    fn fmt_at(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "(")?;
+            self.fmt_at(f, 0)?;
+            return write!(f, ")");
+        }
+        match self {
+            Expr::Number(n) => write!(f, "{n}"),
+            Expr::Quantity(q) => write!(f, "{q}"),
+            Expr::String(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        '\t' => write!(f, "\\t")?,
+                        '\r' => write!(f, "\\r")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Expr::Bool(b) => write!(f, "{b}"),
+            Expr::Null => write!(f, "null"),
+            Expr::Var(path) => write!(f, "{}", path.join(".")),
+            Expr::Unary { op, expr } => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                };
+                write!(f, "{symbol}")?;
+                expr.fmt_at(f, 8)
+            }
+            Expr::Binary { op, left, right } => {
+                left.fmt_at(f, op.precedence())?;
+                write!(f, " {} ", op.symbol())?;
+                right.fmt_at(f, op.precedence() + 1)
+            }
+            Expr::Call { name, args } => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    arg.fmt_at(f, 0)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Index {
+                target,
+                index,
+                optional,
+            } => {
+                target.fmt_at(f, 9)?;
+                if *optional {
+                    write!(f, "?.")?;
+                }
+                match &**index {
+                    Expr::String(key) if is_identifier(key) => {
+                        write!(f, "{}{key}", if *optional { "" } else { "." })
+                    }
+                    index => {
+                        write!(f, "[")?;
+                        index.fmt_at(f, 0)?;
+                        write!(f, "]")
+                    }
+                }
+            }
+            Expr::Lambda { param, body } => {
+                write!(f, "{param} => ")?;
+                body.fmt_at(f, 0)
+            }
+            Expr::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                write!(f, "if ")?;
+                cond.fmt_at(f, 0)?;
+                write!(f, " then ")?;
+                then_branch.fmt_at(f, 0)?;
+                write!(f, " else ")?;
+                else_branch.fmt_at(f, 0)
+            }
+            Expr::Let { name, value, body } => {
+                write!(f, "let {name} = ")?;
+                value.fmt_at(f, 0)?;
+                write!(f, " in ")?;
+                body.fmt_at(f, 0)
+            }
+        }
+    }
+}
+
+/// Prints the expression as source text, adding parentheses only where
+/// precedence requires them.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_at(f, 0)
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&s)
 }
 
 /// Parses token stream into an expression AST.
 pub fn parse(tokens: &[Token]) -> Result<Expr, SyamlError> {
     let mut parser = Parser { tokens, pos: 0 };
-    let expr = parser.parse_or()?;
+    let expr = parser.parse_expr()?;
+    if let Some(err) = parser.unexpected_assign() {
+        return Err(err);
+    }
     if !matches!(parser.current().kind, TokenKind::Eof) {
         return Err(SyamlError::ExpressionError(format!(
             "unexpected token after expression at position {}",
@@ -101,6 +336,93 @@ struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
+    fn parse_expr(&mut self) -> Result<Expr, SyamlError> {
+        if self.at_keyword("if") {
+            self.pos += 1;
+            let cond = self.parse_expr()?;
+            self.expect_keyword("then", "expected 'then' after 'if' condition")?;
+            let then_branch = self.parse_expr()?;
+            self.expect_keyword("else", "expected 'else' after 'then' branch")?;
+            let else_branch = self.parse_expr()?;
+            return Ok(Expr::Conditional {
+                cond: Box::new(cond),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
+
+        if self.at_keyword("let") {
+            self.pos += 1;
+            return self.parse_let_bindings();
+        }
+
+        let expr = self.parse_coalesce()?;
+        if self
+            .consume_if(|k| matches!(k, TokenKind::Question))
+            .is_none()
+        {
+            return Ok(expr);
+        }
+        let then_branch = self.parse_expr()?;
+        self.expect(
+            |k| matches!(k, TokenKind::Colon),
+            "expected ':' in conditional expression",
+        )?;
+        let else_branch = self.parse_expr()?;
+        Ok(Expr::Conditional {
+            cond: Box::new(expr),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+
+    /// Parses `name = value, ... in body` after `let`; several bindings nest
+    /// so later ones can use earlier ones.
+    fn parse_let_bindings(&mut self) -> Result<Expr, SyamlError> {
+        let name = match &self.current().kind {
+            TokenKind::Ident(name) if !KEYWORDS.contains(&name.as_str()) => name.clone(),
+            _ => {
+                return Err(SyamlError::ExpressionError(format!(
+                    "expected a name after 'let' at {}",
+                    self.current().pos
+                )))
+            }
+        };
+        self.pos += 1;
+        self.expect(
+            |k| matches!(k, TokenKind::Assign),
+            "expected '=' after let binding name",
+        )?;
+        let value = self.parse_expr()?;
+        let body = if self.consume_if(|k| matches!(k, TokenKind::Comma)).is_some() {
+            self.parse_let_bindings()?
+        } else {
+            self.expect_keyword("in", "expected 'in' after let binding")?;
+            self.parse_expr()?
+        };
+        Ok(Expr::Let {
+            name,
+            value: Box::new(value),
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_coalesce(&mut self) -> Result<Expr, SyamlError> {
+        let mut expr = self.parse_or()?;
+        while self
+            .consume_if(|k| matches!(k, TokenKind::QuestionQuestion))
+            .is_some()
+        {
+            let right = self.parse_or()?;
+            expr = Expr::Binary {
+                op: BinaryOp::Coalesce,
+                left: Box::new(expr),
+                right: Box::new(right),
+            };
+        }
+        Ok(expr)
+    }
+
     fn parse_or(&mut self) -> Result<Expr, SyamlError> {
         let mut expr = self.parse_and()?;
         while self.consume_if(|k| matches!(k, TokenKind::OrOr)).is_some() {
@@ -256,7 +578,86 @@ impl<'a> Parser<'a> {
             });
         }
 
-        self.parse_primary()
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, SyamlError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self
+                .consume_if(|k| matches!(k, TokenKind::LBracket))
+                .is_some()
+            {
+                let index = self.parse_expr()?;
+                self.expect(
+                    |k| matches!(k, TokenKind::RBracket),
+                    "expected ']' after index",
+                )?;
+                expr = Expr::Index {
+                    target: Box::new(expr),
+                    index: Box::new(index),
+                    optional: false,
+                };
+            } else if let Some(token) =
+                self.consume_if(|k| matches!(k, TokenKind::Dot | TokenKind::QuestionDot))
+            {
+                let optional = matches!(token.kind, TokenKind::QuestionDot);
+                if optional
+                    && self
+                        .consume_if(|k| matches!(k, TokenKind::LBracket))
+                        .is_some()
+                {
+                    let index = self.parse_expr()?;
+                    self.expect(
+                        |k| matches!(k, TokenKind::RBracket),
+                        "expected ']' after index",
+                    )?;
+                    expr = Expr::Index {
+                        target: Box::new(expr),
+                        index: Box::new(index),
+                        optional,
+                    };
+                    continue;
+                }
+                let field = match &self.current().kind {
+                    TokenKind::Ident(v) => v.clone(),
+                    _ => {
+                        return Err(SyamlError::ExpressionError(format!(
+                            "expected identifier after '{}' at {}",
+                            if optional { "?." } else { "." },
+                            self.current().pos
+                        )))
+                    }
+                };
+                self.pos += 1;
+                expr = Expr::Index {
+                    target: Box::new(expr),
+                    index: Box::new(Expr::String(field)),
+                    optional,
+                };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Parses a call argument, which may be a lambda (`x => body`).
+    fn parse_argument(&mut self) -> Result<Expr, SyamlError> {
+        if let TokenKind::Ident(param) = &self.current().kind {
+            if matches!(
+                self.tokens.get(self.pos + 1).map(|t| &t.kind),
+                Some(TokenKind::Arrow)
+            ) {
+                let param = param.clone();
+                self.pos += 2;
+                let body = self.parse_expr()?;
+                return Ok(Expr::Lambda {
+                    param,
+                    body: Box::new(body),
+                });
+            }
+        }
+        self.parse_expr()
     }
 
     fn parse_primary(&mut self) -> Result<Expr, SyamlError> {
@@ -266,6 +667,10 @@ impl<'a> Parser<'a> {
                 self.pos += 1;
                 Ok(Expr::Number(n))
             }
+            TokenKind::Quantity(q) => {
+                self.pos += 1;
+                Ok(Expr::Quantity(q))
+            }
             TokenKind::String(ref s) => {
                 self.pos += 1;
                 Ok(Expr::String(s.clone()))
@@ -278,6 +683,9 @@ impl<'a> Parser<'a> {
                 self.pos += 1;
                 Ok(Expr::Null)
             }
+            TokenKind::Ident(ref s) if KEYWORDS.contains(&s.as_str()) => Err(
+                SyamlError::ExpressionError(format!("unexpected keyword '{s}' at {}", token.pos)),
+            ),
             TokenKind::Ident(ref s) => {
                 let mut segments = vec![s.clone()];
                 self.pos += 1;
@@ -295,10 +703,10 @@ impl<'a> Parser<'a> {
                     segments.push(ident);
                 }
 
-                if segments.len() == 1
-                    && self
-                        .consume_if(|k| matches!(k, TokenKind::LParen))
-                        .is_some()
+                // `alias.name(...)` calls a function imported under `alias`.
+                if self
+                    .consume_if(|k| matches!(k, TokenKind::LParen))
+                    .is_some()
                 {
                     let mut args = Vec::new();
                     if self
@@ -306,7 +714,7 @@ impl<'a> Parser<'a> {
                         .is_none()
                     {
                         loop {
-                            args.push(self.parse_or()?);
+                            args.push(self.parse_argument()?);
                             if self.consume_if(|k| matches!(k, TokenKind::Comma)).is_some() {
                                 continue;
                             }
@@ -319,7 +727,7 @@ impl<'a> Parser<'a> {
                     }
 
                     Ok(Expr::Call {
-                        name: segments[0].clone(),
+                        name: segments.join("."),
                         args,
                     })
                 } else {
@@ -328,7 +736,7 @@ impl<'a> Parser<'a> {
             }
             TokenKind::LParen => {
                 self.pos += 1;
-                let expr = self.parse_or()?;
+                let expr = self.parse_expr()?;
                 self.expect(
                     |k| matches!(k, TokenKind::RParen),
                     "expected ')' after expression",
@@ -346,6 +754,33 @@ impl<'a> Parser<'a> {
         &self.tokens[self.pos]
     }
 
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.current().kind, TokenKind::Ident(name) if name == keyword)
+    }
+
+    fn expect_keyword(&mut self, keyword: &str, message: &str) -> Result<(), SyamlError> {
+        if self.at_keyword(keyword) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(SyamlError::ExpressionError(format!(
+                "{} at {}",
+                message,
+                self.current().pos
+            )))
+        }
+    }
+
+    /// A stray `=` is almost always a mistyped `==`.
+    fn unexpected_assign(&self) -> Option<SyamlError> {
+        matches!(self.current().kind, TokenKind::Assign).then(|| {
+            SyamlError::ExpressionError(format!(
+                "unexpected '=' at {}; use '==' for equality",
+                self.current().pos
+            ))
+        })
+    }
+
     fn consume_if(&mut self, predicate: fn(&TokenKind) -> bool) -> Option<&Token> {
         if predicate(&self.current().kind) {
             let current = &self.tokens[self.pos];
@@ -363,6 +798,8 @@ impl<'a> Parser<'a> {
     ) -> Result<(), SyamlError> {
         if self.consume_if(predicate).is_some() {
             Ok(())
+        } else if let Some(err) = self.unexpected_assign() {
+            Err(err)
         } else {
             Err(SyamlError::ExpressionError(format!(
                 "{} at {}",
@@ -0,0 +1,189 @@
+//! Renders expression ASTs as Rust or TypeScript source for generated code.
+
+use super::parser::{BinaryOp, Expr, UnaryOp};
+
+/// Target language for [`render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    /// Rust source (`if c { a } else { b }`, `|x| body`).
+    Rust,
+    /// TypeScript/JavaScript source (`c ? a : b`, `(x) => body`).
+    TypeScript,
+}
+
+/// Renders `expr` as `target` source.
+///
+/// `var` maps variable paths to target identifiers; names bound by lambdas and
+/// `let` are written as-is. Compound operands are parenthesized, so operator
+/// precedence never depends on the target language.
+pub(crate) fn render(expr: &Expr, target: Target, var: &dyn Fn(&[String]) -> String) -> String {
+    Renderer { target, var }.expr(expr, &[])
+}
+
+struct Renderer<'a> {
+    target: Target,
+    var: &'a dyn Fn(&[String]) -> String,
+}
+
+impl Renderer<'_> {
+    fn expr(&self, expr: &Expr, bound: &[&str]) -> String {
+        match expr {
+            Expr::Number(n) => n.to_string(),
+            // Quantities travel as strings in both targets.
+            Expr::Quantity(q) => format!("{:?}", q.to_string()),
+            Expr::String(s) => match self.target {
+                Target::Rust => format!("{s:?}"),
+                Target::TypeScript => serde_json::to_string(s).unwrap_or_else(|_| format!("{s:?}")),
+            },
+            Expr::Bool(b) => b.to_string(),
+            Expr::Null => match self.target {
+                Target::Rust => "None".to_string(),
+                Target::TypeScript => "null".to_string(),
+            },
+            Expr::Var(path) => {
+                if path
+                    .first()
+                    .is_some_and(|head| bound.contains(&head.as_str()))
+                {
+                    path.join(".")
+                } else {
+                    (self.var)(path)
+                }
+            }
+            Expr::Unary { op, expr } => {
+                let symbol = match op {
+                    UnaryOp::Neg => "-",
+                    UnaryOp::Not => "!",
+                };
+                format!("{symbol}{}", self.operand(expr, bound))
+            }
+            Expr::Binary {
+                op: BinaryOp::Coalesce,
+                left,
+                right,
+            } if self.target == Target::Rust => format!(
+                "{}.unwrap_or({})",
+                self.operand(left, bound),
+                self.expr(right, bound)
+            ),
+            Expr::Binary { op, left, right } => format!(
+                "{} {} {}",
+                self.operand(left, bound),
+                op.symbol(),
+                self.operand(right, bound)
+            ),
+            Expr::Call { name, args } => {
+                let args: Vec<String> = args.iter().map(|arg| self.expr(arg, bound)).collect();
+                format!("{name}({})", args.join(", "))
+            }
+            Expr::Index {
+                target,
+                index,
+                optional,
+            } => {
+                let (target, index) = (self.operand(target, bound), self.expr(index, bound));
+                match (self.target, optional) {
+                    (_, false) => format!("{target}[{index}]"),
+                    (Target::Rust, true) => format!("{target}.and_then(|v| v.get({index}))"),
+                    (Target::TypeScript, true) => format!("{target}?.[{index}]"),
+                }
+            }
+            Expr::Lambda { param, body } => {
+                let body = self.expr(body, &with_name(bound, param));
+                match self.target {
+                    Target::Rust => format!("|{param}| {body}"),
+                    Target::TypeScript => format!("({param}) => {body}"),
+                }
+            }
+            Expr::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                // Rust branches are already delimited by braces.
+                let part = |e: &Expr| match self.target {
+                    Target::Rust => self.expr(e, bound),
+                    Target::TypeScript => self.operand(e, bound),
+                };
+                let (cond, then_branch, else_branch) =
+                    (part(cond), part(then_branch), part(else_branch));
+                match self.target {
+                    Target::Rust => {
+                        format!("if {cond} {{ {then_branch} }} else {{ {else_branch} }}")
+                    }
+                    Target::TypeScript => format!("{cond} ? {then_branch} : {else_branch}"),
+                }
+            }
+            Expr::Let { name, value, body } => {
+                let value = self.expr(value, bound);
+                let body = self.expr(body, &with_name(bound, name));
+                match self.target {
+                    Target::Rust => format!("{{ let {name} = {value}; {body} }}"),
+                    Target::TypeScript => format!("(({name}) => {body})({value})"),
+                }
+            }
+        }
+    }
+
+    /// Renders a sub-expression, parenthesized unless it is atomic.
+    fn operand(&self, expr: &Expr, bound: &[&str]) -> String {
+        let rendered = self.expr(expr, bound);
+        match expr {
+            Expr::Unary { .. }
+            | Expr::Binary { .. }
+            | Expr::Lambda { .. }
+            | Expr::Conditional { .. }
+            | Expr::Let { .. } => format!("({rendered})"),
+            _ => rendered,
+        }
+    }
+}
+
+fn with_name<'a>(bound: &[&'a str], name: &'a str) -> Vec<&'a str> {
+    let mut inner = bound.to_vec();
+    inner.push(name);
+    inner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, Target};
+    use crate::expr::parse_expression;
+
+    fn both(source: &str) -> (String, String) {
+        let expr = parse_expression(source).unwrap();
+        let var = |path: &[String]| path.join("_");
+        (
+            render(&expr, Target::Rust, &var),
+            render(&expr, Target::TypeScript, &var),
+        )
+    }
+
+    #[test]
+    fn renders_conditionals_and_let_bindings() {
+        let (rust, ts) = both("if input.a > 0 then input.a else -input.a");
+        assert_eq!(rust, "if input_a > 0 { input_a } else { -input_a }");
+        assert_eq!(ts, "(input_a > 0) ? input_a : (-input_a)");
+
+        let (rust, ts) = both("let d = data.x * 2 in d + 1 > output");
+        assert_eq!(rust, "{ let d = data_x * 2; (d + 1) > output }");
+        assert_eq!(ts, "((d) => (d + 1) > output)(data_x * 2)");
+    }
+
+    #[test]
+    fn keeps_bound_names_and_literals() {
+        let (rust, ts) = both("all(items, x => x.port != \"a\\\"b\")");
+        assert_eq!(rust, "all(items, |x| x.port != \"a\\\"b\")");
+        assert_eq!(ts, "all(items, (x) => x.port != \"a\\\"b\")");
+    }
+
+    #[test]
+    fn renders_null_safe_access_and_defaults() {
+        let (rust, ts) = both("input.tls?.port ?? 443");
+        assert_eq!(
+            rust,
+            "input_tls.and_then(|v| v.get(\"port\")).unwrap_or(443)"
+        );
+        assert_eq!(ts, "input_tls?.[\"port\"] ?? 443");
+    }
+}
@@ -0,0 +1,1338 @@
+//! Static type inference for expressions.
+//!
+//! Infers the result type of an expression from schema types, type hints and
+//! literal data without evaluating it, and reports unknown references,
+//! operator and argument mismatches and non-boolean conditions at the
+//! sub-expression that causes them.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use serde_json::Value as JsonValue;
+
+use crate::ast::PureFunctionDef;
+use crate::error::SyamlError;
+use crate::quantity::{Quantity, QUANTITY_TYPE_NAMES};
+use crate::resolve::{derived_value_order, is_literal_path};
+
+use super::number::Number;
+use super::parse_expression;
+use super::parser::{BinaryOp, Expr, UnaryOp};
+
+/// Function calls nested deeper than this while inferring a return type are
+/// treated as [`Type::Unknown`].
+const MAX_CALL_DEPTH: usize = 16;
+/// Derived values whose types are being inferred at once, each waiting on
+/// the next, beyond which further references are [`Type::Unknown`].
+const MAX_DERIVED_NESTING: usize = 64;
+
+/// Function bodies see no environment bindings.
+static NO_ENV: BTreeSet<String> = BTreeSet::new();
+
+#[derive(Debug, Clone, PartialEq)]
+/// Statically inferred type of an expression.
+pub enum Type {
+    /// Not known statically; accepted everywhere.
+    Unknown,
+    /// `null`.
+    Null,
+    /// `true` / `false`.
+    Boolean,
+    /// Whole number.
+    Integer,
+    /// Any number.
+    Number,
+    /// String.
+    String,
+    /// String spelling a duration, timestamp or byte size, such as a typed
+    /// env binding or constraint `value`. Operators other than `+`, `==` and
+    /// `!=` treat it as the quantity.
+    Spelled(&'static str),
+    /// Duration, timestamp or byte size from a literal, constructor call,
+    /// arithmetic or a hinted data value.
+    Quantity(&'static str),
+    /// Array with items of the given type.
+    Array(Box<Type>),
+    /// Object with exactly these fields.
+    Object(BTreeMap<String, Type>),
+    /// Object with arbitrary keys and values of the given type.
+    Map(Box<Type>),
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Unknown => "unknown",
+            Type::Null => "null",
+            Type::Boolean => "boolean",
+            Type::Integer => "integer",
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Spelled(kind) | Type::Quantity(kind) => kind,
+            Type::Array(_) => "array",
+            Type::Object(_) | Type::Map(_) => "object",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Type {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Type::Integer | Type::Number)
+    }
+
+    fn is_stringy(&self) -> bool {
+        matches!(self, Type::String | Type::Spelled(_))
+    }
+
+    fn is_object(&self) -> bool {
+        matches!(self, Type::Object(_) | Type::Map(_))
+    }
+
+    /// Type of the value once written to JSON: typed quantities become the
+    /// strings that spell them.
+    fn stored(self) -> Type {
+        match self {
+            Type::Quantity(kind) => Type::Spelled(kind),
+            other => other,
+        }
+    }
+
+    /// Narrowest type covering both `self` and `other`.
+    fn join(self, other: Type) -> Type {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Type::Unknown, _) | (_, Type::Unknown) => Type::Unknown,
+            (Type::Null, t) | (t, Type::Null) => t,
+            (a, b) if a.is_numeric() && b.is_numeric() => Type::Number,
+            (Type::Spelled(a) | Type::Quantity(a), Type::Spelled(b) | Type::Quantity(b))
+                if a == b =>
+            {
+                Type::Spelled(a)
+            }
+            (a, b) if a.is_stringy() && b.is_stringy() => Type::String,
+            (Type::Array(a), Type::Array(b)) => Type::Array(Box::new(a.join(*b))),
+            (Type::Map(a), Type::Map(b)) => Type::Map(Box::new(a.join(*b))),
+            (Type::Object(mut a), Type::Object(b)) => {
+                for (key, ty) in b {
+                    let joined = match a.remove(&key) {
+                        Some(existing) => existing.join(ty),
+                        None => ty,
+                    };
+                    a.insert(key, joined);
+                }
+                Type::Object(a)
+            }
+            _ => Type::Unknown,
+        }
+    }
+
+    /// Element type when used as a collection: array items or object values.
+    fn element(&self) -> Option<Type> {
+        match self {
+            Type::Unknown => Some(Type::Unknown),
+            Type::Array(item) | Type::Map(item) => Some((**item).clone()),
+            Type::Object(fields) => Some(
+                fields
+                    .values()
+                    .cloned()
+                    .reduce(Type::join)
+                    .unwrap_or(Type::Unknown),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Quantity kind of a typed quantity or a string spelling one.
+    fn quantity_kind(&self) -> Option<&'static str> {
+        match self {
+            Type::Quantity(kind) | Type::Spelled(kind) => Some(kind),
+            _ => None,
+        }
+    }
+}
+
+/// Names an expression can refer to, and their types.
+///
+/// Lookup follows the evaluator: lambda and `let` bindings, then named roots
+/// (`value`, `input`, `output`, `data`), `env`, import aliases, the data tree
+/// and finally the fields of the constraint scope.
+pub struct TypeEnv<'a> {
+    types: &'a BTreeMap<String, JsonValue>,
+    roots: BTreeMap<String, Type>,
+    scope: Option<Type>,
+    env: Option<&'a BTreeSet<String>>,
+    env_types: Option<&'a BTreeMap<String, JsonValue>>,
+    imports: Option<&'a BTreeMap<String, JsonValue>>,
+    data: Option<DataTypes<'a>>,
+    literals: Option<&'a BTreeSet<String>>,
+    functions: Option<&'a BTreeMap<String, PureFunctionDef>>,
+    call_depth: usize,
+}
+
+/// Data tree whose derived values are typed on demand.
+struct DataTypes<'a> {
+    root: &'a JsonValue,
+    hints: &'a BTreeMap<String, String>,
+    inferred: RefCell<HashMap<String, Type>>,
+    in_progress: RefCell<HashSet<String>>,
+}
+
+impl<'a> TypeEnv<'a> {
+    /// Environment with only the named schema types; names resolve through
+    /// roots and scope added with [`TypeEnv::with_root`] and
+    /// [`TypeEnv::with_scope`]. `env.*` references are not checked.
+    pub fn new(types: &'a BTreeMap<String, JsonValue>) -> Self {
+        Self {
+            types,
+            roots: BTreeMap::new(),
+            scope: None,
+            env: None,
+            env_types: None,
+            imports: None,
+            data: None,
+            literals: None,
+            functions: None,
+            call_depth: 0,
+        }
+    }
+
+    /// Resolves bare names against `data`, typed by `hints` where present and
+    /// by the literal values otherwise. Derived values are typed from their
+    /// expressions.
+    pub fn with_data(mut self, data: &'a JsonValue, hints: &'a BTreeMap<String, String>) -> Self {
+        self.data = Some(DataTypes {
+            root: data,
+            hints,
+            inferred: RefCell::new(HashMap::new()),
+            in_progress: RefCell::new(HashSet::new()),
+        });
+        self
+    }
+
+    /// Treats strings at or below these data paths (value overrides) as plain
+    /// strings rather than derived values.
+    pub(crate) fn with_literals(mut self, paths: &'a BTreeSet<String>) -> Self {
+        self.literals = Some(paths);
+        self
+    }
+
+    /// Restricts `env.NAME` references to the declared binding names.
+    pub fn with_env(mut self, names: &'a BTreeSet<String>) -> Self {
+        self.env = Some(names);
+        self
+    }
+
+    /// Types `env.NAME` references by the `type:` of their binding; other
+    /// bindings stay unknown.
+    pub fn with_env_types(mut self, types: &'a BTreeMap<String, JsonValue>) -> Self {
+        self.env_types = Some(types);
+        self
+    }
+
+    /// Resolves `alias.path` references against compiled import data.
+    pub fn with_imports(mut self, imports: &'a BTreeMap<String, JsonValue>) -> Self {
+        self.imports = Some(imports);
+        self
+    }
+
+    /// Checks calls to user-defined `functions`. Without them, calls to
+    /// anything but a built-in are left to the evaluator.
+    pub fn with_functions(mut self, functions: &'a BTreeMap<String, PureFunctionDef>) -> Self {
+        self.functions = Some(functions);
+        self
+    }
+
+    /// Environment of the body of `function`: its parameters and the other
+    /// functions, nothing else.
+    fn function_env(&self, function: &PureFunctionDef) -> TypeEnv<'a> {
+        let mut env = TypeEnv::new(self.types).with_env(&NO_ENV);
+        env.functions = self.functions;
+        env.call_depth = self.call_depth + 1;
+        for param in &function.params {
+            let ty = self.schema_type(&param.type_ref);
+            env = env.with_root(&param.name, ty);
+        }
+        env
+    }
+
+    /// Binds a named root such as `value`, `input` or `output`.
+    pub fn with_root(mut self, name: &str, ty: Type) -> Self {
+        self.roots.insert(name.to_string(), ty);
+        self
+    }
+
+    /// Resolves bare names against the fields of `ty` (a constrained type).
+    pub fn with_scope(mut self, ty: Type) -> Self {
+        self.scope = Some(ty);
+        self
+    }
+
+    /// Type of the whole data tree, or [`Type::Unknown`] without data.
+    pub fn data_type(&self) -> Type {
+        match &self.data {
+            Some(data) => self.data_value_type(data, data.root, "$"),
+            None => Type::Unknown,
+        }
+    }
+
+    /// Type of values matching `schema`.
+    pub fn schema_type(&self, schema: &JsonValue) -> Type {
+        self.schema_type_in(schema, &mut Vec::new())
+    }
+
+    /// Type of values of the named type, built-in or from the schema.
+    pub fn named_type(&self, type_name: &str) -> Type {
+        self.named_type_in(type_name, &mut Vec::new())
+    }
+
+    /// `expanding` holds the named types being expanded around `schema`; a
+    /// recursive type is [`Type::Unknown`] where it recurs, which keeps the
+    /// type finite.
+    fn schema_type_in(&self, schema: &JsonValue, expanding: &mut Vec<String>) -> Type {
+        let obj = match schema {
+            JsonValue::String(type_name) => return self.named_type_in(type_name, expanding),
+            JsonValue::Object(obj) => obj,
+            _ => return Type::Unknown,
+        };
+        match obj.get("type").and_then(JsonValue::as_str) {
+            Some("array") => Type::Array(Box::new(
+                obj.get("items")
+                    .map(|items| self.schema_type_in(items, expanding))
+                    .unwrap_or(Type::Unknown),
+            )),
+            Some("object") | None => {
+                if let Some(props) = obj.get("properties").and_then(JsonValue::as_object) {
+                    Type::Object(
+                        props
+                            .iter()
+                            .map(|(k, v)| (k.clone(), self.schema_type_in(v, expanding)))
+                            .collect(),
+                    )
+                } else if let Some(values) = obj.get("values") {
+                    Type::Map(Box::new(self.schema_type_in(values, expanding)))
+                } else {
+                    Type::Unknown
+                }
+            }
+            Some(type_name) => self.named_type_in(type_name, expanding),
+        }
+    }
+
+    fn named_type_in(&self, type_name: &str, expanding: &mut Vec<String>) -> Type {
+        match type_name {
+            "string" => return Type::String,
+            "integer" => return Type::Integer,
+            "number" => return Type::Number,
+            "boolean" => return Type::Boolean,
+            "null" => return Type::Null,
+            "array" => return Type::Array(Box::new(Type::Unknown)),
+            _ => {}
+        }
+        if let Some(kind) = quantity_kind(type_name) {
+            return Type::Spelled(kind);
+        }
+        match self.types.get(type_name) {
+            Some(schema) if !expanding.iter().any(|name| name == type_name) => {
+                expanding.push(type_name.to_string());
+                let ty = self.schema_type_in(schema, expanding);
+                expanding.pop();
+                ty
+            }
+            _ => Type::Unknown,
+        }
+    }
+
+    /// Infers the type of `expr`, appending one message per problem found.
+    pub fn infer(&self, expr: &Expr, issues: &mut Vec<String>) -> Type {
+        self.infer_in(expr, &mut Vec::new(), issues)
+    }
+
+    fn infer_in(
+        &self,
+        expr: &Expr,
+        locals: &mut Vec<(String, Type)>,
+        issues: &mut Vec<String>,
+    ) -> Type {
+        match expr {
+            Expr::Number(Number::Int(_)) => Type::Integer,
+            Expr::Number(_) => Type::Number,
+            Expr::Quantity(q) => Type::Quantity(q.type_name()),
+            Expr::String(_) => Type::String,
+            Expr::Bool(_) => Type::Boolean,
+            Expr::Null => Type::Null,
+            Expr::Var(path) => match self.resolve_var(path, locals) {
+                Ok(ty) => ty,
+                Err(message) => {
+                    issues.push(message);
+                    Type::Unknown
+                }
+            },
+            Expr::Unary { op, expr: inner } => {
+                let ty = self.infer_in(inner, locals, issues);
+                match (op, ty) {
+                    (_, Type::Unknown) => Type::Unknown,
+                    (UnaryOp::Not, Type::Boolean) => Type::Boolean,
+                    (UnaryOp::Not, ty) => {
+                        report(issues, expr, format!("'!' expects boolean, got {ty}"))
+                    }
+                    (UnaryOp::Neg, ty @ (Type::Integer | Type::Number)) => ty,
+                    (UnaryOp::Neg, Type::Quantity("duration")) => Type::Quantity("duration"),
+                    (UnaryOp::Neg, ty) => report(issues, expr, format!("cannot negate {ty}")),
+                }
+            }
+            Expr::Binary { op, left, right } => {
+                let l = self.infer_in(left, locals, issues);
+                let r = self.infer_in(right, locals, issues);
+                binary_type(*op, &l, &r).unwrap_or_else(|m| report(issues, expr, m))
+            }
+            Expr::Call { name, args } => match self.call_type(name, args, locals, issues) {
+                Ok(ty) => ty,
+                Err(message) => report(issues, expr, message),
+            },
+            Expr::Index {
+                target,
+                index,
+                optional,
+            } => {
+                let target_ty = self.infer_in(target, locals, issues);
+                let index_ty = self.infer_in(index, locals, issues);
+                match (&target_ty, &**index) {
+                    // `a?.b` is null when `a` is null or has no `b`.
+                    (Type::Null, _) if *optional => Type::Null,
+                    (Type::Object(fields), Expr::String(key))
+                        if *optional && !fields.contains_key(key) =>
+                    {
+                        Type::Null
+                    }
+                    _ => index_type(&target_ty, &index_ty, index)
+                        .unwrap_or_else(|m| report(issues, expr, m)),
+                }
+            }
+            Expr::Lambda { .. } => report(
+                issues,
+                expr,
+                "lambda expressions are only allowed as arguments to collection functions"
+                    .to_string(),
+            ),
+            Expr::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let cond_ty = self.infer_in(cond, locals, issues);
+                if !matches!(cond_ty, Type::Boolean | Type::Unknown) {
+                    issues.push(format!(
+                        "condition must be boolean, got {cond_ty} in '{cond}'"
+                    ));
+                }
+                let then_ty = self.infer_in(then_branch, locals, issues).stored();
+                let else_ty = self.infer_in(else_branch, locals, issues).stored();
+                then_ty.join(else_ty)
+            }
+            Expr::Let { name, value, body } => {
+                let value_ty = self.infer_in(value, locals, issues).stored();
+                locals.push((name.clone(), value_ty));
+                let body_ty = self.infer_in(body, locals, issues);
+                locals.pop();
+                body_ty
+            }
+        }
+    }
+
+    fn resolve_var(&self, path: &[String], locals: &[(String, Type)]) -> Result<Type, String> {
+        let Some((head, rest)) = path.split_first() else {
+            return Err("empty variable path".to_string());
+        };
+        let unknown = || format!("unknown reference '{}'", path.join("."));
+
+        if let Some((_, ty)) = locals.iter().rev().find(|(name, _)| name == head) {
+            return field_type(ty, rest, path);
+        }
+        if let Some(ty) = self.roots.get(head) {
+            return field_type(ty, rest, path);
+        }
+        if head == "env" {
+            let [name] = rest else {
+                return Err(format!(
+                    "env reference must be env.NAME, got {}",
+                    path.join(".")
+                ));
+            };
+            return match self.env {
+                Some(names) if !names.contains(name) => {
+                    Err(format!("unknown env binding '{name}'"))
+                }
+                _ => Ok(self
+                    .env_types
+                    .and_then(|types| types.get(name))
+                    .map_or(Type::Unknown, |type_ref| self.schema_type(type_ref))),
+            };
+        }
+        if head == "value" {
+            return Err("'value' is only available in constraint expressions".to_string());
+        }
+        if let Some(import) = self.imports.and_then(|imports| imports.get(head)) {
+            return field_type(&literal_type(import), rest, path).map_err(|_| unknown());
+        }
+        if let Some(data) = &self.data {
+            if let Some(ty) = self.data_path_type(data, path)? {
+                return Ok(ty);
+            }
+        }
+        if let Some(scope) = &self.scope {
+            if let Ok(ty) = field_type(scope, path, path) {
+                return Ok(ty);
+            }
+        }
+        // A known head with a missing field is a lookup miss, not a bad name.
+        let head_found = self
+            .data
+            .as_ref()
+            .is_some_and(|data| data.root.get(head).is_some());
+        if !rest.is_empty() && head_found {
+            return Err(format!("path '{}' not found", path.join(".")));
+        }
+        Err(unknown())
+    }
+
+    /// Type of the data value at `path`, or `None` when the data has no such
+    /// key.
+    fn data_path_type(
+        &self,
+        data: &DataTypes<'_>,
+        path: &[String],
+    ) -> Result<Option<Type>, String> {
+        let mut current = data.root;
+        let mut current_path = "$".to_string();
+        for (i, segment) in path.iter().enumerate() {
+            let Some(child) = current.as_object().and_then(|map| map.get(segment)) else {
+                return Ok(None);
+            };
+            current_path = format!("{current_path}.{segment}");
+            let derived = child
+                .as_str()
+                .is_some_and(|raw| self.is_derived_at(raw, &current_path));
+            if derived || data.hints.contains_key(&current_path) {
+                let ty = self.data_value_type(data, child, &current_path);
+                return field_type(&ty, &path[i + 1..], path).map(|ty| Some(typed_quantity(ty)));
+            }
+            current = child;
+        }
+        Ok(Some(typed_quantity(self.data_value_type(
+            data,
+            current,
+            &current_path,
+        ))))
+    }
+
+    fn data_value_type(&self, data: &DataTypes<'_>, value: &JsonValue, path: &str) -> Type {
+        if let Some(type_name) = data.hints.get(path) {
+            return self.named_type(type_name);
+        }
+        match value {
+            JsonValue::String(raw) if self.is_derived_at(raw, path) => {
+                self.derived_type(data, raw, path)
+            }
+            JsonValue::Array(items) => Type::Array(Box::new(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| self.data_value_type(data, item, &format!("{path}[{i}]")))
+                    .reduce(Type::join)
+                    .unwrap_or(Type::Unknown),
+            )),
+            JsonValue::Object(map) => Type::Object(
+                map.iter()
+                    .map(|(k, v)| {
+                        (
+                            k.clone(),
+                            self.data_value_type(data, v, &format!("{path}.{k}")),
+                        )
+                    })
+                    .collect(),
+            ),
+            other => literal_type(other),
+        }
+    }
+
+    /// Whether the data string `raw` at `path` is a derived value.
+    fn is_derived_at(&self, raw: &str, path: &str) -> bool {
+        is_derived(raw)
+            && !self
+                .literals
+                .is_some_and(|literals| is_literal_path(path, literals))
+    }
+
+    /// Type of the derived value `raw` at `path`. Problems are reported where
+    /// the value itself is checked, and cycles are left to the resolver.
+    fn derived_type(&self, data: &DataTypes<'_>, raw: &str, path: &str) -> Type {
+        if let Some(ty) = data.inferred.borrow().get(path) {
+            return ty.clone();
+        }
+        // Values typed out of order (in a cycle) nest; past the limit they
+        // stay unknown rather than exhaust the stack.
+        if data.in_progress.borrow().len() >= MAX_DERIVED_NESTING
+            || !data.in_progress.borrow_mut().insert(path.to_string())
+        {
+            return Type::Unknown;
+        }
+        let ty = self.infer_derived(raw, &mut Vec::new());
+        data.in_progress.borrow_mut().remove(path);
+        data.inferred
+            .borrow_mut()
+            .insert(path.to_string(), ty.clone());
+        ty
+    }
+
+    /// Infers the type of a derived data value (`=expr` or a string with
+    /// `${...}` interpolations). Unparseable expressions are left to the
+    /// resolver and typed [`Type::Unknown`].
+    pub fn infer_derived(&self, raw: &str, issues: &mut Vec<String>) -> Type {
+        let raw = raw.trim();
+        if let Some(source) = raw.strip_prefix('=') {
+            return match parse_expression(source.trim()) {
+                Ok(expr) => self.infer(&expr, issues).stored(),
+                Err(_) => Type::Unknown,
+            };
+        }
+        let segments = interpolation_segments(raw);
+        let whole = segments.len() == 1 && segments[0].0 == 0 && segments[0].1 == raw.len();
+        let mut ty = Type::String;
+        for (_, _, source) in &segments {
+            if let Ok(expr) = parse_expression(source.trim()) {
+                let inner = self.infer(&expr, issues).stored();
+                if whole {
+                    ty = inner;
+                }
+            } else if whole {
+                ty = Type::Unknown;
+            }
+        }
+        ty
+    }
+
+    fn call_type(
+        &self,
+        name: &str,
+        args: &[Expr],
+        locals: &mut Vec<(String, Type)>,
+        issues: &mut Vec<String>,
+    ) -> Result<Type, String> {
+        if let Some(function) = self.functions.and_then(|functions| functions.get(name)) {
+            return self.user_call_type(name, function, args, locals, issues);
+        }
+        if let Some(lambda_required) = match name {
+            "map" | "filter" | "all" | "any" => Some(true),
+            "sum" | "count" | "unique" | "sort" => Some(false),
+            _ => None,
+        } {
+            return self.higher_order_type(name, args, lambda_required, locals, issues);
+        }
+
+        let mut arg_types = Vec::with_capacity(args.len());
+        for arg in args {
+            arg_types.push(self.infer_in(arg, locals, issues).stored());
+        }
+        let tys = arg_types.as_slice();
+        let arity = |min: usize, max: usize| -> Result<(), String> {
+            if (min..=max).contains(&tys.len()) {
+                return Ok(());
+            }
+            Err(match (min, max) {
+                (min, usize::MAX) => {
+                    format!("{name} expects at least {min} arguments, got {}", tys.len())
+                }
+                (min, max) if min == max => {
+                    format!("{name} expects {min} arguments, got {}", tys.len())
+                }
+                (min, max) => {
+                    format!("{name} expects {min} or {max} arguments, got {}", tys.len())
+                }
+            })
+        };
+        let expect = |index: usize, expected: &str, ok: fn(&Type) -> bool| -> Result<(), String> {
+            match tys.get(index) {
+                Some(ty) if *ty != Type::Unknown && !ok(ty) => Err(format!(
+                    "{name}() expects argument {} to be {expected}, got {ty}",
+                    index + 1
+                )),
+                _ => Ok(()),
+            }
+        };
+        let string = |ty: &Type| ty.is_stringy();
+        let number = |ty: &Type| ty.is_numeric();
+
+        match name {
+            "min" | "max" => {
+                arity(1, usize::MAX)?;
+                let mut result = Type::Unknown;
+                for ty in tys {
+                    let kind = match ty {
+                        Type::Unknown => continue,
+                        Type::Integer | Type::Number => ty.clone(),
+                        Type::Spelled(kind) => Type::Spelled(kind),
+                        Type::String => Type::Unknown,
+                        other => {
+                            return Err(format!(
+                                "{name}() expects numbers, durations, timestamps or byte sizes, got {other}"
+                            ))
+                        }
+                    };
+                    result = match (result, kind) {
+                        (Type::Unknown, kind) | (kind, Type::Unknown) => kind,
+                        (a, b) if a.is_numeric() && b.is_numeric() => a.join(b),
+                        (a, b) if a == b => a,
+                        (a, b) => return Err(format!("{name}() cannot compare {a} and {b}")),
+                    };
+                }
+                Ok(result)
+            }
+            "abs" => {
+                arity(1, 1)?;
+                expect(0, "a number", number)?;
+                Ok(tys[0].clone())
+            }
+            "floor" | "ceil" | "round" => {
+                arity(1, 1)?;
+                expect(0, "a number", number)?;
+                Ok(Type::Integer)
+            }
+            "len" => {
+                arity(1, 1)?;
+                expect(0, "a string, array, or object", |ty| {
+                    ty.is_stringy() || ty.is_object() || matches!(ty, Type::Array(_))
+                })?;
+                Ok(Type::Integer)
+            }
+            "coalesce" => {
+                arity(1, usize::MAX)?;
+                Ok(tys.iter().cloned().reduce(Type::join).unwrap_or(Type::Null))
+            }
+            "keys" | "values" => {
+                arity(1, 1)?;
+                expect(0, "an object", Type::is_object)?;
+                Ok(Type::Array(Box::new(if name == "keys" {
+                    Type::String
+                } else {
+                    tys[0].element().unwrap_or(Type::Unknown)
+                })))
+            }
+            "contains" => {
+                arity(2, 2)?;
+                match &tys[0] {
+                    ty if ty.is_stringy() => expect(1, "a string", string)?,
+                    ty if ty.is_object() => expect(1, "a string", string)?,
+                    Type::Array(_) | Type::Unknown => {}
+                    other => {
+                        return Err(format!(
+                            "contains() expects argument 1 to be a string, array, or object, got {other}"
+                        ))
+                    }
+                }
+                Ok(Type::Boolean)
+            }
+            "duration" | "timestamp" | "bytesize" => {
+                arity(1, 1)?;
+                let kind = quantity_kind(name).expect("quantity constructor");
+                match &tys[0] {
+                    Type::String | Type::Unknown => {}
+                    Type::Integer if kind == "bytesize" => {}
+                    Type::Spelled(k) if *k == kind => {}
+                    other => {
+                        return Err(format!(
+                            "{name}() expects argument 1 to be a {kind} string, got {other}"
+                        ))
+                    }
+                }
+                Ok(Type::Quantity(kind))
+            }
+            "seconds" | "milliseconds" | "bytes" | "unix_seconds" => {
+                arity(1, 1)?;
+                let kind = match name {
+                    "bytes" => "bytesize",
+                    "unix_seconds" => "timestamp",
+                    _ => "duration",
+                };
+                match &tys[0] {
+                    Type::String | Type::Unknown => {}
+                    Type::Integer if kind == "bytesize" => {}
+                    ty if ty.quantity_kind() == Some(kind) => {}
+                    other => {
+                        return Err(format!(
+                            "{name}() expects argument 1 to be a {kind}, got {other}"
+                        ))
+                    }
+                }
+                Ok(if name == "bytes" {
+                    Type::Integer
+                } else {
+                    Type::Number
+                })
+            }
+            "lower" | "upper" | "trim" => {
+                arity(1, 1)?;
+                expect(0, "a string", string)?;
+                Ok(Type::String)
+            }
+            "starts_with" | "ends_with" | "matches" => {
+                arity(2, 2)?;
+                expect(0, "a string", string)?;
+                expect(1, "a string", string)?;
+                Ok(Type::Boolean)
+            }
+            "replace" => {
+                arity(3, 3)?;
+                for i in 0..3 {
+                    expect(i, "a string", string)?;
+                }
+                Ok(Type::String)
+            }
+            "split" => {
+                arity(2, 2)?;
+                expect(0, "a string", string)?;
+                expect(1, "a string", string)?;
+                Ok(Type::Array(Box::new(Type::String)))
+            }
+            "join" => {
+                arity(2, 2)?;
+                expect(0, "an array", |ty| matches!(ty, Type::Array(_)))?;
+                expect(1, "a string", string)?;
+                Ok(Type::String)
+            }
+            "substr" => {
+                arity(2, 3)?;
+                expect(0, "a string", string)?;
+                expect(1, "a number", number)?;
+                expect(2, "a number", number)?;
+                Ok(Type::String)
+            }
+            "pad_left" => {
+                arity(2, 3)?;
+                expect(0, "a string", string)?;
+                expect(1, "a number", number)?;
+                expect(2, "a string", string)?;
+                Ok(Type::String)
+            }
+            "format" => {
+                arity(1, usize::MAX)?;
+                expect(0, "a string", string)?;
+                Ok(Type::String)
+            }
+            _ if self.functions.is_none() && !is_builtin_function(name) => Ok(Type::Unknown),
+            _ => Err(format!("unknown function '{name}'")),
+        }
+    }
+
+    fn user_call_type(
+        &self,
+        name: &str,
+        function: &PureFunctionDef,
+        args: &[Expr],
+        locals: &mut Vec<(String, Type)>,
+        issues: &mut Vec<String>,
+    ) -> Result<Type, String> {
+        if args.len() != function.params.len() {
+            return Err(format!(
+                "{name} expects {} arguments, got {}",
+                function.params.len(),
+                args.len()
+            ));
+        }
+        for (i, (arg, param)) in args.iter().zip(&function.params).enumerate() {
+            let ty = self.infer_in(arg, locals, issues).stored();
+            let expected = self.schema_type(&param.type_ref);
+            if !assignable(&ty, &expected) {
+                return Err(format!(
+                    "{name}() expects argument {} ('{}') to be {}, got {ty}",
+                    i + 1,
+                    param.name,
+                    type_ref_name(&param.type_ref)
+                ));
+            }
+        }
+        if let Some(returns) = &function.returns {
+            return Ok(self.schema_type(returns));
+        }
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Ok(Type::Unknown);
+        }
+        // Problems in the body are reported where the function is defined.
+        Ok(match parse_expression(&function.body) {
+            Ok(body) => self
+                .function_env(function)
+                .infer(&body, &mut Vec::new())
+                .stored(),
+            Err(_) => Type::Unknown,
+        })
+    }
+
+    fn higher_order_type(
+        &self,
+        name: &str,
+        args: &[Expr],
+        lambda_required: bool,
+        locals: &mut Vec<(String, Type)>,
+        issues: &mut Vec<String>,
+    ) -> Result<Type, String> {
+        let (min, expected) = if lambda_required {
+            (2, "2")
+        } else {
+            (1, "1 or 2")
+        };
+        if !(min..=2).contains(&args.len()) {
+            return Err(format!(
+                "{name} expects {expected} arguments, got {}",
+                args.len()
+            ));
+        }
+
+        let collection = self.infer_in(&args[0], locals, issues).stored();
+        let arrays_only = matches!(name, "unique" | "sort");
+        let item = match collection.element() {
+            Some(_) if arrays_only && collection.is_object() => None,
+            item => item,
+        };
+        let Some(item) = item else {
+            let expected = if arrays_only {
+                "an array"
+            } else {
+                "an array or object"
+            };
+            return Err(format!(
+                "{name}() expects argument 1 to be {expected}, got {collection}"
+            ));
+        };
+
+        let result = match args.get(1) {
+            Some(Expr::Lambda { param, body }) => {
+                locals.push((param.clone(), item.clone()));
+                let ty = self.infer_in(body, locals, issues).stored();
+                locals.pop();
+                ty
+            }
+            Some(_) => {
+                return Err(format!(
+                    "{name}() expects argument 2 to be a lambda such as 'x => x.port'"
+                ))
+            }
+            None => item.clone(),
+        };
+        let predicate =
+            matches!(name, "filter" | "all" | "any") || (name == "count" && args.len() == 2);
+        if predicate && !matches!(result, Type::Boolean | Type::Unknown) {
+            return Err(format!("{name}() lambda must return boolean, got {result}"));
+        }
+        if name == "sum" && !matches!(result, Type::Integer | Type::Number | Type::Unknown) {
+            return Err(format!("sum() expects numbers, got {result}"));
+        }
+
+        Ok(match name {
+            "map" => match collection {
+                Type::Array(_) => Type::Array(Box::new(result)),
+                Type::Unknown => Type::Unknown,
+                _ => Type::Map(Box::new(result)),
+            },
+            "filter" | "unique" | "sort" => collection,
+            "all" | "any" => Type::Boolean,
+            "count" => Type::Integer,
+            _ if result == Type::Integer => Type::Integer,
+            _ => Type::Number,
+        })
+    }
+}
+
+/// Records `message` against the sub-expression `expr`.
+fn report(issues: &mut Vec<String>, expr: &Expr, message: String) -> Type {
+    issues.push(format!("{message} in '{expr}'"));
+    Type::Unknown
+}
+
+/// Type of a plain JSON value (strings spelling a quantity included).
+fn literal_type(value: &JsonValue) -> Type {
+    match value {
+        JsonValue::Null => Type::Null,
+        JsonValue::Bool(_) => Type::Boolean,
+        JsonValue::Number(n) if n.is_i64() || n.is_u64() => Type::Integer,
+        JsonValue::Number(_) => Type::Number,
+        JsonValue::String(s) => Quantity::parse(s)
+            .map(|q| Type::Spelled(q.type_name()))
+            .unwrap_or(Type::String),
+        JsonValue::Array(items) => Type::Array(Box::new(
+            items
+                .iter()
+                .map(literal_type)
+                .reduce(Type::join)
+                .unwrap_or(Type::Unknown),
+        )),
+        JsonValue::Object(map) => Type::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), literal_type(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Follows field accesses `rest` from a value of type `ty`; `path` is the
+/// whole reference, for messages.
+fn field_type(ty: &Type, rest: &[String], path: &[String]) -> Result<Type, String> {
+    let mut current = ty.clone();
+    for segment in rest {
+        current = match current {
+            Type::Unknown => return Ok(Type::Unknown),
+            Type::Object(mut fields) => fields.remove(segment).ok_or_else(|| {
+                format!(
+                    "path '{}' not found: object has no field '{segment}'",
+                    path.join(".")
+                )
+            })?,
+            Type::Map(values) => *values,
+            other => {
+                return Err(format!(
+                    "path '{}' not found: {other} has no field '{segment}'",
+                    path.join(".")
+                ))
+            }
+        };
+    }
+    Ok(current)
+}
+
+fn index_type(target: &Type, index: &Type, index_expr: &Expr) -> Result<Type, String> {
+    match target {
+        Type::Unknown => Ok(Type::Unknown),
+        Type::Array(item) => match index {
+            Type::Integer | Type::Number | Type::Unknown => Ok((**item).clone()),
+            other => Err(format!("array index must be a number, got {other}")),
+        },
+        Type::Object(fields) => match (index, index_expr) {
+            (_, Expr::String(key)) => fields
+                .get(key)
+                .cloned()
+                .ok_or_else(|| format!("key '{key}' not found")),
+            (Type::Unknown, _) => Ok(Type::Unknown),
+            (ty, _) if ty.is_stringy() => Ok(target.element().unwrap_or(Type::Unknown)),
+            (other, _) => Err(format!("object key must be a string, got {other}")),
+        },
+        Type::Map(values) => match index {
+            Type::Unknown => Ok((**values).clone()),
+            ty if ty.is_stringy() => Ok((**values).clone()),
+            other => Err(format!("object key must be a string, got {other}")),
+        },
+        other => Err(format!("cannot index into {other}")),
+    }
+}
+
+/// Result type of `left op right`, mirroring the evaluator's operator rules.
+fn binary_type(op: BinaryOp, left: &Type, right: &Type) -> Result<Type, String> {
+    let mismatch = || {
+        Err(format!(
+            "cannot apply '{}' to {left} and {right}",
+            op.symbol()
+        ))
+    };
+    let comparison = matches!(
+        op,
+        BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte
+    );
+
+    if matches!(op, BinaryOp::Coalesce) {
+        return match left.clone().join(right.clone()) {
+            Type::Unknown if *left != Type::Unknown && *right != Type::Unknown => mismatch(),
+            ty => Ok(ty),
+        };
+    }
+
+    if matches!(op, BinaryOp::And | BinaryOp::Or) {
+        return match (left, right) {
+            (Type::Boolean | Type::Unknown, Type::Boolean | Type::Unknown) => Ok(Type::Boolean),
+            _ => mismatch(),
+        };
+    }
+
+    let typed = matches!(left, Type::Quantity(_)) || matches!(right, Type::Quantity(_));
+    let spelled = matches!(left, Type::Spelled(_)) || matches!(right, Type::Spelled(_));
+    let string_op = matches!(op, BinaryOp::Add | BinaryOp::Eq | BinaryOp::NotEq);
+    if typed || (spelled && !string_op) {
+        return quantity_binary_type(op, left, right).map_or_else(mismatch, Ok);
+    }
+
+    match op {
+        BinaryOp::Eq | BinaryOp::NotEq => {
+            let comparable = match (left, right) {
+                (Type::Unknown | Type::Null, _) | (_, Type::Unknown | Type::Null) => true,
+                (a, b) if a.is_numeric() && b.is_numeric() => true,
+                (a, b) if a.is_stringy() && b.is_stringy() => true,
+                (a, b) if a.is_object() && b.is_object() => true,
+                (Type::Boolean, Type::Boolean) | (Type::Array(_), Type::Array(_)) => true,
+                _ => false,
+            };
+            if comparable {
+                Ok(Type::Boolean)
+            } else {
+                Err(format!(
+                    "'{}' compares {left} with {right}, which is never equal",
+                    op.symbol()
+                ))
+            }
+        }
+        BinaryOp::Add if left.is_stringy() || right.is_stringy() => Ok(Type::String),
+        _ => {
+            let numeric = |ty: &Type| ty.is_numeric() || *ty == Type::Unknown;
+            if !numeric(left) || !numeric(right) {
+                return mismatch();
+            }
+            Ok(if comparison {
+                Type::Boolean
+            } else if *left == Type::Unknown || *right == Type::Unknown {
+                Type::Unknown
+            } else if *left == Type::Integer
+                && *right == Type::Integer
+                && !matches!(op, BinaryOp::Div)
+            {
+                Type::Integer
+            } else {
+                Type::Number
+            })
+        }
+    }
+}
+
+/// Operator on durations, timestamps and byte sizes; `None` when the
+/// evaluator would reject the combination.
+fn quantity_binary_type(op: BinaryOp, left: &Type, right: &Type) -> Option<Type> {
+    enum Operand {
+        Unknown,
+        Number,
+        Quantity(&'static str),
+    }
+    let other_kind = left.quantity_kind().or(right.quantity_kind());
+    let operand = |ty: &Type| match ty {
+        Type::Unknown => Some(Operand::Unknown),
+        Type::Integer | Type::Number => Some(Operand::Number),
+        Type::Quantity(kind) | Type::Spelled(kind) => Some(Operand::Quantity(kind)),
+        // A string may spell a quantity of the other operand's kind.
+        Type::String => other_kind.map(Operand::Quantity),
+        _ => None,
+    };
+    let (l, r) = (operand(left)?, operand(right)?);
+    let compares = matches!(
+        op,
+        BinaryOp::Eq
+            | BinaryOp::NotEq
+            | BinaryOp::Lt
+            | BinaryOp::Lte
+            | BinaryOp::Gt
+            | BinaryOp::Gte
+    );
+    let result = match (op, l, r) {
+        (_, Operand::Quantity(a), Operand::Quantity(b)) if compares && a == b => Type::Boolean,
+        (_, Operand::Number, Operand::Number) if compares => Type::Boolean,
+        (_, Operand::Unknown, _) | (_, _, Operand::Unknown) if compares => Type::Boolean,
+        (_, Operand::Unknown, _) | (_, _, Operand::Unknown) => Type::Unknown,
+        (
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mod,
+            Operand::Quantity(a @ ("duration" | "bytesize")),
+            Operand::Quantity(b),
+        ) if a == b => Type::Quantity(a),
+        (BinaryOp::Add, Operand::Quantity("timestamp"), Operand::Quantity("duration"))
+        | (BinaryOp::Add, Operand::Quantity("duration"), Operand::Quantity("timestamp"))
+        | (BinaryOp::Sub, Operand::Quantity("timestamp"), Operand::Quantity("duration")) => {
+            Type::Quantity("timestamp")
+        }
+        (BinaryOp::Sub, Operand::Quantity("timestamp"), Operand::Quantity("timestamp")) => {
+            Type::Quantity("duration")
+        }
+        (BinaryOp::Div, Operand::Quantity(a), Operand::Quantity(b))
+            if a == b && a != "timestamp" =>
+        {
+            Type::Number
+        }
+        (
+            BinaryOp::Mul | BinaryOp::Div,
+            Operand::Quantity(kind @ ("duration" | "bytesize")),
+            Operand::Number,
+        )
+        | (BinaryOp::Mul, Operand::Number, Operand::Quantity(kind @ ("duration" | "bytesize"))) => {
+            Type::Quantity(kind)
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Names of the built-in functions.
+fn is_builtin_function(name: &str) -> bool {
+    crate::expr::eval::BUILTIN_FUNCTIONS.contains(&name)
+}
+
+/// How a parameter or result type is written, for messages.
+fn type_ref_name(type_ref: &JsonValue) -> String {
+    match type_ref {
+        JsonValue::String(name) => name.clone(),
+        other => other
+            .get("type")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("object")
+            .to_string(),
+    }
+}
+
+/// A typed data value read by path is the quantity it spells, matching the
+/// evaluator's reading of hinted `duration`, `timestamp` and `bytesize`
+/// values.
+fn typed_quantity(ty: Type) -> Type {
+    match ty {
+        Type::Spelled(kind) => Type::Quantity(kind),
+        other => other,
+    }
+}
+
+fn quantity_kind(type_name: &str) -> Option<&'static str> {
+    QUANTITY_TYPE_NAMES
+        .iter()
+        .find(|name| **name == type_name)
+        .copied()
+}
+
+/// Whether a data string is a derived value: an `=expression` or a string
+/// with `${...}` interpolations.
+fn is_derived(raw: &str) -> bool {
+    let trimmed = raw.trim();
+    trimmed.starts_with('=') || trimmed.contains("${")
+}
+
+/// `(start, end, source)` of each `${...}` segment.
+fn interpolation_segments(raw: &str) -> Vec<(usize, usize, &str)> {
+    crate::resolve::interpolation_regex()
+        .captures_iter(raw)
+        .filter_map(|caps| {
+            let whole = caps.get(0)?;
+            Some((whole.start(), whole.end(), caps.get(1)?.as_str()))
+        })
+        .collect()
+}
+
+/// Checks every derived value in `data` against the types of what it
+/// references, and derived values with a type hint against the hinted type.
+///
+/// Each problem is reported as an expression error at the value's data path.
+pub fn check_data_expressions(env: &TypeEnv<'_>) -> Result<(), SyamlError> {
+    let Some(data) = &env.data else {
+        return Ok(());
+    };
+    let mut nodes = Vec::new();
+    collect_derived(data.root, "$", &mut nodes);
+    nodes.retain(|(path, raw)| env.is_derived_at(raw, path));
+
+    // Typing values in evaluation order finds each one's references already
+    // typed, so long chains do not nest.
+    let raw_at: HashMap<&str, &str> = nodes
+        .iter()
+        .map(|(path, raw)| (path.as_str(), *raw))
+        .collect();
+    let no_imports = BTreeMap::new();
+    let no_literals = BTreeSet::new();
+    let order = derived_value_order(
+        data.root,
+        env.imports.unwrap_or(&no_imports),
+        env.literals.unwrap_or(&no_literals),
+    );
+    for path in &order {
+        if let Some(raw) = raw_at.get(path.as_str()) {
+            env.derived_type(data, raw, path);
+        }
+    }
+
+    let mut errors = Vec::new();
+    for (path, raw) in nodes {
+        let mut issues = Vec::new();
+        let ty = env.infer_derived(raw, &mut issues);
+        if let Some(type_name) = data.hints.get(&path) {
+            let expected = env.named_type(type_name);
+            if !assignable(&ty, &expected) {
+                issues.push(format!(
+                    "result of type {ty} does not match type hint '{type_name}'"
+                ));
+            }
+        }
+        for issue in issues {
+            errors.push(
+                SyamlError::ExpressionError(format!("{issue} (in expression '{}')", raw.trim()))
+                    .at_path(&path),
+            );
+        }
+    }
+    SyamlError::from_errors(errors)
+}
+
+fn collect_derived<'v>(value: &'v JsonValue, path: &str, out: &mut Vec<(String, &'v str)>) {
+    match value {
+        JsonValue::Object(map) => {
+            for (k, v) in map {
+                collect_derived(v, &format!("{path}.{k}"), out);
+            }
+        }
+        JsonValue::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                collect_derived(v, &format!("{path}[{i}]"), out);
+            }
+        }
+        JsonValue::String(raw) if is_derived(raw) => out.push((path.to_string(), raw)),
+        _ => {}
+    }
+}
+
+/// Whether a value of type `actual` can satisfy a schema of type `expected`.
+/// Only combinations that can never validate are rejected.
+fn assignable(actual: &Type, expected: &Type) -> bool {
+    match (actual, expected) {
+        (Type::Unknown | Type::Null, _) | (_, Type::Unknown) => true,
+        (a, b) if a.is_numeric() && b.is_numeric() => true,
+        // Strings may spell quantities, and quantities are written as strings.
+        (Type::String, Type::Spelled(_)) | (Type::Spelled(_), Type::String) => true,
+        (Type::Spelled(a), Type::Spelled(b)) => a == b,
+        (Type::Integer, Type::Spelled("bytesize")) => true,
+        // String constructors turn strings into hinted objects.
+        (Type::String, b) if b.is_object() => true,
+        (a, b) if a.is_object() && b.is_object() => true,
+        (Type::Array(_), Type::Array(_)) => true,
+        (a, b) => a == b,
+    }
+}
+
+/// Checks a boolean condition (a constraint or contract condition) and
+/// returns one message per problem.
+pub fn check_condition(env: &TypeEnv<'_>, expr: &Expr) -> Vec<String> {
+    let mut issues = Vec::new();
+    let ty = env.infer(expr, &mut issues);
+    if !matches!(ty, Type::Boolean | Type::Unknown) {
+        issues.push(format!("must evaluate to boolean, got {ty}"));
+    }
+    issues
+}
+
+/// Checks the body of a user-defined function against its parameter types,
+/// and its result against the declared return type. Returns one message per
+/// problem.
+pub fn check_function(env: &TypeEnv<'_>, function: &PureFunctionDef) -> Vec<String> {
+    let mut issues = Vec::new();
+    let body = match parse_expression(&function.body) {
+        Ok(body) => body,
+        Err(e) => return vec![e.to_string()],
+    };
+    let ty = env
+        .function_env(function)
+        .infer(&body, &mut issues)
+        .stored();
+    if let Some(returns) = &function.returns {
+        if !assignable(&ty, &env.schema_type(returns)) {
+            issues.push(format!(
+                "result of type {ty} does not match return type '{}'",
+                type_ref_name(returns)
+            ));
+        }
+    }
+    issues
+}
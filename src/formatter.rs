@@ -0,0 +1,355 @@
+//! Canonical `.syaml` formatter.
+//!
+//! Formatting works on the lossless [`crate::cst`] tree rather than on parsed
+//! values so that comments survive. Each section is reprinted with:
+//!
+//! - sections in a fixed order (`module`, `meta`, `schema`, `data`, `contracts`)
+//!   separated by one blank line,
+//! - two spaces of indentation per level, including block scalar bodies,
+//! - one space after `:` and `-`, and one space before inline comments,
+//! - single-quoted scalars rewritten with double quotes when that needs no escapes,
+//! - runs of blank lines collapsed to one.
+//!
+//! Keys (with `<Type>` hints, `!`/`^` suffixes and `{{template}}` names), comment
+//! text, flow collections and `=expr` strings are written back unchanged.
+//! Schema `properties` are sorted by name only when requested. The result is
+//! re-parsed and compared with the input, so formatting never changes values.
+
+use crate::cst::{CstBlock, CstDocument, CstEntry, CstEntryKind, CstItem, CstLine};
+use crate::error::SyamlError;
+use crate::mini_yaml::{strip_inline_comment, ChompingMode};
+use crate::section_scanner::{scan_sections, Section};
+use crate::source_map::{LineIndex, SourceMap};
+
+const MARKER: &str = "---!syaml/v0";
+const SECTION_ORDER: [&str; 7] = [
+    "module",
+    "meta",
+    "schema",
+    "functions",
+    "data",
+    "overlay",
+    "contracts",
+];
+const INDENT: &str = "  ";
+
+/// Options for [`format_document`].
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Sort the entries of every schema `properties` mapping by name.
+    pub sort_schema_properties: bool,
+}
+
+/// Formats a `.syaml` document canonically.
+///
+/// Returns an error when the document does not parse, or when the formatted
+/// output would parse to different section values.
+pub fn format_document(input: &str, options: &FormatOptions) -> Result<String, SyamlError> {
+    let (_, scanned) = scan_sections(input)?;
+    let index = LineIndex::new(input);
+    for section in &scanned {
+        crate::parse_located_section(section, &index, &mut SourceMap::new())?;
+    }
+
+    let mut sections = CstDocument::parse(input)?.sections;
+    sections.sort_by_key(|section| {
+        SECTION_ORDER
+            .iter()
+            .position(|name| *name == section.name)
+            .unwrap_or(SECTION_ORDER.len())
+    });
+
+    let mut out = String::new();
+    out.push_str(MARKER);
+    out.push('\n');
+    let mut separate = false;
+    for section in &sections {
+        let lines = format_section(&section.name, section.body.clone(), options);
+        if separate {
+            out.push('\n');
+        }
+        out.push_str("---");
+        out.push_str(&section.name);
+        out.push('\n');
+        for line in &lines {
+            out.push_str(&line.text);
+            out.push('\n');
+        }
+        // A blank line after a `|+` block at the end of a section would become part
+        // of its value.
+        separate = !lines.last().is_some_and(|line| line.keep_block);
+    }
+
+    verify_unchanged(&scanned, &out)?;
+    Ok(out)
+}
+
+/// Returns `true` when `input` is already formatted.
+pub fn is_formatted(input: &str, options: &FormatOptions) -> Result<bool, SyamlError> {
+    Ok(format_document(input, options)? == input)
+}
+
+fn verify_unchanged(original: &[Section], formatted: &str) -> Result<(), SyamlError> {
+    let (_, reformatted) = scan_sections(formatted)?;
+    for section in original {
+        let before = crate::mini_yaml::parse_document(&section.body)?;
+        let after = reformatted
+            .iter()
+            .find(|s| s.name == section.name)
+            .map(|s| crate::mini_yaml::parse_document(&s.body))
+            .transpose()?;
+        if after.as_ref() != Some(&before) {
+            return Err(SyamlError::FormatError(format!(
+                "formatting would change the value of section '{}'; the file was left unchanged",
+                section.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Output line; `keep_block` marks lines of a `|+` / `>+` block scalar.
+struct OutLine {
+    text: String,
+    keep_block: bool,
+}
+
+fn format_section(name: &str, mut body: CstBlock, options: &FormatOptions) -> Vec<OutLine> {
+    if options.sort_schema_properties && name == "schema" {
+        sort_properties(&mut body);
+    }
+
+    let mut out = Vec::new();
+    print_block(&body, 0, &mut out);
+    // Blank lines at the start or end of a body are dropped.
+    let start = out
+        .iter()
+        .position(|line| !line.text.is_empty() || line.keep_block)
+        .unwrap_or(out.len());
+    out.drain(..start);
+    while out
+        .last()
+        .is_some_and(|line| line.text.is_empty() && !line.keep_block)
+    {
+        out.pop();
+    }
+    out
+}
+
+/// Normalized entry text without indentation.
+fn entry_text(entry: &CstEntry) -> String {
+    let value = entry.raw_value();
+    match entry.kind {
+        CstEntryKind::SequenceItem if value.is_empty() => "-".to_string(),
+        CstEntryKind::SequenceItem => format!("- {}", normalize_value(value)),
+        CstEntryKind::Mapping => {
+            let key = entry.key().unwrap_or_default();
+            if value.is_empty() {
+                format!("{key}:")
+            } else {
+                format!("{key}: {}", normalize_value(value))
+            }
+        }
+        CstEntryKind::Scalar => normalize_value(value),
+    }
+}
+
+/// Normalizes an inline value: single space before a trailing comment and
+/// double quotes for single-quoted strings that need no escaping.
+fn normalize_value(raw: &str) -> String {
+    let value = strip_inline_comment(raw);
+    let comment = raw[value.len()..].trim();
+    let value = requote(value);
+    if comment.is_empty() {
+        value
+    } else if value.is_empty() {
+        comment.to_string()
+    } else {
+        format!("{value} {comment}")
+    }
+}
+
+fn requote(value: &str) -> String {
+    let inner = value
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''));
+    match inner {
+        Some(inner) if !inner.contains(['\'', '"', '\\']) && !inner.starts_with('=') => {
+            format!("\"{inner}\"")
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn sort_properties(block: &mut CstBlock) {
+    for entry in block.entries_mut() {
+        let is_properties = entry.key() == Some("properties");
+        let Some(children) = entry.children.as_mut() else {
+            continue;
+        };
+        if is_properties {
+            sort_entries(children);
+        }
+        sort_properties(children);
+    }
+}
+
+/// Sorts mapping entries by key; comments and blank lines move with the entry
+/// that follows them.
+fn sort_entries(block: &mut CstBlock) {
+    let mut groups: Vec<Vec<CstItem>> = Vec::new();
+    let mut pending = Vec::new();
+    for item in block.items.drain(..) {
+        let is_entry = matches!(item, CstItem::Entry(_));
+        pending.push(item);
+        if is_entry {
+            groups.push(std::mem::take(&mut pending));
+        }
+    }
+    groups.sort_by(|a, b| group_key(a).cmp(&group_key(b)));
+    block.items = groups.into_iter().flatten().chain(pending).collect();
+}
+
+fn group_key(group: &[CstItem]) -> Option<&str> {
+    match group.last()? {
+        CstItem::Entry(entry) => entry.key(),
+        CstItem::Trivia(_) => None,
+    }
+}
+
+fn print_block(block: &CstBlock, level: usize, out: &mut Vec<OutLine>) {
+    let indent = INDENT.repeat(level);
+    for item in &block.items {
+        match item {
+            CstItem::Trivia(line) => print_trivia(line, &indent, out),
+            CstItem::Entry(entry) => {
+                push_line(out, format!("{indent}{}", entry_text(entry)), false);
+                print_block_scalar(entry, &indent, out);
+                if let Some(children) = &entry.children {
+                    print_block(children, level + 1, out);
+                }
+            }
+        }
+    }
+}
+
+/// Prints block scalar content re-indented relative to its entry.
+///
+/// Trailing blank lines of a block whose chomping discards them are printed as
+/// ordinary blank lines.
+fn print_block_scalar(entry: &CstEntry, indent: &str, out: &mut Vec<OutLine>) {
+    let Some(header) = entry.block_scalar_header() else {
+        return;
+    };
+    let content_indent = header
+        .explicit_indent
+        .map(|v| entry.indent() + v)
+        .or_else(|| {
+            entry
+                .block_scalar
+                .iter()
+                .find(|line| !line.is_blank())
+                .map(CstLine::indent)
+        })
+        .unwrap_or(0);
+    let keep = matches!(header.chomping, ChompingMode::Keep);
+    let mut lines = entry.block_scalar.as_slice();
+    let mut trailing_blanks = 0;
+    if !keep {
+        while lines.last().is_some_and(CstLine::is_blank) {
+            lines = &lines[..lines.len() - 1];
+            trailing_blanks += 1;
+        }
+    }
+
+    let block_indent = format!(
+        "{indent}{}",
+        " ".repeat(header.explicit_indent.unwrap_or(INDENT.len()))
+    );
+    for line in lines {
+        let text = if line.is_blank() {
+            String::new()
+        } else {
+            format!("{block_indent}{}", &line.text[content_indent..])
+        };
+        push_line(out, text, keep);
+    }
+    for _ in 0..trailing_blanks {
+        print_trivia(&CstLine::new(""), indent, out);
+    }
+}
+
+fn print_trivia(line: &CstLine, indent: &str, out: &mut Vec<OutLine>) {
+    if line.is_blank() {
+        if out.last().is_some_and(|line| !line.text.is_empty()) {
+            push_line(out, String::new(), false);
+        }
+    } else {
+        push_line(out, format!("{indent}{}", line.text.trim()), false);
+    }
+}
+
+fn push_line(out: &mut Vec<OutLine>, text: String, keep_block: bool) {
+    out.push(OutLine { text, keep_block });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_document, FormatOptions};
+
+    fn fmt(input: &str) -> String {
+        format_document(input, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn reorders_sections_and_normalizes_indentation() {
+        let input = "---!syaml/v0\n---data\nservice <Service>:\n    host:    'localhost'   # primary\n    ports:\n        -   80\n---schema\nService:\n    type: object\n";
+        let expected = "---!syaml/v0\n---schema\nService:\n  type: object\n\n---data\nservice <Service>:\n  host: \"localhost\" # primary\n  ports:\n    - 80\n";
+        assert_eq!(fmt(input), expected);
+        assert_eq!(fmt(expected), expected);
+    }
+
+    #[test]
+    fn keeps_keys_expressions_and_comments_verbatim() {
+        let input = "---!syaml/v0\n---data\n# header comment\nport^ <Port>: 8080\n\n\n{{name}}!: '=a + b'\ntotal: =price * 2\n";
+        let expected = "---!syaml/v0\n---data\n# header comment\nport^ <Port>: 8080\n\n{{name}}!: '=a + b'\ntotal: =price * 2\n";
+        assert_eq!(fmt(input), expected);
+    }
+
+    #[test]
+    fn reindents_block_scalars_relative_to_their_key() {
+        let input = "---!syaml/v0\n---data\nouter:\n    script: |\n        echo hi\n          indented\n\n        done\n    next: 1\n";
+        let expected = "---!syaml/v0\n---data\nouter:\n  script: |\n    echo hi\n      indented\n\n    done\n  next: 1\n";
+        assert_eq!(fmt(input), expected);
+    }
+
+    #[test]
+    fn keeps_anchors_with_nested_values_and_block_scalars() {
+        let input = "---!syaml/v0\n---data\nbase: &base\n    retries: 3\nscript: &s |\n    echo hi\nservice:\n    <<: *base\n    run: *s\n";
+        let expected = "---!syaml/v0\n---data\nbase: &base\n  retries: 3\nscript: &s |\n  echo hi\nservice:\n  <<: *base\n  run: *s\n";
+        assert_eq!(fmt(input), expected);
+    }
+
+    #[test]
+    fn sorts_schema_properties_only_when_asked() {
+        let input = "---!syaml/v0\n---schema\nService:\n  type: object\n  properties:\n    port: integer\n    # the host name\n    host: string\n";
+        assert_eq!(fmt(input), input);
+        let sorted = format_document(
+            input,
+            &FormatOptions {
+                sort_schema_properties: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            sorted,
+            "---!syaml/v0\n---schema\nService:\n  type: object\n  properties:\n    # the host name\n    host: string\n    port: integer\n"
+        );
+    }
+
+    #[test]
+    fn rejects_unparseable_documents() {
+        let input = "---!syaml/v0\n---data\na: 1\n   b: 2\n";
+        assert!(format_document(input, &FormatOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Parsing and validation for the `---functions` section.
+//!
+//! Each entry defines a pure function: typed parameters and an expression
+//! body that sees only those parameters and other functions.
+//!
+//! ```yaml
+//! ---functions
+//! capacity:
+//!   params:
+//!     - cores <integer>
+//!     - memory_gb <number>
+//!   returns: integer
+//!   body: floor(min(cores * 250, memory_gb * 100))
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value as JsonValue;
+
+use crate::ast::{FunctionsDoc, PureFunctionDef, PureFunctionParam};
+use crate::error::SyamlError;
+use crate::expr::eval::BUILTIN_FUNCTIONS;
+use crate::expr::parse_expression;
+use crate::expr::parser::Expr;
+use crate::expr::typecheck::{check_function, TypeEnv};
+use crate::type_hints::split_key_and_hint;
+
+const KEYWORDS: [&str; 8] = ["if", "then", "else", "let", "in", "true", "false", "null"];
+
+/// Parses a `---functions` section value into a [`FunctionsDoc`].
+pub fn parse_functions(value: &JsonValue) -> Result<FunctionsDoc, SyamlError> {
+    let map = value.as_object().ok_or_else(|| {
+        SyamlError::FunctionsError("functions section must be a mapping/object".to_string())
+    })?;
+
+    let mut functions = BTreeMap::new();
+    for (name, func_value) in map {
+        if !is_identifier(name) || BUILTIN_FUNCTIONS.contains(&name.as_str()) {
+            return Err(SyamlError::FunctionsError(format!(
+                "invalid function name '{name}': must be an identifier that is not a keyword or built-in function"
+            )));
+        }
+        functions.insert(name.clone(), parse_function_def(name, func_value)?);
+    }
+
+    Ok(FunctionsDoc { functions })
+}
+
+fn parse_function_def(name: &str, value: &JsonValue) -> Result<PureFunctionDef, SyamlError> {
+    let map = value.as_object().ok_or_else(|| {
+        SyamlError::FunctionsError(format!("functions.{name} must be a mapping/object"))
+    })?;
+
+    for key in map.keys() {
+        if !matches!(key.as_str(), "params" | "returns" | "body" | "description") {
+            return Err(SyamlError::FunctionsError(format!(
+                "functions.{name}: unknown key '{key}' (expected params, returns, body, description)"
+            )));
+        }
+    }
+
+    let params = match map.get("params") {
+        None => Vec::new(),
+        Some(JsonValue::Array(items)) => {
+            let mut params: Vec<PureFunctionParam> = Vec::with_capacity(items.len());
+            for item in items {
+                let param = parse_param(name, item)?;
+                if params.iter().any(|p| p.name == param.name) {
+                    return Err(SyamlError::FunctionsError(format!(
+                        "functions.{name}.params: duplicate parameter '{}'",
+                        param.name
+                    )));
+                }
+                params.push(param);
+            }
+            params
+        }
+        Some(_) => {
+            return Err(SyamlError::FunctionsError(format!(
+                "functions.{name}.params must be a list of 'name <Type>' entries"
+            )))
+        }
+    };
+
+    let body = map
+        .get("body")
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| {
+            SyamlError::FunctionsError(format!(
+                "functions.{name} must define 'body' as an expression string"
+            ))
+        })?
+        .trim();
+    let body = body.strip_prefix('=').unwrap_or(body).trim().to_string();
+    parse_expression(&body).map_err(|e| {
+        SyamlError::FunctionsError(format!("functions.{name}.body: invalid expression: {e}"))
+    })?;
+
+    let description = match map.get("description") {
+        None => None,
+        Some(JsonValue::String(s)) => Some(s.clone()),
+        Some(_) => {
+            return Err(SyamlError::FunctionsError(format!(
+                "functions.{name}.description must be a string"
+            )))
+        }
+    };
+
+    Ok(PureFunctionDef {
+        params,
+        returns: map.get("returns").cloned(),
+        body,
+        description,
+    })
+}
+
+fn parse_param(func_name: &str, value: &JsonValue) -> Result<PureFunctionParam, SyamlError> {
+    let invalid = || {
+        SyamlError::FunctionsError(format!(
+            "functions.{func_name}.params: invalid parameter {value}; expected 'name <Type>'"
+        ))
+    };
+    let raw = value.as_str().ok_or_else(invalid)?;
+    let (name, type_name) = split_key_and_hint(raw).map_err(|_| invalid())?;
+    let type_name = type_name.ok_or_else(invalid)?;
+    if !is_identifier(&name) {
+        return Err(SyamlError::FunctionsError(format!(
+            "functions.{func_name}.params: invalid parameter name '{name}'"
+        )));
+    }
+    Ok(PureFunctionParam {
+        name,
+        type_ref: JsonValue::String(type_name),
+    })
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name)
+}
+
+/// Validates that parameter and return types of every function exist.
+///
+/// Every unknown type is reported.
+pub fn validate_function_type_references(
+    doc: &FunctionsDoc,
+    types: &BTreeMap<String, JsonValue>,
+) -> Result<(), SyamlError> {
+    let mut errors = Vec::new();
+    for (func_name, func_def) in &doc.functions {
+        let refs = func_def
+            .params
+            .iter()
+            .map(|p| (format!("params.{}", p.name), &p.type_ref))
+            .chain(func_def.returns.iter().map(|r| ("returns".to_string(), r)));
+        for (field, type_ref) in refs {
+            let type_name = match type_ref {
+                JsonValue::String(name) => Some(name.as_str()),
+                other => other.get("type").and_then(JsonValue::as_str),
+            };
+            if let Some(type_name) = type_name {
+                if !crate::contracts::is_builtin_type(type_name) && !types.contains_key(type_name) {
+                    errors.push(SyamlError::FunctionsError(format!(
+                        "functions.{func_name}.{field}: unknown type '{type_name}'"
+                    )));
+                }
+            }
+        }
+    }
+    SyamlError::from_errors(errors)
+}
+
+/// Rejects functions that call themselves, directly or through other
+/// functions of the same document.
+pub fn validate_function_recursion(doc: &FunctionsDoc) -> Result<(), SyamlError> {
+    let calls: BTreeMap<&str, BTreeSet<String>> = doc
+        .functions
+        .iter()
+        .map(|(name, def)| {
+            let mut called = BTreeSet::new();
+            if let Ok(body) = parse_expression(&def.body) {
+                collect_calls(&body, &mut called);
+            }
+            called.retain(|callee| doc.functions.contains_key(callee));
+            (name.as_str(), called)
+        })
+        .collect();
+
+    let mut done = BTreeSet::new();
+    for name in calls.keys() {
+        let mut chain = Vec::new();
+        if let Some(cycle) = find_cycle(name, &calls, &mut chain, &mut done) {
+            return Err(SyamlError::FunctionsError(format!(
+                "functions.{}: recursive call chain {}; functions must not call themselves",
+                cycle[0],
+                cycle.join(" -> ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Depth-first search from `name`; returns the call chain of the first cycle
+/// found, starting and ending at the same function.
+fn find_cycle<'a>(
+    name: &'a str,
+    calls: &'a BTreeMap<&'a str, BTreeSet<String>>,
+    chain: &mut Vec<&'a str>,
+    done: &mut BTreeSet<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(start) = chain.iter().position(|n| *n == name) {
+        let mut cycle: Vec<String> = chain[start..].iter().map(|n| n.to_string()).collect();
+        cycle.push(name.to_string());
+        return Some(cycle);
+    }
+    if done.contains(name) {
+        return None;
+    }
+    chain.push(name);
+    for callee in calls.get(name).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(callee, calls, chain, done) {
+            return Some(cycle);
+        }
+    }
+    chain.pop();
+    done.insert(name);
+    None
+}
+
+/// Type-checks every function body against its parameter and return types.
+///
+/// `functions` holds every callable function, imported ones included. Every
+/// problem is reported.
+pub fn check_function_bodies(
+    doc: &FunctionsDoc,
+    types: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+) -> Result<(), SyamlError> {
+    let env = TypeEnv::new(types).with_functions(functions);
+    let mut errors = Vec::new();
+    for (name, def) in &doc.functions {
+        for issue in check_function(&env, def) {
+            errors.push(SyamlError::FunctionsError(format!(
+                "functions.{name}.body: {issue} (in expression '{}')",
+                def.body
+            )));
+        }
+    }
+    SyamlError::from_errors(errors)
+}
+
+/// Rewrites the body of an imported function so calls to functions from the
+/// same document go through the import alias (`capacity(...)` becomes
+/// `alias.capacity(...)`).
+pub(crate) fn qualify_function_calls(body: &str, renames: &BTreeMap<String, String>) -> String {
+    match parse_expression(body) {
+        Ok(mut expr) => {
+            rename_calls(&mut expr, renames);
+            expr.to_string()
+        }
+        Err(_) => body.to_string(),
+    }
+}
+
+fn collect_calls(expr: &Expr, out: &mut BTreeSet<String>) {
+    if let Expr::Call { name, .. } = expr {
+        out.insert(name.clone());
+    }
+    for child in children(expr) {
+        collect_calls(child, out);
+    }
+}
+
+fn rename_calls(expr: &mut Expr, renames: &BTreeMap<String, String>) {
+    if let Expr::Call { name, .. } = expr {
+        if let Some(renamed) = renames.get(name.as_str()) {
+            *name = renamed.clone();
+        }
+    }
+    for child in children_mut(expr) {
+        rename_calls(child, renames);
+    }
+}
+
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Number(_)
+        | Expr::Quantity(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Var(_) => Vec::new(),
+        Expr::Unary { expr, .. } => vec![expr],
+        Expr::Lambda { body, .. } => vec![body],
+        Expr::Binary { left, right, .. } => vec![left, right],
+        Expr::Index { target, index, .. } => vec![target, index],
+        Expr::Let { value, body, .. } => vec![value, body],
+        Expr::Call { args, .. } => args.iter().collect(),
+        Expr::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => vec![cond, then_branch, else_branch],
+    }
+}
+
+fn children_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    match expr {
+        Expr::Number(_)
+        | Expr::Quantity(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Null
+        | Expr::Var(_) => Vec::new(),
+        Expr::Unary { expr, .. } => vec![expr],
+        Expr::Lambda { body, .. } => vec![body],
+        Expr::Binary { left, right, .. } => vec![left, right],
+        Expr::Index { target, index, .. } => vec![target, index],
+        Expr::Let { value, body, .. } => vec![value, body],
+        Expr::Call { args, .. } => args.iter_mut().collect(),
+        Expr::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => vec![cond, then_branch, else_branch],
+    }
+}
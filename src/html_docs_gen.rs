@@ -9,9 +9,11 @@ use std::path::{Path, PathBuf};
 
 use serde_json::Value as JsonValue;
 
-use crate::ast::{ContractsDoc, Meta, SchemaDoc};
+use crate::ast::{ContractsDoc, Meta, ParsedDocument, SchemaDoc};
+use crate::resolve::get_json_path;
 use crate::section_scanner::scan_sections;
-use crate::{parse_document_or_manifest, SyamlError};
+use crate::sensitive::{self, REDACTED};
+use crate::{parse_document_or_manifest, path_is_within, SyamlError};
 
 // ─── Public API ──────────────────────────────────────────────────────────────
 
@@ -20,8 +22,8 @@ use crate::{parse_document_or_manifest, SyamlError};
 /// No cross-file import links are produced; all type references within the
 /// document are rendered as internal anchor links.
 pub fn generate_html_docs(input: &str) -> Result<String, SyamlError> {
-    let parsed = parse_document_or_manifest(input)?;
-    let raw_data = extract_raw_data_section(input);
+    let mut parsed = parse_document_or_manifest(input)?;
+    let raw_data = hide_sensitive_data(&mut parsed, input);
     let raw_schema = extract_raw_schema_section(input);
     let file_title = "SYAML Documentation";
     Ok(assemble_page(
@@ -44,8 +46,8 @@ pub fn generate_html_docs_from_path(path: impl AsRef<Path>) -> Result<String, Sy
     let input = fs::read_to_string(path).map_err(|e| {
         SyamlError::ImportError(format!("failed to read '{}': {e}", path.display()))
     })?;
-    let parsed = parse_document_or_manifest(&input)?;
-    let raw_data = extract_raw_data_section(&input);
+    let mut parsed = parse_document_or_manifest(&input)?;
+    let raw_data = hide_sensitive_data(&mut parsed, &input);
     let raw_schema = extract_raw_schema_section(&input);
     let file_title = path
         .file_stem()
@@ -64,6 +66,31 @@ pub fn generate_html_docs_from_path(path: impl AsRef<Path>) -> Result<String, Sy
     ))
 }
 
+/// Redacts data values whose schema is marked sensitive, and their source
+/// text in the raw data section of `input`, so generated pages never show
+/// them. Returns that raw data section.
+fn hide_sensitive_data(parsed: &mut ParsedDocument, input: &str) -> Option<String> {
+    let paths = sensitive::schema_sensitive_paths(
+        &parsed.data.value,
+        &parsed.data.type_hints,
+        &parsed.schema.types,
+    );
+    if paths.is_empty() {
+        return extract_raw_data_section(input);
+    }
+    let spans = parsed.source_map.iter().filter_map(|(path, span)| {
+        let is_leaf = get_json_path(&parsed.data.value, path)
+            .is_some_and(|value| !value.is_object() && !value.is_array());
+        let hidden = paths
+            .iter()
+            .any(|sensitive| path_is_within(path, sensitive));
+        (is_leaf && hidden).then_some(span)
+    });
+    let masked = sensitive::mask_spans(input, spans);
+    sensitive::redact(&mut parsed.data.value, &paths);
+    extract_raw_data_section(&masked)
+}
+
 // ─── Page assembly ───────────────────────────────────────────────────────────
 
 fn assemble_page(
@@ -87,6 +114,7 @@ fn assemble_page(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn assemble_page_with_paths(
     title: &str,
     meta: Option<&Meta>,
@@ -310,17 +338,18 @@ fn render_meta_section(meta: &Meta, import_html_paths: &BTreeMap<String, String>
     // Env bindings
     if !meta.env.is_empty() {
         html.push_str("<h3>Environment Bindings</h3>\n<table class=\"prop-table\">\n");
-        html.push_str("<thead><tr><th>Symbol</th><th>Key</th><th>Required</th><th>Default</th></tr></thead>\n<tbody>\n");
+        html.push_str("<thead><tr><th>Symbol</th><th>Source</th><th>Required</th><th>Default</th></tr></thead>\n<tbody>\n");
         for (symbol, binding) in &meta.env {
-            let default_val = binding
-                .default
-                .as_ref()
-                .map(|v| html_escape(&json_value_display(v)))
-                .unwrap_or_else(|| "—".to_string());
+            let default_val = match &binding.default {
+                Some(_) if binding.sensitive => REDACTED.to_string(),
+                Some(v) => html_escape(&json_value_display(v)),
+                None => "—".to_string(),
+            };
             html.push_str(&format!(
-                "<tr><td><code>{}</code></td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                "<tr><td><code>{}</code>{}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
                 html_escape(symbol),
-                html_escape(&binding.key),
+                sensitive_badge(binding.sensitive),
+                html_escape(&binding.describe_source()),
                 if binding.required { "yes" } else { "no" },
                 default_val,
             ));
@@ -769,6 +798,9 @@ fn render_type_card(
     // Kind badge
     let kind = detect_type_kind(type_val);
     html.push_str(&format!("<span class=\"kind-badge\">{kind}</span>\n"));
+    if is_sensitive(type_val) {
+        html.push_str("<span class=\"sensitive-badge\">sensitive</span>\n");
+    }
     // Toggle button (only when we have source)
     if raw_source.is_some() {
         html.push_str(&format!(
@@ -838,6 +870,9 @@ fn detect_type_kind(val: &JsonValue) -> &'static str {
         "boolean" => "boolean",
         "string" => "string",
         "null" => "null",
+        "duration" => "duration",
+        "timestamp" => "timestamp",
+        "bytesize" => "bytesize",
         _ => {
             // If it has a `properties` key it's likely an object even without explicit type
             if val.get("properties").is_some() {
@@ -869,9 +904,10 @@ fn render_object_type(val: &JsonValue, import_html_paths: &BTreeMap<String, Stri
         let notes = collect_field_notes(field_val);
 
         html.push_str(&format!(
-            "<tr><td><code>{field}</code>{dep}</td><td>{type_display}</td><td>{req}</td><td>{notes}</td></tr>\n",
+            "<tr><td><code>{field}</code>{dep}{sensitive}</td><td>{type_display}</td><td>{req}</td><td>{notes}</td></tr>\n",
             field = html_escape(field_name),
             dep = if is_deprecated(field_val) { " <span class=\"deprecated-badge\">deprecated</span>" } else { "" },
+            sensitive = sensitive_badge(is_sensitive(field_val)),
             type_display = type_display,
             req = if required { "✓" } else { "" },
             notes = notes,
@@ -905,6 +941,20 @@ fn is_deprecated(val: &JsonValue) -> bool {
         .unwrap_or(false)
 }
 
+fn is_sensitive(val: &JsonValue) -> bool {
+    val.get("sensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn sensitive_badge(sensitive: bool) -> &'static str {
+    if sensitive {
+        " <span class=\"sensitive-badge\">sensitive</span>"
+    } else {
+        ""
+    }
+}
+
 fn render_enum_type(val: &JsonValue) -> String {
     let Some(enum_value) = val.get("enum") else {
         return String::new();
@@ -991,7 +1041,7 @@ fn render_enum_type(val: &JsonValue) -> String {
             for path in &columns {
                 let cell = get_enum_path_value(value, path)
                     .map(json_value_display)
-                    .unwrap_or_else(String::new);
+                    .unwrap_or_default();
                 html.push_str(&format!("<td><code>{}</code></td>", html_escape(&cell)));
             }
             html.push_str("</tr>\n");
@@ -1363,7 +1413,7 @@ fn is_builtin(name: &str) -> bool {
     matches!(
         name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || crate::quantity::QUANTITY_TYPE_NAMES.contains(&name)
 }
 
 // ─── Utility helpers ─────────────────────────────────────────────────────────
@@ -1608,6 +1658,14 @@ main {
   background: #ffebe9;
   color: #d73a49;
 }
+.sensitive-badge {
+  font-size: 11px;
+  font-weight: 600;
+  padding: 1px 5px;
+  border-radius: 4px;
+  background: #fff8c5;
+  color: #9a6700;
+}
 
 .type-source-toggle {
   margin-left: auto;
@@ -1840,7 +1898,7 @@ pub fn collect_import_graph(root: &Path) -> Result<BTreeMap<PathBuf, String>, Sy
         // Enqueue local imports
         if let Some(meta) = &parsed.meta {
             let base = current_path.parent().unwrap_or(Path::new("."));
-            for (_alias, binding) in &meta.imports {
+            for binding in meta.imports.values() {
                 let raw = &binding.path;
                 if raw.starts_with("http://") || raw.starts_with("https://") || raw.starts_with('@')
                 {
@@ -1993,7 +2051,8 @@ pub fn generate_html_docs_site(
             SyamlError::ImportError(format!("failed to read '{}': {e}", file_path.display()))
         })?;
 
-        let parsed = parse_document_or_manifest(&content)?;
+        let mut parsed = parse_document_or_manifest(&content)?;
+        let raw_data = hide_sensitive_data(&mut parsed, &content);
 
         // Build a title that includes the directory context so that pages from
         // different modules are clearly distinguishable (e.g. "files / module").
@@ -2062,7 +2121,6 @@ pub fn generate_html_docs_site(
         } else {
             Some(data_val)
         };
-        let raw_data = extract_raw_data_section(&content);
         let raw_schema = extract_raw_schema_section(&content);
 
         // Compute the relative path from this page back to index.html.
@@ -2136,6 +2194,7 @@ pub fn generate_html_docs_site(
 /// `index_href` is the relative path from this page back to index.html; when
 /// `Some`, a home icon link is rendered at the top of the sidebar.
 /// `raw_data` is the verbatim `---data` section body from the source file.
+#[allow(clippy::too_many_arguments)]
 fn assemble_page_with_import_links(
     title: &str,
     meta: Option<&Meta>,
@@ -17,6 +17,7 @@ const STRIP_KEYS: &[&str] = &[
     "removed",
     "field_number",
     "optional",
+    "sensitive",
 ];
 
 // ── Public API ─────────────────────────────────────────────────────────────
@@ -67,6 +68,7 @@ fn convert_string_type(s: &str) -> JsonValue {
             out.insert("type".to_string(), JsonValue::String(s.to_string()));
             JsonValue::Object(out)
         }
+        "duration" | "timestamp" | "bytesize" => quantity_schema(s),
         // Named type reference → $ref
         _ => {
             let mut out = JsonMap::new();
@@ -96,6 +98,7 @@ fn convert_object_type(map: &JsonMap<String, JsonValue>) -> JsonValue {
         Some("array") => convert_array_type(map),
         Some("union") => convert_union_type(map),
         Some(primitive) if is_primitive(primitive) => convert_primitive_type(map, primitive),
+        Some(quantity @ ("duration" | "timestamp" | "bytesize")) => quantity_schema(quantity),
         Some(named) => {
             // Named type reference expressed as { type: "MyType" }
             let mut out = JsonMap::new();
@@ -122,6 +125,28 @@ fn is_primitive(s: &str) -> bool {
     matches!(s, "string" | "integer" | "number" | "boolean" | "null")
 }
 
+/// JSON Schema for the string spelling of a `duration`, `timestamp` or
+/// `bytesize`. Their `minimum` / `maximum` bounds have no JSON Schema
+/// equivalent and are dropped.
+fn quantity_schema(type_name: &str) -> JsonValue {
+    match type_name {
+        "duration" => serde_json::json!({
+            "type": "string",
+            "pattern": r"^-?([0-9]+(\.[0-9]+)?(ns|us|µs|ms|s|m|h|d))+$"
+        }),
+        "timestamp" => serde_json::json!({ "type": "string", "format": "date-time" }),
+        _ => serde_json::json!({
+            "oneOf": [
+                { "type": "integer", "minimum": 0 },
+                {
+                    "type": "string",
+                    "pattern": r"^-?[0-9]+(\.[0-9]+)?(B|kB|[KMGTPE]B|[KMGTPE]iB)$"
+                }
+            ]
+        }),
+    }
+}
+
 /// Convert a primitive type node, copying over compatible JSON Schema keywords.
 fn convert_primitive_type(map: &JsonMap<String, JsonValue>, primitive: &str) -> JsonValue {
     let mut out = JsonMap::new();
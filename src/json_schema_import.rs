@@ -37,7 +37,7 @@ struct ConversionContext {
     /// Counter for anonymous inline-promoted types (reserved for future use).
     #[allow(dead_code)]
     anon_counter: usize,
-    /// Stack of currently-resolving $ref names (cycle detection).
+    /// Stack of currently-resolving $ref names (recursive references).
     ref_stack: Vec<String>,
 }
 
@@ -361,10 +361,7 @@ fn convert_object_schema(
         return Ok(JsonValue::Object(out));
     }
 
-    let props = obj["properties"]
-        .as_object()
-        .map(|m| m.clone())
-        .unwrap_or_default();
+    let props = obj["properties"].as_object().cloned().unwrap_or_default();
 
     let mut out = JsonMap::new();
     out.insert("type".to_string(), json_str("object"));
@@ -756,11 +753,9 @@ fn ensure_ref_converted(name: &str, ctx: &mut ConversionContext) -> Result<(), S
         return Ok(());
     }
 
-    // Cycle check
+    // A recursive $ref: the type is emitted once its own conversion finishes.
     if ctx.ref_stack.contains(&name.to_string()) {
-        return Err(SyamlError::SchemaError(format!(
-            "circular $ref detected for '{name}'"
-        )));
+        return Ok(());
     }
 
     let schema = match ctx.defs.get(name).cloned() {
@@ -778,7 +773,7 @@ fn ensure_ref_converted(name: &str, ctx: &mut ConversionContext) -> Result<(), S
 fn resolve_ref_name(ref_str: &str) -> String {
     // "#/$defs/Foo" → "Foo"
     // "#/definitions/Foo" → "Foo"
-    if let Some(last) = ref_str.split('/').last() {
+    if let Some(last) = ref_str.split('/').next_back() {
         last.to_string()
     } else {
         ref_str.to_string()
@@ -987,7 +982,6 @@ fn render_property_entry(name: &str, schema: &JsonValue, indent: usize, out: &mu
     match schema {
         JsonValue::String(s) => {
             out.push_str(&format!("{pad}{name}: {s}\n"));
-            return;
         }
         JsonValue::Object(m) => {
             // Check for optional inline shorthand
@@ -1126,6 +1120,28 @@ mod tests {
         assert!(out.contains("tag: Tag"), "got: {out}");
     }
 
+    #[test]
+    fn recursive_ref_resolution() {
+        let out = convert(
+            r##"{
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "children": {"type": "array", "items": {"$ref": "#/$defs/Node"}},
+                        "next": {"$ref": "#/$defs/Node"}
+                    },
+                    "required": ["name"]
+                }
+            }
+        }"##,
+        );
+        assert!(out.contains("items: Node"), "got: {out}");
+        assert!(out.contains("next: Node?"), "got: {out}");
+        assert_eq!(out.matches("Node:").count(), 1, "got: {out}");
+    }
+
     #[test]
     fn any_of_union() {
         let out = convert(
@@ -5,12 +5,15 @@
 //! 1. Section scanning and shape validation (`---!syaml/v0`, section order, required sections).
 //! 2. Parsing section bodies with the built-in YAML subset parser.
 //! 3. Schema extraction and type-hint normalization.
-//! 4. Explicit import-value extraction.
-//! 5. Template expansion.
-//! 6. Environment binding resolution.
-//! 7. Derived expression/interpolation resolution.
-//! 8. String constructor coercion for hinted object types.
-//! 9. Type-hint and constraint validation.
+//! 4. Function validation (types, recursion, bodies).
+//! 5. Explicit import-value extraction.
+//! 6. Template expansion.
+//! 7. Overlay patches (see [`overlay`]) and value overrides from [`CompileOptions`].
+//! 8. Environment binding resolution.
+//! 9. Static type checking of derived expressions.
+//! 10. Derived expression/interpolation resolution.
+//! 11. String constructor coercion for hinted object types.
+//! 12. Type-hint and constraint validation.
 //!
 //! Use [`compile_document`] for full compilation, [`validate_document`] for validation-only
 //! workflows, [`compile_document_to_json`] / [`compile_document_to_yaml`] for serialized output,
@@ -23,24 +26,42 @@ pub mod ast;
 pub mod coerce;
 /// Parsing and validation for the `---contracts` section.
 pub mod contracts;
+/// Lossless concrete syntax tree for `.syaml` files.
+pub mod cst;
+/// Structured error/warning records for machine-readable output.
+pub mod diagnostics;
 /// Error types used throughout parsing, compilation, and validation.
 pub mod error;
 /// Expression lexer/parser/evaluator used by derived values and constraints.
 pub mod expr;
 /// URL-based import fetching, disk caching, and lockfile management.
 pub mod fetch;
+/// Canonical `.syaml` source formatting (`super-yaml fmt`).
+pub mod formatter;
+/// Parsing and validation for the `---functions` section.
+pub mod functions;
 /// HTML documentation generator for `.syaml` files.
 pub mod html_docs_gen;
 /// super_yaml schema to JSON Schema export.
 pub mod json_schema_export;
 /// JSON Schema to super_yaml schema conversion.
 pub mod json_schema_import;
+/// Language Server Protocol server for editors.
+pub mod lsp;
 /// Minimal YAML subset parser used for section bodies.
 pub mod mini_yaml;
 /// Module manifest parsing, discovery, and import policy enforcement.
 pub mod module;
+/// Overlay documents and environment profiles that patch a base document.
+pub mod overlay;
+/// Value overrides applied on top of document data (`--set path=value`).
+pub mod overrides;
 /// Proto3 file generation from named schema definitions.
 pub mod proto_codegen;
+/// Provenance records tracing how each compiled value was produced.
+pub mod provenance;
+/// Built-in `duration`, `timestamp` and `bytesize` value types.
+pub mod quantity;
 /// Environment and expression resolution over parsed data.
 pub mod resolve;
 /// Rust type generation from named schema definitions.
@@ -49,6 +70,10 @@ pub mod rust_codegen;
 pub mod schema;
 /// Top-level section marker scanner and order validator.
 pub mod section_scanner;
+/// Sensitive value marking, propagation and redaction.
+pub mod sensitive;
+/// Source spans for data paths and rustc-style error snippets.
+pub mod source_map;
 /// Data-template expansion (`{{template.path}}` keys + `{{VAR}}` placeholders).
 pub mod template;
 /// Type-hint extraction (`key <Type>`) and normalization.
@@ -59,6 +84,8 @@ pub mod typescript_codegen;
 pub mod validate;
 /// Import integrity verification: hash, signature, and version checks.
 pub mod verify;
+/// Dependency discovery and change polling for `--watch`.
+pub mod watch;
 /// JSON-to-YAML renderer used by compiled YAML output.
 pub mod yaml_writer;
 pub use html_docs_gen::{
@@ -66,40 +93,45 @@ pub use html_docs_gen::{
     generate_html_docs_from_path, generate_html_docs_site,
 };
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde_json::Value as JsonValue;
 
 use ast::{
-    CompiledDocument, DataDoc, EnvBinding, ImportBinding, Meta, ModuleManifest, ParsedDocument,
-    SignatureBinding,
+    CompiledDocument, DataDoc, EnvBinding, EnvParse, EnvSource, FunctionsDoc, ImportBinding, Meta,
+    ModuleManifest, ParsedDocument, PureFunctionDef, SignatureBinding, Warning,
 };
 use coerce::coerce_string_constructors_for_type_hints;
 pub use error::SyamlError;
+use expr::typecheck::{check_data_expressions, TypeEnv};
 use fetch::FetchContext;
 pub use json_schema_export::to_json_schema;
 pub use json_schema_import::{from_json_schema, from_json_schema_path};
+use overrides::apply_overrides;
 pub use proto_codegen::{generate_proto_types, generate_proto_types_from_path};
+use provenance::{Provenance, StepKind};
 use resolve::{
-    resolve_data_references, resolve_enum_member_references, resolve_env_bindings,
-    resolve_expressions_with_imports,
+    quantity_paths, resolve_data_references_except, resolve_enum_member_references,
+    resolve_env_bindings_in, resolve_expressions_except, validate_override_values,
 };
 pub use resolve::{EnvProvider, MapEnvProvider, ProcessEnvProvider};
 pub use rust_codegen::{
-    generate_rust_types, generate_rust_types_and_data_from_path, generate_rust_types_from_path,
+    generate_rust_types, generate_rust_types_and_data_from_path,
+    generate_rust_types_and_redacted_data_from_path, generate_rust_types_from_path,
 };
 use schema::{parse_schema, validate_schema_type_references, validate_strict_field_numbers};
-use section_scanner::scan_sections;
-use template::expand_data_templates;
-use type_hints::normalize_data_with_hints;
+use section_scanner::{scan_sections, Section};
+use source_map::{LineIndex, SourceMap};
+use template::{expand_data_templates, expand_data_templates_in, locked_template_fields};
+use type_hints::{canonical_data_key, normalize_data_with_hints};
 pub use typescript_codegen::{
     generate_typescript_types, generate_typescript_types_and_data_from_path,
-    generate_typescript_types_from_path,
+    generate_typescript_types_and_redacted_data_from_path, generate_typescript_types_from_path,
 };
 use validate::{
-    build_effective_constraints, validate_constraints_with_imports, validate_type_hints,
+    build_effective_constraints, validate_constraints_with_imports, validate_type_hints_concealing,
     validate_versioned_fields,
 };
 
@@ -110,6 +142,7 @@ use validate::{
 /// bindings and does not run constraints.
 pub fn parse_document(input: &str) -> Result<ParsedDocument, SyamlError> {
     let (version, sections) = scan_sections(input)?;
+    let index = LineIndex::new(input);
 
     let mut meta: Option<Meta> = None;
     let mut schema = parse_schema(&JsonValue::Object(serde_json::Map::new()))?;
@@ -119,46 +152,72 @@ pub fn parse_document(input: &str) -> Result<ParsedDocument, SyamlError> {
         freeze_markers: BTreeMap::new(),
     };
     let mut contracts: Option<crate::ast::ContractsDoc> = None;
+    let mut function_defs: Option<FunctionsDoc> = None;
+    let mut source_map = SourceMap::new();
+    let mut meta_overlay = None;
+    let mut section_overlay = None;
+    let mut has_data = false;
 
     for section in sections {
-        let section_value = parse_section_value(&section.name, &section.body)?;
+        let section_value = parse_located_section(&section, &index, &mut source_map)?;
         match section.name.as_str() {
             "meta" => {
                 meta = Some(parse_meta(&section_value)?);
+                meta_overlay = overlay::parse_overlay_meta(&section_value)?;
             }
             "schema" => {
                 schema = parse_schema(&section_value)?;
             }
             "data" => {
-                let (value, type_hints, freeze_markers) =
-                    normalize_data_with_hints(&section_value)?;
+                let (value, type_hints, freeze_markers) = normalize_data_with_hints(&section_value)
+                    .map_err(|e| locate_error(e, &source_map))?;
                 data = DataDoc {
                     value,
                     type_hints,
                     freeze_markers,
                 };
+                has_data = true;
+            }
+            "overlay" => {
+                let (base, patch) = overlay::parse_overlay_section(&section_value)?;
+                let (value, type_hints, freeze_markers) = normalize_data_with_hints(&patch)?;
+                let patch = DataDoc {
+                    value,
+                    type_hints,
+                    freeze_markers,
+                };
+                section_overlay = Some((base, patch));
             }
             "contracts" => {
                 contracts = Some(contracts::parse_contracts(&section_value)?);
             }
+            "functions" => {
+                function_defs = Some(functions::parse_functions(&section_value)?);
+            }
             "module" => {
                 // Module sections are only valid in module.syaml; handled by module::parse_module_manifest.
                 // Reject them in regular document parsing.
                 return Err(SyamlError::SectionError(
                     "'---module' section is only allowed in module.syaml manifest files"
                         .to_string(),
-                ));
+                )
+                .with_span(index.line_span(section.start_line)));
             }
             _ => unreachable!("validated by section scanner"),
         }
     }
 
+    let overlay = settle_overlay(meta_overlay, section_overlay, has_data, &mut data)?;
+
     Ok(ParsedDocument {
         version,
         meta,
         schema,
         data,
         contracts,
+        functions: function_defs,
+        source_map,
+        overlay,
     })
 }
 
@@ -169,6 +228,7 @@ pub fn parse_document(input: &str) -> Result<ParsedDocument, SyamlError> {
 /// (e.g., when resolving schema imports in code generation).
 pub fn parse_document_or_manifest(input: &str) -> Result<ParsedDocument, SyamlError> {
     let (version, sections) = scan_sections(input)?;
+    let index = LineIndex::new(input);
 
     let mut meta: Option<Meta> = None;
     let mut schema = parse_schema(&JsonValue::Object(serde_json::Map::new()))?;
@@ -178,28 +238,48 @@ pub fn parse_document_or_manifest(input: &str) -> Result<ParsedDocument, SyamlEr
         freeze_markers: BTreeMap::new(),
     };
     let mut contracts: Option<crate::ast::ContractsDoc> = None;
+    let mut function_defs: Option<FunctionsDoc> = None;
+    let mut source_map = SourceMap::new();
+    let mut meta_overlay = None;
+    let mut section_overlay = None;
+    let mut has_data = false;
 
     for section in sections {
-        let section_value = parse_section_value(&section.name, &section.body)?;
+        let section_value = parse_located_section(&section, &index, &mut source_map)?;
         match section.name.as_str() {
             "meta" => {
                 meta = Some(parse_meta(&section_value)?);
+                meta_overlay = overlay::parse_overlay_meta(&section_value)?;
             }
             "schema" => {
                 schema = parse_schema(&section_value)?;
             }
             "data" => {
-                let (value, type_hints, freeze_markers) =
-                    normalize_data_with_hints(&section_value)?;
+                let (value, type_hints, freeze_markers) = normalize_data_with_hints(&section_value)
+                    .map_err(|e| locate_error(e, &source_map))?;
                 data = DataDoc {
                     value,
                     type_hints,
                     freeze_markers,
                 };
+                has_data = true;
+            }
+            "overlay" => {
+                let (base, patch) = overlay::parse_overlay_section(&section_value)?;
+                let (value, type_hints, freeze_markers) = normalize_data_with_hints(&patch)?;
+                let patch = DataDoc {
+                    value,
+                    type_hints,
+                    freeze_markers,
+                };
+                section_overlay = Some((base, patch));
             }
             "contracts" => {
                 contracts = Some(contracts::parse_contracts(&section_value)?);
             }
+            "functions" => {
+                function_defs = Some(functions::parse_functions(&section_value)?);
+            }
             "module" => {
                 // Skip — the module section carries manifest metadata only.
                 // Full manifest parsing is done by module::parse_module_manifest.
@@ -208,15 +288,66 @@ pub fn parse_document_or_manifest(input: &str) -> Result<ParsedDocument, SyamlEr
         }
     }
 
+    let overlay = settle_overlay(meta_overlay, section_overlay, has_data, &mut data)?;
+
     Ok(ParsedDocument {
         version,
         meta,
         schema,
         data,
         contracts,
+        functions: function_defs,
+        source_map,
+        overlay,
     })
 }
 
+/// Picks the overlay base declared by `meta.overlay` or an `---overlay`
+/// section, moving an `---overlay` patch into `data`.
+fn settle_overlay(
+    meta_overlay: Option<String>,
+    section_overlay: Option<(String, DataDoc)>,
+    has_data: bool,
+    data: &mut DataDoc,
+) -> Result<Option<String>, SyamlError> {
+    match (meta_overlay, section_overlay) {
+        (Some(_), Some(_)) => Err(SyamlError::OverlayError(
+            "declare the overlay base with meta.overlay or an ---overlay section, not both"
+                .to_string(),
+        )),
+        (None, Some(_)) if has_data => Err(SyamlError::OverlayError(
+            "a document with an ---overlay section cannot also have a ---data section".to_string(),
+        )),
+        (None, Some((base, patch))) => {
+            *data = patch;
+            Ok(Some(base))
+        }
+        (base, None) => Ok(base),
+    }
+}
+
+/// Options for [`compile_document_with_options`] and
+/// [`compile_document_from_path_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Data values to replace, as `(path, value)` pairs applied in order.
+    ///
+    /// Paths are data paths such as `service.replicas` or `$.items[0]`; see
+    /// [`overrides::parse_override`] for `path=value` strings. Overrides apply
+    /// to the root document only, before derived values resolve, and are
+    /// checked by type hints and constraints. Overriding a frozen (`^`) key or
+    /// a locked template field is an error.
+    pub overrides: Vec<(String, JsonValue)>,
+    /// Overrides the default `$SYAML_CACHE_DIR` / `~/.cache/super_yaml/` URL
+    /// import cache location.
+    pub cache_dir: Option<PathBuf>,
+    /// Bypasses the lockfile cache and re-fetches all URL imports.
+    pub update_imports: bool,
+    /// Environment profile to compile: the file is compiled with its
+    /// `<stem>.<profile>.syaml` overlay applied (see [`overlay::resolve_profile`]).
+    pub profile: Option<String>,
+}
+
 /// Compiles a `.syaml` document into resolved data.
 ///
 /// Compilation includes expression resolution, environment substitution, type-hint
@@ -225,12 +356,32 @@ pub fn compile_document(
     input: &str,
     env_provider: &dyn EnvProvider,
 ) -> Result<CompiledDocument, SyamlError> {
+    compile_document_with_options(input, env_provider, &CompileOptions::default())
+}
+
+/// Compiles a `.syaml` document with value overrides applied.
+///
+/// URL imports are not fetched, so `cache_dir` and `update_imports` are unused.
+/// There is no file to find a profile overlay next to, so `profile` is
+/// rejected.
+pub fn compile_document_with_options(
+    input: &str,
+    env_provider: &dyn EnvProvider,
+    options: &CompileOptions,
+) -> Result<CompiledDocument, SyamlError> {
+    if let Some(profile) = &options.profile {
+        return Err(SyamlError::OverlayError(format!(
+            "profile '{profile}' needs a file path to find its overlay"
+        )));
+    }
     let cwd = std::env::current_dir()?;
     let mut ctx = CompileContext::new(env_provider);
+    ctx.overrides = options.overrides.clone();
     let compiled = compile_document_internal(input, &cwd, &mut ctx)?;
     Ok(CompiledDocument {
         value: compiled.value,
         warnings: compiled.warnings,
+        sensitive_paths: compiled.sensitive_paths,
     })
 }
 
@@ -255,7 +406,25 @@ pub fn compile_document_from_path_with_fetch(
     cache_dir: Option<PathBuf>,
     force_update: bool,
 ) -> Result<CompiledDocument, SyamlError> {
-    let path = path.as_ref();
+    let options = CompileOptions {
+        cache_dir,
+        update_imports: force_update,
+        ..CompileOptions::default()
+    };
+    compile_document_from_path_with_options(path, env_provider, &options)
+}
+
+/// Compiles a `.syaml` file with value overrides and URL import fetch options.
+pub fn compile_document_from_path_with_options(
+    path: impl AsRef<Path>,
+    env_provider: &dyn EnvProvider,
+    options: &CompileOptions,
+) -> Result<CompiledDocument, SyamlError> {
+    let profile_path = match &options.profile {
+        Some(profile) => Some(overlay::resolve_profile(path.as_ref(), profile)?),
+        None => None,
+    };
+    let path = profile_path.as_deref().unwrap_or(path.as_ref());
     let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
     let root_dir = canonical
         .parent()
@@ -265,16 +434,107 @@ pub fn compile_document_from_path_with_fetch(
         env_provider,
         import_cache: HashMap::new(),
         import_stack: Vec::new(),
-        fetch_ctx: FetchContext::new(&root_dir, cache_dir, force_update),
+        fetch_ctx: FetchContext::new(&root_dir, options.cache_dir.clone(), options.update_imports),
+        provenance: None,
+        overrides: options.overrides.clone(),
+        overlays: Vec::new(),
+        open_files: HashMap::new(),
     };
     let compiled = compile_document_from_file(path, &mut ctx)?;
     fetch::flush_lockfile(&ctx.fetch_ctx)?;
     Ok(CompiledDocument {
         value: compiled.value,
         warnings: compiled.warnings,
+        sensitive_paths: compiled.sensitive_paths,
     })
 }
 
+/// Compiles `.syaml` source text as though it were the contents of `path`.
+///
+/// Imports resolve relative to `path` and error spans name it, but the file itself
+/// is not read. This is meant for editors compiling unsaved buffers: URL imports are
+/// served from the cache when possible and the lockfile is never written.
+pub fn compile_document_from_source(
+    input: &str,
+    path: impl AsRef<Path>,
+    env_provider: &dyn EnvProvider,
+) -> Result<CompiledDocument, SyamlError> {
+    compile_document_from_source_with_open_files(input, path, &HashMap::new(), env_provider)
+}
+
+/// Compiles `.syaml` source text as though it were the contents of `path`, like
+/// [`compile_document_from_source`], reading the text in `open_files` instead of
+/// the file on disk for each canonical path it holds.
+///
+/// Editors pass their other open buffers here, so a document sees unsaved edits
+/// to the files it imports.
+pub fn compile_document_from_source_with_open_files(
+    input: &str,
+    path: impl AsRef<Path>,
+    open_files: &HashMap<PathBuf, String>,
+    env_provider: &dyn EnvProvider,
+) -> Result<CompiledDocument, SyamlError> {
+    let path = path.as_ref();
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let root_dir = canonical
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    let mut ctx = CompileContext {
+        env_provider,
+        import_cache: HashMap::new(),
+        import_stack: Vec::new(),
+        fetch_ctx: FetchContext::new(&root_dir, None, false),
+        provenance: None,
+        overrides: Vec::new(),
+        overlays: Vec::new(),
+        open_files: open_files.clone(),
+    };
+    let compiled = compile_document_from_content(input, &canonical, &mut ctx)?;
+    Ok(CompiledDocument {
+        value: compiled.value,
+        warnings: compiled.warnings,
+        sensitive_paths: compiled.sensitive_paths,
+    })
+}
+
+/// Compiles a `.syaml` file and records how each output value was produced.
+///
+/// The [`Provenance`] covers the file's own data. Values read from imports are
+/// traced to the import path they came from, not into the imported file.
+pub fn compile_document_with_provenance_from_path(
+    path: impl AsRef<Path>,
+    env_provider: &dyn EnvProvider,
+) -> Result<(CompiledDocument, Provenance), SyamlError> {
+    let path = path.as_ref();
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let root_dir = canonical
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+    let mut ctx = CompileContext {
+        env_provider,
+        import_cache: HashMap::new(),
+        import_stack: Vec::new(),
+        fetch_ctx: FetchContext::new(&root_dir, None, false),
+        provenance: Some(Provenance::new(Some(path.display().to_string()))),
+        overrides: Vec::new(),
+        overlays: Vec::new(),
+        open_files: HashMap::new(),
+    };
+    let compiled = compile_document_from_file(path, &mut ctx)?;
+    fetch::flush_lockfile(&ctx.fetch_ctx)?;
+    let provenance = ctx.provenance.take().unwrap_or_default();
+    Ok((
+        CompiledDocument {
+            value: compiled.value,
+            warnings: compiled.warnings,
+            sensitive_paths: compiled.sensitive_paths,
+        },
+        provenance,
+    ))
+}
+
 /// Validates a `.syaml` document without returning compiled output.
 ///
 /// This runs the full compilation pipeline and discards the result.
@@ -331,7 +591,31 @@ struct CompiledWithTypes {
     value: JsonValue,
     exported_types: BTreeMap<String, JsonValue>,
     exported_type_constraints: BTreeMap<String, BTreeMap<String, Vec<String>>>,
-    warnings: Vec<String>,
+    exported_functions: BTreeMap<String, PureFunctionDef>,
+    warnings: Vec<Warning>,
+    sensitive_paths: BTreeSet<String>,
+}
+
+/// What a compile learned before it failed, used to locate and mask its errors.
+#[derive(Default)]
+struct FailureContext {
+    /// Sensitive paths. Snippets of their source text do not print, and
+    /// errors at or around them hide their messages.
+    sensitive: BTreeSet<String>,
+    /// Set once validation starts: validators keep sensitive values out of
+    /// their own messages.
+    validating: bool,
+    /// Overlay spans of the values overlay patches wrote.
+    patched: SourceMap,
+}
+
+impl FailureContext {
+    /// Whether the value at `path` is sensitive or holds a sensitive value.
+    fn touches_sensitive(&self, path: &str) -> bool {
+        self.sensitive
+            .iter()
+            .any(|sensitive| path_is_within(path, sensitive) || path_is_within(sensitive, path))
+    }
 }
 
 struct CompileContext<'a> {
@@ -339,6 +623,14 @@ struct CompileContext<'a> {
     import_cache: HashMap<PathBuf, CompiledWithTypes>,
     import_stack: Vec<PathBuf>,
     fetch_ctx: FetchContext,
+    /// Recorder for the root document; imports compile without one.
+    provenance: Option<Provenance>,
+    /// Value overrides for the root document; imports compile without them.
+    overrides: Vec<(String, JsonValue)>,
+    /// Overlay patches for the next document compiled, in the order they apply.
+    overlays: Vec<overlay::Patch>,
+    /// Text to read instead of the file at a canonical path (unsaved editor buffers).
+    open_files: HashMap<PathBuf, String>,
 }
 
 impl<'a> CompileContext<'a> {
@@ -348,6 +640,10 @@ impl<'a> CompileContext<'a> {
             import_cache: HashMap::new(),
             import_stack: Vec::new(),
             fetch_ctx: FetchContext::disabled(),
+            provenance: None,
+            overrides: Vec::new(),
+            overlays: Vec::new(),
+            open_files: HashMap::new(),
         }
     }
 }
@@ -358,6 +654,9 @@ fn compile_document_internal(
     ctx: &mut CompileContext<'_>,
 ) -> Result<CompiledWithTypes, SyamlError> {
     let parsed = parse_document(input)?;
+    if let Some(base) = parsed.overlay.clone() {
+        return compile_overlay(parsed, &base, base_dir, None, ctx);
+    }
     compile_parsed_document(parsed, base_dir, ctx)
 }
 
@@ -366,12 +665,15 @@ fn compile_document_from_file(
     ctx: &mut CompileContext<'_>,
 ) -> Result<CompiledWithTypes, SyamlError> {
     let canonical_path = canonicalize_path(path)?;
-    let input = fs::read_to_string(&canonical_path).map_err(|e| {
-        SyamlError::ImportError(format!(
-            "failed to read import '{}': {e}",
-            canonical_path.display()
-        ))
-    })?;
+    let input = match ctx.open_files.get(&canonical_path) {
+        Some(text) => text.clone(),
+        None => fs::read_to_string(&canonical_path).map_err(|e| {
+            SyamlError::ImportError(format!(
+                "failed to read import '{}': {e}",
+                canonical_path.display()
+            ))
+        })?,
+    };
     compile_document_from_content(&input, &canonical_path, ctx)
 }
 
@@ -380,7 +682,9 @@ fn compile_document_from_content(
     canonical_path: &Path,
     ctx: &mut CompileContext<'_>,
 ) -> Result<CompiledWithTypes, SyamlError> {
-    if let Some(cached) = ctx.import_cache.get(canonical_path) {
+    // A document compiled with overlay patches differs from the file alone.
+    let cacheable = ctx.overlays.is_empty();
+    if let Some(cached) = ctx.import_cache.get(canonical_path).filter(|_| cacheable) {
         return Ok(cached.clone());
     }
 
@@ -420,9 +724,15 @@ fn compile_document_from_content(
 
     ctx.import_stack.pop();
 
-    let compiled = compiled?;
-    ctx.import_cache
-        .insert(canonical_path.to_path_buf(), compiled.clone());
+    let mut compiled = compiled.map_err(|e| e.with_file(canonical_path.display().to_string()))?;
+    for span in compiled.warnings.iter_mut().filter_map(|w| w.span.as_mut()) {
+        span.file
+            .get_or_insert_with(|| canonical_path.display().to_string());
+    }
+    if cacheable {
+        ctx.import_cache
+            .insert(canonical_path.to_path_buf(), compiled.clone());
+    }
     Ok(compiled)
 }
 
@@ -458,6 +768,8 @@ fn compile_module_manifest(
                 &mut dummy_types,
                 &mut BTreeMap::new(),
                 &mut dummy_data,
+                &mut BTreeMap::new(),
+                &mut BTreeSet::new(),
                 ctx,
             )?;
             exported_types = dummy_types;
@@ -473,7 +785,9 @@ fn compile_module_manifest(
         value: JsonValue::Object(serde_json::Map::new()),
         exported_types,
         exported_type_constraints,
+        exported_functions: BTreeMap::new(),
         warnings: Vec::new(),
+        sensitive_paths: BTreeSet::new(),
     })
 }
 
@@ -485,6 +799,9 @@ fn compile_document_with_module_context(
     ctx: &mut CompileContext<'_>,
 ) -> Result<CompiledWithTypes, SyamlError> {
     let mut parsed = parse_document(input)?;
+    if let Some(base) = parsed.overlay.clone() {
+        return compile_overlay(parsed, &base, base_dir, Some(canonical_path), ctx);
+    }
 
     // Step 1: find module manifest (if any) and enforce policy on file's own imports
     let manifest_result = find_and_load_module_manifest(canonical_path)?;
@@ -511,6 +828,50 @@ fn compile_document_with_module_context(
     compile_parsed_document(parsed, base_dir, ctx)
 }
 
+/// Compiles the base document of an overlay with the overlay's patch applied,
+/// before the patches of any overlays of this overlay.
+///
+/// `file` is the overlay's own path, named by the spans of the values its
+/// patch writes; an overlay compiled from a string is named `<input>`.
+fn compile_overlay(
+    parsed: ParsedDocument,
+    base: &str,
+    base_dir: &Path,
+    file: Option<&Path>,
+    ctx: &mut CompileContext<'_>,
+) -> Result<CompiledWithTypes, SyamlError> {
+    overlay::check_overlay_document(&parsed)?;
+    if is_url(base) || base.starts_with('@') {
+        return Err(SyamlError::OverlayError(format!(
+            "overlay base '{base}' must be a local file path"
+        )));
+    }
+    let base_path = base_dir.join(base);
+    if !base_path.is_file() {
+        return Err(SyamlError::OverlayError(format!(
+            "overlay base '{base}' not found"
+        )));
+    }
+    let file = file.map_or_else(|| "<input>".to_string(), |f| f.display().to_string());
+    let mut source_map = SourceMap::new();
+    for (path, span) in parsed.source_map.iter() {
+        let mut span = span.clone();
+        span.file.get_or_insert_with(|| file.clone());
+        source_map.insert(path.clone(), span);
+    }
+    ctx.overlays.insert(
+        0,
+        overlay::Patch {
+            data: parsed.data,
+            source_map,
+        },
+    );
+    if let Some(provenance) = ctx.provenance.as_mut() {
+        provenance.follow_overlay(base);
+    }
+    compile_document_from_file(&base_path, ctx)
+}
+
 /// Returns a copy of `manifest` with any imports that resolve to `self_path` removed.
 /// This prevents module-injected imports from creating self-import cycles.
 fn filter_self_referential_imports(
@@ -582,13 +943,75 @@ fn resolve_module_imports_in_meta(
 }
 
 fn compile_parsed_document(
-    parsed: ParsedDocument,
+    mut parsed: ParsedDocument,
+    base_dir: &Path,
+    ctx: &mut CompileContext<'_>,
+) -> Result<CompiledWithTypes, SyamlError> {
+    let source_map = std::mem::take(&mut parsed.source_map);
+    // Taken before imports compile, so only this document is recorded.
+    let mut provenance = ctx.provenance.take();
+    if let Some(provenance) = provenance.as_mut() {
+        provenance.set_source_map(source_map.clone());
+    }
+    let overrides = std::mem::take(&mut ctx.overrides);
+    let overlays = std::mem::take(&mut ctx.overlays);
+    let mut failure = FailureContext::default();
+    let compiled = compile_parsed_document_unlocated(
+        parsed,
+        base_dir,
+        ctx,
+        provenance.as_mut(),
+        &overlays,
+        &overrides,
+        &mut failure,
+    );
+    // Values written by overlays are located in the overlay that wrote them.
+    let source_map = overlay::layer_spans(&failure.patched, &source_map);
+    if let Some(provenance) = provenance.as_mut().filter(|_| !failure.patched.is_empty()) {
+        provenance.set_source_map(source_map.clone());
+    }
+    ctx.provenance = provenance;
+    let mut compiled = compiled.map_err(|e| {
+        let located =
+            locate_compile_error(e, &source_map, &overrides).mark_sensitive_spans(&|path| {
+                failure
+                    .sensitive
+                    .iter()
+                    .any(|sensitive| path_is_within(path, sensitive))
+            });
+        if failure.validating {
+            located
+        } else {
+            located.hide_sensitive_messages(&|path| failure.touches_sensitive(path))
+        }
+    })?;
+    for warning in &mut compiled.warnings {
+        warning.span = warning
+            .data_path
+            .as_deref()
+            .and_then(|path| source_map.lookup(path))
+            .cloned();
+    }
+    Ok(compiled)
+}
+
+/// Compiles a parsed document. Sensitive values and paths are added to
+/// `failure` as soon as they are known, so the caller can mask them in any
+/// error, as are the overlay spans of patched values.
+fn compile_parsed_document_unlocated(
+    mut parsed: ParsedDocument,
     base_dir: &Path,
     ctx: &mut CompileContext<'_>,
+    provenance: Option<&mut Provenance>,
+    overlays: &[overlay::Patch],
+    overrides: &[(String, JsonValue)],
+    failure: &mut FailureContext,
 ) -> Result<CompiledWithTypes, SyamlError> {
     let mut schema = parsed.schema;
     let mut data = parsed.data.value.clone();
     let mut imported_data = HashMap::new();
+    let mut function_registry = BTreeMap::new();
+    let mut sensitive = BTreeSet::new();
 
     let excluded_hints = if let Some(meta) = parsed.meta.as_ref() {
         merge_imports(
@@ -597,14 +1020,51 @@ fn compile_parsed_document(
             &mut schema.types,
             &mut schema.type_constraints,
             &mut imported_data,
+            &mut function_registry,
+            &mut sensitive,
             ctx,
         )?
     } else {
         HashMap::new()
     };
+    sensitive.extend(
+        parsed
+            .meta
+            .iter()
+            .flat_map(|m| &m.env)
+            .filter(|(_, binding)| binding.sensitive)
+            .map(|(symbol, _)| format!("env.{symbol}")),
+    );
+    // Sensitivity follows values through the recorded steps, so it needs a
+    // provenance record even when the caller did not ask for one.
+    let tracks_sensitivity =
+        !sensitive.is_empty() || sensitive::schema_marks_sensitive(&schema.types);
+    if tracks_sensitivity {
+        failure.sensitive = &sensitive
+            | &sensitive::schema_sensitive_paths(&data, &parsed.data.type_hints, &schema.types);
+    }
+    let mut sensitivity_record = Provenance::default();
+    let mut provenance = match provenance {
+        Some(provenance) => Some(provenance),
+        None if tracks_sensitivity => Some(&mut sensitivity_record),
+        None => None,
+    };
     validate_schema_type_references(&schema.types)
         .map_err(|e| augment_with_section_hint(e, &excluded_hints))?;
 
+    if let Some(ref function_defs) = parsed.functions {
+        function_registry.extend(
+            function_defs
+                .functions
+                .iter()
+                .map(|(name, def)| (name.clone(), def.clone())),
+        );
+        functions::validate_function_type_references(function_defs, &schema.types)?;
+        functions::validate_function_recursion(function_defs)?;
+        functions::check_function_bodies(function_defs, &schema.types, &function_registry)
+            .map_err(|e| augment_with_section_hint(e, &excluded_hints))?;
+    }
+
     let strict_field_numbers = parsed
         .meta
         .as_ref()
@@ -620,36 +1080,213 @@ fn compile_parsed_document(
         .as_ref()
         .and_then(|m| m.file.get("schema_version"))
         .and_then(|v| v.as_str())
-        .map(|s| semver::Version::parse(s))
+        .map(semver::Version::parse)
         .transpose()
         .map_err(|e| SyamlError::VersionError(format!("invalid meta.file.schema_version: {e}")))?;
 
-    extract_explicit_import_values(&mut data, &imported_data)?;
-    expand_data_templates(&mut data, &imported_data)?;
-    resolve_data_references(&mut data)?;
-
-    let env_values = resolve_env_bindings(parsed.meta.as_ref(), ctx.env_provider)?;
     let imports_for_eval: BTreeMap<String, JsonValue> = imported_data
         .iter()
         .map(|(k, v)| (k.clone(), v.clone()))
         .collect();
-    resolve_expressions_with_imports(&mut data, &env_values, &imports_for_eval)
-        .map_err(|e| augment_with_section_hint(e, &excluded_hints))?;
-    resolve_enum_member_references(&mut data, &parsed.data.type_hints, &schema)
-        .map_err(|e| augment_with_section_hint(e, &excluded_hints))?;
-    coerce_string_constructors_for_type_hints(&mut data, &parsed.data.type_hints, &schema.types)?;
+    if let Some(provenance) = provenance.as_deref_mut() {
+        let sources = parsed
+            .meta
+            .iter()
+            .flat_map(|m| &m.imports)
+            .map(|(alias, binding)| (alias.clone(), binding.path.clone()))
+            .collect();
+        provenance.set_imports(&imports_for_eval, sources);
+    }
 
-    validate_type_hints(&data, &parsed.data.type_hints, &schema)
-        .map_err(|e| augment_with_section_hint(e, &excluded_hints))?;
+    traced(&mut provenance, StepKind::Import, &mut data, |data| {
+        extract_explicit_import_values(data, &imported_data)
+    })?;
+    let locked_fields = if overrides.is_empty() && overlays.is_empty() {
+        BTreeSet::new()
+    } else {
+        locked_template_fields(&data, &imported_data)
+    };
+    traced(&mut provenance, StepKind::Template, &mut data, |data| {
+        expand_data_templates(data, &imported_data)
+    })?;
+    traced(&mut provenance, StepKind::Overlay, &mut data, |data| {
+        for patch in overlays {
+            let mut patch_data = patch.data.clone();
+            extract_explicit_import_values(&mut patch_data.value, &imported_data)?;
+            expand_data_templates_in(&mut patch_data.value, data, &imported_data)?;
+            let written = overlay::apply_overlay(
+                data,
+                &patch_data,
+                &patch.source_map,
+                &mut parsed.data.type_hints,
+                &mut parsed.data.freeze_markers,
+                &locked_fields,
+            )?;
+            failure.patched = overlay::layer_spans(&written, &failure.patched);
+        }
+        Ok(())
+    })?;
+    let mut literals = BTreeSet::new();
+    traced(&mut provenance, StepKind::Override, &mut data, |data| {
+        literals = apply_overrides(data, overrides, &parsed.data.freeze_markers, &locked_fields)?;
+        Ok(())
+    })?;
+    validate_override_values(&data, &literals, &parsed.data.type_hints, &schema)?;
+    traced(&mut provenance, StepKind::Reference, &mut data, |data| {
+        resolve_data_references_except(data, &literals)
+    })?;
+
+    let env_values = resolve_env_bindings_in(
+        parsed.meta.as_ref(),
+        ctx.env_provider,
+        base_dir,
+        &schema.types,
+    )?;
+    if tracks_sensitivity {
+        // Expressions have not run yet, so what they will read is found from
+        // their source.
+        let mut known = &sensitive
+            | &sensitive::schema_sensitive_paths(&data, &parsed.data.type_hints, &schema.types);
+        if let Some(provenance) = provenance.as_deref() {
+            sensitive::propagate(provenance, &mut known);
+        }
+        let readers = sensitive::derived_readers(&data, &imports_for_eval, &known);
+        failure.sensitive = &known | &readers;
+    }
+    if let Some(provenance) = provenance.as_deref_mut() {
+        let origins = parsed
+            .meta
+            .iter()
+            .flat_map(|m| &m.env)
+            .map(|(symbol, binding)| {
+                let missing = match binding.source {
+                    EnvSource::Env => format!("{} is not set", binding.key),
+                    _ => format!("{} is not available", binding.describe_source()),
+                };
+                let origin = if matches!(
+                    resolve::read_binding(binding, ctx.env_provider, base_dir),
+                    Ok(Some(_))
+                ) {
+                    binding.describe_source()
+                } else if binding.default.is_some() {
+                    format!("default; {missing}")
+                } else {
+                    missing
+                };
+                (symbol.clone(), origin)
+            })
+            .collect();
+        provenance.set_env(&env_values, origins);
+    }
+    let env_names: BTreeSet<String> = parsed
+        .meta
+        .iter()
+        .flat_map(|m| m.env.keys().cloned())
+        .collect();
+    // A typed binding that may resolve to null keeps an unknown type.
+    let env_types: BTreeMap<String, JsonValue> = parsed
+        .meta
+        .iter()
+        .flat_map(|m| &m.env)
+        .filter(|(_, binding)| binding.required || binding.default.is_some())
+        .filter_map(|(symbol, binding)| Some((symbol.clone(), binding.type_ref.clone()?)))
+        .collect();
+    let type_env = TypeEnv::new(&schema.types)
+        .with_data(&data, &parsed.data.type_hints)
+        .with_env(&env_names)
+        .with_env_types(&env_types)
+        .with_imports(&imports_for_eval)
+        .with_functions(&function_registry)
+        .with_literals(&literals);
+    // Type errors are reported together with the validation failures below;
+    // values still resolve unless evaluation fails too.
+    let mut errors = Vec::new();
+    if let Err(e) = check_data_expressions(&type_env) {
+        errors.push(augment_with_section_hint(e, &excluded_hints));
+    }
+    let quantities = quantity_paths(&data, &parsed.data.type_hints, &schema.types);
+    traced(&mut provenance, StepKind::Expression, &mut data, |data| {
+        resolve_expressions_except(
+            data,
+            &env_values,
+            &imports_for_eval,
+            &function_registry,
+            &literals,
+            &quantities,
+        )
+    })
+    .map_err(|e| after_type_errors(&mut errors, augment_with_section_hint(e, &excluded_hints)))?;
+    traced(&mut provenance, StepKind::EnumMember, &mut data, |data| {
+        resolve_enum_member_references(data, &parsed.data.type_hints, &schema)
+    })
+    .map_err(|e| after_type_errors(&mut errors, augment_with_section_hint(e, &excluded_hints)))?;
+    traced(&mut provenance, StepKind::Constructor, &mut data, |data| {
+        coerce_string_constructors_for_type_hints(data, &parsed.data.type_hints, &schema.types)
+    })
+    .map_err(|e| after_type_errors(&mut errors, e))?;
+    if let Some(provenance) = provenance.as_deref_mut() {
+        provenance.set_data(&data);
+    }
+    if tracks_sensitivity {
+        sensitive.extend(sensitive::schema_sensitive_paths(
+            &data,
+            &parsed.data.type_hints,
+            &schema.types,
+        ));
+        if let Some(provenance) = provenance.as_deref() {
+            sensitive::propagate(provenance, &mut sensitive);
+        }
+        failure.sensitive.clone_from(&sensitive);
+        if let Some(provenance) = provenance {
+            provenance.set_sensitive(&sensitive);
+        }
+    }
+
+    // Validation stages only read the resolved data, so all of them run and every
+    // independent failure is reported together.
+    failure.validating = true;
+    if let Err(e) =
+        validate_type_hints_concealing(&data, &parsed.data.type_hints, &schema, &|path| {
+            failure.touches_sensitive(path)
+        })
+    {
+        // A derived value that failed its type check is not reported twice.
+        let mistyped = error_paths(&errors);
+        errors.extend(drop_errors_within(
+            augment_with_section_hint(e, &excluded_hints),
+            &mistyped,
+        ));
+    }
     let constraints = build_effective_constraints(&parsed.data.type_hints, &schema);
-    validate_constraints_with_imports(&data, &env_values, &constraints, &imports_for_eval)?;
+    if let Err(e) = validate_constraints_with_imports(
+        &data,
+        &env_values,
+        &constraints,
+        &imports_for_eval,
+        &function_registry,
+    ) {
+        // A value that already failed its type check would only add noise here.
+        let mistyped = error_paths(&errors);
+        // Evaluation messages may quote the values a constraint read.
+        errors.extend(
+            drop_errors_within(e, &mistyped)
+                .into_iter()
+                .map(|e| e.hide_sensitive_messages(&|path| failure.touches_sensitive(path))),
+        );
+    }
 
-    let warnings = validate_versioned_fields(
+    let warnings = match validate_versioned_fields(
         &data,
         &parsed.data.type_hints,
         &schema,
         target_schema_version.as_ref(),
-    )?;
+    ) {
+        Ok(warnings) => warnings,
+        Err(e) => {
+            errors.push(e);
+            Vec::new()
+        }
+    };
 
     if let Some(ref func_doc) = parsed.contracts {
         let import_aliases: std::collections::BTreeSet<String> = parsed
@@ -658,30 +1295,60 @@ fn compile_parsed_document(
             .flat_map(|m| m.imports.keys().cloned())
             .collect();
         let all_types = schema.types.clone();
-        contracts::validate_contracts_type_references(func_doc, &all_types)?;
-        contracts::validate_permission_data_paths(func_doc, &data, &import_aliases)?;
-        contracts::validate_permission_mutability_alignment(
-            func_doc,
-            &schema,
-            &parsed.data.type_hints,
-        )?;
-        contracts::validate_permission_instance_lock_conflicts(
-            func_doc,
-            &parsed.data.freeze_markers,
-        )?;
-        contracts::validate_specification_strict_conditions(func_doc)?;
+        let results = [
+            contracts::validate_contracts_type_references(func_doc, &all_types),
+            contracts::validate_permission_data_paths(func_doc, &data, &import_aliases),
+            contracts::validate_permission_mutability_alignment(
+                func_doc,
+                &schema,
+                &parsed.data.type_hints,
+            ),
+            contracts::validate_permission_instance_lock_conflicts(
+                func_doc,
+                &parsed.data.freeze_markers,
+            ),
+            contracts::validate_specification_strict_conditions(
+                func_doc,
+                &schema.types,
+                &data,
+                &parsed.data.type_hints,
+            ),
+        ];
+        errors.extend(results.into_iter().filter_map(Result::err));
     }
 
+    SyamlError::from_errors(errors)?;
+
     strip_private_top_level_data_keys(&mut data);
+    let sensitive_paths = sensitive::data_paths(&sensitive, &data);
 
     Ok(CompiledWithTypes {
         value: data,
         exported_types: schema.types,
         exported_type_constraints: schema.type_constraints,
+        exported_functions: function_registry,
         warnings,
+        sensitive_paths,
     })
 }
 
+/// Runs a data-rewriting stage, recording what it changed when `provenance`
+/// is present.
+fn traced(
+    provenance: &mut Option<&mut Provenance>,
+    kind: StepKind,
+    data: &mut JsonValue,
+    stage: impl FnOnce(&mut JsonValue) -> Result<(), SyamlError>,
+) -> Result<(), SyamlError> {
+    let Some(provenance) = provenance.as_deref_mut() else {
+        return stage(data);
+    };
+    let before = data.clone();
+    stage(data)?;
+    provenance.record_changes(kind, &before, data);
+    Ok(())
+}
+
 fn strip_private_top_level_data_keys(data: &mut JsonValue) {
     let Some(root) = data.as_object_mut() else {
         return;
@@ -693,12 +1360,15 @@ fn is_private_top_level_key(key: &str) -> bool {
     key.starts_with('_')
 }
 
+#[allow(clippy::too_many_arguments)]
 fn merge_imports(
     meta: &Meta,
     base_dir: &Path,
     type_registry: &mut BTreeMap<String, JsonValue>,
     type_constraints: &mut BTreeMap<String, BTreeMap<String, Vec<String>>>,
     imported_data: &mut HashMap<String, JsonValue>,
+    function_registry: &mut BTreeMap<String, PureFunctionDef>,
+    imported_sensitive: &mut BTreeSet<String>,
     ctx: &mut CompileContext<'_>,
 ) -> Result<HashMap<String, String>, SyamlError> {
     let mut excluded_hints: HashMap<String, String> = HashMap::new();
@@ -707,12 +1377,15 @@ fn merge_imports(
         let source = fetch::resolve_import_source(base_dir, &binding.path, &ctx.fetch_ctx)?;
         let display_id = source.display_id();
 
-        let content = fetch::read_import_source(&source, &mut ctx.fetch_ctx).map_err(|e| {
-            SyamlError::ImportError(format!(
-                "failed to read import '{}' for namespace '{}': {e}",
-                display_id, alias
-            ))
-        })?;
+        let content = match ctx.open_files.get(source.canonical_path()) {
+            Some(text) => text.clone(),
+            None => fetch::read_import_source(&source, &mut ctx.fetch_ctx).map_err(|e| {
+                SyamlError::ImportError(format!(
+                    "failed to read import '{}' for namespace '{}': {e}",
+                    display_id, alias
+                ))
+            })?,
+        };
 
         if let Some(ref expected_hash) = binding.hash {
             verify::verify_hash(content.as_bytes(), expected_hash).map_err(|e| {
@@ -734,10 +1407,15 @@ fn merge_imports(
 
         let canonical = source.canonical_path().to_path_buf();
         let imported = compile_document_from_content(&content, &canonical, ctx).map_err(|e| {
-            SyamlError::ImportError(format!(
+            let wrapped = SyamlError::ImportError(format!(
                 "failed to compile import '{}' for namespace '{}': {e}",
                 display_id, alias
-            ))
+            ));
+            // The nested data path refers to the imported file; only its span carries over.
+            match e.span() {
+                Some(span) => wrapped.with_span(span.clone()),
+                None => wrapped,
+            }
         })?;
 
         if let Some(ref version_req) = binding.version {
@@ -778,8 +1456,36 @@ fn merge_imports(
             }
         }
 
+        if imports_section(&binding.sections, "functions") {
+            insert_imported_functions(
+                function_registry,
+                alias,
+                &imported.exported_functions,
+                &imported.exported_types,
+                imports_section(&binding.sections, "schema"),
+            )?;
+        } else {
+            for function_name in imported.exported_functions.keys() {
+                excluded_hints.insert(
+                    format!("{alias}.{function_name}"),
+                    format!(
+                        "function '{alias}.{function_name}' exists in the 'functions' section \
+                         of '{display_id}', but 'functions' is not included in the sections \
+                         for import '{alias}'; add 'functions' to sections to call it"
+                    ),
+                );
+            }
+        }
+
         if imports_section(&binding.sections, "data") {
             imported_data.insert(alias.clone(), imported.value.clone());
+            // `$.a.b` in the imported document is `alias.a.b` here.
+            imported_sensitive.extend(
+                imported
+                    .sensitive_paths
+                    .iter()
+                    .map(|path| format!("{alias}{}", &path[1..])),
+            );
         } else {
             excluded_hints.insert(
                 alias.clone(),
@@ -802,10 +1508,57 @@ fn imports_section(sections: &Option<Vec<String>>, section: &str) -> bool {
     }
 }
 
+/// Data paths of `errors`.
+fn error_paths(errors: &[SyamlError]) -> Vec<String> {
+    errors
+        .iter()
+        .flat_map(|e| e.errors())
+        .filter_map(|e| e.data_path().map(str::to_string))
+        .collect()
+}
+
+/// The failures in `error`, without those about a value within `paths`.
+fn drop_errors_within(error: SyamlError, paths: &[String]) -> Vec<SyamlError> {
+    let children = match error {
+        SyamlError::Multiple(children) => children,
+        other => vec![other],
+    };
+    children
+        .into_iter()
+        .filter(|e| {
+            !e.data_path()
+                .is_some_and(|path| paths.iter().any(|m| path_is_within(path, m)))
+        })
+        .collect()
+}
+
+/// Reports `error` after the type errors found so far, leaving it out when
+/// it is about a value that already failed its type check.
+fn after_type_errors(type_errors: &mut Vec<SyamlError>, error: SyamlError) -> SyamlError {
+    if type_errors.is_empty() {
+        return error;
+    }
+    let mistyped = error_paths(type_errors);
+    let mut errors = std::mem::take(type_errors);
+    errors.extend(drop_errors_within(error, &mistyped));
+    SyamlError::from_errors(errors).expect_err("type errors are pending")
+}
+
 fn augment_with_section_hint(e: SyamlError, hints: &HashMap<String, String>) -> SyamlError {
     if hints.is_empty() {
         return e;
     }
+    if let SyamlError::Located { .. } = e {
+        return e.map_inner(|inner| augment_with_section_hint(inner, hints));
+    }
+    if let SyamlError::Multiple(errors) = e {
+        return SyamlError::Multiple(
+            errors
+                .into_iter()
+                .map(|e| augment_with_section_hint(e, hints))
+                .collect(),
+        );
+    }
     let msg = e.to_string();
     // Check longer keys first to prefer more specific hints (e.g. alias.Type over alias).
     let mut sorted: Vec<(&String, &String)> = hints.iter().collect();
@@ -866,6 +1619,57 @@ fn insert_imported_types(
     Ok(())
 }
 
+/// Adds the functions of an import as `alias.name`. Calls between them and,
+/// when the schema is imported too, references to the import's types are
+/// qualified with the alias.
+fn insert_imported_functions(
+    target_functions: &mut BTreeMap<String, PureFunctionDef>,
+    alias: &str,
+    imported_functions: &BTreeMap<String, PureFunctionDef>,
+    imported_types: &BTreeMap<String, JsonValue>,
+    with_schema: bool,
+) -> Result<(), SyamlError> {
+    let call_renames: BTreeMap<String, String> = imported_functions
+        .keys()
+        .map(|name| (name.clone(), format!("{alias}.{name}")))
+        .collect();
+    let type_renames: BTreeMap<String, String> = if with_schema {
+        imported_types
+            .keys()
+            .map(|name| (name.clone(), format!("{alias}.{name}")))
+            .collect()
+    } else {
+        BTreeMap::new()
+    };
+    let known_type_names: HashSet<String> = type_renames.keys().cloned().collect();
+    let rewrite_type = |type_ref: &JsonValue| match type_ref {
+        JsonValue::String(name) => type_renames
+            .get(name)
+            .map(|renamed| JsonValue::String(renamed.clone()))
+            .unwrap_or_else(|| type_ref.clone()),
+        other => rewrite_schema_type_references(other, &known_type_names, &type_renames),
+    };
+
+    for (name, def) in imported_functions {
+        let prefixed_name = call_renames.get(name).expect("present");
+        if target_functions.contains_key(prefixed_name) {
+            return Err(SyamlError::ImportError(format!(
+                "imported function '{}' conflicts with existing function '{}'",
+                name, prefixed_name
+            )));
+        }
+        let mut def = def.clone();
+        def.body = functions::qualify_function_calls(&def.body, &call_renames);
+        for param in &mut def.params {
+            param.type_ref = rewrite_type(&param.type_ref);
+        }
+        def.returns = def.returns.as_ref().map(rewrite_type);
+        target_functions.insert(prefixed_name.clone(), def);
+    }
+
+    Ok(())
+}
+
 fn insert_imported_type_constraints(
     target_constraints: &mut BTreeMap<String, BTreeMap<String, Vec<String>>>,
     alias: &str,
@@ -1000,11 +1804,74 @@ fn is_builtin_primitive_type(type_name: &str) -> bool {
     matches!(
         type_name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || crate::quantity::QUANTITY_TYPE_NAMES.contains(&type_name)
 }
 
 fn parse_section_value(section: &str, body: &str) -> Result<JsonValue, SyamlError> {
-    mini_yaml::parse_document(body).map_err(|e| match e {
+    mini_yaml::parse_document(body).map_err(|e| name_yaml_error_section(e, section))
+}
+
+/// Parses a section body, recording data-section node spans into `source_map`.
+///
+/// Parse errors are located in the full document rather than the section body.
+pub(crate) fn parse_located_section(
+    section: &Section,
+    index: &LineIndex<'_>,
+    source_map: &mut SourceMap,
+) -> Result<JsonValue, SyamlError> {
+    let (value, locations) =
+        mini_yaml::parse_document_with_locations(&section.body).map_err(|e| {
+            let e = name_yaml_error_section(e, &section.name);
+            match e {
+                SyamlError::Located {
+                    error,
+                    mut location,
+                } => {
+                    if let Some(span) = location.span.as_mut() {
+                        let body_index = LineIndex::new(&section.body);
+                        let line_start = body_index.line_start(span.line).unwrap_or(0);
+                        *span = index.span(
+                            span.line + section.start_line,
+                            span.start.saturating_sub(line_start),
+                            span.end.saturating_sub(line_start),
+                        );
+                    }
+                    SyamlError::Located { error, location }
+                }
+                other => other,
+            }
+        })?;
+
+    if section.name == "data" {
+        for location in locations {
+            let mut path = String::from("$");
+            for segment in &location.path {
+                match segment {
+                    mini_yaml::LocationSegment::Key(key) => {
+                        path.push('.');
+                        path.push_str(&canonical_data_key(key));
+                    }
+                    mini_yaml::LocationSegment::Index(i) => {
+                        path.push_str(&format!("[{i}]"));
+                    }
+                }
+            }
+            source_map.insert(
+                path,
+                index.span(
+                    location.line + section.start_line,
+                    location.start,
+                    location.end,
+                ),
+            );
+        }
+    }
+
+    Ok(value)
+}
+
+fn name_yaml_error_section(e: SyamlError, section: &str) -> SyamlError {
+    e.map_inner(|inner| match inner {
         SyamlError::YamlParseError { message, .. } => SyamlError::YamlParseError {
             section: section.to_string(),
             message,
@@ -1013,6 +1880,67 @@ fn parse_section_value(section: &str, body: &str) -> Result<JsonValue, SyamlErro
     })
 }
 
+/// Returns `true` when `path` is `ancestor` or one of its descendants.
+pub(crate) fn path_is_within(path: &str, ancestor: &str) -> bool {
+    match path.strip_prefix(ancestor) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+        None => false,
+    }
+}
+
+/// Attaches the source span of an error's data path, when one was recorded.
+fn locate_error(e: SyamlError, source_map: &SourceMap) -> SyamlError {
+    if let SyamlError::Multiple(errors) = e {
+        return SyamlError::Multiple(
+            errors
+                .into_iter()
+                .map(|e| locate_error(e, source_map))
+                .collect(),
+        );
+    }
+    if e.span().is_some() {
+        return e;
+    }
+    let Some(span) = e.data_path().and_then(|path| source_map.lookup(path)) else {
+        return e;
+    };
+    let span = span.clone();
+    e.with_span(span)
+}
+
+/// Attaches source spans like [`locate_error`], except to errors about a
+/// value replaced by one of `overrides`: the override has no source text, so
+/// the message names it instead.
+fn locate_compile_error(
+    e: SyamlError,
+    source_map: &SourceMap,
+    overrides: &[(String, JsonValue)],
+) -> SyamlError {
+    if let SyamlError::Multiple(errors) = e {
+        return SyamlError::Multiple(
+            errors
+                .into_iter()
+                .map(|e| locate_compile_error(e, source_map, overrides))
+                .collect(),
+        );
+    }
+    let overridden = e.data_path().and_then(|path| {
+        overrides.iter().rev().find_map(|(target, value)| {
+            let target = overrides::normalize_override_path(target).ok()?;
+            path_is_within(path, &target).then_some((target, value))
+        })
+    });
+    match overridden {
+        // Rejections already name the overridden path.
+        Some(_) if matches!(e.inner(), SyamlError::OverrideError(_)) => e,
+        Some((target, value)) => {
+            let set = overrides::describe_override(&target, value);
+            e.map_messages(&|message| format!("{message} (set by {set})"))
+        }
+        None => locate_error(e, source_map),
+    }
+}
+
 fn parse_meta(value: &JsonValue) -> Result<Meta, SyamlError> {
     let map = value.as_object().ok_or_else(|| {
         SyamlError::SchemaError("meta section must be a mapping/object".to_string())
@@ -1064,17 +1992,65 @@ fn parse_env_binding(symbol: &str, value: &JsonValue) -> Result<EnvBinding, Syam
     })?;
 
     let from = map.get("from").and_then(|v| v.as_str()).unwrap_or("env");
+    let string_field = |field: &str| {
+        map.get(field)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| {
+                SyamlError::SchemaError(format!(
+                    "meta.env.{} with from='{}' must define string {}",
+                    symbol, from, field
+                ))
+            })
+    };
 
-    if from != "env" {
-        return Err(SyamlError::SchemaError(format!(
-            "meta.env.{} has unsupported from='{}'; only 'env' is supported",
-            symbol, from
-        )));
-    }
-
-    let key = map.get("key").and_then(|v| v.as_str()).ok_or_else(|| {
-        SyamlError::SchemaError(format!("meta.env.{} must define string key", symbol))
-    })?;
+    let (source, key) = match from {
+        "env" => (
+            EnvSource::Env,
+            map.get("key")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    SyamlError::SchemaError(format!("meta.env.{} must define string key", symbol))
+                })?
+                .to_string(),
+        ),
+        "file" => {
+            let trim = match map.get("trim") {
+                None => true,
+                Some(JsonValue::Bool(trim)) => *trim,
+                Some(_) => {
+                    return Err(SyamlError::SchemaError(format!(
+                        "meta.env.{}.trim must be a boolean",
+                        symbol
+                    )))
+                }
+            };
+            (EnvSource::File { trim }, string_field("path")?)
+        }
+        "literal_file" => (EnvSource::LiteralFile, string_field("path")?),
+        "dotenv" => (
+            EnvSource::Dotenv {
+                file: string_field("file")?,
+            },
+            string_field("key")?,
+        ),
+        // Compiling must never run programs: documents arrive through URL
+        // imports and shared repositories, and a command would run with the
+        // caller's rights before anything could vet it.
+        "command" => {
+            return Err(SyamlError::SchemaError(format!(
+                "meta.env.{} has unsupported from='command': documents cannot run programs; \
+                 run the command yourself and pass its output in an environment variable or a file",
+                symbol
+            )))
+        }
+        other => {
+            return Err(SyamlError::SchemaError(format!(
+                "meta.env.{} has unsupported from='{}'; expected env, file, literal_file or dotenv",
+                symbol, other
+            )))
+        }
+    };
 
     let required = map
         .get("required")
@@ -1082,10 +2058,51 @@ fn parse_env_binding(symbol: &str, value: &JsonValue) -> Result<EnvBinding, Syam
         .unwrap_or(true);
     let default = map.get("default").cloned();
 
+    let type_ref = match map.get("type") {
+        None => None,
+        Some(type_ref @ (JsonValue::String(_) | JsonValue::Object(_) | JsonValue::Array(_))) => {
+            Some(schema::normalize_schema_node(type_ref.clone()))
+        }
+        Some(_) => {
+            return Err(SyamlError::SchemaError(format!(
+                "meta.env.{}.type must be a type name or schema",
+                symbol
+            )))
+        }
+    };
+    let parse = match map.get("parse").map(|v| v.as_str()) {
+        None => None,
+        Some(Some("scalar")) => Some(EnvParse::Scalar),
+        Some(Some("string")) => Some(EnvParse::String),
+        Some(Some("json")) => Some(EnvParse::Json),
+        Some(Some("csv")) => Some(EnvParse::Csv),
+        Some(_) => {
+            return Err(SyamlError::SchemaError(format!(
+                "meta.env.{}.parse must be one of scalar, string, json or csv",
+                symbol
+            )))
+        }
+    };
+
+    let sensitive = match map.get("sensitive") {
+        None => false,
+        Some(JsonValue::Bool(sensitive)) => *sensitive,
+        Some(_) => {
+            return Err(SyamlError::SchemaError(format!(
+                "meta.env.{}.sensitive must be a boolean",
+                symbol
+            )))
+        }
+    };
+
     Ok(EnvBinding {
-        key: key.to_string(),
+        key,
         required,
         default,
+        source,
+        type_ref,
+        parse,
+        sensitive,
     })
 }
 
@@ -1097,7 +2114,7 @@ fn parse_import_binding(alias: &str, value: &JsonValue) -> Result<ImportBinding,
         )));
     }
 
-    const VALID_IMPORT_SECTIONS: &[&str] = &["schema", "data", "contracts"];
+    const VALID_IMPORT_SECTIONS: &[&str] = &["schema", "data", "contracts", "functions"];
 
     let (path, hash, signature, version, sections) = match value {
         JsonValue::String(path) => (path.clone(), None, None, None, None),
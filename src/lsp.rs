@@ -0,0 +1,1372 @@
+//! Language Server Protocol server for `.syaml` files.
+//!
+//! [`LspServer`] is transport-agnostic: it consumes decoded JSON-RPC messages and
+//! returns the messages to send back. [`serve`] wires it to a reader and writer
+//! with `Content-Length` framing; [`run_stdio`] serves stdin/stdout, which is what
+//! `super-yaml lsp` runs.
+//!
+//! Open documents are kept parsed in memory, together with the files they import,
+//! so hover, definition, completion and rename do not re-read the workspace on
+//! every request. Diagnostics come from a full compile of the open buffer, with
+//! imports read from other open buffers before disk.
+//!
+//! [`LspServer`]: crate::lsp::LspServer
+//! [`serve`]: crate::lsp::serve
+//! [`run_stdio`]: crate::lsp::run_stdio
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::ast::ParsedDocument;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::schema::resolve_type_schema;
+use crate::section_scanner::scan_sections;
+use crate::source_map::{LineIndex, SourceSpan};
+use crate::{
+    compile_document, compile_document_from_source_with_open_files, parse_document, MapEnvProvider,
+};
+
+const BUILTIN_TYPES: [&str; 10] = [
+    "string",
+    "integer",
+    "number",
+    "boolean",
+    "object",
+    "array",
+    "null",
+    "duration",
+    "timestamp",
+    "bytesize",
+];
+
+/// Schema keys whose values are free text or expressions, never type references.
+const NON_TYPE_SCHEMA_KEYS: [&str; 12] = [
+    "constraints",
+    "description",
+    "default",
+    "const",
+    "enum",
+    "pattern",
+    "regex",
+    "as_string",
+    "examples",
+    "title",
+    "message",
+    "deprecated",
+];
+
+const ERROR_PARSE: i64 = -32700;
+const ERROR_METHOD_NOT_FOUND: i64 = -32601;
+const ERROR_INVALID_REQUEST: i64 = -32600;
+const ERROR_INVALID_PARAMS: i64 = -32602;
+const ERROR_REQUEST_FAILED: i64 = -32803;
+
+/// Runs the language server over stdin/stdout until the client sends `exit`.
+pub fn run_stdio() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    serve(&mut stdin.lock(), &mut stdout.lock())
+}
+
+/// Runs the language server over `reader` and `writer` until the client sends
+/// `exit` or the input ends.
+///
+/// A message that cannot be decoded is answered with a JSON-RPC parse error and
+/// the server keeps reading.
+pub fn serve(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<()> {
+    let mut server = LspServer::new();
+    loop {
+        let message = match read_message(reader) {
+            Ok(Some(message)) => message,
+            Ok(None) => break,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let error =
+                    error_response(JsonValue::Null, ERROR_PARSE, &format!("parse error: {e}"));
+                write_message(writer, &error)?;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for outgoing in server.handle(message) {
+            write_message(writer, &outgoing)?;
+        }
+        if server.exit_requested() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Reads one `Content-Length` framed JSON-RPC message. Returns `None` at end of input.
+///
+/// A body that is not JSON is an [`io::ErrorKind::InvalidData`] error; its frame
+/// has been consumed, so the next message can still be read.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<JsonValue>> {
+    let mut content_length: Option<usize> = None;
+    let mut invalid_length = false;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if invalid_length {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid Content-Length",
+                ));
+            }
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok();
+                invalid_length = content_length.is_none();
+            }
+        }
+    }
+
+    let mut body = vec![0u8; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Writes one JSON-RPC message with `Content-Length` framing.
+pub fn write_message(writer: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// In-memory language server state.
+#[derive(Default)]
+pub struct LspServer {
+    documents: BTreeMap<String, OpenDocument>,
+    files: HashMap<PathBuf, CachedFile>,
+    root: Option<PathBuf>,
+    shutdown_requested: bool,
+    exit_requested: bool,
+}
+
+struct OpenDocument {
+    text: String,
+    path: Option<PathBuf>,
+    parsed: Option<ParsedDocument>,
+    /// Import alias -> canonical path of the imported local file.
+    imports: BTreeMap<String, PathBuf>,
+}
+
+/// A file read from disk because an open document imports it (or for rename).
+struct CachedFile {
+    modified: Option<SystemTime>,
+    text: String,
+    parsed: Option<ParsedDocument>,
+}
+
+/// Symbol under the cursor.
+#[derive(Debug, Clone, PartialEq)]
+enum Symbol {
+    /// Schema type, optionally qualified by an import alias (`alias.Type`).
+    Type { alias: Option<String>, name: String },
+    /// Absolute data reference (`$.a.b[0]`).
+    DataPath(String),
+}
+
+impl LspServer {
+    /// Creates a server with no open documents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` once the client has sent the `exit` notification.
+    pub fn exit_requested(&self) -> bool {
+        self.exit_requested
+    }
+
+    /// Handles one incoming message and returns the responses and notifications to send.
+    pub fn handle(&mut self, message: JsonValue) -> Vec<JsonValue> {
+        if !message.is_object() {
+            return vec![error_response(
+                JsonValue::Null,
+                ERROR_INVALID_REQUEST,
+                "message is not a JSON-RPC object",
+            )];
+        }
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(JsonValue::as_str) else {
+            // Responses to server-initiated requests are not used.
+            return Vec::new();
+        };
+        let params = message.get("params").cloned().unwrap_or(JsonValue::Null);
+
+        if self.shutdown_requested && method != "exit" {
+            return match id {
+                Some(id) => vec![error_response(
+                    id,
+                    ERROR_INVALID_REQUEST,
+                    "server is shutting down",
+                )],
+                None => Vec::new(),
+            };
+        }
+
+        match method {
+            "initialize" => {
+                self.root = params
+                    .get("rootUri")
+                    .and_then(JsonValue::as_str)
+                    .and_then(uri_to_path)
+                    .or_else(|| {
+                        params
+                            .get("rootPath")
+                            .and_then(JsonValue::as_str)
+                            .map(PathBuf::from)
+                    });
+                respond(id, Ok(initialize_result()))
+            }
+            "initialized" => Vec::new(),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                respond(id, Ok(JsonValue::Null))
+            }
+            "exit" => {
+                self.exit_requested = true;
+                Vec::new()
+            }
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                let (Some(uri), Some(text)) = (
+                    doc.get("uri").and_then(JsonValue::as_str),
+                    doc.get("text").and_then(JsonValue::as_str),
+                ) else {
+                    return Vec::new();
+                };
+                self.update_document(uri, text.to_string());
+                self.publish_all_diagnostics()
+            }
+            "textDocument/didChange" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Vec::new();
+                };
+                let Some(text) = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(JsonValue::as_str)
+                else {
+                    return Vec::new();
+                };
+                self.update_document(uri, text.to_string());
+                let mut messages = vec![self.publish_diagnostics(uri)];
+                messages.extend(self.publish_importer_diagnostics(uri));
+                messages
+            }
+            "textDocument/didSave" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Vec::new();
+                };
+                if let Some(text) = params.get("text").and_then(JsonValue::as_str) {
+                    self.update_document(uri, text.to_string());
+                }
+                // Documents importing the saved file may have new diagnostics.
+                if let Some(path) = uri_to_path(uri) {
+                    self.files.remove(&canonical(&path));
+                }
+                self.publish_all_diagnostics()
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Vec::new();
+                };
+                // Importers go back to reading the file from disk.
+                let mut messages = self.publish_importer_diagnostics(uri);
+                self.documents.remove(uri);
+                messages.insert(0, publish_notification(uri, Vec::new()));
+                messages
+            }
+            "textDocument/hover" => {
+                let result = self.with_position(&params, |server, uri, line, col| {
+                    server.hover(uri, line, col)
+                });
+                respond(id, Ok(result.unwrap_or(JsonValue::Null)))
+            }
+            "textDocument/definition" => {
+                let result = self.with_position(&params, |server, uri, line, col| {
+                    server.definition(uri, line, col)
+                });
+                respond(id, Ok(result.unwrap_or(JsonValue::Null)))
+            }
+            "textDocument/completion" => {
+                let result = self.with_position(&params, |server, uri, line, col| {
+                    Some(server.completion(uri, line, col))
+                });
+                respond(id, Ok(result.unwrap_or_else(|| json!([]))))
+            }
+            "textDocument/rename" => {
+                let new_name = params
+                    .get("newName")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or("")
+                    .to_string();
+                if !is_identifier(&new_name) {
+                    return respond(
+                        id,
+                        Err((
+                            ERROR_INVALID_PARAMS,
+                            format!("'{new_name}' is not a valid type name"),
+                        )),
+                    );
+                }
+                let result = self.with_position(&params, |server, uri, line, col| {
+                    Some(server.rename(uri, line, col, &new_name))
+                });
+                match result {
+                    Some(Ok(edit)) => respond(id, Ok(edit)),
+                    Some(Err(message)) => respond(id, Err((ERROR_REQUEST_FAILED, message))),
+                    None => respond(id, Ok(JsonValue::Null)),
+                }
+            }
+            _ => match id {
+                Some(id) => vec![error_response(
+                    id,
+                    ERROR_METHOD_NOT_FOUND,
+                    &format!("method '{method}' is not supported"),
+                )],
+                None => Vec::new(),
+            },
+        }
+    }
+
+    fn update_document(&mut self, uri: &str, text: String) {
+        let path = uri_to_path(uri).map(|p| canonical(&p));
+        // Keep the last successful parse while the buffer is mid-edit so navigation
+        // and completion keep working.
+        let parsed = parse_document(&text).ok().or_else(|| {
+            self.documents
+                .get_mut(uri)
+                .and_then(|previous| previous.parsed.take())
+        });
+        let imports = match (&parsed, &path) {
+            (Some(parsed), Some(path)) => local_imports(parsed, path),
+            _ => BTreeMap::new(),
+        };
+        for import_path in imports.values() {
+            self.load_file(import_path);
+        }
+        self.documents.insert(
+            uri.to_string(),
+            OpenDocument {
+                text,
+                path,
+                parsed,
+                imports,
+            },
+        );
+    }
+
+    /// Loads (or refreshes) a file from disk into the cache.
+    fn load_file(&mut self, path: &Path) -> Option<&CachedFile> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let stale = match self.files.get(path) {
+            Some(cached) => cached.modified != modified,
+            None => true,
+        };
+        if stale {
+            let text = fs::read_to_string(path).ok()?;
+            let parsed = parse_document(&text).ok();
+            self.files.insert(
+                path.to_path_buf(),
+                CachedFile {
+                    modified,
+                    text,
+                    parsed,
+                },
+            );
+        }
+        self.files.get(path)
+    }
+
+    /// Returns the text and parse of `path`, preferring an open buffer over disk.
+    fn file_source(&mut self, path: &Path) -> Option<(String, Option<ParsedDocument>)> {
+        if let Some(doc) = self
+            .documents
+            .values()
+            .find(|doc| doc.path.as_deref() == Some(path))
+        {
+            return Some((doc.text.clone(), doc.parsed.clone()));
+        }
+        self.load_file(path)
+            .map(|cached| (cached.text.clone(), cached.parsed.clone()))
+    }
+
+    fn publish_all_diagnostics(&mut self) -> Vec<JsonValue> {
+        let uris: Vec<String> = self.documents.keys().cloned().collect();
+        uris.iter()
+            .map(|uri| self.publish_diagnostics(uri))
+            .collect()
+    }
+
+    /// Publishes diagnostics for the other open documents that import `uri`.
+    fn publish_importer_diagnostics(&self, uri: &str) -> Vec<JsonValue> {
+        let Some(path) = self.documents.get(uri).and_then(|doc| doc.path.as_ref()) else {
+            return Vec::new();
+        };
+        self.documents
+            .iter()
+            .filter(|(other, doc)| *other != uri && doc.imports.values().any(|p| p == path))
+            .map(|(other, _)| self.publish_diagnostics(other))
+            .collect()
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> JsonValue {
+        let Some(doc) = self.documents.get(uri) else {
+            return publish_notification(uri, Vec::new());
+        };
+        let env = MapEnvProvider::new(HashMap::new());
+        let result = match &doc.path {
+            Some(path) => {
+                // Imports read open buffers first, so unsaved edits are seen.
+                let open_files = self
+                    .documents
+                    .iter()
+                    .filter(|(other, _)| *other != uri)
+                    .filter_map(|(_, other)| Some((other.path.clone()?, other.text.clone())))
+                    .collect();
+                compile_document_from_source_with_open_files(&doc.text, path, &open_files, &env)
+            }
+            None => compile_document(&doc.text, &env),
+        };
+        let records = match result {
+            Ok(compiled) => compiled.warnings.iter().map(Diagnostic::warning).collect(),
+            Err(err) => Diagnostic::from_error(&err),
+        };
+
+        let own_file = doc.path.as_ref().map(|p| p.display().to_string());
+        let diagnostics = records
+            .into_iter()
+            .map(|record| {
+                let range = match &record.span {
+                    Some(span) if record.file.is_none() || record.file == own_file => {
+                        span_to_range(&doc.text, span)
+                    }
+                    _ => empty_range(0, 0),
+                };
+                let mut message = record.message;
+                if let Some(file) = record
+                    .file
+                    .as_ref()
+                    .filter(|f| Some(*f) != own_file.as_ref())
+                {
+                    message = format!("{message} (in {file})");
+                }
+                json!({
+                    "range": range,
+                    "severity": match record.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "code": record.code,
+                    "source": "super-yaml",
+                    "message": message,
+                })
+            })
+            .collect();
+        publish_notification(uri, diagnostics)
+    }
+
+    /// Runs `f` with the document URI and the cursor as a 0-based line and byte column.
+    fn with_position<T>(
+        &mut self,
+        params: &JsonValue,
+        f: impl FnOnce(&mut Self, &str, usize, usize) -> Option<T>,
+    ) -> Option<T> {
+        let uri = params["textDocument"]["uri"].as_str()?.to_string();
+        let line = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+        let doc = self.documents.get(&uri)?;
+        let line_text = LineIndex::new(&doc.text).line_text(line + 1)?;
+        let col = utf16_to_byte(line_text, character);
+        f(self, &uri, line, col)
+    }
+
+    fn symbol_at(&self, uri: &str, line: usize, col: usize) -> Option<(Symbol, usize, usize)> {
+        let doc = self.documents.get(uri)?;
+        let line_text = LineIndex::new(&doc.text).line_text(line + 1)?;
+        let (start, end) = token_bounds(line_text, col)?;
+        let token = &line_text[start..end];
+
+        if token.starts_with("$.") {
+            return Some((
+                Symbol::DataPath(token.trim_end_matches('.').to_string()),
+                start,
+                end,
+            ));
+        }
+
+        let parsed = doc.parsed.as_ref()?;
+        let segments: Vec<&str> = token.split('.').collect();
+        let symbol = if parsed.schema.types.contains_key(token) {
+            Symbol::Type {
+                alias: None,
+                name: token.to_string(),
+            }
+        } else if segments.len() >= 2 && doc.imports.contains_key(segments[0]) {
+            Symbol::Type {
+                alias: Some(segments[0].to_string()),
+                name: segments[1].to_string(),
+            }
+        } else if segments.len() == 2 && parsed.schema.types.contains_key(segments[0]) {
+            // Enum member reference (`Type.member`).
+            Symbol::Type {
+                alias: None,
+                name: segments[0].to_string(),
+            }
+        } else {
+            return None;
+        };
+        Some((symbol, start, end))
+    }
+
+    fn definition(&mut self, uri: &str, line: usize, col: usize) -> Option<JsonValue> {
+        let (symbol, _, _) = self.symbol_at(uri, line, col)?;
+        match symbol {
+            Symbol::DataPath(path) => {
+                let doc = self.documents.get(uri)?;
+                let span = doc.parsed.as_ref()?.source_map.lookup(&path)?;
+                Some(location(uri, span_to_range(&doc.text, span)))
+            }
+            Symbol::Type { alias: None, name } => {
+                let doc = self.documents.get(uri)?;
+                let (line, start, end) = find_type_definition(&doc.text, &name)?;
+                Some(location(uri, byte_range(&doc.text, line, start, end)))
+            }
+            Symbol::Type {
+                alias: Some(alias),
+                name,
+            } => {
+                let path = self.documents.get(uri)?.imports.get(&alias)?.clone();
+                let (text, _) = self.file_source(&path)?;
+                let (line, start, end) = find_type_definition(&text, &name)?;
+                Some(location(
+                    &path_to_uri(&path),
+                    byte_range(&text, line, start, end),
+                ))
+            }
+        }
+    }
+
+    fn hover(&mut self, uri: &str, line: usize, col: usize) -> Option<JsonValue> {
+        if let Some((symbol, start, end)) = self.symbol_at(uri, line, col) {
+            let text = self.documents.get(uri)?.text.clone();
+            let contents = match symbol {
+                Symbol::Type { alias, name } => {
+                    let qualified = qualified_name(alias.as_deref(), &name);
+                    let schema = self.resolve_named_type(uri, &qualified)?;
+                    hover_markdown(&format!("type `{qualified}`"), Some(&schema))
+                }
+                Symbol::DataPath(path) => {
+                    let (label, schema) = self.schema_for_data_path(uri, &path)?;
+                    hover_markdown(&format!("`{path}`: `{label}`"), Some(&schema))
+                }
+            };
+            return Some(json!({
+                "contents": contents,
+                "range": byte_range(&text, line, start, end),
+            }));
+        }
+
+        // Hovering a data key shows the schema type it resolves to.
+        let path = self.data_path_at_line(uri, line)?;
+        let (label, schema) = self.schema_for_data_path(uri, &path)?;
+        Some(json!({
+            "contents": hover_markdown(&format!("`{path}`: `{label}`"), Some(&schema)),
+        }))
+    }
+
+    fn completion(&mut self, uri: &str, line: usize, col: usize) -> JsonValue {
+        let Some(doc) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        let Some(line_text) = LineIndex::new(&doc.text).line_text(line + 1) else {
+            return json!([]);
+        };
+        let before = line_text[..col.min(line_text.len())].to_string();
+        let before = before.as_str();
+        let section = section_at_line(&doc.text, line + 1);
+        let token_start = before
+            .rfind(|c: char| !is_token_char(c))
+            .map(|i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(0);
+        let typed = &before[token_start..];
+
+        if typed.starts_with('$') {
+            let Some(parsed) = doc.parsed.as_ref() else {
+                return json!([]);
+            };
+            let range = byte_range(&doc.text, line, token_start, col);
+            let items: Vec<JsonValue> = parsed
+                .source_map
+                .iter()
+                .map(|(path, _)| path)
+                .filter(|path| path.starts_with(typed))
+                .map(|path| {
+                    json!({
+                        "label": path,
+                        "kind": 18,
+                        "textEdit": { "range": range, "newText": path },
+                    })
+                })
+                .collect();
+            return JsonValue::Array(items);
+        }
+
+        let in_hint = section.as_deref() == Some("data") && {
+            let open = before.rfind('<');
+            let close = before.rfind('>');
+            matches!((open, close), (Some(o), None) if !before[..o].contains(':'))
+                || matches!((open, close), (Some(o), Some(c)) if o > c)
+        };
+        let in_schema_value = section.as_deref() == Some("schema") && {
+            match before.split_once(':') {
+                Some((key, _)) => {
+                    let key = key.trim().trim_start_matches("- ");
+                    !NON_TYPE_SCHEMA_KEYS.contains(&key)
+                }
+                None => before.trim_start().starts_with("- "),
+            }
+        };
+        if in_hint || in_schema_value {
+            return JsonValue::Array(self.type_completions(uri));
+        }
+
+        if section.as_deref() == Some("data")
+            && before
+                .trim_start()
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return JsonValue::Array(self.property_completions(uri, line, before));
+        }
+
+        json!([])
+    }
+
+    fn type_completions(&mut self, uri: &str) -> Vec<JsonValue> {
+        let Some(doc) = self.documents.get(uri) else {
+            return Vec::new();
+        };
+        let mut items: Vec<JsonValue> = BUILTIN_TYPES
+            .iter()
+            .map(|name| json!({ "label": name, "kind": 14, "detail": "builtin" }))
+            .collect();
+        if let Some(parsed) = &doc.parsed {
+            items.extend(
+                parsed
+                    .schema
+                    .types
+                    .keys()
+                    .map(|name| json!({ "label": name, "kind": 7 })),
+            );
+        }
+        let imports = doc.imports.clone();
+        for (alias, path) in imports {
+            let Some((_, Some(parsed))) = self.file_source(&path) else {
+                continue;
+            };
+            items.extend(parsed.schema.types.keys().map(|name| {
+                json!({
+                    "label": format!("{alias}.{name}"),
+                    "kind": 7,
+                    "detail": path.display().to_string(),
+                })
+            }));
+        }
+        items
+    }
+
+    fn property_completions(&mut self, uri: &str, line: usize, before: &str) -> Vec<JsonValue> {
+        let indent = before.len() - before.trim_start().len();
+        let parent = match self.parent_data_path(uri, line, indent) {
+            Some(parent) => parent,
+            None => return Vec::new(),
+        };
+        let Some((_, schema)) = self.schema_for_data_path(uri, &parent) else {
+            return Vec::new();
+        };
+        let existing: BTreeSet<String> = self
+            .documents
+            .get(uri)
+            .and_then(|doc| doc.parsed.as_ref())
+            .and_then(|parsed| crate::resolve::get_json_path(&parsed.data.value, &parent))
+            .and_then(JsonValue::as_object)
+            .map(|obj| obj.keys().cloned().collect())
+            .unwrap_or_default();
+        let Some(properties) = schema.get("properties").and_then(JsonValue::as_object) else {
+            return Vec::new();
+        };
+        properties
+            .iter()
+            .filter(|(name, _)| !existing.contains(*name))
+            .map(|(name, prop)| {
+                json!({
+                    "label": name,
+                    "kind": 10,
+                    "detail": schema_label(prop),
+                    "insertText": format!("{name}: "),
+                })
+            })
+            .collect()
+    }
+
+    fn rename(
+        &mut self,
+        uri: &str,
+        line: usize,
+        col: usize,
+        new_name: &str,
+    ) -> Result<JsonValue, String> {
+        let Some((Symbol::Type { alias, name }, _, _)) = self.symbol_at(uri, line, col) else {
+            return Err("rename is only supported for schema types".to_string());
+        };
+        let doc = self.documents.get(uri).ok_or("document is not open")?;
+        let defining_path = match &alias {
+            Some(alias) => doc.imports.get(alias).cloned(),
+            None => doc.path.clone(),
+        };
+
+        let mut changes: BTreeMap<String, Vec<JsonValue>> = BTreeMap::new();
+        let Some(defining_path) = defining_path else {
+            // Unsaved buffer: only the buffer itself can reference the type.
+            let text = doc.text.clone();
+            changes.insert(uri.to_string(), rename_edits(&text, None, &name, new_name));
+            return Ok(json!({ "changes": changes }));
+        };
+
+        let mut candidates: BTreeSet<PathBuf> = self
+            .documents
+            .values()
+            .filter_map(|doc| doc.path.clone())
+            .collect();
+        candidates.insert(defining_path.clone());
+        if let Some(root) = self.root.clone() {
+            collect_syaml_files(&root, &mut candidates);
+        }
+
+        for path in candidates {
+            let Some((text, parsed)) = self.file_source(&path) else {
+                continue;
+            };
+            let mut edits = Vec::new();
+            if path == defining_path {
+                edits.extend(rename_edits(&text, None, &name, new_name));
+            }
+            if let Some(parsed) = &parsed {
+                for (import_alias, import_path) in local_imports(parsed, &path) {
+                    if import_path == defining_path {
+                        edits.extend(rename_edits(&text, Some(&import_alias), &name, new_name));
+                    }
+                }
+            }
+            if !edits.is_empty() {
+                let file_uri = self
+                    .documents
+                    .iter()
+                    .find(|(_, doc)| doc.path.as_deref() == Some(path.as_path()))
+                    .map(|(uri, _)| uri.clone())
+                    .unwrap_or_else(|| path_to_uri(&path));
+                changes.entry(file_uri).or_default().extend(edits);
+            }
+        }
+        Ok(json!({ "changes": changes }))
+    }
+
+    /// Resolves `Type` or `alias.Type` to its schema as seen from document `uri`.
+    fn resolve_named_type(&mut self, uri: &str, name: &str) -> Option<JsonValue> {
+        let doc = self.documents.get(uri)?;
+        let parsed = doc.parsed.as_ref()?;
+        if let Ok(schema) = resolve_type_schema(&parsed.schema, name) {
+            return Some(schema);
+        }
+        let (alias, local) = name.split_once('.')?;
+        let path = doc.imports.get(alias)?.clone();
+        let (_, imported) = self.file_source(&path)?;
+        resolve_type_schema(&imported?.schema, local).ok()
+    }
+
+    /// Returns a display label and schema for the value at `path`, using its own type
+    /// hint or descending from the nearest hinted ancestor.
+    fn schema_for_data_path(&mut self, uri: &str, path: &str) -> Option<(String, JsonValue)> {
+        let hints = self
+            .documents
+            .get(uri)?
+            .parsed
+            .as_ref()?
+            .data
+            .type_hints
+            .clone();
+        let mut ancestor = Some(path.to_string());
+        let mut remainder: Vec<String> = Vec::new();
+        while let Some(current) = ancestor {
+            if let Some(type_name) = hints.get(&current) {
+                let mut label = type_name.clone();
+                let mut schema = self.resolve_named_type(uri, type_name)?;
+                for segment in remainder.iter().rev() {
+                    schema = self.expand_named(uri, schema);
+                    let next = if segment.starts_with('[') {
+                        schema.get("items").cloned()
+                    } else {
+                        schema
+                            .get("properties")
+                            .and_then(|props| props.get(segment))
+                            .cloned()
+                            .or_else(|| schema.get("values").cloned())
+                    }?;
+                    label = schema_label(&next);
+                    schema = next;
+                }
+                return Some((label, self.expand_named(uri, schema)));
+            }
+            let (parent, segment) = split_last_segment(&current)?;
+            remainder.push(segment);
+            ancestor = Some(parent);
+        }
+        None
+    }
+
+    /// Replaces a `{type: Named}` reference (or shorthand string) with the named schema.
+    fn expand_named(&mut self, uri: &str, schema: JsonValue) -> JsonValue {
+        let name = match &schema {
+            JsonValue::String(name) => Some(name.clone()),
+            JsonValue::Object(obj) if obj.len() == 1 => obj
+                .get("type")
+                .and_then(JsonValue::as_str)
+                .map(str::to_string),
+            _ => None,
+        };
+        match name {
+            Some(name) if !BUILTIN_TYPES.contains(&name.as_str()) => {
+                self.resolve_named_type(uri, &name).unwrap_or(schema)
+            }
+            _ => schema,
+        }
+    }
+
+    /// Finds the data path whose key or value sits on 0-based `line`.
+    fn data_path_at_line(&self, uri: &str, line: usize) -> Option<String> {
+        let doc = self.documents.get(uri)?;
+        doc.parsed
+            .as_ref()?
+            .source_map
+            .iter()
+            .filter(|(_, span)| span.line == line + 1)
+            .map(|(path, _)| path.clone())
+            .max_by_key(String::len)
+    }
+
+    /// Finds the data path of the mapping that a key indented by `indent` on `line` belongs to.
+    fn parent_data_path(&self, uri: &str, line: usize, indent: usize) -> Option<String> {
+        if indent == 0 {
+            return Some("$".to_string());
+        }
+        let doc = self.documents.get(uri)?;
+        let index = LineIndex::new(&doc.text);
+        for candidate in (0..line).rev() {
+            let text = index.line_text(candidate + 1)?;
+            let trimmed = text.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if text.len() - trimmed.len() < indent {
+                return self.data_path_at_line(uri, candidate);
+            }
+        }
+        None
+    }
+}
+
+fn initialize_result() -> JsonValue {
+    json!({
+        "capabilities": {
+            "textDocumentSync": { "openClose": true, "change": 1, "save": { "includeText": true } },
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "completionProvider": { "triggerCharacters": ["<", ".", "$", " "] },
+            "renameProvider": true,
+        },
+        "serverInfo": { "name": "super-yaml", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+fn respond(id: Option<JsonValue>, result: Result<JsonValue, (i64, String)>) -> Vec<JsonValue> {
+    let Some(id) = id else {
+        return Vec::new();
+    };
+    match result {
+        Ok(result) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+        Err((code, message)) => vec![error_response(id, code, &message)],
+    }
+}
+
+fn error_response(id: JsonValue, code: i64, message: &str) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn publish_notification(uri: &str, diagnostics: Vec<JsonValue>) -> JsonValue {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+fn location(uri: &str, range: JsonValue) -> JsonValue {
+    json!({ "uri": uri, "range": range })
+}
+
+fn hover_markdown(title: &str, schema: Option<&JsonValue>) -> JsonValue {
+    let mut value = title.to_string();
+    if let Some(schema) = schema {
+        let pretty = serde_json::to_string_pretty(schema).unwrap_or_default();
+        value.push_str(&format!("\n\n```json\n{pretty}\n```"));
+    }
+    json!({ "kind": "markdown", "value": value })
+}
+
+fn schema_label(schema: &JsonValue) -> String {
+    match schema {
+        JsonValue::String(name) => name.clone(),
+        JsonValue::Object(obj) => obj
+            .get("type")
+            .and_then(JsonValue::as_str)
+            .unwrap_or("object")
+            .to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn qualified_name(alias: Option<&str>, name: &str) -> String {
+    match alias {
+        Some(alias) => format!("{alias}.{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Import alias -> canonical path, for imports that are local files.
+fn local_imports(parsed: &ParsedDocument, path: &Path) -> BTreeMap<String, PathBuf> {
+    let Some(meta) = &parsed.meta else {
+        return BTreeMap::new();
+    };
+    let base = path.parent().unwrap_or(Path::new("."));
+    meta.imports
+        .iter()
+        .filter(|(_, binding)| {
+            let raw = binding.path.trim();
+            !raw.starts_with("http://") && !raw.starts_with("https://") && !raw.starts_with('@')
+        })
+        .filter_map(|(alias, binding)| {
+            let resolved = base.join(binding.path.trim());
+            fs::canonicalize(resolved).ok().map(|p| (alias.clone(), p))
+        })
+        .collect()
+}
+
+fn collect_syaml_files(dir: &Path, out: &mut BTreeSet<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" && name != "node_modules" {
+                collect_syaml_files(&path, out);
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("syaml") {
+            out.insert(canonical(&path));
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Converts a `file://` URI to a path, decoding percent escapes.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let bytes = rest.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&rest[i + 1..i + 3], 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Converts a path to a `file://` URI, percent-encoding reserved characters.
+pub fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.display().to_string().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            other => uri.push_str(&format!("%{other:02X}")),
+        }
+    }
+    uri
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '$' | '[' | ']')
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns the byte range of the token around byte column `col`.
+fn token_bounds(line: &str, col: usize) -> Option<(usize, usize)> {
+    let col = col.min(line.len());
+    let start = line[..col]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_token_char(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(col);
+    let end = line[col..]
+        .char_indices()
+        .find(|(_, c)| !is_token_char(*c))
+        .map(|(i, _)| col + i)
+        .unwrap_or(line.len());
+    (start < end).then_some((start, end))
+}
+
+fn split_last_segment(path: &str) -> Option<(String, String)> {
+    if path == "$" {
+        return None;
+    }
+    let idx = path.rfind(['.', '['])?;
+    let parent = if idx == 1 && path.starts_with("$.") {
+        "$".to_string()
+    } else {
+        path[..idx].to_string()
+    };
+    let segment = path[idx..].trim_start_matches('.').to_string();
+    Some((parent, segment))
+}
+
+/// Returns the section containing 1-based file `line`.
+fn section_at_line(text: &str, line: usize) -> Option<String> {
+    let (_, sections) = scan_sections(text).ok()?;
+    sections
+        .into_iter()
+        .rfind(|section| section.start_line < line)
+        .map(|section| section.name)
+}
+
+/// Finds a schema type definition key; returns a 0-based line and byte range.
+fn find_type_definition(text: &str, name: &str) -> Option<(usize, usize, usize)> {
+    let index = LineIndex::new(text);
+    let (_, sections) = scan_sections(text).ok()?;
+    let schema = sections.iter().find(|s| s.name == "schema")?;
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (offset, raw) in schema.body.lines().enumerate() {
+        let line = schema.start_line + offset + 1;
+        let Some(line_text) = index.line_text(line) else {
+            continue;
+        };
+        let trimmed = line_text.trim_start();
+        let indent = raw.len() - raw.trim_start().len();
+        let Some(rest) = trimmed.strip_prefix(name) else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        if !(rest.starts_with(':') || rest.starts_with('<')) {
+            continue;
+        }
+        if best.is_none_or(|(_, best_indent, _)| indent < best_indent) {
+            best = Some((line - 1, indent, indent + name.len()));
+        }
+    }
+    best
+}
+
+/// Text edits renaming references to `name` (qualified by `alias` when given).
+fn rename_edits(text: &str, alias: Option<&str>, name: &str, new_name: &str) -> Vec<JsonValue> {
+    type_reference_ranges(text, alias, name)
+        .into_iter()
+        .map(|(line, start, end)| {
+            json!({ "range": byte_range(text, line, start, end), "newText": new_name })
+        })
+        .collect()
+}
+
+/// Finds references to a type by scanning schema, data and contracts sections.
+///
+/// Returns 0-based lines and byte ranges covering only the type name (not the alias).
+fn type_reference_ranges(
+    text: &str,
+    alias: Option<&str>,
+    name: &str,
+) -> Vec<(usize, usize, usize)> {
+    let target = qualified_name(alias, name);
+    let Ok((_, sections)) = scan_sections(text) else {
+        return Vec::new();
+    };
+    let index = LineIndex::new(text);
+    let def_indent = if alias.is_none() {
+        find_type_definition(text, name).map(|(_, indent, _)| indent)
+    } else {
+        None
+    };
+
+    let mut out = Vec::new();
+    for section in &sections {
+        if !matches!(
+            section.name.as_str(),
+            "schema" | "functions" | "data" | "overlay" | "contracts"
+        ) {
+            continue;
+        }
+        for offset in 0..section.body.lines().count() {
+            let line = section.start_line + offset + 1;
+            let Some(line_text) = index.line_text(line) else {
+                continue;
+            };
+            let code = strip_comment(line_text);
+            let key_end = key_separator(code);
+            for (start, end) in token_ranges(code) {
+                let token = &code[start..end];
+                let in_key = key_end.is_none_or(|k| start < k);
+                let in_angle = code[..start].rfind('<').is_some_and(|open| {
+                    code[open..start]
+                        .chars()
+                        .all(|c| c == '<' || c.is_whitespace())
+                });
+                let matched = match section.name.as_str() {
+                    "data" if in_key => in_angle && token == target,
+                    "data" => {
+                        token == target
+                            || token
+                                .strip_prefix(&target)
+                                .and_then(|rest| rest.strip_prefix('.'))
+                                .is_some_and(is_identifier)
+                    }
+                    _ if in_key => {
+                        (in_angle && token == target)
+                            || (token == target
+                                && def_indent == Some(start)
+                                && section.name == "schema")
+                    }
+                    _ => {
+                        let key = key_end
+                            .map(|k| code[..k].trim().trim_start_matches("- ").trim())
+                            .unwrap_or("");
+                        token == target && !NON_TYPE_SCHEMA_KEYS.contains(&key)
+                    }
+                };
+                if matched {
+                    let name_start = start + target.len() - name.len();
+                    out.push((line - 1, name_start, name_start + name.len()));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_single = false;
+    let mut in_double = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '#' if !in_single
+                && !in_double
+                && (i == 0 || line[..i].ends_with(char::is_whitespace)) =>
+            {
+                return &line[..i];
+            }
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Byte offset of the `:` separating a mapping key from its value, if any.
+fn key_separator(code: &str) -> Option<usize> {
+    let mut depth = 0i32;
+    for (i, c) in code.char_indices() {
+        match c {
+            '<' | '[' | '{' => depth += 1,
+            '>' | ']' | '}' => depth -= 1,
+            '"' | '\'' => return None,
+            ':' if depth == 0 => {
+                let next = code[i + 1..].chars().next();
+                if next.is_none_or(char::is_whitespace) {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Byte ranges of identifier-like tokens (`[A-Za-z0-9_.]+`) outside quoted strings.
+fn token_ranges(code: &str) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut quote: Option<char> = None;
+    for (i, c) in code.char_indices() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        let is_token = c.is_ascii_alphanumeric() || c == '_' || c == '.';
+        match (start, is_token) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                out.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+        if c == '"' || c == '\'' {
+            quote = Some(c);
+        }
+    }
+    if let Some(s) = start {
+        out.push((s, code.len()));
+    }
+    out
+}
+
+fn utf16_to_byte(line: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in line.char_indices() {
+        if units >= character {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+fn byte_to_utf16(line: &str, byte: usize) -> usize {
+    let byte = byte.min(line.len());
+    let mut end = byte;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    line[..end].encode_utf16().count()
+}
+
+fn empty_range(line: usize, character: usize) -> JsonValue {
+    json!({
+        "start": { "line": line, "character": character },
+        "end": { "line": line, "character": character },
+    })
+}
+
+/// LSP range for 0-based `line` and byte columns `start..end` of that line.
+fn byte_range(text: &str, line: usize, start: usize, end: usize) -> JsonValue {
+    let line_text = LineIndex::new(text).line_text(line + 1).unwrap_or("");
+    json!({
+        "start": { "line": line, "character": byte_to_utf16(line_text, start) },
+        "end": { "line": line, "character": byte_to_utf16(line_text, end) },
+    })
+}
+
+fn span_to_range(text: &str, span: &SourceSpan) -> JsonValue {
+    let index = LineIndex::new(text);
+    let line = span.line.saturating_sub(1);
+    let line_start = index.line_start(span.line).unwrap_or(0);
+    byte_range(
+        text,
+        line,
+        span.start.saturating_sub(line_start),
+        span.end.saturating_sub(line_start),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::{
+        path_to_uri, read_message, token_bounds, type_reference_ranges, uri_to_path, write_message,
+    };
+
+    #[test]
+    fn message_framing_round_trips() {
+        let mut buffer = Vec::new();
+        let message = json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} });
+        write_message(&mut buffer, &message).unwrap();
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_message(&mut reader).unwrap(), Some(message));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn uris_round_trip_with_escapes() {
+        let path = std::path::Path::new("/tmp/my config/a.syaml");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///tmp/my%20config/a.syaml");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn token_bounds_cover_qualified_names_and_paths() {
+        let line = "port <shared.Port>: $.a.b[0]";
+        let (start, end) = token_bounds(line, 8).unwrap();
+        assert_eq!(&line[start..end], "shared.Port");
+        let (start, end) = token_bounds(line, 22).unwrap();
+        assert_eq!(&line[start..end], "$.a.b[0]");
+    }
+
+    #[test]
+    fn type_references_skip_free_text_and_properties() {
+        let text = "---!syaml/v0\n---schema\nPort:\n  type: integer\nService:\n  type: object\n  properties:\n    Port: Port\n    note:\n      type: string\n      description: Port number\n---data\nport <Port>: 80\n";
+        let lines: Vec<usize> = type_reference_ranges(text, None, "Port")
+            .into_iter()
+            .map(|(line, _, _)| line)
+            .collect();
+        // Definition, shorthand property value and data hint; not the property key
+        // or the description text.
+        assert_eq!(lines, vec![2, 7, 12]);
+    }
+}
@@ -1,31 +1,75 @@
 //! Minimal YAML subset parser used for `.syaml` sections.
 //!
 //! This parser supports mappings, sequences, basic scalars, quoted strings,
-//! and inline `{...}` / `[...]` collections. It is intentionally limited and
-//! tailored for predictable configuration parsing.
+//! inline `{...}` / `[...]` collections, and anchors (`&name`), aliases
+//! (`*name`) and `<<` merge keys within one body. It is intentionally limited
+//! and tailored for predictable configuration parsing.
+
+use std::collections::HashMap;
 
 use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
 
 use crate::error::SyamlError;
+use crate::source_map::SourceSpan;
 
 const MAX_DOCUMENT_LINES: usize = 100_000;
-const MAX_CONTAINER_DEPTH: usize = 64;
-const MAX_COLLECTION_ITEMS: usize = 50_000;
+pub(crate) const MAX_CONTAINER_DEPTH: usize = 64;
+pub(crate) const MAX_COLLECTION_ITEMS: usize = 50_000;
 const MAX_INLINE_VALUE_LEN: usize = 64 * 1024;
 
-fn yaml_parse_error(message: String) -> SyamlError {
+pub(crate) fn yaml_parse_error(message: String) -> SyamlError {
     SyamlError::YamlParseError {
         section: "unknown".to_string(),
         message,
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Path segment of a parsed node, using raw (un-normalized) mapping keys.
+pub enum LocationSegment {
+    /// Mapping key exactly as written (after unquoting).
+    Key(String),
+    /// Sequence index.
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Source position of a parsed block node, relative to the parsed body.
+///
+/// Inline scalars and `{...}` / `[...]` collections point at the value; block
+/// mappings and sequences point at the owning key or `-` marker.
+pub struct NodeLocation {
+    /// Path from the body root to the node.
+    pub path: Vec<LocationSegment>,
+    /// 1-based line number within the body.
+    pub line: usize,
+    /// Byte column (0-based) where the span starts on that line.
+    pub start: usize,
+    /// Byte column (0-based, exclusive) where the span ends on that line.
+    pub end: usize,
+}
+
 /// Parses a YAML-subset document body into JSON.
 pub fn parse_document(input: &str) -> Result<JsonValue, SyamlError> {
+    parse_document_with_locations(input)
+        .map(|(value, _)| value)
+        .map_err(SyamlError::into_inner)
+}
+
+/// Parses a YAML-subset document body into JSON and records node locations.
+///
+/// Errors are wrapped in [`SyamlError::Located`] with a span relative to `input`.
+pub fn parse_document_with_locations(
+    input: &str,
+) -> Result<(JsonValue, Vec<NodeLocation>), SyamlError> {
     let lines: Vec<Line<'_>> = input
         .lines()
         .enumerate()
-        .map(|(i, raw)| Line { number: i + 1, raw })
+        .map(|(i, raw)| Line {
+            number: i + 1,
+            offset: raw.as_ptr() as usize - input.as_ptr() as usize,
+            raw,
+        })
         .collect();
 
     if lines.len() > MAX_DOCUMENT_LINES {
@@ -40,11 +84,13 @@ pub fn parse_document(input: &str) -> Result<JsonValue, SyamlError> {
     }
 
     if idx >= lines.len() {
-        return Ok(JsonValue::Object(JsonMap::new()));
+        return Ok((JsonValue::Object(JsonMap::new()), Vec::new()));
     }
 
     let indent = leading_spaces(lines[idx].raw);
-    parse_block(&lines, &mut idx, indent, 0)
+    let mut recorder = Recorder::default();
+    let value = parse_block(&lines, &mut idx, indent, 0, &mut recorder)?;
+    Ok((value, recorder.locations))
 }
 
 /// Parses a single scalar YAML-subset value into JSON.
@@ -55,21 +101,232 @@ pub fn parse_scalar(input: &str) -> Result<JsonValue, SyamlError> {
 #[derive(Clone, Copy)]
 struct Line<'a> {
     number: usize,
+    offset: usize,
     raw: &'a str,
 }
 
+impl Line<'_> {
+    /// Span of the non-blank content of this line, relative to the parsed body.
+    fn span(&self) -> SourceSpan {
+        let start = leading_spaces(self.raw);
+        let end = self.raw.trim_end().len().max(start);
+        self.span_between(start, end)
+    }
+
+    fn span_between(&self, start: usize, end: usize) -> SourceSpan {
+        SourceSpan {
+            file: None,
+            line: self.number,
+            column: self.raw[..start].chars().count() + 1,
+            start: self.offset + start,
+            end: self.offset + end,
+            sensitive: false,
+        }
+    }
+
+    /// Byte column of `suffix`, which must be a subslice of this line.
+    fn column_of(&self, suffix: &str) -> usize {
+        suffix.as_ptr() as usize - self.raw.as_ptr() as usize
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    path: Vec<LocationSegment>,
+    locations: Vec<NodeLocation>,
+    anchors: Anchors,
+}
+
+impl Recorder {
+    fn record(&mut self, line: &Line<'_>, start: usize, end: usize) {
+        self.locations.push(NodeLocation {
+            path: self.path.clone(),
+            line: line.number,
+            start,
+            end,
+        });
+    }
+}
+
+/// Anchors defined so far in one body, and the budget for alias expansion.
+///
+/// Every alias copies its anchored value, so nested aliases can grow a small
+/// document exponentially. The total number of nodes produced by aliases is
+/// capped at [`MAX_COLLECTION_ITEMS`], and an alias may not push its value past
+/// [`MAX_CONTAINER_DEPTH`].
+pub(crate) struct Anchors {
+    enabled: bool,
+    values: HashMap<String, JsonValue>,
+    expanded_nodes: usize,
+}
+
+impl Default for Anchors {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            values: HashMap::new(),
+            expanded_nodes: 0,
+        }
+    }
+}
+
+impl Anchors {
+    /// Anchor state for standalone scalars, where `&` and `*` have no meaning.
+    fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    /// Records `value` under `name`; a later anchor with the same name replaces it.
+    pub(crate) fn define(&mut self, name: &str, value: &JsonValue) {
+        if self.enabled {
+            self.values.insert(name.to_string(), value.clone());
+        }
+    }
+
+    fn resolve(&mut self, name: &str, depth: usize) -> Result<JsonValue, SyamlError> {
+        let value = self
+            .values
+            .get(name)
+            .ok_or_else(|| yaml_parse_error(format!("unknown alias '*{name}'")))?;
+        if depth + value_depth(value) > MAX_CONTAINER_DEPTH {
+            return Err(yaml_parse_error(format!(
+                "alias '*{name}' exceeds maximum nesting depth ({MAX_CONTAINER_DEPTH})"
+            )));
+        }
+        self.expanded_nodes += node_count(value);
+        if self.expanded_nodes > MAX_COLLECTION_ITEMS {
+            return Err(yaml_parse_error(format!(
+                "alias expansion exceeds max node count ({MAX_COLLECTION_ITEMS})"
+            )));
+        }
+        Ok(value.clone())
+    }
+}
+
+fn value_depth(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Array(items) => 1 + items.iter().map(value_depth).max().unwrap_or(0),
+        JsonValue::Object(map) => 1 + map.values().map(value_depth).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+fn node_count(value: &JsonValue) -> usize {
+    match value {
+        JsonValue::Array(items) => 1 + items.iter().map(node_count).sum::<usize>(),
+        JsonValue::Object(map) => 1 + map.values().map(node_count).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// Splits a leading `&name` anchor off a raw value.
+///
+/// Returns the anchor name and the rest of the value, which is empty when only
+/// a comment follows the anchor. A lone `&` is not an anchor.
+pub(crate) fn split_anchor(raw: &str) -> Result<Option<(&str, &str)>, SyamlError> {
+    let Some(after) = raw.trim_start().strip_prefix('&') else {
+        return Ok(None);
+    };
+    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+    let name = &after[..end];
+    if name.is_empty() {
+        return Ok(None);
+    }
+    validate_anchor_name(name, '&')?;
+    let rest = after[end..].trim_start();
+    if strip_inline_comment(rest).trim().is_empty() {
+        return Ok(Some((name, "")));
+    }
+    Ok(Some((name, rest)))
+}
+
+/// Returns the alias name when `raw` (comment included) is a `*name` alias.
+fn alias_name(raw: &str) -> Result<Option<&str>, SyamlError> {
+    let Some(name) = strip_inline_comment(raw.trim()).trim().strip_prefix('*') else {
+        return Ok(None);
+    };
+    if name.is_empty() || name.starts_with(char::is_whitespace) {
+        return Ok(None);
+    }
+    validate_anchor_name(name, '*')?;
+    Ok(Some(name))
+}
+
+fn validate_anchor_name(name: &str, sigil: char) -> Result<(), SyamlError> {
+    if name
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '[' | ']' | '{' | '}' | ','))
+    {
+        return Err(yaml_parse_error(format!(
+            "invalid anchor name '{sigil}{name}'"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns `true` for the `<<` merge key (an unquoted raw key).
+pub(crate) fn is_merge_key(raw_key: &str) -> bool {
+    raw_key == "<<"
+}
+
+/// Converts the value of a `<<` merge key into the mappings it merges, in
+/// priority order.
+pub(crate) fn merge_sources(
+    value: JsonValue,
+) -> Result<Vec<JsonMap<String, JsonValue>>, SyamlError> {
+    let invalid =
+        || yaml_parse_error("merge key '<<' expects a mapping or a list of mappings".to_string());
+    match value {
+        JsonValue::Object(map) => Ok(vec![map]),
+        JsonValue::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                JsonValue::Object(map) => Ok(map),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
+
+/// Adds merged entries to `map`; keys written in the mapping itself win, and
+/// earlier merge sources win over later ones.
+pub(crate) fn apply_merges(
+    map: &mut JsonMap<String, JsonValue>,
+    sources: Vec<JsonMap<String, JsonValue>>,
+) -> Result<(), SyamlError> {
+    for source in sources {
+        for (key, value) in source {
+            map.entry(key).or_insert(value);
+        }
+    }
+    if map.len() > MAX_COLLECTION_ITEMS {
+        return Err(yaml_parse_error(format!(
+            "mapping exceeds max item count ({MAX_COLLECTION_ITEMS})"
+        )));
+    }
+    Ok(())
+}
+
+fn located(err: SyamlError, line: &Line<'_>) -> SyamlError {
+    err.with_span(line.span())
+}
+
 #[derive(Clone, Copy)]
-enum ChompingMode {
+pub(crate) enum ChompingMode {
     Clip,
     Strip,
     Keep,
 }
 
 #[derive(Clone, Copy)]
-struct BlockScalarHeader {
-    folded: bool,
-    chomping: ChompingMode,
-    explicit_indent: Option<usize>,
+pub(crate) struct BlockScalarHeader {
+    pub(crate) folded: bool,
+    pub(crate) chomping: ChompingMode,
+    pub(crate) explicit_indent: Option<usize>,
 }
 
 fn parse_block(
@@ -77,11 +334,16 @@ fn parse_block(
     idx: &mut usize,
     indent: usize,
     depth: usize,
+    recorder: &mut Recorder,
 ) -> Result<JsonValue, SyamlError> {
     if depth > MAX_CONTAINER_DEPTH {
-        return Err(yaml_parse_error(format!(
+        let err = yaml_parse_error(format!(
             "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
-        )));
+        ));
+        return Err(match lines.get(*idx) {
+            Some(line) => located(err, line),
+            None => err,
+        });
     }
 
     while *idx < lines.len() && is_ignorable(lines[*idx].raw) {
@@ -98,22 +360,26 @@ fn parse_block(
         return Ok(JsonValue::Object(JsonMap::new()));
     }
     if current_indent > indent {
-        return Err(SyamlError::YamlParseError {
-            section: "unknown".to_string(),
-            message: format!(
-                "unexpected indentation at line {}: expected {}, found {}",
-                line.number, indent, current_indent
-            ),
-        });
+        return Err(located(
+            SyamlError::YamlParseError {
+                section: "unknown".to_string(),
+                message: format!(
+                    "unexpected indentation at line {}: expected {}, found {}",
+                    line.number, indent, current_indent
+                ),
+            },
+            &line,
+        ));
     }
 
     let trimmed = line.raw[indent..].trim_start();
-    if trimmed.starts_with("- ") {
-        parse_sequence(lines, idx, indent, depth)
+    if sequence_item_rest(trimmed).is_some() {
+        parse_sequence(lines, idx, indent, depth, recorder)
     } else if has_unquoted_colon(trimmed) {
-        parse_mapping(lines, idx, indent, depth)
+        parse_mapping(lines, idx, indent, depth, recorder)
     } else {
-        let value = parse_inline_value(trimmed, depth + 1)?;
+        let value = parse_inline_value_with(trimmed, depth + 1, &mut recorder.anchors)
+            .map_err(|e| located(e, &line))?;
         *idx += 1;
         Ok(value)
     }
@@ -124,8 +390,10 @@ fn parse_mapping(
     idx: &mut usize,
     indent: usize,
     depth: usize,
+    recorder: &mut Recorder,
 ) -> Result<JsonValue, SyamlError> {
     let mut map = JsonMap::new();
+    let mut merges = Vec::new();
 
     while *idx < lines.len() {
         if is_ignorable(lines[*idx].raw) {
@@ -139,41 +407,63 @@ fn parse_mapping(
             break;
         }
         if current_indent > indent {
-            return Err(SyamlError::YamlParseError {
-                section: "unknown".to_string(),
-                message: format!(
-                    "unexpected indentation in mapping at line {}: expected {}",
-                    line.number, indent
-                ),
-            });
+            return Err(located(
+                SyamlError::YamlParseError {
+                    section: "unknown".to_string(),
+                    message: format!(
+                        "unexpected indentation in mapping at line {}: expected {}",
+                        line.number, indent
+                    ),
+                },
+                &line,
+            ));
         }
 
         let trimmed = line.raw[indent..].trim_start();
-        if trimmed.starts_with("- ") {
-            return Err(SyamlError::YamlParseError {
-                section: "unknown".to_string(),
-                message: format!("mixed sequence/mapping at line {}", line.number),
-            });
+        if sequence_item_rest(trimmed).is_some() {
+            return Err(located(
+                SyamlError::YamlParseError {
+                    section: "unknown".to_string(),
+                    message: format!("mixed sequence/mapping at line {}", line.number),
+                },
+                &line,
+            ));
         }
 
-        let colon = find_unquoted_colon(trimmed).ok_or_else(|| SyamlError::YamlParseError {
-            section: "unknown".to_string(),
-            message: format!("expected key:value at line {}", line.number),
+        let colon = find_unquoted_colon(trimmed).ok_or_else(|| {
+            located(
+                SyamlError::YamlParseError {
+                    section: "unknown".to_string(),
+                    message: format!("expected key:value at line {}", line.number),
+                },
+                &line,
+            )
         })?;
 
         let key_raw = trimmed[..colon].trim();
-        let key = parse_key(key_raw)?;
+        let key = parse_key(key_raw).map_err(|e| located(e, &line))?;
         if map.contains_key(&key) {
-            return Err(SyamlError::YamlParseError {
-                section: "unknown".to_string(),
-                message: format!("duplicate key '{}' at line {}", key, line.number),
-            });
+            return Err(located(
+                SyamlError::YamlParseError {
+                    section: "unknown".to_string(),
+                    message: format!("duplicate key '{}' at line {}", key, line.number),
+                },
+                &line,
+            ));
         }
 
         let value_raw = trimmed[colon + 1..].trim_start();
+        let (anchor, value_raw) = match split_anchor(value_raw).map_err(|e| located(e, &line))? {
+            Some((name, rest)) => (Some(name), rest),
+            None => (None, value_raw),
+        };
+        let key_start = line.column_of(trimmed);
+        let key_end = key_start + key_raw.len();
         *idx += 1;
 
+        recorder.path.push(LocationSegment::Key(key.clone()));
         let value = if value_raw.is_empty() {
+            recorder.record(&line, key_start, key_end);
             let mut lookahead = *idx;
             while lookahead < lines.len() && is_ignorable(lines[lookahead].raw) {
                 lookahead += 1;
@@ -185,24 +475,46 @@ fn parse_mapping(
                 if next_indent <= indent {
                     JsonValue::Null
                 } else {
-                    parse_block(lines, idx, next_indent, depth + 1)?
+                    parse_block(lines, idx, next_indent, depth + 1, recorder)?
                 }
             }
-        } else if let Some(header) = parse_block_scalar_header(value_raw)? {
+        } else if let Some(header) =
+            parse_block_scalar_header(value_raw).map_err(|e| located(e, &line))?
+        {
+            recorder.record(&line, key_start, key_end);
             JsonValue::String(parse_block_scalar(lines, idx, indent, header)?)
         } else {
-            parse_inline_value(value_raw, depth + 1)?
+            let value_start = line.column_of(value_raw);
+            recorder.record(
+                &line,
+                value_start,
+                value_start + strip_inline_comment(value_raw).len(),
+            );
+            parse_inline_value_with(value_raw, depth + 1, &mut recorder.anchors)
+                .map_err(|e| located(e, &line))?
         };
+        recorder.path.pop();
+        if let Some(name) = anchor {
+            recorder.anchors.define(name, &value);
+        }
 
+        if is_merge_key(key_raw) {
+            merges.extend(merge_sources(value).map_err(|e| located(e, &line))?);
+            continue;
+        }
         map.insert(key, value);
         if map.len() > MAX_COLLECTION_ITEMS {
-            return Err(yaml_parse_error(format!(
-                "mapping exceeds max item count ({MAX_COLLECTION_ITEMS}) at line {}",
-                line.number
-            )));
+            return Err(located(
+                yaml_parse_error(format!(
+                    "mapping exceeds max item count ({MAX_COLLECTION_ITEMS}) at line {}",
+                    line.number
+                )),
+                &line,
+            ));
         }
     }
 
+    apply_merges(&mut map, merges)?;
     Ok(JsonValue::Object(map))
 }
 
@@ -211,6 +523,7 @@ fn parse_sequence(
     idx: &mut usize,
     indent: usize,
     depth: usize,
+    recorder: &mut Recorder,
 ) -> Result<JsonValue, SyamlError> {
     let mut items = Vec::new();
 
@@ -226,24 +539,33 @@ fn parse_sequence(
             break;
         }
         if current_indent > indent {
-            return Err(SyamlError::YamlParseError {
-                section: "unknown".to_string(),
-                message: format!(
-                    "unexpected indentation in sequence at line {}: expected {}",
-                    line.number, indent
-                ),
-            });
+            return Err(located(
+                SyamlError::YamlParseError {
+                    section: "unknown".to_string(),
+                    message: format!(
+                        "unexpected indentation in sequence at line {}: expected {}",
+                        line.number, indent
+                    ),
+                },
+                &line,
+            ));
         }
 
         let trimmed = line.raw[indent..].trim_start();
-        if !trimmed.starts_with("- ") {
+        let Some(rest) = sequence_item_rest(trimmed) else {
             break;
-        }
+        };
 
-        let rest = trimmed[2..].trim_start();
+        let (anchor, rest) = match split_anchor(rest).map_err(|e| located(e, &line))? {
+            Some((name, rest)) => (Some(name), rest),
+            None => (None, rest),
+        };
+        let dash = line.column_of(trimmed);
         *idx += 1;
 
+        recorder.path.push(LocationSegment::Index(items.len()));
         let value = if rest.is_empty() {
+            recorder.record(&line, dash, dash + 1);
             let mut lookahead = *idx;
             while lookahead < lines.len() && is_ignorable(lines[lookahead].raw) {
                 lookahead += 1;
@@ -255,28 +577,56 @@ fn parse_sequence(
                 if next_indent <= indent {
                     JsonValue::Null
                 } else {
-                    parse_block(lines, idx, next_indent, depth + 1)?
+                    parse_block(lines, idx, next_indent, depth + 1, recorder)?
                 }
             }
-        } else if let Some(header) = parse_block_scalar_header(rest)? {
+        } else if let Some(header) =
+            parse_block_scalar_header(rest).map_err(|e| located(e, &line))?
+        {
+            recorder.record(&line, dash, dash + 1);
             JsonValue::String(parse_block_scalar(lines, idx, indent, header)?)
         } else {
-            parse_inline_value(rest, depth + 1)?
+            let rest_start = line.column_of(rest);
+            recorder.record(
+                &line,
+                rest_start,
+                rest_start + strip_inline_comment(rest).len(),
+            );
+            parse_inline_value_with(rest, depth + 1, &mut recorder.anchors)
+                .map_err(|e| located(e, &line))?
         };
+        recorder.path.pop();
+        if let Some(name) = anchor {
+            recorder.anchors.define(name, &value);
+        }
 
         items.push(value);
         if items.len() > MAX_COLLECTION_ITEMS {
-            return Err(yaml_parse_error(format!(
-                "sequence exceeds max item count ({MAX_COLLECTION_ITEMS}) at line {}",
-                line.number
-            )));
+            return Err(located(
+                yaml_parse_error(format!(
+                    "sequence exceeds max item count ({MAX_COLLECTION_ITEMS}) at line {}",
+                    line.number
+                )),
+                &line,
+            ));
         }
     }
 
     Ok(JsonValue::Array(items))
 }
 
-fn parse_inline_value(raw: &str, depth: usize) -> Result<JsonValue, SyamlError> {
+/// Parses an inline value with no anchor context; `&` and `*` are literal text.
+pub(crate) fn parse_inline_value(raw: &str, depth: usize) -> Result<JsonValue, SyamlError> {
+    parse_inline_value_with(raw, depth, &mut Anchors::disabled())
+}
+
+/// Parses an inline value, resolving `*alias` values and recording `&anchor`
+/// values (including inside `{...}` / `[...]` collections).
+pub(crate) fn parse_inline_value_with(
+    raw: &str,
+    depth: usize,
+    anchors: &mut Anchors,
+) -> Result<JsonValue, SyamlError> {
     if depth > MAX_CONTAINER_DEPTH {
         return Err(yaml_parse_error(format!(
             "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
@@ -294,16 +644,27 @@ fn parse_inline_value(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
         return Ok(JsonValue::Null);
     }
 
+    if anchors.enabled {
+        if let Some((name, rest)) = split_anchor(s)? {
+            let value = parse_inline_value_with(rest, depth, anchors)?;
+            anchors.define(name, &value);
+            return Ok(value);
+        }
+        if let Some(name) = alias_name(s)? {
+            return anchors.resolve(name, depth);
+        }
+    }
+
     if s.starts_with('"') || s.starts_with('\'') {
         return Ok(JsonValue::String(parse_quoted_string(s)?));
     }
 
     if s.starts_with('{') {
-        return parse_inline_object(s, depth + 1);
+        return parse_inline_object(s, depth + 1, anchors);
     }
 
     if s.starts_with('[') {
-        return parse_inline_array(s, depth + 1);
+        return parse_inline_array(s, depth + 1, anchors);
     }
 
     if s == "true" {
@@ -331,7 +692,11 @@ fn parse_inline_value(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
     Ok(JsonValue::String(strip_inline_comment(s).to_string()))
 }
 
-fn parse_inline_object(raw: &str, depth: usize) -> Result<JsonValue, SyamlError> {
+fn parse_inline_object(
+    raw: &str,
+    depth: usize,
+    anchors: &mut Anchors,
+) -> Result<JsonValue, SyamlError> {
     if depth > MAX_CONTAINER_DEPTH {
         return Err(yaml_parse_error(format!(
             "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
@@ -351,6 +716,7 @@ fn parse_inline_object(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
 
     let parts = split_top_level(inner, ',');
     let mut map = JsonMap::new();
+    let mut merges = Vec::new();
 
     for part in parts {
         let p = part.trim();
@@ -362,14 +728,19 @@ fn parse_inline_object(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
             message: format!("invalid inline object entry '{}': expected ':'", p),
         })?;
 
-        let key = parse_key(p[..colon].trim())?;
+        let key_raw = p[..colon].trim();
+        let value = parse_inline_value_with(p[colon + 1..].trim(), depth + 1, anchors)?;
+        if anchors.enabled && is_merge_key(key_raw) {
+            merges.extend(merge_sources(value)?);
+            continue;
+        }
+        let key = parse_key(key_raw)?;
         if map.contains_key(&key) {
             return Err(yaml_parse_error(format!(
                 "duplicate key '{}' in inline object",
                 key
             )));
         }
-        let value = parse_inline_value(p[colon + 1..].trim(), depth + 1)?;
         map.insert(key, value);
         if map.len() > MAX_COLLECTION_ITEMS {
             return Err(yaml_parse_error(format!(
@@ -378,10 +749,15 @@ fn parse_inline_object(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
         }
     }
 
+    apply_merges(&mut map, merges)?;
     Ok(JsonValue::Object(map))
 }
 
-fn parse_inline_array(raw: &str, depth: usize) -> Result<JsonValue, SyamlError> {
+fn parse_inline_array(
+    raw: &str,
+    depth: usize,
+    anchors: &mut Anchors,
+) -> Result<JsonValue, SyamlError> {
     if depth > MAX_CONTAINER_DEPTH {
         return Err(yaml_parse_error(format!(
             "maximum nesting depth exceeded ({MAX_CONTAINER_DEPTH})"
@@ -406,7 +782,7 @@ fn parse_inline_array(raw: &str, depth: usize) -> Result<JsonValue, SyamlError>
         if p.is_empty() {
             continue;
         }
-        items.push(parse_inline_value(p, depth + 1)?);
+        items.push(parse_inline_value_with(p, depth + 1, anchors)?);
         if items.len() > MAX_COLLECTION_ITEMS {
             return Err(yaml_parse_error(format!(
                 "inline array exceeds max item count ({MAX_COLLECTION_ITEMS})"
@@ -481,7 +857,9 @@ fn split_top_level(input: &str, delimiter: char) -> Vec<String> {
     out
 }
 
-fn parse_block_scalar_header(raw: &str) -> Result<Option<BlockScalarHeader>, SyamlError> {
+pub(crate) fn parse_block_scalar_header(
+    raw: &str,
+) -> Result<Option<BlockScalarHeader>, SyamlError> {
     let s = strip_inline_comment(raw).trim();
     if s.is_empty() {
         return Ok(None);
@@ -565,8 +943,14 @@ fn parse_block_scalar(
         *idx += 1;
     }
 
+    Ok(render_block_scalar(&content, header))
+}
+
+/// Renders block scalar content lines (indentation already removed, blank lines
+/// empty) according to the header's style and chomping.
+pub(crate) fn render_block_scalar(content: &[String], header: BlockScalarHeader) -> String {
     let mut rendered = if header.folded {
-        fold_block_lines(&content)
+        fold_block_lines(content)
     } else {
         content.join("\n")
     };
@@ -594,7 +978,7 @@ fn parse_block_scalar(
         }
     }
 
-    Ok(rendered)
+    rendered
 }
 
 fn fold_block_lines(lines: &[String]) -> String {
@@ -621,7 +1005,7 @@ fn fold_block_lines(lines: &[String]) -> String {
     out
 }
 
-fn parse_key(raw: &str) -> Result<String, SyamlError> {
+pub(crate) fn parse_key(raw: &str) -> Result<String, SyamlError> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
         return Err(SyamlError::YamlParseError {
@@ -694,7 +1078,7 @@ fn has_unquoted_colon(input: &str) -> bool {
     find_unquoted_colon(input).is_some()
 }
 
-fn find_unquoted_colon(input: &str) -> Option<usize> {
+pub(crate) fn find_unquoted_colon(input: &str) -> Option<usize> {
     let mut in_single = false;
     let mut in_double = false;
     let mut depth_brace = 0i32;
@@ -729,7 +1113,7 @@ fn find_unquoted_colon(input: &str) -> Option<usize> {
     None
 }
 
-fn strip_inline_comment(input: &str) -> &str {
+pub(crate) fn strip_inline_comment(input: &str) -> &str {
     let mut in_single = false;
     let mut in_double = false;
     let mut escape = false;
@@ -764,11 +1148,20 @@ fn strip_inline_comment(input: &str) -> &str {
     input
 }
 
-fn leading_spaces(line: &str) -> usize {
+/// Returns the text after a `- ` sequence marker (or a bare `-`), if `trimmed` is a
+/// sequence item.
+pub(crate) fn sequence_item_rest(trimmed: &str) -> Option<&str> {
+    if trimmed == "-" {
+        return Some(&trimmed[1..]);
+    }
+    trimmed.strip_prefix("- ").map(str::trim_start)
+}
+
+pub(crate) fn leading_spaces(line: &str) -> usize {
     line.chars().take_while(|c| *c == ' ').count()
 }
 
-fn is_ignorable(line: &str) -> bool {
+pub(crate) fn is_ignorable(line: &str) -> bool {
     let trimmed = line.trim();
     trimmed.is_empty() || trimmed.starts_with('#')
 }
@@ -777,7 +1170,14 @@ fn is_ignorable(line: &str) -> bool {
 mod tests {
     use serde_json::json;
 
-    use super::parse_document;
+    use super::{parse_document, parse_document_with_locations, LocationSegment};
+
+    #[test]
+    fn bare_dash_starts_a_nested_sequence_item() {
+        let input = "items:\n  -\n    name: a\n  - b\n";
+        let parsed = parse_document(input).unwrap();
+        assert_eq!(parsed, json!({"items": [{"name": "a"}, "b"]}));
+    }
 
     #[test]
     fn parses_simple_mapping_and_sequence() {
@@ -821,6 +1221,67 @@ mod tests {
         assert!(err.to_string().contains("maximum nesting depth exceeded"));
     }
 
+    #[test]
+    fn resolves_anchors_aliases_and_merge_keys() {
+        let input = "defaults: &defaults\n  retries: 3\n  timeout: 10\nhosts: &hosts [a, b]\nservice:\n  <<: *defaults\n  timeout: 30\n  hosts: *hosts\nother: { <<: *defaults, name: x }\nmulti:\n  <<: [{ a: 1 }, { a: 2, b: 2 }]\nitems:\n  - &first 1\n  - *first\n";
+        let parsed = parse_document(input).unwrap();
+        assert_eq!(
+            parsed["service"],
+            json!({"retries": 3, "timeout": 30, "hosts": ["a", "b"]})
+        );
+        assert_eq!(
+            parsed["other"],
+            json!({"retries": 3, "timeout": 10, "name": "x"})
+        );
+        assert_eq!(parsed["multi"], json!({"a": 1, "b": 2}));
+        assert_eq!(parsed["items"], json!([1, 1]));
+        assert!(parsed["service"].get("<<").is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_aliases_and_invalid_merges() {
+        let err = parse_document("a: *missing\n").unwrap_err();
+        assert!(
+            err.to_string().contains("unknown alias '*missing'"),
+            "{err}"
+        );
+        let err = parse_document("a: &x 1\nb:\n  <<: *x\n").unwrap_err();
+        assert!(
+            err.to_string().contains("merge key '<<' expects a mapping"),
+            "{err}"
+        );
+        // An anchor is only visible after its value, so a value cannot alias itself.
+        assert!(parse_document("a: &x\n  b: *x\n").is_err());
+        // A lone `&` or `*` stays plain text.
+        assert_eq!(
+            parse_document("a: & co\nb: * x\n").unwrap(),
+            json!({"a": "& co", "b": "* x"})
+        );
+    }
+
+    #[test]
+    fn caps_alias_expansion() {
+        let mut input = String::from("l0: &l0 [x, x, x, x, x, x, x, x, x, x]\n");
+        for level in 1..8 {
+            let prev = format!("*l{}", level - 1);
+            let items = [prev.as_str(); 10].join(", ");
+            input.push_str(&format!("l{level}: &l{level} [{items}]\n"));
+        }
+        let err = parse_document(&input).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("alias expansion exceeds max node count"),
+            "{err}"
+        );
+
+        let mut deep = String::from("a0: &a0 1\n");
+        for level in 1..70 {
+            deep.push_str(&format!("a{level}: &a{level} [*a{}]\n", level - 1));
+        }
+        let err = parse_document(&deep).unwrap_err();
+        assert!(err.to_string().contains("maximum nesting depth"), "{err}");
+    }
+
     #[test]
     fn parses_literal_block_scalars() {
         let input = "message: |-\n  first line\n  second line\n";
@@ -844,4 +1305,26 @@ mod tests {
         let parsed = parse_document(input).unwrap();
         assert_eq!(parsed, json!({"items":["one\ntwo", "alpha beta"]}));
     }
+
+    #[test]
+    fn records_value_and_key_locations() {
+        let input = "name: test # note\nnested:\n  items:\n    - 1\n";
+        let (_, locations) = parse_document_with_locations(input).unwrap();
+        let name = &locations[0];
+        assert_eq!(name.path, vec![LocationSegment::Key("name".to_string())]);
+        assert_eq!((name.line, name.start, name.end), (1, 6, 10));
+        let item = locations.last().unwrap();
+        assert_eq!(item.line, 4);
+        assert_eq!(item.path.last(), Some(&LocationSegment::Index(0)));
+    }
+
+    #[test]
+    fn parse_errors_carry_line_spans() {
+        let input = "a: 1\nb: [1, 2\n";
+        let err = parse_document_with_locations(input).unwrap_err();
+        let span = err.span().unwrap();
+        assert_eq!(span.line, 2);
+        assert_eq!(span.column, 1);
+        assert!(parse_document(input).unwrap_err().location().is_none());
+    }
 }
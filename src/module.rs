@@ -27,13 +27,13 @@ pub const REGISTRY_FILENAME: &str = "syaml.syaml";
 ///
 /// Validates that:
 /// - The document has a `---module` section.
-/// - `---data` and `---contracts` sections are absent.
+/// - `---data`, `---contracts` and `---overlay` sections are absent.
 pub fn parse_module_manifest(input: &str) -> Result<ModuleManifest, SyamlError> {
     let (_version, sections) = scan_sections(input)?;
 
     // Enforce manifest-only constraints
     for sec in &sections {
-        if matches!(sec.name.as_str(), "data" | "contracts") {
+        if matches!(sec.name.as_str(), "data" | "contracts" | "overlay") {
             return Err(SyamlError::ModuleManifestError(format!(
                 "'---{}' section is not allowed in module.syaml",
                 sec.name
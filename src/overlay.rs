@@ -0,0 +1,397 @@
+//! Overlays: documents that patch the data of a base document, used for
+//! environment profiles (`service.prod.syaml` patching `service.syaml`).
+//!
+//! An overlay names its base with `meta.overlay`, and its `---data` section
+//! is the patch. An `---overlay` section holding `base` and `data` says the
+//! same in one section. Compiling an overlay compiles its base with the patch
+//! applied; an overlay may itself be the base of another overlay.
+//!
+//! Patches are merged after the base's templates expand and before value
+//! overrides, `$.` references and derived values, so the base's expressions,
+//! type hints and constraints all see the patched data:
+//!
+//! - mappings merge key by key, recursively;
+//! - any other value, lists included, replaces the base value;
+//! - `key+: [...]` appends items to the list at `key`;
+//! - `key-: ~` deletes `key`.
+//!
+//! Patch values may use `=expr`, `${...}`, `$.path` references and the
+//! base's templates. Keys frozen with `^` and locked template fields
+//! (`name!`) reject patches, like they reject overrides.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::ast::{DataDoc, FreezeMarkers, ParsedDocument};
+use crate::error::SyamlError;
+use crate::overrides::{display_path, protection};
+use crate::path_is_within;
+use crate::source_map::SourceMap;
+
+/// Longest chain of overlays followed when resolving a profile.
+const MAX_OVERLAY_CHAIN: usize = 16;
+
+/// The data patch of an overlay document, with the overlay's source spans.
+#[derive(Debug, Clone)]
+pub(crate) struct Patch {
+    pub(crate) data: DataDoc,
+    pub(crate) source_map: SourceMap,
+}
+
+/// Reads `meta.overlay`, the path of the base document an overlay patches.
+pub(crate) fn parse_overlay_meta(meta: &JsonValue) -> Result<Option<String>, SyamlError> {
+    match meta.get("overlay") {
+        None => Ok(None),
+        Some(JsonValue::String(base)) if !base.trim().is_empty() => Ok(Some(base.clone())),
+        Some(_) => Err(SyamlError::OverlayError(
+            "meta.overlay must be the path of the base document".to_string(),
+        )),
+    }
+}
+
+/// Reads an `---overlay` section: `base` (required) and the `data` patch.
+pub(crate) fn parse_overlay_section(value: &JsonValue) -> Result<(String, JsonValue), SyamlError> {
+    let map = value.as_object().ok_or_else(|| {
+        SyamlError::OverlayError("overlay section must be a mapping/object".to_string())
+    })?;
+    if let Some(key) = map
+        .keys()
+        .find(|key| !matches!(key.as_str(), "base" | "data"))
+    {
+        return Err(SyamlError::OverlayError(format!(
+            "unknown key '{key}' in overlay section; expected base and data"
+        )));
+    }
+    let base = match map.get("base") {
+        Some(JsonValue::String(base)) if !base.trim().is_empty() => base.clone(),
+        _ => {
+            return Err(SyamlError::OverlayError(
+                "overlay.base must be the path of the base document".to_string(),
+            ))
+        }
+    };
+    let data = match map.get("data") {
+        None | Some(JsonValue::Null) => JsonValue::Object(JsonMap::new()),
+        Some(data @ JsonValue::Object(_)) => data.clone(),
+        Some(_) => {
+            return Err(SyamlError::OverlayError(
+                "overlay.data must be a mapping/object".to_string(),
+            ))
+        }
+    };
+    Ok((base, data))
+}
+
+/// Rejects overlay content other than `meta.file`, the base and the patch.
+///
+/// Types, functions, contracts, env bindings and imports belong to the base
+/// document, whose expressions the patch is resolved with.
+pub(crate) fn check_overlay_document(parsed: &ParsedDocument) -> Result<(), SyamlError> {
+    let misplaced = if parsed.meta.as_ref().is_some_and(|m| !m.env.is_empty()) {
+        Some("meta.env")
+    } else if parsed.meta.as_ref().is_some_and(|m| !m.imports.is_empty()) {
+        Some("meta.imports")
+    } else if !parsed.schema.types.is_empty() {
+        Some("a schema section")
+    } else if parsed.functions.is_some() {
+        Some("a functions section")
+    } else if parsed.contracts.is_some() {
+        Some("a contracts section")
+    } else {
+        None
+    };
+    match misplaced {
+        Some(what) => Err(SyamlError::OverlayError(format!(
+            "an overlay cannot declare {what}; move it to the base document"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Path of the overlay for `profile` next to `base`:
+/// `service.syaml` with profile `prod` is `service.prod.syaml`.
+pub fn profile_path(base: &Path, profile: &str) -> PathBuf {
+    let stem = base
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = base
+        .extension()
+        .map(|ext| ext.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "syaml".to_string());
+    base.with_file_name(format!("{stem}.{profile}.{extension}"))
+}
+
+/// Finds the overlay file for `profile` of `base` (see [`profile_path`]) and
+/// checks that it patches `base`, directly or through other overlays.
+pub fn resolve_profile(base: &Path, profile: &str) -> Result<PathBuf, SyamlError> {
+    if profile.is_empty()
+        || !profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(SyamlError::OverlayError(format!(
+            "invalid profile name '{profile}'; use letters, digits, '-' and '_'"
+        )));
+    }
+    let overlay_path = profile_path(base, profile);
+    if !overlay_path.is_file() {
+        return Err(SyamlError::OverlayError(format!(
+            "profile '{profile}' not found: expected overlay file {}",
+            overlay_path.display()
+        )));
+    }
+
+    let base_canonical = fs::canonicalize(base).unwrap_or_else(|_| base.to_path_buf());
+    let mut current = overlay_path.clone();
+    for _ in 0..MAX_OVERLAY_CHAIN {
+        let input = fs::read_to_string(&current)?;
+        let parsed = crate::parse_document(&input)
+            .map_err(|e| e.with_file(current.display().to_string()))?;
+        let Some(next) = parsed.overlay else {
+            break;
+        };
+        let next = current.parent().unwrap_or(Path::new(".")).join(next);
+        if fs::canonicalize(&next).unwrap_or_else(|_| next.clone()) == base_canonical {
+            return Ok(overlay_path);
+        }
+        current = next;
+    }
+    Err(SyamlError::OverlayError(format!(
+        "profile '{profile}': {} is not an overlay of {}",
+        overlay_path.display(),
+        base.display()
+    )))
+}
+
+/// Merges `patch` into `data`, moving the patch's type hints and freeze
+/// markers to the paths they end up at.
+///
+/// Returns the spans in `spans` (the overlay's source map) of every value
+/// the patch wrote, keyed by the data path it was written to. Rejected
+/// patches are located in the overlay too.
+pub(crate) fn apply_overlay(
+    data: &mut JsonValue,
+    patch: &DataDoc,
+    spans: &SourceMap,
+    type_hints: &mut BTreeMap<String, String>,
+    freeze_markers: &mut FreezeMarkers,
+    locked_fields: &BTreeSet<String>,
+) -> Result<SourceMap, SyamlError> {
+    let (Some(target), Some(changes)) = (data.as_object_mut(), patch.value.as_object()) else {
+        return Err(SyamlError::OverlayError(
+            "overlay data and base data must be mappings/objects".to_string(),
+        ));
+    };
+    let mut merge = Merge {
+        patch,
+        spans,
+        written: SourceMap::new(),
+        type_hints,
+        freeze_markers,
+        locked_fields,
+    };
+    merge.object(target, changes, "$", "$")?;
+    Ok(merge.written)
+}
+
+/// The spans of `over`, plus those of `under` at paths that `over` did not
+/// write: an overlay's spans replace the base's for the values it rewrote.
+pub(crate) fn layer_spans(over: &SourceMap, under: &SourceMap) -> SourceMap {
+    let mut layered = over.clone();
+    for (path, span) in under.iter() {
+        if over.lookup(path).is_none() {
+            layered.insert(path.clone(), span.clone());
+        }
+    }
+    layered
+}
+
+struct Merge<'a> {
+    patch: &'a DataDoc,
+    spans: &'a SourceMap,
+    written: SourceMap,
+    type_hints: &'a mut BTreeMap<String, String>,
+    freeze_markers: &'a mut FreezeMarkers,
+    locked_fields: &'a BTreeSet<String>,
+}
+
+impl Merge<'_> {
+    /// Merges the `changes` found at `patch_path` of the patch into the
+    /// mapping at `path` of the data.
+    fn object(
+        &mut self,
+        target: &mut JsonMap<String, JsonValue>,
+        changes: &JsonMap<String, JsonValue>,
+        patch_path: &str,
+        path: &str,
+    ) -> Result<(), SyamlError> {
+        for (raw_key, change) in changes {
+            let patch_child = format!("{patch_path}.{raw_key}");
+            if let Some(key) = directive_key(raw_key, '+') {
+                let child = format!("{path}.{key}");
+                self.check(&child, &patch_child)?;
+                let JsonValue::Array(items) = change else {
+                    return Err(self.rejected(
+                        &child,
+                        &patch_child,
+                        format!(
+                            "'{raw_key}' appends a list, found {}",
+                            json_type_name(change)
+                        ),
+                    ));
+                };
+                let Some(JsonValue::Array(list)) = target.get_mut(key) else {
+                    return Err(self.rejected(
+                        &child,
+                        &patch_child,
+                        "there is no list to append to".to_string(),
+                    ));
+                };
+                for (i, item) in items.iter().enumerate() {
+                    let at = format!("{child}[{}]", list.len());
+                    list.push(item.clone());
+                    let patch_item = format!("{patch_child}[{i}]");
+                    self.take_markers(&patch_item, &at, true)?;
+                    self.take_spans(&patch_item, &at);
+                }
+                self.take_markers(&patch_child, &child, false)?;
+            } else if let Some(key) = directive_key(raw_key, '-') {
+                let child = format!("{path}.{key}");
+                if !change.is_null() {
+                    return Err(self.rejected(
+                        &child,
+                        &patch_child,
+                        format!("'{raw_key}' deletes '{key}'; its value must be ~"),
+                    ));
+                }
+                self.check(&child, &patch_child)?;
+                if target.remove(key).is_none() {
+                    return Err(self.rejected(
+                        &child,
+                        &patch_child,
+                        "there is no key to delete".to_string(),
+                    ));
+                }
+                self.type_hints.retain(|p, _| !path_is_within(p, &child));
+                self.freeze_markers
+                    .retain(|p, _| !path_is_within(p, &child));
+            } else {
+                let child = format!("{path}.{raw_key}");
+                match (target.get_mut(raw_key), change) {
+                    (Some(JsonValue::Object(existing)), JsonValue::Object(nested)) => {
+                        self.object(existing, nested, &patch_child, &child)?;
+                        self.take_markers(&patch_child, &child, false)?;
+                    }
+                    _ => {
+                        self.check(&child, &patch_child)?;
+                        // The base's hint for the key itself still checks the new value.
+                        let within = |p: &String| p != &child && path_is_within(p, &child);
+                        self.type_hints.retain(|p, _| !within(p));
+                        self.freeze_markers.retain(|p, _| !within(p));
+                        target.insert(raw_key.clone(), change.clone());
+                        self.take_markers(&patch_child, &child, true)?;
+                        self.take_spans(&patch_child, &child);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check(&self, path: &str, patch_path: &str) -> Result<(), SyamlError> {
+        match protection(path, self.freeze_markers, self.locked_fields) {
+            Some(reason) => Err(self.rejected(path, patch_path, reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects the patch at `patch_path`, which writes `path`, pointing at the
+    /// patch in the overlay.
+    fn rejected(&self, path: &str, patch_path: &str, reason: String) -> SyamlError {
+        let e =
+            SyamlError::OverlayError(format!("cannot patch '{}': {reason}", display_path(path)))
+                .at_path(path);
+        match self.spans.lookup(patch_path) {
+            Some(span) => e.with_span(span.clone()),
+            None => e,
+        }
+    }
+
+    /// Records the overlay spans at `from` and below it as the spans of the
+    /// same places under `to`. Without a span of its own (an item of a flow
+    /// list), `to` gets the span of the nearest patch key above `from`.
+    fn take_spans(&mut self, from: &str, to: &str) {
+        for (path, span) in self.spans.iter() {
+            if path_is_within(path, from) {
+                self.written
+                    .insert(format!("{to}{}", &path[from.len()..]), span.clone());
+            }
+        }
+        if let Some(span) = self.spans.lookup(from) {
+            self.written.insert(to, span.clone());
+        }
+    }
+
+    /// Copies the patch's markers at `from` (and below it, with `subtree`)
+    /// to the same places under `to`. A patch hint must match the hint it
+    /// lands on, so the base's type still checks the patched value.
+    fn take_markers(&mut self, from: &str, to: &str, subtree: bool) -> Result<(), SyamlError> {
+        let moved = |marked: &str| {
+            let found = if subtree {
+                path_is_within(marked, from)
+            } else {
+                marked == from
+            };
+            found.then(|| format!("{to}{}", &marked[from.len()..]))
+        };
+        for (marked, hint) in &self.patch.type_hints {
+            let Some(path) = moved(marked) else {
+                continue;
+            };
+            match self.type_hints.get(&path) {
+                Some(base) if base != hint => {
+                    return Err(self.rejected(
+                        &path,
+                        marked,
+                        format!("its type hint <{hint}> differs from the base hint <{base}>"),
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    self.type_hints.insert(path, hint.clone());
+                }
+            }
+        }
+        for (marked, frozen) in &self.patch.freeze_markers {
+            if let Some(path) = moved(marked) {
+                self.freeze_markers.insert(path, *frozen);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The key a `key+` / `key-` directive applies to.
+fn directive_key(raw: &str, suffix: char) -> Option<&str> {
+    raw.strip_suffix(suffix).filter(|key| !key.is_empty())
+}
+
+fn json_type_name(value: &JsonValue) -> &'static str {
+    if value.is_null() {
+        "null"
+    } else if value.is_boolean() {
+        "boolean"
+    } else if value.is_number() {
+        "number"
+    } else if value.is_string() {
+        "string"
+    } else if value.is_array() {
+        "array"
+    } else {
+        "object"
+    }
+}
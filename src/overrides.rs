@@ -0,0 +1,127 @@
+//! Value overrides: data values replaced at compile time without editing the
+//! document (`super-yaml compile --set service.replicas=5`).
+//!
+//! Overrides are written into the data tree after template expansion and
+//! before `$.` references and derived values resolve, so everything that reads
+//! an overridden value sees the new one. Override values themselves are
+//! literal: they are never evaluated as expressions, interpolations or
+//! references. Type hints and constraints still check them, the type first,
+//! before anything reads the value. Errors about an overridden value name the
+//! override instead of pointing at the source text it replaced.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value as JsonValue;
+
+use crate::ast::FreezeMarkers;
+use crate::error::SyamlError;
+use crate::mini_yaml::parse_scalar;
+use crate::path_is_within;
+use crate::resolve::{query_json_path, set_json_path};
+
+/// Parses a `path=value` override. The value is read like an inline data
+/// value: `5` is an integer, `true` a boolean, `[a, b]` a list and anything
+/// else a string.
+pub fn parse_override(spec: &str) -> Result<(String, JsonValue), SyamlError> {
+    let (path, raw) = spec.split_once('=').ok_or_else(|| {
+        SyamlError::OverrideError(format!("invalid override '{spec}'; expected path=value"))
+    })?;
+    let value = if raw.trim().is_empty() {
+        JsonValue::String(String::new())
+    } else {
+        parse_scalar(raw).map_err(|e| {
+            SyamlError::OverrideError(format!("invalid value for override '{path}': {e}"))
+        })?
+    };
+    Ok((normalize_override_path(path)?, value))
+}
+
+/// Spells an override path as a normalized data path: `service.port` and
+/// `$.service.port` both become `$.service.port`.
+pub fn normalize_override_path(path: &str) -> Result<String, SyamlError> {
+    let path = path.trim();
+    let normalized = if path.starts_with("$.") {
+        path.to_string()
+    } else {
+        format!("$.{path}")
+    };
+    let invalid = || SyamlError::OverrideError(format!("invalid override path '{path}'"));
+    if normalized == "$." || normalized.contains('*') {
+        return Err(invalid());
+    }
+    query_json_path(&JsonValue::Null, &normalized).map_err(|_| invalid())?;
+    Ok(normalized)
+}
+
+/// Writes `overrides` into `data` in order and returns the paths written.
+///
+/// An override may replace any existing value or add a key to an existing
+/// object. It is rejected when it would change a frozen (`^`) key, a value
+/// inside or containing one, or a locked template field.
+pub(crate) fn apply_overrides(
+    data: &mut JsonValue,
+    overrides: &[(String, JsonValue)],
+    freeze_markers: &FreezeMarkers,
+    locked_fields: &BTreeSet<String>,
+) -> Result<BTreeSet<String>, SyamlError> {
+    let mut applied = BTreeSet::new();
+    for (path, value) in overrides {
+        let path = &normalize_override_path(path)?;
+        let rejected = |reason: String| {
+            SyamlError::OverrideError(format!(
+                "cannot override '{}': {reason}",
+                display_path(path)
+            ))
+            .at_path(path)
+        };
+        if let Some(reason) = protection(path, freeze_markers, locked_fields) {
+            return Err(rejected(reason));
+        }
+
+        set_json_path(data, path, value.clone())
+            .map_err(|_| rejected("the path does not exist in data".to_string()))?;
+        applied.insert(path.clone());
+    }
+    Ok(applied)
+}
+
+/// Explains why the value at `path` may not be replaced, if it may not: a
+/// frozen (`^`) key or a locked template field at, inside or containing it.
+pub(crate) fn protection(
+    path: &str,
+    freeze_markers: &FreezeMarkers,
+    locked_fields: &BTreeSet<String>,
+) -> Option<String> {
+    let overlaps = |marked: &str| path_is_within(path, marked) || path_is_within(marked, path);
+    let subject = |marked: &str| {
+        if marked == path {
+            "it".to_string()
+        } else {
+            format!("'{}'", display_path(marked))
+        }
+    };
+
+    if let Some((frozen, _)) = freeze_markers
+        .iter()
+        .find(|(marked, frozen)| **frozen && overlaps(marked))
+    {
+        return Some(format!("{} is frozen with '^'", subject(frozen)));
+    }
+    locked_fields
+        .iter()
+        .find(|locked| overlaps(locked))
+        .map(|locked| format!("{} is a locked template field", subject(locked)))
+}
+
+/// The override as written on the command line: `--set service.replicas=0`.
+pub(crate) fn describe_override(path: &str, value: &JsonValue) -> String {
+    let value = match value {
+        JsonValue::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    format!("--set {}={value}", display_path(path))
+}
+
+pub(crate) fn display_path(path: &str) -> &str {
+    path.strip_prefix("$.").unwrap_or(path)
+}
@@ -7,6 +7,7 @@ use std::path::{Path, PathBuf};
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
 use crate::ast::ImportBinding;
+use crate::quantity::QUANTITY_TYPE_NAMES;
 use crate::schema::parse_field_version_meta;
 use crate::{parse_document, parse_document_or_manifest, SyamlError};
 
@@ -198,6 +199,25 @@ fn render_proto_types(types: &BTreeMap<String, JsonValue>) -> Result<String, Sya
     out.push_str("// Generated by super-yaml (first-pass proto codegen).\n");
     out.push_str("// WARNING: This file is generated. Do not edit manually.\n");
 
+    let body = messages.concat();
+    let imports: Vec<&str> = [
+        ("google.protobuf.Duration", "google/protobuf/duration.proto"),
+        (
+            "google.protobuf.Timestamp",
+            "google/protobuf/timestamp.proto",
+        ),
+    ]
+    .into_iter()
+    .filter(|(ty, _)| body.contains(ty))
+    .map(|(_, path)| path)
+    .collect();
+    if !imports.is_empty() {
+        out.push('\n');
+        for path in imports {
+            out.push_str(&format!("import \"{path}\";\n"));
+        }
+    }
+
     if !skipped.is_empty() {
         out.push('\n');
         for s in &skipped {
@@ -437,6 +457,7 @@ fn proto_type_for_schema(
         Some("integer") => Some("int64".to_string()),
         Some("number") => Some("double".to_string()),
         Some("boolean") => Some("bool".to_string()),
+        Some(q) if QUANTITY_TYPE_NAMES.contains(&q) => Some(proto_quantity_type(q).to_string()),
         Some("array") => {
             // nested array — not standard proto3, skip
             None
@@ -445,15 +466,8 @@ fn proto_type_for_schema(
             // Named type reference
             Some(proto_message_name(named_type))
         }
-        None => {
-            // Could be a named type reference via the "type" key not present
-            // but "properties" exists → inline object → unsupported
-            if obj.get("properties").is_some() {
-                None
-            } else {
-                None
-            }
-        }
+        // No named type reference; inline objects (with "properties") are unsupported.
+        None => None,
     }
 }
 
@@ -467,11 +481,21 @@ fn proto_scalar_type(schema: &JsonValue, _types: &BTreeMap<String, JsonValue>) -
         Some("integer") => "int64".to_string(),
         Some("number") => "double".to_string(),
         Some("boolean") => "bool".to_string(),
+        Some(q) if QUANTITY_TYPE_NAMES.contains(&q) => proto_quantity_type(q).to_string(),
         Some(t) => proto_message_name(t),
         None => "bytes".to_string(),
     }
 }
 
+/// Maps built-in quantity types onto protobuf well-known types.
+fn proto_quantity_type(type_name: &str) -> &'static str {
+    match type_name {
+        "duration" => "google.protobuf.Duration",
+        "timestamp" => "google.protobuf.Timestamp",
+        _ => "uint64",
+    }
+}
+
 fn is_scalar_proto_type(t: &str) -> bool {
     matches!(
         t,
@@ -0,0 +1,506 @@
+//! Provenance records: how each compiled data value was produced.
+//!
+//! While a document compiles, every pipeline stage that rewrites data (import
+//! extraction, template expansion, overlay patches, `$.` references, derived
+//! expressions, enum members, string constructors) records a
+//! [`ProvenanceStep`] at the paths it changed. [`Provenance::explain`] renders the derivation chain of
+//! one output path, following the values each step read.
+//!
+//! [`ProvenanceStep`]: crate::provenance::ProvenanceStep
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+use crate::error::SyamlError;
+use crate::path_is_within;
+use crate::resolve::{is_data_reference, node_var_paths, parent_path_of};
+use crate::sensitive::{self, REDACTED};
+use crate::source_map::{SourceMap, SourceSpan};
+
+/// Pipeline stage that produced a value, in pipeline order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    /// Value copied from an imported namespace (`shared.defaults.port`).
+    Import,
+    /// Object produced by a template invocation (`{{_templates.service}}`).
+    Template,
+    /// Value written by an overlay patch (`service.prod.syaml`).
+    Overlay,
+    /// Value replaced by a compile-time override (`--set path=value`).
+    Override,
+    /// Direct data reference (`$.defaults.port`, `.sibling`).
+    Reference,
+    /// Derived expression (`=replicas * 2`).
+    Expression,
+    /// String interpolation (`"${host}:${port}"`).
+    Interpolation,
+    /// Enum member reference (`Color.red`).
+    EnumMember,
+    /// String constructor applied for a type hint.
+    Constructor,
+}
+
+impl StepKind {
+    fn label(self) -> &'static str {
+        match self {
+            StepKind::Import => "import",
+            StepKind::Template => "template",
+            StepKind::Overlay => "overlay",
+            StepKind::Override => "override",
+            StepKind::Reference => "reference",
+            StepKind::Expression => "expression",
+            StepKind::Interpolation => "interpolation",
+            StepKind::EnumMember => "enum member",
+            StepKind::Constructor => "constructor",
+        }
+    }
+}
+
+/// One rewrite of the value at a data path.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProvenanceStep {
+    /// Stage that rewrote the value.
+    pub kind: StepKind,
+    /// Value before the step: as written, or as left by an earlier stage.
+    pub input: JsonValue,
+    /// Value after the step.
+    pub output: JsonValue,
+    /// What the step read: data paths (`$.a.b`), env symbols (`env.NAME`) and
+    /// import paths (`alias.a.b`).
+    pub reads: Vec<String>,
+}
+
+/// How every data path of a compiled document got its value.
+#[derive(Debug, Clone, Default)]
+pub struct Provenance {
+    file: Option<String>,
+    source_map: SourceMap,
+    steps: BTreeMap<String, Vec<ProvenanceStep>>,
+    data: JsonValue,
+    imports: BTreeMap<String, JsonValue>,
+    import_sources: BTreeMap<String, String>,
+    env: BTreeMap<String, JsonValue>,
+    env_origins: BTreeMap<String, String>,
+    sensitive: BTreeSet<String>,
+}
+
+impl Provenance {
+    /// Creates an empty record for a document loaded from `file`.
+    pub fn new(file: Option<String>) -> Self {
+        Self {
+            file,
+            ..Self::default()
+        }
+    }
+
+    /// Steps recorded at exactly `path` (`$.a.b`), in pipeline order.
+    pub fn steps(&self, path: &str) -> &[ProvenanceStep] {
+        self.steps.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every path with recorded steps.
+    pub(crate) fn recorded(&self) -> impl Iterator<Item = (&str, &[ProvenanceStep])> {
+        self.steps
+            .iter()
+            .map(|(path, steps)| (path.as_str(), steps.as_slice()))
+    }
+
+    /// Moves the record from an overlay file to the base document at `base`,
+    /// a path relative to the overlay: source locations then name the base.
+    pub(crate) fn follow_overlay(&mut self, base: &str) {
+        if let Some(file) = &self.file {
+            let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+            let mut joined = dir.to_path_buf();
+            for part in Path::new(base).components() {
+                match part {
+                    Component::CurDir => {}
+                    Component::ParentDir
+                        if matches!(
+                            joined.components().next_back(),
+                            Some(Component::Normal(_))
+                        ) =>
+                    {
+                        joined.pop();
+                    }
+                    other => joined.push(other),
+                }
+            }
+            self.file = Some(joined.display().to_string());
+        }
+    }
+
+    pub(crate) fn set_source_map(&mut self, source_map: SourceMap) {
+        self.source_map = source_map;
+    }
+
+    pub(crate) fn set_imports(
+        &mut self,
+        imports: &BTreeMap<String, JsonValue>,
+        sources: BTreeMap<String, String>,
+    ) {
+        self.imports = imports.clone();
+        self.import_sources = sources;
+    }
+
+    /// Records resolved env bindings and where each value came from.
+    pub(crate) fn set_env(
+        &mut self,
+        env: &BTreeMap<String, JsonValue>,
+        origins: BTreeMap<String, String>,
+    ) {
+        self.env = env.clone();
+        self.env_origins = origins;
+    }
+
+    /// Marks the data paths, env symbols and import paths holding sensitive
+    /// values: explanations print [`REDACTED`] in their place and in the
+    /// inputs of their steps that carry a value.
+    pub(crate) fn set_sensitive(&mut self, sensitive: &BTreeSet<String>) {
+        self.sensitive = sensitive.clone();
+    }
+
+    /// Keeps the resolved data so explanations can show intermediate values,
+    /// including private keys that are stripped from the output.
+    pub(crate) fn set_data(&mut self, data: &JsonValue) {
+        self.data = data.clone();
+    }
+
+    /// Records a step at every path where `after` differs from `before`.
+    pub(crate) fn record_changes(&mut self, kind: StepKind, before: &JsonValue, after: &JsonValue) {
+        self.record_at("$", kind, before, after);
+    }
+
+    fn record_at(&mut self, path: &str, kind: StepKind, before: &JsonValue, after: &JsonValue) {
+        if before == after {
+            return;
+        }
+        match (before, after) {
+            (JsonValue::Object(b), JsonValue::Object(a))
+                if !(kind == StepKind::Template && template_ref(b).is_some()) =>
+            {
+                for (key, child) in a {
+                    let child_path = format!("{path}.{key}");
+                    match b.get(key) {
+                        Some(old) => self.record_at(&child_path, kind, old, child),
+                        None => self.push(&child_path, kind, &JsonValue::Null, child),
+                    }
+                }
+            }
+            (JsonValue::Array(b), JsonValue::Array(a)) if a.len() == b.len() => {
+                for (i, (old, new)) in b.iter().zip(a).enumerate() {
+                    self.record_at(&format!("{path}[{i}]"), kind, old, new);
+                }
+            }
+            _ => self.push(path, kind, before, after),
+        }
+    }
+
+    fn push(&mut self, path: &str, kind: StepKind, before: &JsonValue, after: &JsonValue) {
+        // The expression stage resolves `=expr` and `${...}` strings alike.
+        let kind = match before {
+            JsonValue::String(raw)
+                if kind == StepKind::Expression && !raw.trim().starts_with('=') =>
+            {
+                StepKind::Interpolation
+            }
+            _ => kind,
+        };
+        let reads = self.step_reads(path, kind, before);
+        self.steps
+            .entry(path.to_string())
+            .or_default()
+            .push(ProvenanceStep {
+                kind,
+                input: before.clone(),
+                output: after.clone(),
+                reads,
+            });
+    }
+
+    fn step_reads(&self, path: &str, kind: StepKind, before: &JsonValue) -> Vec<String> {
+        match (kind, before) {
+            (StepKind::Import, JsonValue::String(raw)) => vec![raw.trim().to_string()],
+            (StepKind::Template, JsonValue::Object(map)) => template_ref(map)
+                .map(|target| vec![self.qualify(target.split('.').map(str::to_string).collect())])
+                .unwrap_or_default(),
+            (StepKind::Reference, JsonValue::String(raw)) if is_data_reference(raw.trim()) => {
+                let raw = raw.trim();
+                let target = if raw.starts_with("$.") {
+                    Some(raw.to_string())
+                } else {
+                    parent_path_of(path).map(|parent| format!("{parent}{raw}"))
+                };
+                target.into_iter().collect()
+            }
+            (StepKind::Expression | StepKind::Interpolation, JsonValue::String(raw)) => {
+                let mut reads = BTreeSet::new();
+                for var_path in node_var_paths(raw) {
+                    reads.insert(self.qualify(var_path));
+                }
+                reads.into_iter().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Spells a variable path as a read: `env.NAME`, `alias.a` or `$.a.b`.
+    fn qualify(&self, var_path: Vec<String>) -> String {
+        let joined = var_path.join(".");
+        if var_path[0] == "env" || self.imports.contains_key(&var_path[0]) {
+            joined
+        } else {
+            format!("$.{joined}")
+        }
+    }
+
+    /// Renders how the value at `path` was computed: each step with its
+    /// source location and result, then, indented, the values it read.
+    ///
+    /// `path` is a data path such as `server.url`, `$.server.url` or
+    /// `items[0].port`. Sensitive values, and values derived from them, are
+    /// shown as [`REDACTED`].
+    pub fn explain(&self, path: &str) -> Result<String, SyamlError> {
+        let path = normalize_path(path);
+        if lookup(&self.data, &path).is_none() {
+            return Err(SyamlError::PathNotFound(display_path(&path).to_string()));
+        }
+        let mut out = String::new();
+        self.explain_read(&path, 0, &mut BTreeSet::new(), &mut out);
+        Ok(out)
+    }
+
+    fn explain_read(
+        &self,
+        read: &str,
+        depth: usize,
+        seen: &mut BTreeSet<String>,
+        out: &mut String,
+    ) {
+        let indent = "  ".repeat(depth);
+        if let Some(name) = read.strip_prefix("env.") {
+            let value = self
+                .env
+                .get(name)
+                .map_or("<unset>".to_string(), |v| self.render_at(read, v));
+            let origin = self
+                .env_origins
+                .get(name)
+                .map(|o| format!(" ({o})"))
+                .unwrap_or_default();
+            out.push_str(&format!("{indent}{read} = {value}{origin}\n"));
+            return;
+        }
+        if !read.starts_with('$') {
+            let mut segments = read.split('.');
+            let alias = segments.next().unwrap_or_default();
+            let value = self
+                .imports
+                .get(alias)
+                .and_then(|root| segments.try_fold(root, |v, s| v.get(s)))
+                .map_or("<missing>".to_string(), |v| self.render_at(read, v));
+            let source = self
+                .import_sources
+                .get(alias)
+                .map(|s| format!(" (import '{alias}' from {s})"))
+                .unwrap_or_default();
+            out.push_str(&format!("{indent}{read} = {value}{source}\n"));
+            return;
+        }
+
+        let value =
+            lookup(&self.data, read).map_or("<missing>".to_string(), |v| self.render_at(read, v));
+        let location = self
+            .source_map
+            .lookup(read)
+            .map(|span| format!(" ({})", self.location(span)))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "{indent}{} = {value}{location}\n",
+            display_path(read)
+        ));
+        if !seen.insert(read.to_string()) {
+            out.push_str(&format!("{indent}  (explained above)\n"));
+            return;
+        }
+
+        let steps = self.steps_for(read);
+        if steps.is_empty() {
+            out.push_str(&format!("{indent}  literal\n"));
+        }
+        for (step, suffix) in steps {
+            let at = &read[..read.len() - suffix.len()];
+            let input = render_input(step, self.is_sensitive(at));
+            out.push_str(&format!(
+                "{indent}  {} {input} -> {}\n",
+                step.kind.label(),
+                self.render_at(at, &step.output)
+            ));
+            for read in &step.reads {
+                self.explain_read(&format!("{read}{suffix}"), depth + 2, seen, out);
+            }
+        }
+    }
+
+    /// Steps that shaped the value at `path`, including those recorded at an
+    /// ancestor (a template or reference that produced the enclosing object),
+    /// each with the suffix leading from that ancestor to `path`.
+    fn steps_for(&self, path: &str) -> Vec<(&ProvenanceStep, String)> {
+        let mut found = Vec::new();
+        let mut current = Some(path.to_string());
+        while let Some(candidate) = current {
+            let suffix = &path[candidate.len()..];
+            for step in self.steps(&candidate) {
+                let copies = matches!(
+                    step.kind,
+                    StepKind::Import | StepKind::Template | StepKind::Reference
+                );
+                if candidate == path || copies {
+                    found.push((step, suffix.to_string()));
+                }
+            }
+            current = parent_path_of(&candidate);
+        }
+        found.sort_by_key(|(step, _)| step.kind);
+        found
+    }
+
+    /// Renders the value at `path`, redacting it when it is sensitive and
+    /// redacting any sensitive value nested inside it.
+    fn render_at(&self, path: &str, value: &JsonValue) -> String {
+        if self.is_sensitive(path) {
+            return render(&JsonValue::String(REDACTED.to_string()));
+        }
+        let nested: BTreeSet<String> = self
+            .sensitive
+            .iter()
+            .filter(|sensitive| path_is_within(sensitive, path))
+            .map(|sensitive| format!("${}", &sensitive[path.len()..]))
+            .collect();
+        if nested.is_empty() {
+            return render(value);
+        }
+        let mut value = value.clone();
+        sensitive::redact(&mut value, &nested);
+        render(&value)
+    }
+
+    fn is_sensitive(&self, path: &str) -> bool {
+        self.sensitive
+            .iter()
+            .any(|sensitive| path_is_within(path, sensitive))
+    }
+
+    fn location(&self, span: &SourceSpan) -> String {
+        let file = match (span.file.as_deref(), self.file.as_deref()) {
+            (Some(file), Some(compiled)) => Some(relative_to_compiled(file, compiled)),
+            (file, compiled) => file.or(compiled).map(str::to_string),
+        };
+        match file {
+            Some(file) => format!("{file}:{}:{}", span.line, span.column),
+            None => format!("line {}, column {}", span.line, span.column),
+        }
+    }
+}
+
+/// Names `file` (an absolute overlay path) the way `compiled` was named:
+/// relative to the working directory unless `compiled` is absolute, so all
+/// locations share one root.
+fn relative_to_compiled(file: &str, compiled: &str) -> String {
+    let file_path = Path::new(file);
+    if !file_path.is_absolute() || Path::new(compiled).is_absolute() {
+        return file.to_string();
+    }
+    let Ok(root) = std::env::current_dir().and_then(fs::canonicalize) else {
+        return file.to_string();
+    };
+    let shared = root
+        .components()
+        .zip(file_path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in shared..root.components().count() {
+        relative.push("..");
+    }
+    relative.extend(file_path.components().skip(shared));
+    relative.display().to_string()
+}
+
+/// The template path of an invocation object (`{{_templates.service}}: ...`).
+fn template_ref(map: &serde_json::Map<String, JsonValue>) -> Option<&str> {
+    map.keys().find_map(|key| {
+        key.strip_prefix("{{")
+            .and_then(|k| k.strip_suffix("}}"))
+            .map(str::trim)
+    })
+}
+
+/// Renders the input of `step`. Inputs that are source text (references and
+/// expressions) always print; those that are values (template variables,
+/// overlay and override values, constructor strings) print as [`REDACTED`]
+/// when the step wrote a sensitive value.
+fn render_input(step: &ProvenanceStep, sensitive: bool) -> String {
+    let carries_value = matches!(
+        step.kind,
+        StepKind::Template | StepKind::Overlay | StepKind::Override | StepKind::Constructor
+    );
+    match (&step.kind, &step.input) {
+        (StepKind::Template, JsonValue::Object(map)) => {
+            let target = template_ref(map).unwrap_or_default();
+            let vars = if sensitive {
+                REDACTED.to_string()
+            } else {
+                map.iter()
+                    .find(|(key, _)| key.starts_with("{{"))
+                    .map(|(_, vars)| render(vars))
+                    .unwrap_or_default()
+            };
+            format!("{{{{{target}}}}} with {vars}")
+        }
+        _ if sensitive && carries_value => render(&JsonValue::String(REDACTED.to_string())),
+        (_, JsonValue::String(raw)) => raw.trim().to_string(),
+        (_, other) => render(other),
+    }
+}
+
+fn render(value: &JsonValue) -> String {
+    value.to_string()
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.trim();
+    if path == "$" || path.starts_with("$.") || path.starts_with("$[") {
+        path.to_string()
+    } else if path.starts_with('[') {
+        format!("${path}")
+    } else {
+        format!("$.{path}")
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    path.strip_prefix("$.").unwrap_or(path)
+}
+
+/// Value at a normalized data path (`$.a.b[0]`).
+fn lookup<'a>(root: &'a JsonValue, path: &str) -> Option<&'a JsonValue> {
+    let rest = path.strip_prefix('$')?;
+    let mut current = root;
+    for segment in rest.split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = segment
+            .split_once('[')
+            .map_or((segment, ""), |(k, i)| (k, i));
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split('[').filter(|s| !s.is_empty()) {
+            current = current.get(index.trim_end_matches(']').parse::<usize>().ok()?)?;
+        }
+    }
+    Some(current)
+}
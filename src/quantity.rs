@@ -0,0 +1,549 @@
+//! Built-in `duration`, `timestamp` and `bytesize` value types.
+//!
+//! In data all three are written as strings (`30s`, `2024-05-01T12:00:00Z`,
+//! `512MiB`); a `bytesize` may also be a plain integer byte count. The types
+//! here parse and print those spellings and are what generated Rust code uses
+//! for fields of these types.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/// Type names of the built-in quantity types, as used in schemas and hints.
+pub const QUANTITY_TYPE_NAMES: [&str; 3] = ["duration", "timestamp", "bytesize"];
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_DAY: i128 = 86_400 * NANOS_PER_SECOND;
+
+/// Duration units, largest first; the order is also the printing order.
+const DURATION_UNITS: [(&str, i128); 7] = [
+    ("d", NANOS_PER_DAY),
+    ("h", 3_600 * NANOS_PER_SECOND),
+    ("m", 60 * NANOS_PER_SECOND),
+    ("s", NANOS_PER_SECOND),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Byte-size units, preferred spelling first within each family.
+const BINARY_BYTE_UNITS: [(&str, i128); 6] = [
+    ("EiB", 1 << 60),
+    ("PiB", 1 << 50),
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+];
+const DECIMAL_BYTE_UNITS: [(&str, i128); 6] = [
+    ("EB", 1_000_000_000_000_000_000),
+    ("PB", 1_000_000_000_000_000),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+/// Signed span of time with nanosecond precision, written like `1h30m` or
+/// `250ms`.
+///
+/// Units are `d`, `h`, `m`, `s`, `ms`, `us` (or `µs`) and `ns`; components
+/// may be fractional (`1.5h`) as long as the total is a whole number of
+/// nanoseconds.
+pub struct Duration {
+    nanos: i128,
+}
+
+impl Duration {
+    /// Duration of `nanos` nanoseconds.
+    pub const fn from_nanos(nanos: i128) -> Self {
+        Self { nanos }
+    }
+
+    /// Total length in nanoseconds.
+    pub const fn as_nanos(&self) -> i128 {
+        self.nanos
+    }
+
+    /// Converts to [`std::time::Duration`]; `None` when negative or too long.
+    pub fn to_std(&self) -> Option<std::time::Duration> {
+        let nanos = u128::try_from(self.nanos).ok()?;
+        let secs = u64::try_from(nanos / NANOS_PER_SECOND as u128).ok()?;
+        Some(std::time::Duration::new(
+            secs,
+            (nanos % NANOS_PER_SECOND as u128) as u32,
+        ))
+    }
+}
+
+impl FromStr for Duration {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| format!("invalid duration '{text}': {reason}");
+        let (negative, body) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if body.is_empty() {
+            return Err(error("expected a number followed by a unit such as '30s'"));
+        }
+        let mut total: i128 = 0;
+        let mut rest = body;
+        while !rest.is_empty() {
+            let (amount, unit, next) = split_component(rest);
+            if amount.is_empty() {
+                return Err(error("expected a number before each unit"));
+            }
+            let scale = DURATION_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit || (unit == "µs" && *name == "us"))
+                .map(|(_, scale)| *scale)
+                .ok_or_else(|| {
+                    error(&format!(
+                        "unknown unit '{unit}' (expected d, h, m, s, ms, us or ns)"
+                    ))
+                })?;
+            let nanos = scaled_whole(amount, scale)
+                .ok_or_else(|| error("not a whole number of nanoseconds"))?;
+            total = total
+                .checked_add(nanos)
+                .ok_or_else(|| error("value is too large"))?;
+            rest = next;
+        }
+        Ok(Self::from_nanos(if negative { -total } else { total }))
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nanos == 0 {
+            return f.write_str("0s");
+        }
+        if self.nanos < 0 {
+            f.write_str("-")?;
+        }
+        let mut rest = self.nanos.unsigned_abs();
+        for (unit, scale) in DURATION_UNITS {
+            let scale = scale as u128;
+            if rest >= scale {
+                write!(f, "{}{unit}", rest / scale)?;
+                rest %= scale;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+/// Instant in time with nanosecond precision, written in RFC 3339 form such
+/// as `2024-05-01T12:00:00Z` or `2024-05-01T14:00:00.5+02:00`.
+///
+/// Timestamps are printed in UTC and lie in the UTC years 0000 to 9999.
+pub struct Timestamp {
+    unix_nanos: i128,
+}
+
+impl Timestamp {
+    /// Instant `unix_nanos` nanoseconds after `1970-01-01T00:00:00Z`.
+    pub const fn from_unix_nanos(unix_nanos: i128) -> Self {
+        Self { unix_nanos }
+    }
+
+    /// Nanoseconds since `1970-01-01T00:00:00Z`.
+    pub const fn unix_nanos(&self) -> i128 {
+        self.unix_nanos
+    }
+
+    /// Whether the instant lies in the RFC 3339 range, years `0000` to `9999`
+    /// in UTC.
+    pub fn is_in_range(&self) -> bool {
+        let first = i128::from(days_from_civil(0, 1, 1)) * NANOS_PER_DAY;
+        let end = i128::from(days_from_civil(10_000, 1, 1)) * NANOS_PER_DAY;
+        (first..end).contains(&self.unix_nanos)
+    }
+
+    /// Converts to [`std::time::SystemTime`]; `None` when out of range.
+    pub fn to_system_time(&self) -> Option<std::time::SystemTime> {
+        let offset = Duration::from_nanos(self.unix_nanos.abs()).to_std()?;
+        if self.unix_nanos >= 0 {
+            std::time::UNIX_EPOCH.checked_add(offset)
+        } else {
+            std::time::UNIX_EPOCH.checked_sub(offset)
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!("invalid timestamp '{text}': expected RFC 3339 such as '2024-05-01T12:00:00Z'")
+        };
+        let bytes = text.as_bytes();
+        let digits = |range: std::ops::Range<usize>| -> Option<i64> {
+            let part = bytes.get(range)?;
+            if !part.iter().all(u8::is_ascii_digit) {
+                return None;
+            }
+            std::str::from_utf8(part).ok()?.parse().ok()
+        };
+        let separators_ok = bytes.len() >= 20
+            && bytes[4] == b'-'
+            && bytes[7] == b'-'
+            && matches!(bytes[10], b'T' | b't' | b' ')
+            && bytes[13] == b':'
+            && bytes[16] == b':';
+        if !separators_ok {
+            return Err(error());
+        }
+        let fields = (
+            digits(0..4),
+            digits(5..7),
+            digits(8..10),
+            digits(11..13),
+            digits(14..16),
+            digits(17..19),
+        );
+        let (Some(year), Some(month), Some(day), Some(hour), Some(minute), Some(second)) = fields
+        else {
+            return Err(error());
+        };
+        if !(1..=12).contains(&month)
+            || day < 1
+            || day > days_in_month(year, month)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(error());
+        }
+
+        let mut pos = 19;
+        let mut fraction_nanos: i128 = 0;
+        if bytes[pos] == b'.' {
+            let start = pos + 1;
+            let end = start
+                + bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+            if end == start || end - start > 9 {
+                return Err(error());
+            }
+            let raw: i128 = text[start..end].parse().map_err(|_| error())?;
+            fraction_nanos = raw * 10i128.pow((9 - (end - start)) as u32);
+            pos = end;
+        }
+        let offset_seconds: i64 = match &bytes[pos..] {
+            [b'Z' | b'z'] => 0,
+            [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+                let (Some(h), Some(m)) = (digits(pos + 1..pos + 3), digits(pos + 4..pos + 6))
+                else {
+                    return Err(error());
+                };
+                if h > 23 || m > 59 {
+                    return Err(error());
+                }
+                let offset = h * 3_600 + m * 60;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return Err(error()),
+        };
+
+        let seconds =
+            days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second
+                - offset_seconds;
+        let timestamp =
+            Self::from_unix_nanos(i128::from(seconds) * NANOS_PER_SECOND + fraction_nanos);
+        if !timestamp.is_in_range() {
+            return Err(format!(
+                "timestamp '{text}' is out of range: UTC years run from 0000 to 9999"
+            ));
+        }
+        Ok(timestamp)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.unix_nanos.div_euclid(NANOS_PER_SECOND);
+        let nanos = self.unix_nanos.rem_euclid(NANOS_PER_SECOND);
+        let days = seconds.div_euclid(86_400);
+        let time = seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days as i64);
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            time / 3_600,
+            time % 3_600 / 60,
+            time % 60
+        )?;
+        if nanos != 0 {
+            let fraction = format!("{nanos:09}");
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        f.write_str("Z")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "JsonValue", into = "String")]
+/// Amount of memory or storage, written like `512MiB`, `1.5GB` or `100B`,
+/// or as a plain integer number of bytes.
+///
+/// Binary units are `KiB`, `MiB`, `GiB`, `TiB`, `PiB` and `EiB`; decimal
+/// units are `KB` (or `kB`), `MB`, `GB`, `TB`, `PB` and `EB`.
+pub struct ByteSize {
+    bytes: i128,
+}
+
+impl ByteSize {
+    /// Size of `bytes` bytes.
+    pub const fn from_bytes(bytes: i128) -> Self {
+        Self { bytes }
+    }
+
+    /// Size in bytes.
+    pub const fn bytes(&self) -> i128 {
+        self.bytes
+    }
+
+    /// Size in bytes as `u64`; `None` when negative or too large.
+    pub fn as_u64(&self) -> Option<u64> {
+        u64::try_from(self.bytes).ok()
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = |reason: &str| format!("invalid byte size '{text}': {reason}");
+        let (negative, body) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let (amount, unit, rest) = split_component(body);
+        if amount.is_empty() || !rest.is_empty() {
+            return Err(error(
+                "expected a number followed by a unit such as '512MiB'",
+            ));
+        }
+        let scale = match unit {
+            "B" => 1,
+            "kB" => 1_000,
+            _ => BINARY_BYTE_UNITS
+                .iter()
+                .chain(DECIMAL_BYTE_UNITS.iter())
+                .find(|(name, _)| *name == unit)
+                .map(|(_, scale)| *scale)
+                .ok_or_else(|| {
+                    error(&format!(
+                        "unknown unit '{unit}' (expected B, KB, MB, GB, TB, PB, EB or KiB ... EiB)"
+                    ))
+                })?,
+        };
+        let bytes =
+            scaled_whole(amount, scale).ok_or_else(|| error("not a whole number of bytes"))?;
+        Ok(Self::from_bytes(if negative { -bytes } else { bytes }))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exact = BINARY_BYTE_UNITS
+            .iter()
+            .chain(DECIMAL_BYTE_UNITS.iter())
+            .find(|(_, scale)| self.bytes != 0 && self.bytes % scale == 0);
+        match exact {
+            Some((unit, scale)) => write!(f, "{}{unit}", self.bytes / scale),
+            None => write!(f, "{}B", self.bytes),
+        }
+    }
+}
+
+impl TryFrom<JsonValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        match &value {
+            JsonValue::String(text) => text.parse(),
+            JsonValue::Number(n) => n
+                .as_u64()
+                .map(|bytes| Self::from_bytes(bytes.into()))
+                .ok_or_else(|| format!("invalid byte size {n}: expected a non-negative integer")),
+            other => Err(format!(
+                "invalid byte size {other}: expected a string such as '512MiB' or an integer"
+            )),
+        }
+    }
+}
+
+macro_rules! string_conversions {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<String> for $ty {
+            type Error = String;
+
+            fn try_from(text: String) -> Result<Self, Self::Error> {
+                text.parse()
+            }
+        }
+
+        impl From<$ty> for String {
+            fn from(value: $ty) -> Self {
+                value.to_string()
+            }
+        }
+    )*};
+}
+
+string_conversions!(Duration, Timestamp, ByteSize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Value of one of the built-in quantity types.
+pub enum Quantity {
+    /// A `duration` value.
+    Duration(Duration),
+    /// A `timestamp` value.
+    Timestamp(Timestamp),
+    /// A `bytesize` value.
+    ByteSize(ByteSize),
+}
+
+impl Quantity {
+    /// Parses `value` as the quantity type `type_name` (`duration`,
+    /// `timestamp` or `bytesize`).
+    ///
+    /// Returns `None` when `type_name` is not a quantity type.
+    pub fn from_json_as(type_name: &str, value: &JsonValue) -> Option<Result<Quantity, String>> {
+        let text = value.as_str();
+        let not_string = || format!("expected {type_name} string, found {value}");
+        Some(match type_name {
+            "duration" => text
+                .ok_or_else(not_string)
+                .and_then(str::parse)
+                .map(Quantity::Duration),
+            "timestamp" => text
+                .ok_or_else(not_string)
+                .and_then(str::parse)
+                .map(Quantity::Timestamp),
+            "bytesize" => ByteSize::try_from(value.clone()).map(Quantity::ByteSize),
+            _ => return None,
+        })
+    }
+
+    /// Parses a string as whichever quantity type it spells, if any.
+    pub fn parse(text: &str) -> Option<Quantity> {
+        if let Ok(d) = text.parse() {
+            Some(Quantity::Duration(d))
+        } else if let Ok(b) = text.parse() {
+            Some(Quantity::ByteSize(b))
+        } else {
+            text.parse().ok().map(Quantity::Timestamp)
+        }
+    }
+
+    /// Schema type name of this value.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Quantity::Duration(_) => "duration",
+            Quantity::Timestamp(_) => "timestamp",
+            Quantity::ByteSize(_) => "bytesize",
+        }
+    }
+
+    /// Orders two values of the same type; `None` for different types.
+    pub fn compare(&self, other: &Quantity) -> Option<Ordering> {
+        match (self, other) {
+            (Quantity::Duration(a), Quantity::Duration(b)) => Some(a.cmp(b)),
+            (Quantity::Timestamp(a), Quantity::Timestamp(b)) => Some(a.cmp(b)),
+            (Quantity::ByteSize(a), Quantity::ByteSize(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
+    /// Canonical JSON spelling (a string).
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::String(self.to_string())
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantity::Duration(d) => d.fmt(f),
+            Quantity::Timestamp(t) => t.fmt(f),
+            Quantity::ByteSize(b) => b.fmt(f),
+        }
+    }
+}
+
+/// Splits `"1.5h30m"` into `("1.5", "h", "30m")`.
+fn split_component(text: &str) -> (&str, &str, &str) {
+    let is_amount = |c: char| c.is_ascii_digit() || c == '.';
+    let amount_end = text.find(|c| !is_amount(c)).unwrap_or(text.len());
+    let (amount, rest) = text.split_at(amount_end);
+    let unit_end = rest.find(is_amount).unwrap_or(rest.len());
+    let (unit, rest) = rest.split_at(unit_end);
+    (amount, unit, rest)
+}
+
+/// `amount * scale` when that is a whole number.
+fn scaled_whole(amount: &str, scale: i128) -> Option<i128> {
+    let amount = Decimal::from_str_exact(amount).ok()?;
+    let scaled = amount
+        .checked_mul(Decimal::try_from_i128_with_scale(scale, 0).ok()?)?
+        .normalize();
+    (scaled.scale() == 0).then(|| scaled.mantissa())
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,47 +1,77 @@
 //! Environment and expression resolution for parsed data.
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
 use regex::Regex;
 use serde_json::Value as JsonValue;
 
-use crate::ast::{EnvBinding, Meta, SchemaDoc};
+use crate::ast::{EnvBinding, EnvParse, EnvSource, Meta, PureFunctionDef, SchemaDoc};
 use crate::error::SyamlError;
 use crate::expr::eval::{evaluate, EvalContext, EvalError};
 use crate::expr::parse_expression;
 use crate::mini_yaml;
-use crate::schema::{resolve_type_schema, validate_json_against_schema_with_types};
+use crate::quantity::QUANTITY_TYPE_NAMES;
+use crate::schema::{
+    collect_var_paths, normalize_schema_node, resolve_type_schema,
+    validate_json_against_schema_concealed, validate_json_against_schema_with_types,
+};
 
-const MAX_DERIVED_EXPRESSIONS: usize = 1024;
 const MAX_INTERPOLATIONS_PER_STRING: usize = 128;
 const MAX_EXPRESSION_SOURCE_LEN: usize = 4096;
+const MAX_QUANTITY_SCHEMA_DEPTH: usize = 256;
 
 /// Environment lookup abstraction used during compilation.
 pub trait EnvProvider {
     /// Returns the environment value for `key`, if available.
     fn get(&self, key: &str) -> Option<String>;
+
+    /// Returns the contents of the file at `path` for a `from: file`,
+    /// `from: literal_file` or `from: dotenv` binding, if available.
+    ///
+    /// Relative binding paths are joined to the document's directory before
+    /// this is called. File access is denied unless a provider allows it.
+    fn read_file(&self, path: &Path) -> Option<String> {
+        let _ = path;
+        None
+    }
 }
 
-/// [`EnvProvider`] implementation backed by process environment variables.
+/// [`EnvProvider`] implementation backed by process environment variables
+/// and the filesystem.
 pub struct ProcessEnvProvider;
 
 impl EnvProvider for ProcessEnvProvider {
     fn get(&self, key: &str) -> Option<String> {
         std::env::var(key).ok()
     }
+
+    fn read_file(&self, path: &Path) -> Option<String> {
+        std::fs::read_to_string(path).ok()
+    }
 }
 
 #[derive(Debug, Clone)]
 /// [`EnvProvider`] implementation backed by a caller-provided map.
 pub struct MapEnvProvider {
     values: HashMap<String, String>,
+    files: HashMap<PathBuf, String>,
 }
 
 impl MapEnvProvider {
     /// Creates a new map-backed provider.
     pub fn new(values: HashMap<String, String>) -> Self {
-        Self { values }
+        Self {
+            values,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Serves `contents` as the file at `path` to file and dotenv bindings.
+    pub fn with_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.insert(path.into(), contents.into());
+        self
     }
 }
 
@@ -49,12 +79,33 @@ impl EnvProvider for MapEnvProvider {
     fn get(&self, key: &str) -> Option<String> {
         self.values.get(key).cloned()
     }
+
+    fn read_file(&self, path: &Path) -> Option<String> {
+        self.files.get(path).cloned()
+    }
 }
 
 /// Resolves all `meta.env` bindings into concrete JSON values.
+///
+/// Relative file paths in bindings resolve against the current directory and
+/// typed bindings may only use built-in types; use [`resolve_env_bindings_in`]
+/// for a document's own directory and schema types.
 pub fn resolve_env_bindings(
     meta: Option<&Meta>,
     env_provider: &dyn EnvProvider,
+) -> Result<BTreeMap<String, JsonValue>, SyamlError> {
+    resolve_env_bindings_in(meta, env_provider, Path::new(""), &BTreeMap::new())
+}
+
+/// Resolves all `meta.env` bindings, reading relative file paths from
+/// `base_dir` and checking typed bindings against built-in and named `types`.
+///
+/// Errors name the binding but never include the value that was read.
+pub fn resolve_env_bindings_in(
+    meta: Option<&Meta>,
+    env_provider: &dyn EnvProvider,
+    base_dir: &Path,
+    types: &BTreeMap<String, JsonValue>,
 ) -> Result<BTreeMap<String, JsonValue>, SyamlError> {
     let mut out = BTreeMap::new();
     let Some(meta) = meta else {
@@ -62,7 +113,7 @@ pub fn resolve_env_bindings(
     };
 
     for (symbol, binding) in &meta.env {
-        let value = resolve_one_binding(symbol, binding, env_provider)?;
+        let value = resolve_one_binding(symbol, binding, env_provider, base_dir, types)?;
         out.insert(symbol.clone(), value);
     }
 
@@ -73,24 +124,211 @@ fn resolve_one_binding(
     symbol: &str,
     binding: &EnvBinding,
     env_provider: &dyn EnvProvider,
+    base_dir: &Path,
+    types: &BTreeMap<String, JsonValue>,
 ) -> Result<JsonValue, SyamlError> {
-    if let Some(raw) = env_provider.get(&binding.key) {
-        parse_env_scalar(&raw).map_err(|_e| {
-            SyamlError::EnvError(format!(
-                "failed to parse env '{}': invalid scalar value",
-                binding.key
-            ))
-        })
+    let subject = || match &binding.source {
+        EnvSource::Env => format!("env '{}'", binding.key),
+        _ => binding.describe_source(),
+    };
+    if let Some(type_name) = binding.type_ref.as_ref().and_then(schema_type_name) {
+        if type_name != "union"
+            && !crate::contracts::is_builtin_type(type_name)
+            && !types.contains_key(type_name)
+        {
+            return Err(SyamlError::EnvError(format!(
+                "meta.env.{symbol}: unknown type '{type_name}'"
+            )));
+        }
+    }
+
+    let value = if let Some(raw) = read_binding(binding, env_provider, base_dir)? {
+        decode_binding(binding, &raw, types).map_err(|reason| {
+            SyamlError::EnvError(format!("failed to parse {}: {reason}", subject()))
+        })?
     } else if let Some(default) = &binding.default {
-        Ok(default.clone())
+        default.clone()
     } else if binding.required {
-        Err(SyamlError::EnvError(format!(
-            "missing required environment variable '{}' for symbol '{}'",
-            binding.key, symbol
-        )))
+        return Err(SyamlError::EnvError(match &binding.source {
+            EnvSource::Env => format!(
+                "missing required environment variable '{}' for symbol '{}'",
+                binding.key, symbol
+            ),
+            _ => format!(
+                "missing required {} for symbol '{}'",
+                binding.describe_source(),
+                symbol
+            ),
+        }));
     } else {
-        Ok(JsonValue::Null)
+        return Ok(JsonValue::Null);
+    };
+
+    if let Some(type_ref) = &binding.type_ref {
+        let schema = normalize_schema_node(type_ref.clone());
+        let path = format!("$.{symbol}");
+        let validate = if binding.sensitive || crate::sensitive::schema_is_sensitive(&schema, types)
+        {
+            validate_json_against_schema_concealed
+        } else {
+            validate_json_against_schema_with_types
+        };
+        validate(&value, &schema, &path, types).map_err(|e| {
+            let reason = match e.inner() {
+                SyamlError::SchemaError(message) => message.clone(),
+                other => other.to_string(),
+            };
+            SyamlError::EnvError(format!(
+                "{} for symbol '{}' does not match its type: {reason}",
+                subject(),
+                symbol
+            ))
+        })?;
     }
+    Ok(value)
+}
+
+/// Turns the raw text of a binding into a value according to its parse mode.
+fn decode_binding(
+    binding: &EnvBinding,
+    raw: &str,
+    types: &BTreeMap<String, JsonValue>,
+) -> Result<JsonValue, &'static str> {
+    let base = binding
+        .type_ref
+        .as_ref()
+        .and_then(|type_ref| base_schema(type_ref, types));
+    let parse = binding.parse.unwrap_or(match binding.source {
+        EnvSource::LiteralFile | EnvSource::File { trim: false } => EnvParse::String,
+        _ if base.and_then(schema_type_name) == Some("string") => EnvParse::String,
+        _ => EnvParse::Scalar,
+    });
+    match parse {
+        EnvParse::Scalar => parse_env_scalar(raw).map_err(|_| "invalid scalar value"),
+        EnvParse::String => Ok(JsonValue::String(raw.to_string())),
+        EnvParse::Json => serde_json::from_str(raw).map_err(|_| "invalid JSON"),
+        EnvParse::Csv => {
+            let strings = base
+                .filter(|base| schema_type_name(base) == Some("array"))
+                .and_then(|base| base.get("items"))
+                .and_then(|items| base_schema(items, types))
+                .and_then(schema_type_name)
+                == Some("string");
+            if raw.trim().is_empty() {
+                return Ok(JsonValue::Array(Vec::new()));
+            }
+            raw.split(',')
+                .map(|item| {
+                    if strings {
+                        Ok(JsonValue::String(item.trim().to_string()))
+                    } else {
+                        parse_env_scalar(item).map_err(|_| "invalid scalar value in list")
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(JsonValue::Array)
+        }
+    }
+}
+
+/// Type name of a type reference: the name itself or an inline schema's
+/// `type`.
+fn schema_type_name(type_ref: &JsonValue) -> Option<&str> {
+    match type_ref {
+        JsonValue::String(name) => Some(name),
+        other => other.get("type").and_then(JsonValue::as_str),
+    }
+}
+
+/// Follows named type references until a built-in type or inline schema.
+fn base_schema<'a>(
+    type_ref: &'a JsonValue,
+    types: &'a BTreeMap<String, JsonValue>,
+) -> Option<&'a JsonValue> {
+    let mut current = type_ref;
+    for _ in 0..=types.len() {
+        match schema_type_name(current).and_then(|name| types.get(name)) {
+            Some(next) => current = next,
+            None => return Some(current),
+        }
+    }
+    None
+}
+
+/// Raw text of a binding's value, or `None` when its variable, file or
+/// dotenv entry is unavailable.
+pub(crate) fn read_binding(
+    binding: &EnvBinding,
+    env_provider: &dyn EnvProvider,
+    base_dir: &Path,
+) -> Result<Option<String>, SyamlError> {
+    Ok(match &binding.source {
+        EnvSource::Env => env_provider.get(&binding.key),
+        EnvSource::File { trim: true } => env_provider
+            .read_file(&base_dir.join(&binding.key))
+            .map(|contents| contents.trim().to_string()),
+        EnvSource::File { trim: false } | EnvSource::LiteralFile => {
+            env_provider.read_file(&base_dir.join(&binding.key))
+        }
+        EnvSource::Dotenv { file } => match env_provider.read_file(&base_dir.join(file)) {
+            Some(contents) => parse_dotenv(&contents)
+                .map_err(|e| SyamlError::EnvError(format!("dotenv file '{file}': {e}")))?
+                .remove(&binding.key),
+            None => None,
+        },
+    })
+}
+
+/// Parses `KEY=value` lines. Blank lines and `#` comments are skipped, an
+/// `export ` prefix is allowed, `"..."` values take `\n`, `\"` and `\\`
+/// escapes, `'...'` values are literal and unquoted values end at ` #`.
+fn parse_dotenv(contents: &str) -> Result<HashMap<String, String>, String> {
+    let mut out = HashMap::new();
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let invalid = || format!("line {}: expected KEY=value", idx + 1);
+        let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let mut out = String::new();
+            let mut chars = quoted.chars();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => out.push('\n'),
+                        Some(other) => out.push(other),
+                        None => break,
+                    },
+                    Some(c) => out.push(c),
+                    None => return Err(format!("line {}: unterminated quoted value", idx + 1)),
+                }
+            }
+            out
+        } else if let Some(quoted) = value.strip_prefix('\'') {
+            quoted
+                .split_once('\'')
+                .ok_or_else(|| format!("line {}: unterminated quoted value", idx + 1))?
+                .0
+                .to_string()
+        } else {
+            value
+                .split_once(" #")
+                .map_or(value, |(value, _)| value)
+                .trim_end()
+                .to_string()
+        };
+        out.insert(key.to_string(), value);
+    }
+    Ok(out)
 }
 
 fn parse_env_scalar(raw: &str) -> Result<JsonValue, SyamlError> {
@@ -106,68 +344,290 @@ pub fn resolve_expressions(
     env: &BTreeMap<String, JsonValue>,
 ) -> Result<(), SyamlError> {
     let imports = BTreeMap::new();
-    resolve_expressions_with_imports(data, env, &imports)
+    resolve_expressions_with_imports(data, env, &imports, &BTreeMap::new())
 }
 
 /// Resolves derived expressions/interpolations with imported namespaces available
-/// to expression references (for example `shared.defaults.port`).
+/// to expression references (for example `shared.defaults.port`) and `functions`
+/// callable by name.
+///
+/// Every node is evaluated once, after the nodes it reads. A dependency cycle is
+/// reported as the chain of paths that forms it.
 pub fn resolve_expressions_with_imports(
     data: &mut JsonValue,
     env: &BTreeMap<String, JsonValue>,
     imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+) -> Result<(), SyamlError> {
+    resolve_expressions_except(
+        data,
+        env,
+        imports,
+        functions,
+        &BTreeSet::new(),
+        &BTreeMap::new(),
+    )
+}
+
+/// [`resolve_expressions_with_imports`], leaving strings at or below the
+/// `literals` paths (value overrides) as written. Values at the `quantities`
+/// paths are read as the named quantity type (see [`quantity_paths`]).
+pub(crate) fn resolve_expressions_except(
+    data: &mut JsonValue,
+    env: &BTreeMap<String, JsonValue>,
+    imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+    literals: &BTreeSet<String>,
+    quantities: &BTreeMap<String, &'static str>,
 ) -> Result<(), SyamlError> {
     let mut expr_nodes = Vec::new();
     collect_expression_nodes(data, "$", &mut expr_nodes);
+    expr_nodes.retain(|node| !is_literal_path(&node.path, literals));
 
     if expr_nodes.is_empty() {
         return Ok(());
     }
 
-    if expr_nodes.len() > MAX_DERIVED_EXPRESSIONS {
-        return Err(SyamlError::ExpressionError(format!(
-            "too many derived expressions/interpolations: {} (max {MAX_DERIVED_EXPRESSIONS})",
-            expr_nodes.len()
-        )));
+    let deps = expression_dependencies(&expr_nodes, imports);
+    let order = match topological_order(&deps) {
+        Ok(order) => order,
+        Err(cycle) => {
+            let chain: Vec<&str> = cycle
+                .iter()
+                .map(|&i| display_path(&expr_nodes[i].path))
+                .collect();
+            return Err(SyamlError::CycleError(format!(
+                "derived values form a dependency cycle: {}",
+                chain.join(" -> ")
+            ))
+            .at_path(&expr_nodes[cycle[0]].path));
+        }
+    };
+
+    // Dependencies are evaluated first, so nothing a node reads is pending.
+    let pending = HashSet::new();
+    for i in order {
+        let node = &expr_nodes[i];
+        match eval_node(node, data, env, imports, functions, quantities, &pending) {
+            Ok(value) => set_json_path(data, &node.path, value)?,
+            Err(EvalError::Unresolved(dep)) => {
+                return Err(SyamlError::CycleError(format!(
+                    "derived value depends on unresolved '{}'",
+                    display_path(&dep)
+                ))
+                .at_path(&node.path))
+            }
+            Err(EvalError::Fatal(err)) => return Err(err.at_path(&node.path)),
+        }
     }
 
-    let mut unresolved: HashSet<String> = expr_nodes.iter().map(|n| n.path.clone()).collect();
-    let max_passes = expr_nodes.len() + 1;
+    Ok(())
+}
 
-    for _ in 0..max_passes {
-        if unresolved.is_empty() {
-            return Ok(());
+/// Data paths whose schema, reached through the type hints, is `duration`,
+/// `timestamp` or `bytesize`, with that type name. Expressions read these
+/// values as quantities rather than strings.
+pub(crate) fn quantity_paths(
+    data: &JsonValue,
+    type_hints: &BTreeMap<String, String>,
+    types: &BTreeMap<String, JsonValue>,
+) -> BTreeMap<String, &'static str> {
+    let mut out = BTreeMap::new();
+    for (path, type_name) in type_hints {
+        if let Some(value) = get_json_path(data, path) {
+            let schema = JsonValue::String(type_name.clone());
+            collect_quantity_paths(&schema, value, path, types, 0, &mut out);
         }
+    }
+    out
+}
 
-        let mut progress = false;
-        for node in &expr_nodes {
-            if !unresolved.contains(&node.path) {
-                continue;
+fn collect_quantity_paths(
+    schema: &JsonValue,
+    value: &JsonValue,
+    path: &str,
+    types: &BTreeMap<String, JsonValue>,
+    depth: usize,
+    out: &mut BTreeMap<String, &'static str>,
+) {
+    if depth > MAX_QUANTITY_SCHEMA_DEPTH {
+        return;
+    }
+    let type_name = match schema {
+        JsonValue::String(name) => Some(name.as_str()),
+        _ => schema.get("type").and_then(JsonValue::as_str),
+    };
+    if let Some(type_name) = type_name {
+        if let Some(kind) = QUANTITY_TYPE_NAMES.iter().find(|name| **name == type_name) {
+            out.insert(path.to_string(), *kind);
+            return;
+        }
+        if let Some(named) = types.get(type_name) {
+            collect_quantity_paths(named, value, path, types, depth + 1, out);
+        }
+    }
+    let Some(schema_obj) = schema.as_object() else {
+        return;
+    };
+
+    let properties = schema_obj.get("properties").and_then(JsonValue::as_object);
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                let child_schema = properties
+                    .and_then(|props| props.get(key))
+                    .or_else(|| schema_obj.get("values"));
+                if let Some(child_schema) = child_schema {
+                    let child_path = format!("{path}.{key}");
+                    collect_quantity_paths(child_schema, child, &child_path, types, depth + 1, out);
+                }
             }
+        }
+        JsonValue::Array(items) => {
+            if let Some(item_schema) = schema_obj.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    let item_path = format!("{path}[{i}]");
+                    collect_quantity_paths(item_schema, item, &item_path, types, depth + 1, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Paths of the derived values in `data`, outside `literals`, each after the
+/// derived values it reads. Empty when they form a cycle, which
+/// [`resolve_expressions_except`] reports.
+pub(crate) fn derived_value_order(
+    data: &JsonValue,
+    imports: &BTreeMap<String, JsonValue>,
+    literals: &BTreeSet<String>,
+) -> Vec<String> {
+    let mut expr_nodes = Vec::new();
+    collect_expression_nodes(data, "$", &mut expr_nodes);
+    expr_nodes.retain(|node| !is_literal_path(&node.path, literals));
+    let deps = expression_dependencies(&expr_nodes, imports);
+    topological_order(&deps)
+        .map(|order| {
+            order
+                .into_iter()
+                .map(|i| std::mem::take(&mut expr_nodes[i].path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-            match eval_node(node, data, env, imports, &unresolved) {
-                Ok(value) => {
-                    set_json_path(data, &node.path, value)?;
-                    unresolved.remove(&node.path);
-                    progress = true;
+/// For each node, the indexes of the nodes it reads: a node at the referenced
+/// path, above it (a derived value containing it), or below it (part of the
+/// referenced value).
+fn expression_dependencies(
+    nodes: &[ExpressionNode],
+    imports: &BTreeMap<String, JsonValue>,
+) -> Vec<Vec<usize>> {
+    let by_path: BTreeMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.path.as_str(), i))
+        .collect();
+
+    nodes
+        .iter()
+        .map(|node| {
+            let mut deps = BTreeSet::new();
+            for var_path in node_var_paths(&node.raw) {
+                if var_path[0] == "env" || imports.contains_key(&var_path[0]) {
+                    continue;
                 }
-                Err(EvalError::Unresolved(_dep)) => {}
-                Err(EvalError::Fatal(err)) => return Err(err),
+                let path = format!("$.{}", var_path.join("."));
+                let ancestors = path
+                    .char_indices()
+                    .filter(|(i, c)| *i > 1 && matches!(c, '.' | '['))
+                    .map(|(i, _)| &path[..i]);
+                for candidate in ancestors.chain(std::iter::once(path.as_str())) {
+                    deps.extend(by_path.get(candidate));
+                }
+                let descendants = by_path
+                    .range(path.as_str()..)
+                    .take_while(|(p, _)| p.starts_with(path.as_str()))
+                    .filter(|(p, _)| matches!(p.as_bytes().get(path.len()), Some(b'.' | b'[')));
+                deps.extend(descendants.map(|(_, i)| i));
             }
+            deps.into_iter().collect()
+        })
+        .collect()
+}
+
+/// Variable paths read by a derived value or by the segments of an
+/// interpolated string. Sources that do not parse read nothing; evaluation
+/// reports them.
+pub(crate) fn node_var_paths(raw: &str) -> Vec<Vec<String>> {
+    let raw = raw.trim();
+    let sources: Vec<&str> = match raw.strip_prefix('=') {
+        Some(source) => vec![source],
+        None => interpolation_regex()
+            .captures_iter(raw)
+            .filter_map(|caps| caps.get(1).map(|m| m.as_str()))
+            .collect(),
+    };
+    let mut paths = Vec::new();
+    for source in sources {
+        if let Ok(expr) = parse_expression(source.trim()) {
+            collect_var_paths(&expr, &mut paths);
         }
+    }
+    paths.retain(|path| !path.is_empty());
+    paths
+}
 
-        if !progress {
-            let mut paths: Vec<String> = unresolved.into_iter().collect();
-            paths.sort();
-            return Err(SyamlError::CycleError(format!(
-                "could not resolve derived values; possible dependency cycle among: {}",
-                paths.join(", ")
-            )));
+/// Orders nodes so that each comes after its dependencies, keeping document
+/// order otherwise. Fails with one dependency cycle, first node repeated at
+/// the end.
+fn topological_order(deps: &[Vec<usize>]) -> Result<Vec<usize>, Vec<usize>> {
+    let mut dependents = vec![Vec::new(); deps.len()];
+    let mut waiting: Vec<usize> = deps.iter().map(Vec::len).collect();
+    for (i, node_deps) in deps.iter().enumerate() {
+        for &dep in node_deps {
+            dependents[dep].push(i);
         }
     }
 
-    Err(SyamlError::CycleError(
-        "expression resolution exceeded max passes".to_string(),
-    ))
+    let mut ready: BTreeSet<usize> = (0..deps.len()).filter(|&i| waiting[i] == 0).collect();
+    let mut order = Vec::with_capacity(deps.len());
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for &dependent in &dependents[i] {
+            waiting[dependent] -= 1;
+            if waiting[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+    if order.len() == deps.len() {
+        return Ok(order);
+    }
+
+    // Every node left waits on another node left, so following those edges
+    // from any of them must come back around.
+    let mut chain = Vec::new();
+    let mut seen = HashMap::new();
+    let mut current = (0..deps.len())
+        .find(|&i| waiting[i] > 0)
+        .expect("unordered node");
+    while let std::collections::hash_map::Entry::Vacant(entry) = seen.entry(current) {
+        entry.insert(chain.len());
+        chain.push(current);
+        current = *deps[current]
+            .iter()
+            .find(|&&dep| waiting[dep] > 0)
+            .expect("waiting node has a waiting dependency");
+    }
+    let mut cycle = chain.split_off(seen[&current]);
+    cycle.push(current);
+    Err(cycle)
+}
+
+fn display_path(path: &str) -> &str {
+    path.strip_prefix("$.").unwrap_or(path)
 }
 
 #[derive(Debug, Clone)]
@@ -208,6 +668,8 @@ fn eval_node(
     data: &JsonValue,
     env: &BTreeMap<String, JsonValue>,
     imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+    quantities: &BTreeMap<String, &'static str>,
     unresolved: &HashSet<String>,
 ) -> Result<JsonValue, EvalError> {
     let raw = node.raw.trim();
@@ -223,11 +685,13 @@ fn eval_node(
             current_value: None,
             current_scope: None,
             named_scopes: std::collections::BTreeMap::new(),
+            functions,
+            quantities,
         };
         return evaluate(&parsed, &ctx);
     }
 
-    evaluate_interpolation(raw, data, env, imports, unresolved)
+    evaluate_interpolation(raw, data, env, imports, functions, quantities, unresolved)
 }
 
 fn evaluate_interpolation(
@@ -235,6 +699,8 @@ fn evaluate_interpolation(
     data: &JsonValue,
     env: &BTreeMap<String, JsonValue>,
     imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+    quantities: &BTreeMap<String, &'static str>,
     unresolved: &HashSet<String>,
 ) -> Result<JsonValue, EvalError> {
     let all_re = interpolation_regex();
@@ -271,6 +737,8 @@ fn evaluate_interpolation(
             current_value: None,
             current_scope: None,
             named_scopes: std::collections::BTreeMap::new(),
+            functions,
+            quantities,
         };
         return evaluate(&parsed, &ctx);
     }
@@ -291,6 +759,8 @@ fn evaluate_interpolation(
             current_value: None,
             current_scope: None,
             named_scopes: std::collections::BTreeMap::new(),
+            functions,
+            quantities,
         };
         let eval = evaluate(&parsed, &ctx)?;
         out.push_str(&json_to_string(&eval));
@@ -301,7 +771,7 @@ fn evaluate_interpolation(
     Ok(JsonValue::String(out))
 }
 
-fn interpolation_regex() -> &'static Regex {
+pub(crate) fn interpolation_regex() -> &'static Regex {
     static RE: OnceLock<Regex> = OnceLock::new();
     RE.get_or_init(|| Regex::new(r"\$\{([^}]+)\}").expect("valid regex"))
 }
@@ -323,7 +793,11 @@ fn json_to_string(value: &JsonValue) -> String {
     }
 }
 
-fn set_json_path(root: &mut JsonValue, path: &str, value: JsonValue) -> Result<(), SyamlError> {
+pub(crate) fn set_json_path(
+    root: &mut JsonValue,
+    path: &str,
+    value: JsonValue,
+) -> Result<(), SyamlError> {
     let segments = parse_path(path)?;
     if segments.is_empty() {
         *root = value;
@@ -388,7 +862,39 @@ enum PathSegment {
     Index(usize),
 }
 
+/// Path segment of a query: a [`PathSegment`] or a `*` / `[*]` wildcard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum QuerySegment {
+    Key(String),
+    Index(usize),
+    AnyKey,
+    AnyIndex,
+}
+
+impl QuerySegment {
+    fn key(key: &str) -> Self {
+        if key == "*" {
+            QuerySegment::AnyKey
+        } else {
+            QuerySegment::Key(key.to_string())
+        }
+    }
+}
+
 fn parse_path(path: &str) -> Result<Vec<PathSegment>, SyamlError> {
+    parse_query_path(path)?
+        .into_iter()
+        .map(|segment| match segment {
+            QuerySegment::Key(key) => Ok(PathSegment::Key(key)),
+            QuerySegment::Index(i) => Ok(PathSegment::Index(i)),
+            QuerySegment::AnyKey | QuerySegment::AnyIndex => Err(SyamlError::ExpressionError(
+                format!("wildcards are not allowed in path '{}'", path),
+            )),
+        })
+        .collect()
+}
+
+fn parse_query_path(path: &str) -> Result<Vec<QuerySegment>, SyamlError> {
     if path == "$" {
         return Ok(Vec::new());
     }
@@ -409,7 +915,7 @@ fn parse_path(path: &str) -> Result<Vec<PathSegment>, SyamlError> {
         let ch = chars[i];
         if ch == '.' {
             if !current.is_empty() {
-                out.push(PathSegment::Key(current.clone()));
+                out.push(QuerySegment::key(&current));
                 current.clear();
             }
             i += 1;
@@ -418,7 +924,7 @@ fn parse_path(path: &str) -> Result<Vec<PathSegment>, SyamlError> {
 
         if ch == '[' {
             if !current.is_empty() {
-                out.push(PathSegment::Key(current.clone()));
+                out.push(QuerySegment::key(&current));
                 current.clear();
             }
             i += 1;
@@ -434,10 +940,14 @@ fn parse_path(path: &str) -> Result<Vec<PathSegment>, SyamlError> {
                 )));
             }
             i += 1;
+            if num == "*" {
+                out.push(QuerySegment::AnyIndex);
+                continue;
+            }
             let idx: usize = num.parse().map_err(|_| {
                 SyamlError::ExpressionError(format!("invalid array index '{}' in '{}'", num, path))
             })?;
-            out.push(PathSegment::Index(idx));
+            out.push(QuerySegment::Index(idx));
             continue;
         }
 
@@ -446,7 +956,7 @@ fn parse_path(path: &str) -> Result<Vec<PathSegment>, SyamlError> {
     }
 
     if !current.is_empty() {
-        out.push(PathSegment::Key(current));
+        out.push(QuerySegment::key(&current));
     }
 
     Ok(out)
@@ -469,7 +979,59 @@ pub fn get_json_path<'a>(root: &'a JsonValue, path: &str) -> Option<&'a JsonValu
     Some(current)
 }
 
-fn is_data_reference(s: &str) -> bool {
+/// Finds every value matching `path`, which uses the [`get_json_path`]
+/// syntax plus wildcards: `$.services.*.port` visits every key of
+/// `services` and `$.items[*]` every array element.
+///
+/// Matches are returned with their concrete paths, objects in key order. A
+/// path without wildcards yields at most one match.
+pub fn query_json_path<'a>(
+    root: &'a JsonValue,
+    path: &str,
+) -> Result<Vec<(String, &'a JsonValue)>, SyamlError> {
+    let mut matches = vec![("$".to_string(), root)];
+    for segment in parse_query_path(path)? {
+        let mut next = Vec::new();
+        for (at, value) in matches {
+            match &segment {
+                QuerySegment::Key(key) => {
+                    if let Some(child) = value.as_object().and_then(|map| map.get(key)) {
+                        next.push((format!("{at}.{key}"), child));
+                    }
+                }
+                QuerySegment::Index(i) => {
+                    if let Some(child) = value.as_array().and_then(|items| items.get(*i)) {
+                        next.push((format!("{at}[{i}]"), child));
+                    }
+                }
+                QuerySegment::AnyKey => {
+                    for (key, child) in value.as_object().into_iter().flatten() {
+                        next.push((format!("{at}.{key}"), child));
+                    }
+                }
+                QuerySegment::AnyIndex => {
+                    for (i, child) in value.as_array().into_iter().flatten().enumerate() {
+                        next.push((format!("{at}[{i}]"), child));
+                    }
+                }
+            }
+        }
+        matches = next;
+    }
+    Ok(matches)
+}
+
+/// Whether `path` is one of `literals` or lies below one of them.
+pub(crate) fn is_literal_path(path: &str, literals: &BTreeSet<String>) -> bool {
+    literals
+        .iter()
+        .any(|literal| match path.strip_prefix(literal.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+            None => false,
+        })
+}
+
+pub(crate) fn is_data_reference(s: &str) -> bool {
     static RE: OnceLock<Regex> = OnceLock::new();
     let re = RE.get_or_init(|| {
         Regex::new(r"^(\$\.|\.)([A-Za-z_][A-Za-z0-9_]*)(\.[A-Za-z_][A-Za-z0-9_]*)*$")
@@ -478,15 +1040,13 @@ fn is_data_reference(s: &str) -> bool {
     re.is_match(s)
 }
 
-fn parent_path_of(path: &str) -> Option<String> {
+pub(crate) fn parent_path_of(path: &str) -> Option<String> {
     if path == "$" {
         return None;
     }
     let mut last_sep = None;
     for (idx, ch) in path.char_indices() {
-        if ch == '.' && idx > 1 {
-            last_sep = Some(idx);
-        } else if ch == '[' {
+        if (ch == '.' && idx > 1) || ch == '[' {
             last_sep = Some(idx);
         }
     }
@@ -529,8 +1089,18 @@ fn collect_data_reference_nodes(value: &JsonValue, path: &str, out: &mut Vec<Exp
 /// Standalone string values matching `$.segment[.segment]*` or `.segment[.segment]*`
 /// are replaced with the referenced value from the document root or the parent object.
 pub fn resolve_data_references(data: &mut JsonValue) -> Result<(), SyamlError> {
+    resolve_data_references_except(data, &BTreeSet::new())
+}
+
+/// [`resolve_data_references`], leaving strings at or below the `literals`
+/// paths (value overrides) as written.
+pub(crate) fn resolve_data_references_except(
+    data: &mut JsonValue,
+    literals: &BTreeSet<String>,
+) -> Result<(), SyamlError> {
     let mut ref_nodes = Vec::new();
     collect_data_reference_nodes(data, "$", &mut ref_nodes);
+    ref_nodes.retain(|node| !is_literal_path(&node.path, literals));
 
     if ref_nodes.is_empty() {
         return Ok(());
@@ -558,6 +1128,7 @@ pub fn resolve_data_references(data: &mut JsonValue) -> Result<(), SyamlError> {
                         "relative reference `{}` used at the root level",
                         node.raw
                     ))
+                    .at_path(&node.path)
                 })?;
                 format!("{}{}", parent, node.raw)
             };
@@ -569,6 +1140,7 @@ pub fn resolve_data_references(data: &mut JsonValue) -> Result<(), SyamlError> {
             let value = get_json_path(data, &target)
                 .ok_or_else(|| {
                     SyamlError::ExpressionError(format!("data reference '{}' not found", node.raw))
+                        .at_path(&node.path)
                 })?
                 .clone();
 
@@ -583,7 +1155,8 @@ pub fn resolve_data_references(data: &mut JsonValue) -> Result<(), SyamlError> {
             return Err(SyamlError::CycleError(format!(
                 "could not resolve data references; possible dependency cycle among: {}",
                 paths.join(", ")
-            )));
+            ))
+            .at_path(&paths[0]));
         }
     }
 
@@ -626,13 +1199,14 @@ pub fn resolve_enum_member_references(
                             "enum member reference '{}' at {} is not compatible with expected schema: {}",
                             node.raw, node.path, e
                         ))
+                        .at_path(&node.path)
                     })?;
                 }
                 set_json_path(data, &node.path, resolved)?;
             }
             Err(err) => {
                 if expected_schema.is_some() {
-                    return Err(err);
+                    return Err(err.at_path(&node.path));
                 }
             }
         }
@@ -693,10 +1267,8 @@ fn resolve_one_enum_member_value(
 ) -> Result<JsonValue, SyamlError> {
     let enum_schema = schema.types.get(enum_type_name).ok_or_else(|| {
         SyamlError::TypeHintError(format!(
-            "unknown enum type '{}' referenced by '{}' at {}",
-            enum_type_name,
-            format!("{}.{}", enum_type_name, member_key),
-            path
+            "unknown enum type '{}' referenced by '{}.{}' at {}",
+            enum_type_name, enum_type_name, member_key, path
         ))
     })?;
     let enum_obj = enum_schema.as_object().ok_or_else(|| {
@@ -726,6 +1298,41 @@ fn resolve_one_enum_member_value(
     Ok(member.clone())
 }
 
+/// Checks each overridden value at `paths` against the schema its path
+/// expects, so a mistyped override fails at its own path before derived
+/// values read it. Strings for a type with string constructors are left to
+/// the constructor.
+pub(crate) fn validate_override_values(
+    data: &JsonValue,
+    paths: &BTreeSet<String>,
+    type_hints: &BTreeMap<String, String>,
+    schema: &SchemaDoc,
+) -> Result<(), SyamlError> {
+    let mut errors = Vec::new();
+    for path in paths {
+        let Some(value) = get_json_path(data, path) else {
+            continue;
+        };
+        let constructed = value.is_string()
+            && type_hints
+                .get(path)
+                .and_then(|type_name| schema.types.get(type_name))
+                .is_some_and(|type_schema| type_schema.get("constructors").is_some());
+        if constructed {
+            continue;
+        }
+        let Ok(Some(expected)) = infer_expected_schema_for_path(path, type_hints, schema) else {
+            continue;
+        };
+        if let Err(e) =
+            validate_json_against_schema_with_types(value, &expected, path, &schema.types)
+        {
+            errors.push(e.at_path(path));
+        }
+    }
+    SyamlError::from_errors(errors)
+}
+
 fn infer_expected_schema_for_path(
     path: &str,
     type_hints: &BTreeMap<String, String>,
@@ -910,5 +1517,5 @@ fn is_builtin_type_name(type_name: &str) -> bool {
     matches!(
         type_name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || crate::quantity::QUANTITY_TYPE_NAMES.contains(&type_name)
 }
@@ -1,14 +1,16 @@
 //! Rust type generation from named schema definitions.
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
 use crate::ast::{ImportBinding, ParsedDocument};
+use crate::quantity::QUANTITY_TYPE_NAMES;
 use crate::schema::parse_field_version_meta;
-use crate::{parse_document, parse_document_or_manifest, SyamlError};
+use crate::sensitive::{self, REDACTED};
+use crate::{parse_document, parse_document_or_manifest, path_is_within, SyamlError};
 
 const RUST_KEYWORDS: &[&str] = &[
     "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
@@ -199,6 +201,7 @@ struct RenderState {
     needs_serde_derives: bool,
     needs_serde_json_value: bool,
     needs_constraint_runtime: bool,
+    needs_secret_wrapper: bool,
 }
 
 fn render_rust_types(schemas: &CollectedSchemas) -> String {
@@ -334,6 +337,7 @@ fn render_rust_types_expanded(schemas: &CollectedSchemas) -> String {
         needs_serde_derives: false,
         needs_serde_json_value: false,
         needs_constraint_runtime: false,
+        needs_secret_wrapper: false,
     };
 
     let mut definitions = Vec::new();
@@ -360,6 +364,10 @@ fn render_rust_types_expanded(schemas: &CollectedSchemas) -> String {
         out.push_str(&render_constraint_runtime_helpers());
         out.push('\n');
     }
+    if state.needs_secret_wrapper {
+        out.push_str(&render_secret_wrapper());
+        out.push('\n');
+    }
 
     for def in definitions {
         out.push_str(&def);
@@ -400,7 +408,7 @@ fn render_type_definition(
 
     let mut out = if is_union_schema(schema_obj) {
         state.needs_serde_derives = true;
-        render_union_enum(&rust_name, schema_obj, state)
+        render_union_enum(source_name, &rust_name, schema_obj, state)
     } else if let Some(members) = collect_keyed_enum_members(schema_obj) {
         state.needs_serde_derives = true;
         render_keyed_enum_helpers(source_name, &rust_name, schema_obj, &members, state)
@@ -410,7 +418,7 @@ fn render_type_definition(
     } else if is_object_schema(schema_obj) {
         if let Some(properties) = schema_obj.get("properties").and_then(JsonValue::as_object) {
             state.needs_serde_derives = true;
-            render_object_struct(&rust_name, properties, schema_obj, state)
+            render_object_struct(source_name, &rust_name, properties, schema_obj, state)
         } else if let Some(values_schema) = schema_obj.get("values") {
             let value_type = rust_type_for_schema(values_schema, state);
             format!("pub type {rust_name} = std::collections::BTreeMap<String, {value_type}>;\n")
@@ -419,7 +427,8 @@ fn render_type_definition(
             format!("pub type {rust_name} = Value;\n")
         }
     } else {
-        let rust_type = rust_type_for_schema(schema, state);
+        // A sensitive named type is wrapped where it is used, not here.
+        let rust_type = plain_rust_type_for_schema(schema, state);
         format!("pub type {rust_name} = {rust_type};\n")
     };
 
@@ -510,6 +519,7 @@ fn is_union_schema(schema_obj: &JsonMap<String, JsonValue>) -> bool {
 }
 
 fn render_union_enum(
+    source_name: &str,
     name: &str,
     schema_obj: &JsonMap<String, JsonValue>,
     state: &mut RenderState,
@@ -526,7 +536,7 @@ fn render_union_enum(
             for (key, opt_schema) in opt_map {
                 let variant_name = sanitize_variant_name(key, "Variant");
                 let unique_variant = unique_identifier(&variant_name, &mut used);
-                let rust_type = rust_type_for_schema(opt_schema, state);
+                let rust_type = boxed_rust_type(source_name, opt_schema, state);
                 out.push_str(&format!("    {unique_variant}({rust_type}),\n"));
             }
         }
@@ -534,7 +544,7 @@ fn render_union_enum(
             for (idx, opt_schema) in items.iter().enumerate() {
                 let variant_name = infer_union_variant_name(opt_schema, idx, state);
                 let unique_variant = unique_identifier(&variant_name, &mut used);
-                let rust_type = rust_type_for_schema(opt_schema, state);
+                let rust_type = boxed_rust_type(source_name, opt_schema, state);
                 out.push_str(&format!("    {unique_variant}({rust_type}),\n"));
             }
         }
@@ -586,9 +596,7 @@ fn collect_keyed_enum_members(
     if enum_map.is_empty() {
         return None;
     }
-    if schema_obj.get("type").and_then(JsonValue::as_str).is_none() {
-        return None;
-    }
+    schema_obj.get("type").and_then(JsonValue::as_str)?;
     let mut members: Vec<(String, JsonValue)> = enum_map
         .iter()
         .map(|(k, v)| (k.clone(), v.clone()))
@@ -696,6 +704,7 @@ fn render_keyed_enum_helpers(
 }
 
 fn render_object_struct(
+    source_name: &str,
     name: &str,
     properties: &JsonMap<String, JsonValue>,
     schema_obj: &JsonMap<String, JsonValue>,
@@ -719,7 +728,7 @@ fn render_object_struct(
         let field_name = unique_identifier(&base_field, &mut used_fields);
         let optional = !required.contains(key.as_str());
 
-        let mut rust_type = rust_type_for_schema(schema, state);
+        let mut rust_type = boxed_rust_type(source_name, schema, state);
         if optional {
             rust_type = format!("Option<{rust_type}>");
         }
@@ -839,7 +848,98 @@ fn required_property_set(
     out
 }
 
+/// Rust type of a field or variant of the named type `owner`, boxed when it
+/// would otherwise contain `owner` (see [`needs_box`]).
+fn boxed_rust_type(owner: &str, schema: &JsonValue, state: &mut RenderState) -> String {
+    let rust_type = rust_type_for_schema(schema, state);
+    if needs_box(owner, schema, &state.all_types) {
+        format!("Box<{rust_type}>")
+    } else {
+        rust_type
+    }
+}
+
+/// Whether a field or variant of `owner` with `schema` must be a `Box`: its
+/// named type holds `owner` again, directly or through other named types,
+/// with no `Vec` or map in between, so `owner` would have an infinite size.
+fn needs_box(owner: &str, schema: &JsonValue, types: &BTreeMap<String, JsonValue>) -> bool {
+    let mut seen = HashSet::new();
+    let mut pending: Vec<&str> = named_type_ref(schema, types).into_iter().collect();
+    while let Some(type_name) = pending.pop() {
+        if type_name == owner {
+            return true;
+        }
+        if !seen.insert(type_name) {
+            continue;
+        }
+        if let Some(named) = types.get(type_name) {
+            pending.extend(inline_type_refs(named, types));
+        }
+    }
+    false
+}
+
+/// Named types a value of `schema` holds inline: the type it aliases, and
+/// the named types of its properties and union options. Array items and map
+/// values live on the heap and are left out.
+fn inline_type_refs<'a>(
+    schema: &'a JsonValue,
+    types: &BTreeMap<String, JsonValue>,
+) -> Vec<&'a str> {
+    let Some(schema_obj) = schema.as_object() else {
+        return Vec::new();
+    };
+    let mut out: Vec<&str> = named_type_ref(schema, types).into_iter().collect();
+    if let Some(properties) = schema_obj.get("properties").and_then(JsonValue::as_object) {
+        out.extend(
+            properties
+                .values()
+                .filter_map(|property| named_type_ref(property, types)),
+        );
+    }
+    if is_union_schema(schema_obj) {
+        match schema_obj.get("options") {
+            Some(JsonValue::Object(options)) => out.extend(
+                options
+                    .values()
+                    .filter_map(|option| named_type_ref(option, types)),
+            ),
+            Some(JsonValue::Array(options)) => out.extend(
+                options
+                    .iter()
+                    .filter_map(|option| named_type_ref(option, types)),
+            ),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The schema's named type, when it refers to one defined in `types`.
+fn named_type_ref<'a>(
+    schema: &'a JsonValue,
+    types: &BTreeMap<String, JsonValue>,
+) -> Option<&'a str> {
+    schema
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .filter(|type_name| types.contains_key(*type_name))
+}
+
+/// Rust type for a schema, wrapped in `Secret<T>` when the schema or the
+/// named type it refers to is marked `sensitive: true`.
 fn rust_type_for_schema(schema: &JsonValue, state: &mut RenderState) -> String {
+    let rust_type = plain_rust_type_for_schema(schema, state);
+    if crate::sensitive::schema_is_sensitive(schema, &state.all_types) {
+        state.needs_serde_derives = true;
+        state.needs_secret_wrapper = true;
+        format!("Secret<{rust_type}>")
+    } else {
+        rust_type
+    }
+}
+
+fn plain_rust_type_for_schema(schema: &JsonValue, state: &mut RenderState) -> String {
     let Some(schema_obj) = schema.as_object() else {
         state.needs_serde_json_value = true;
         return "Value".to_string();
@@ -866,6 +966,68 @@ fn rust_type_for_schema(schema: &JsonValue, state: &mut RenderState) -> String {
     "Value".to_string()
 }
 
+/// Wrapper for sensitive values: serializes like the wrapped value, but its
+/// `Debug` output never shows it.
+fn render_secret_wrapper() -> String {
+    concat!(
+        "/// Sensitive value: serializes like `T`, but `Debug` never prints it.\n",
+        "#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]\n",
+        "#[serde(transparent)]\n",
+        "pub struct Secret<T>(pub T);\n",
+        "\n",
+        "impl<T> std::fmt::Debug for Secret<T> {\n",
+        "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n",
+        "        f.write_str(\"Secret([redacted])\")\n",
+        "    }\n",
+        "}\n",
+    )
+    .to_string()
+}
+
+/// Rust type for a built-in `duration`, `timestamp` or `bytesize` value.
+fn rust_quantity_type(type_name: &str) -> &'static str {
+    match type_name {
+        "duration" => "super_yaml::quantity::Duration",
+        "timestamp" => "super_yaml::quantity::Timestamp",
+        _ => "super_yaml::quantity::ByteSize",
+    }
+}
+
+/// Built-in quantity type (`duration`, `timestamp` or `bytesize`) that a
+/// schema resolves to, following named type aliases.
+fn quantity_type_of(
+    schema: &JsonValue,
+    types: &BTreeMap<String, JsonValue>,
+) -> Option<&'static str> {
+    let mut current = schema;
+    for _ in 0..=types.len() {
+        let type_name = current
+            .as_str()
+            .or_else(|| current.get("type").and_then(JsonValue::as_str))?;
+        if let Some(quantity) = QUANTITY_TYPE_NAMES.iter().find(|q| **q == type_name) {
+            return Some(quantity);
+        }
+        current = types.get(type_name)?;
+    }
+    None
+}
+
+/// Expression building a quantity value from its compiled form: the text
+/// is parsed at runtime, and a plain byte count uses `ByteSize::from_bytes`.
+fn rust_quantity_literal(value: &JsonValue, type_name: &str) -> Option<String> {
+    let rust_type = rust_quantity_type(type_name);
+    match value {
+        JsonValue::String(text) => Some(format!(
+            "\"{}\".parse::<{rust_type}>().unwrap()",
+            escape_string(text)
+        )),
+        JsonValue::Number(n) if type_name == "bytesize" => n
+            .as_i64()
+            .map(|bytes| format!("{rust_type}::from_bytes({bytes})")),
+        _ => None,
+    }
+}
+
 fn rust_type_for_type_name(
     type_name: &str,
     schema_obj: &JsonMap<String, JsonValue>,
@@ -877,6 +1039,7 @@ fn rust_type_for_type_name(
         "number" => "f64".to_string(),
         "boolean" => "bool".to_string(),
         "null" => "()".to_string(),
+        "duration" | "timestamp" | "bytesize" => rust_quantity_type(type_name).to_string(),
         "array" => {
             if let Some(items) = schema_obj.get("items") {
                 let item_type = rust_type_for_schema(items, state);
@@ -1093,12 +1256,32 @@ fn escape_string(raw: &str) -> String {
 ///
 /// The compiled (resolved) `---data` section is emitted as `pub const` or `pub fn`
 /// items after the type definitions, separated by a `// --- Data ---` comment.
+///
+/// Fails when the data holds sensitive values, which generated code would
+/// carry in plain text; see [`generate_rust_types_and_redacted_data_from_path`].
 pub fn generate_rust_types_and_data_from_path(
     path: impl AsRef<Path>,
     env_provider: &dyn crate::EnvProvider,
 ) -> Result<String, SyamlError> {
-    let path = path.as_ref();
+    generate_rust_types_and_data(path.as_ref(), env_provider, false)
+}
+
+/// Like [`generate_rust_types_and_data_from_path`], but redacts sensitive
+/// data instead of failing. A top-level string that is sensitive becomes a
+/// `Secret<String>` holding `[redacted]`; any other top-level item holding a
+/// sensitive value is left out, with a comment in its place.
+pub fn generate_rust_types_and_redacted_data_from_path(
+    path: impl AsRef<Path>,
+    env_provider: &dyn crate::EnvProvider,
+) -> Result<String, SyamlError> {
+    generate_rust_types_and_data(path.as_ref(), env_provider, true)
+}
 
+fn generate_rust_types_and_data(
+    path: &Path,
+    env_provider: &dyn crate::EnvProvider,
+    redact: bool,
+) -> Result<String, SyamlError> {
     // Parse to get type hints (path → schema type name).
     let input = fs::read_to_string(path).map_err(|e| {
         SyamlError::ImportError(format!("failed to read '{}': {e}", path.display()))
@@ -1117,12 +1300,24 @@ pub fn generate_rust_types_and_data_from_path(
 
     // Compile to get resolved data values.
     let compiled = crate::compile_document_from_path_with_fetch(path, env_provider, None, false)?;
+    if !redact {
+        sensitive::refuse_embedding(&compiled.sensitive_paths)?;
+    }
 
     // Render types using the expanded schema set.
     let types_output = render_rust_types_expanded(&expanded);
 
     // Render data items using the same expanded types + type_names.
-    let data_output = render_rust_data(&compiled.value, &type_hints, &expanded.types, &type_names);
+    let mut data_output = render_rust_data(
+        &compiled.value,
+        &type_hints,
+        &expanded.types,
+        &type_names,
+        &compiled.sensitive_paths,
+    );
+    if data_output.contains("Secret(") && !types_output.contains("pub struct Secret<T>") {
+        data_output = format!("{}\n{data_output}", render_secret_wrapper());
+    }
 
     let contracts_output = if let Some(ref func_doc) = parsed.contracts {
         crate::contracts::generate_rust_function_stubs(func_doc, &expanded.types)
@@ -1149,6 +1344,7 @@ fn render_rust_data(
     type_hints: &BTreeMap<String, String>,
     types: &BTreeMap<String, JsonValue>,
     type_names: &BTreeMap<String, String>,
+    sensitive: &BTreeSet<String>,
 ) -> String {
     let Some(obj) = data.as_object() else {
         return String::new();
@@ -1164,7 +1360,19 @@ fn render_rust_data(
         // Resolve effective schema: if hint is a named type, look it up directly.
         let schema = type_hint.and_then(|hint| types.get(hint));
         let var_name = sanitize_field_name(key);
-        let item = render_rust_data_item(&var_name, type_hint, value, schema, types, type_names);
+        let item = if sensitive
+            .iter()
+            .any(|path| path_is_within(path, &hint_path))
+        {
+            let rust_type = data_rust_type(type_hint, schema, value, type_names);
+            if sensitive.contains(&hint_path) && value.is_string() && rust_type == "String" {
+                format!("pub fn {var_name}() -> Secret<String> {{\n    Secret(\"{REDACTED}\".to_string())\n}}\n")
+            } else {
+                format!("// `{var_name}` is left out: it holds sensitive values.\n")
+            }
+        } else {
+            render_rust_data_item(&var_name, type_hint, value, schema, types, type_names)
+        };
         out.push_str(&item);
         out.push('\n');
     }
@@ -1184,6 +1392,7 @@ fn data_rust_type(
             "integer" => return "i64".to_string(),
             "number" => return "f64".to_string(),
             "boolean" => return "bool".to_string(),
+            "duration" | "timestamp" | "bytesize" => return rust_quantity_type(hint).to_string(),
             other => {
                 if let Some(mapped) = type_names.get(other) {
                     return mapped.clone();
@@ -1215,6 +1424,11 @@ fn render_rust_data_item(
     // SCREAMING_SNAKE_CASE for const identifiers.
     let const_name = var_name.to_uppercase();
 
+    let quantity = type_hint.and_then(|hint| quantity_type_of(&JsonValue::from(hint), types));
+    if let Some(lit) = quantity.and_then(|q| rust_quantity_literal(value, q)) {
+        return format!("pub fn {var_name}() -> {rust_type} {{\n    {lit}\n}}\n");
+    }
+
     match value {
         JsonValue::Null => {
             format!("pub const {const_name}: () = ();\n")
@@ -1278,6 +1492,7 @@ fn render_rust_data_item(
                     // Struct with named properties.
                     if let Some(props) = schema_obj.get("properties").and_then(|p| p.as_object()) {
                         let required = required_property_set(schema_obj, props);
+                        let owner = type_hint.filter(|name| types.contains_key(*name));
                         let mut field_lines = String::new();
                         let mut sorted_keys: Vec<_> = props.keys().collect();
                         sorted_keys.sort_unstable();
@@ -1287,9 +1502,10 @@ fn render_rust_data_item(
                             let is_optional = !required.contains(prop_key.as_str());
                             if is_optional {
                                 if let Some(field_val) = obj_map.get(prop_key) {
-                                    let lit = rust_value_literal(
+                                    let lit = struct_field_literal(
+                                        owner,
                                         field_val,
-                                        Some(prop_schema),
+                                        prop_schema,
                                         types,
                                         type_names,
                                     );
@@ -1299,9 +1515,10 @@ fn render_rust_data_item(
                                     field_lines.push_str(&format!("        {field_name}: None,\n"));
                                 }
                             } else if let Some(field_val) = obj_map.get(prop_key) {
-                                let lit = rust_value_literal(
+                                let lit = struct_field_literal(
+                                    owner,
                                     field_val,
-                                    Some(prop_schema),
+                                    prop_schema,
                                     types,
                                     type_names,
                                 );
@@ -1346,6 +1563,22 @@ fn render_rust_data_item(
     }
 }
 
+/// Literal for a field of the struct generated for the named type `owner`,
+/// in a `Box::new` where the field is boxed (see [`needs_box`]).
+fn struct_field_literal(
+    owner: Option<&str>,
+    value: &JsonValue,
+    schema: &JsonValue,
+    types: &BTreeMap<String, JsonValue>,
+    type_names: &BTreeMap<String, String>,
+) -> String {
+    let literal = rust_value_literal(value, Some(schema), types, type_names);
+    match owner {
+        Some(owner) if needs_box(owner, schema, types) => format!("Box::new({literal})"),
+        _ => literal,
+    }
+}
+
 /// Generates a Rust literal expression for `value` given its `schema`.
 ///
 /// Used recursively for struct fields, array items, and dict values.
@@ -1354,11 +1587,29 @@ fn rust_value_literal(
     schema: Option<&JsonValue>,
     types: &BTreeMap<String, JsonValue>,
     type_names: &BTreeMap<String, String>,
+) -> String {
+    let literal = plain_rust_value_literal(value, schema, types, type_names);
+    match schema {
+        Some(s) if crate::sensitive::schema_is_sensitive(s, types) => format!("Secret({literal})"),
+        _ => literal,
+    }
+}
+
+fn plain_rust_value_literal(
+    value: &JsonValue,
+    schema: Option<&JsonValue>,
+    types: &BTreeMap<String, JsonValue>,
+    type_names: &BTreeMap<String, String>,
 ) -> String {
     // Resolve a schema type-reference ({type: "SomeName"}) to the named schema + Rust name.
     let (effective_schema, effective_rust_name) =
         resolve_schema_type_ref(schema, types, type_names);
 
+    let quantity = schema.and_then(|s| quantity_type_of(s, types));
+    if let Some(lit) = quantity.and_then(|q| rust_quantity_literal(value, q)) {
+        return lit;
+    }
+
     match value {
         JsonValue::Null => "()".to_string(),
         JsonValue::Bool(b) => b.to_string(),
@@ -1405,6 +1656,7 @@ fn rust_value_literal(
                     if let Some(props) = schema_obj.get("properties").and_then(|p| p.as_object()) {
                         if let Some(struct_name) = effective_rust_name.as_deref() {
                             let required = required_property_set(schema_obj, props);
+                            let owner = schema.and_then(|s| named_type_ref(s, types));
                             let mut field_parts: Vec<String> = Vec::new();
                             let mut sorted_keys: Vec<_> = props.keys().collect();
                             sorted_keys.sort_unstable();
@@ -1414,9 +1666,10 @@ fn rust_value_literal(
                                 let is_optional = !required.contains(prop_key.as_str());
                                 if is_optional {
                                     if let Some(field_val) = obj_map.get(prop_key) {
-                                        let lit = rust_value_literal(
+                                        let lit = struct_field_literal(
+                                            owner,
                                             field_val,
-                                            Some(prop_schema),
+                                            prop_schema,
                                             types,
                                             type_names,
                                         );
@@ -1425,9 +1678,10 @@ fn rust_value_literal(
                                         field_parts.push(format!("{field_name}: None"));
                                     }
                                 } else if let Some(field_val) = obj_map.get(prop_key) {
-                                    let lit = rust_value_literal(
+                                    let lit = struct_field_literal(
+                                        owner,
                                         field_val,
-                                        Some(prop_schema),
+                                        prop_schema,
                                         types,
                                         type_names,
                                     );
@@ -1488,6 +1742,20 @@ fn rust_schema_to_type(
     schema: Option<&JsonValue>,
     types: &BTreeMap<String, JsonValue>,
     type_names: &BTreeMap<String, String>,
+) -> String {
+    let rust_type = plain_rust_schema_to_type(schema, types, type_names);
+    match schema {
+        Some(s) if crate::sensitive::schema_is_sensitive(s, types) => {
+            format!("Secret<{rust_type}>")
+        }
+        _ => rust_type,
+    }
+}
+
+fn plain_rust_schema_to_type(
+    schema: Option<&JsonValue>,
+    types: &BTreeMap<String, JsonValue>,
+    type_names: &BTreeMap<String, String>,
 ) -> String {
     let Some(schema_obj) = schema.and_then(|s| s.as_object()) else {
         return "serde_json::Value".to_string();
@@ -1500,6 +1768,7 @@ fn rust_schema_to_type(
             "number" => "f64".to_string(),
             "boolean" => "bool".to_string(),
             "null" => "()".to_string(),
+            "duration" | "timestamp" | "bytesize" => rust_quantity_type(type_name).to_string(),
             "array" => {
                 let item_type = schema_obj
                     .get("items")
@@ -17,14 +17,35 @@ use crate::ast::SchemaDoc;
 use crate::error::SyamlError;
 use crate::expr::{
     parse_expression,
-    parser::{BinaryOp, Expr},
+    parser::Expr,
+    typecheck::{check_condition, TypeEnv},
 };
+use crate::quantity::{Quantity, QUANTITY_TYPE_NAMES};
 
-const MAX_SCHEMA_VALIDATION_DEPTH: usize = 64;
+/// Recursive types nest as deep as their data, so this allows a few schema
+/// levels (a named type, its property, its array items) per data level.
+const MAX_SCHEMA_VALIDATION_DEPTH: usize = 256;
 
 struct SchemaValidationContext<'a> {
     types: &'a BTreeMap<String, JsonValue>,
-    type_stack: Vec<String>,
+    /// Named types entered so far, with the value path each was entered at.
+    /// A type may recur below itself (recursive types), but re-entering it at
+    /// the same path is a cycle that never reaches the data.
+    type_stack: Vec<(String, String)>,
+    /// Leave the checked value out of messages (see
+    /// [`validate_json_against_schema_concealed`]).
+    conceal_values: bool,
+}
+
+impl SchemaValidationContext<'_> {
+    /// `value` as shown in a message, or `placeholder` when values are concealed.
+    fn shown(&self, value: impl std::fmt::Display, placeholder: &str) -> String {
+        if self.conceal_values {
+            placeholder.to_string()
+        } else {
+            value.to_string()
+        }
+    }
 }
 
 /// Parses a `schema` section JSON value into [`SchemaDoc`].
@@ -100,6 +121,9 @@ pub fn validate_schema_type_references(
 /// Like [`validate_schema_type_references`] but also accepts an `extends_map`
 /// so that child types are treated as valid substitutes for their parent types
 /// (IS-A relationship).
+///
+/// Types may refer to themselves, directly or through other types, as long as
+/// some finite value matches them (see [`validate_recursive_types`]).
 pub fn validate_schema_type_references_with_extends(
     types: &BTreeMap<String, JsonValue>,
     extends_map: &BTreeMap<String, String>,
@@ -108,7 +132,129 @@ pub fn validate_schema_type_references_with_extends(
         let root_path = format!("schema.{}", type_name);
         validate_schema_type_references_inner(schema, types, extends_map, &root_path)?;
     }
-    Ok(())
+    validate_recursive_types(types)
+}
+
+/// Rejects recursive types that no finite value can match: every value of
+/// such a type would contain another one, as with a required `next: Node`
+/// property or aliases referring to each other. Recursion must go through an
+/// optional property, an array, a map or a union option that ends it.
+pub fn validate_recursive_types(types: &BTreeMap<String, JsonValue>) -> Result<(), SyamlError> {
+    let mut finite: HashSet<&str> = HashSet::new();
+    loop {
+        let before = finite.len();
+        for (type_name, schema) in types {
+            if !finite.contains(type_name.as_str()) && has_finite_value(schema, types, &finite) {
+                finite.insert(type_name);
+            }
+        }
+        if finite.len() == before {
+            break;
+        }
+    }
+
+    // Name a type on the cycle, not one that merely contains such a type.
+    let mut unmatched = types
+        .keys()
+        .filter(|type_name| !finite.contains(type_name.as_str()));
+    let Some(first) = unmatched.clone().next() else {
+        return Ok(());
+    };
+    let type_name = unmatched
+        .find(|type_name| reaches_type(type_name, type_name, types))
+        .unwrap_or(first);
+    Err(SyamlError::SchemaError(format!(
+        "recursive type '{type_name}' at schema.{type_name} has no finite value: every \
+         {type_name} must contain another one; make the recursive property optional or \
+         hold it in an array or map"
+    )))
+}
+
+/// Whether some finite value matches `schema`, given the named types already
+/// known to have one.
+fn has_finite_value(
+    schema: &JsonValue,
+    types: &BTreeMap<String, JsonValue>,
+    finite: &HashSet<&str>,
+) -> bool {
+    let Some(obj) = schema.as_object() else {
+        return true;
+    };
+    if obj.contains_key("enum") {
+        return true;
+    }
+    match obj.get("type").and_then(JsonValue::as_str) {
+        Some("union") => {
+            return match obj.get("options") {
+                Some(JsonValue::Array(options)) => options
+                    .iter()
+                    .any(|option| has_finite_value(option, types, finite)),
+                Some(JsonValue::Object(options)) => options
+                    .values()
+                    .any(|option| has_finite_value(option, types, finite)),
+                _ => true,
+            };
+        }
+        Some(type_name) if types.contains_key(type_name) && !finite.contains(type_name) => {
+            return false;
+        }
+        _ => {}
+    }
+
+    if let Some(props) = obj.get("properties").and_then(JsonValue::as_object) {
+        let explicit_required = parse_required_property_set(obj, "").ok().flatten();
+        for (key, child) in props {
+            let required = match explicit_required.as_ref() {
+                Some(set) => set.contains(key.as_str()),
+                None => !property_is_optional(child, "", key).unwrap_or(false),
+            };
+            if required && !has_finite_value(child, types, finite) {
+                return false;
+            }
+        }
+    }
+    let min_items = obj.get("minItems").and_then(JsonValue::as_u64).unwrap_or(0);
+    if let Some(items) = obj.get("items") {
+        if min_items > 0 && !has_finite_value(items, types, finite) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether the schema of the named type `from` refers to `target`, directly
+/// or through other named types.
+fn reaches_type(from: &str, target: &str, types: &BTreeMap<String, JsonValue>) -> bool {
+    fn collect<'a>(node: &'a JsonValue, out: &mut Vec<&'a str>) {
+        match node {
+            JsonValue::Object(map) => {
+                if let Some(type_name) = map.get("type").and_then(JsonValue::as_str) {
+                    out.push(type_name);
+                }
+                map.values().for_each(|child| collect(child, out));
+            }
+            JsonValue::Array(items) => items.iter().for_each(|item| collect(item, out)),
+            _ => {}
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut pending = vec![from];
+    while let Some(type_name) = pending.pop() {
+        let Some(schema) = types.get(type_name) else {
+            continue;
+        };
+        if !seen.insert(type_name) {
+            continue;
+        }
+        let mut referenced = Vec::new();
+        collect(schema, &mut referenced);
+        if referenced.contains(&target) {
+            return true;
+        }
+        pending.extend(referenced);
+    }
+    false
 }
 
 fn validate_schema_type_references_inner(
@@ -457,7 +603,9 @@ fn expand_extends_types(
     Ok(())
 }
 
-fn normalize_schema_node(schema: JsonValue) -> JsonValue {
+/// Expands shorthand (`integer`, `Port?`, `A | B`, `[a, b]`, shorthand
+/// properties and items) into full schema objects.
+pub(crate) fn normalize_schema_node(schema: JsonValue) -> JsonValue {
     match schema {
         JsonValue::Array(values) if values.iter().all(JsonValue::is_string) => {
             let mut out = serde_json::Map::new();
@@ -1093,276 +1241,80 @@ fn validate_type_constraint_variable_scope(
                 }
             }
 
-            // Validate constraint operations against schema types
-            validate_constraint_operations(
-                &ast,
-                scope_schema,
-                expression,
-                type_name,
-                constraint_path,
-                types,
-            )?;
+            let scope_type = TypeEnv::new(types).schema_type(scope_schema);
+            let checker = TypeEnv::new(types)
+                .with_root("value", scope_type.clone())
+                .with_scope(scope_type);
+            if let Some(issue) = check_condition(&checker, &ast).into_iter().next() {
+                return Err(SyamlError::SchemaError(format!(
+                    "constraint '{}' at schema.{} path '{}': {}",
+                    expression, type_name, constraint_path, issue
+                )));
+            }
         }
     }
 
     Ok(())
 }
 
-fn validate_constraint_operations(
-    expr: &Expr,
-    scope_schema: &JsonValue,
-    expression: &str,
-    type_name: &str,
-    constraint_path: &str,
-    types: &BTreeMap<String, JsonValue>,
-) -> Result<(), SyamlError> {
-    use crate::expr::parser::BinaryOp;
-
-    match expr {
-        Expr::Binary { op, left, right } => {
-            match op {
-                BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte => {
-                    // Comparison operators require numeric operands
-                    // String comparisons are not allowed as they are unreliable and error-prone
-                    let left_type = infer_expression_type(left, scope_schema, types);
-                    let right_type = infer_expression_type(right, scope_schema, types);
-
-                    if !is_numeric_type(&left_type) || !is_numeric_type(&right_type) {
-                        return Err(SyamlError::SchemaError(format!(
-                            "constraint '{}' at schema.{} path '{}' uses '{}' operator with non-numeric types (left: {}, right: {})",
-                            expression, type_name, constraint_path, op_symbol(*op),
-                            left_type.as_deref().unwrap_or("unknown"), right_type.as_deref().unwrap_or("unknown")
-                        )));
-                    }
-                }
-                BinaryOp::Eq | BinaryOp::NotEq => {
-                    // Equality operators are generally allowed for any types
-                }
-                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-                    // Arithmetic operators require numeric operands
-                    let left_type = infer_expression_type(left, scope_schema, types);
-
-                    let right_type = infer_expression_type(right, scope_schema, types);
-
-                    if !is_numeric_type(&left_type) || !is_numeric_type(&right_type) {
-                        return Err(SyamlError::SchemaError(format!(
-                            "constraint '{}' at schema.{} path '{}' uses '{}' operator with non-numeric types (left: {}, right: {})",
-                            expression, type_name, constraint_path, op_symbol(*op),
-                            left_type.as_deref().unwrap_or("unknown"), right_type.as_deref().unwrap_or("unknown")
-                        )));
-                    }
-                }
-                _ => {
-                    // Other operators are allowed
-                }
-            }
-
-            // Recursively validate sub-expressions
-            validate_constraint_operations(
-                left,
-                scope_schema,
-                expression,
-                type_name,
-                constraint_path,
-                types,
-            )?;
-            validate_constraint_operations(
-                right,
-                scope_schema,
-                expression,
-                type_name,
-                constraint_path,
-                types,
-            )?;
-        }
-        Expr::Unary { expr: inner, .. } => {
-            // Recursively validate inner expression
-            validate_constraint_operations(
-                inner,
-                scope_schema,
-                expression,
-                type_name,
-                constraint_path,
-                types,
-            )?;
-        }
-        Expr::Call { name, args } => {
-            // Handle known functions that return specific types
-            match name.as_str() {
-                "len" => {
-                    // len() returns a number - this is valid for comparisons
-                }
-                _ => {
-                    // Unknown function - could return any type
-                }
-            }
+/// Collects the variable paths an expression reads, excluding names bound by
+/// lambdas. A null-safe chain (`a?.b.c`) counts as the single path it reads.
+pub(crate) fn collect_var_paths(expr: &Expr, out: &mut Vec<Vec<String>>) {
+    collect_free_var_paths(expr, &[], out);
+}
 
-            // Validate arguments
-            for arg in args {
-                validate_constraint_operations(
-                    arg,
-                    scope_schema,
-                    expression,
-                    type_name,
-                    constraint_path,
-                    types,
-                )?;
-            }
-        }
-        _ => {
-            // Other expression types don't need validation
+fn collect_free_var_paths(expr: &Expr, bound: &[&str], out: &mut Vec<Vec<String>>) {
+    if let Some((path, _)) = expr.null_safe_path() {
+        if !bound.contains(&path[0].as_str()) {
+            out.push(path);
         }
+        return;
     }
-
-    Ok(())
-}
-
-fn infer_expression_type(
-    expr: &Expr,
-    scope_schema: &JsonValue,
-    types: &BTreeMap<String, JsonValue>,
-) -> Option<String> {
     match expr {
         Expr::Var(path) => {
-            if path.is_empty() {
-                return None;
-            }
-            let first = &path[0];
-            if first == "value" {
-                // This is the current value being constrained
-                return infer_schema_type(scope_schema, types);
-            } else {
-                // This is a property access
-                let mut current_schema = scope_schema;
-                for segment in path {
-                    if let Some(obj) = current_schema.as_object() {
-                        if let Some(props) = obj.get("properties") {
-                            if let Some(prop_schema) =
-                                props.as_object().and_then(|p| p.get(segment))
-                            {
-                                current_schema = prop_schema;
-                                continue;
-                            }
-                        }
-                    }
-                    return None; // Path not found
-                }
-                return infer_schema_type(current_schema, types);
+            if !path
+                .first()
+                .is_some_and(|head| bound.contains(&head.as_str()))
+            {
+                out.push(path.clone());
             }
         }
-        Expr::Binary { op, left, right } => {
-            let left_type = infer_expression_type(left, scope_schema, types);
-            let right_type = infer_expression_type(right, scope_schema, types);
-
-            match op {
-                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-                    // Arithmetic operations result in numbers if both operands are numbers
-                    if is_numeric_type(&left_type) && is_numeric_type(&right_type) {
-                        Some("number".to_string())
-                    } else {
-                        None
-                    }
-                }
-                BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte => {
-                    // Comparison operations result in booleans
-                    Some("boolean".to_string())
-                }
-                BinaryOp::Eq | BinaryOp::NotEq => {
-                    // Equality operations result in booleans
-                    Some("boolean".to_string())
-                }
-                BinaryOp::And | BinaryOp::Or => {
-                    // Logical operations result in booleans
-                    Some("boolean".to_string())
-                }
-            }
+        Expr::Unary { expr, .. } => collect_free_var_paths(expr, bound, out),
+        Expr::Binary { left, right, .. }
+        | Expr::Index {
+            target: left,
+            index: right,
+            ..
+        } => {
+            collect_free_var_paths(left, bound, out);
+            collect_free_var_paths(right, bound, out);
         }
-        Expr::Call { name, .. } => {
-            // Handle known functions that return specific types
-            match name.as_str() {
-                "len" => Some("number".to_string()), // len() returns a number
-                "min" | "max" => Some("number".to_string()), // min/max return numbers
-                _ => None,                           // Unknown function return type
-            }
-        }
-        Expr::Number(_) => Some("number".to_string()),
-        Expr::String(_) => Some("string".to_string()),
-        Expr::Bool(_) => Some("boolean".to_string()),
-        _ => None,
-    }
-}
-
-fn infer_schema_type(schema: &JsonValue, types: &BTreeMap<String, JsonValue>) -> Option<String> {
-    if let Some(type_val) = schema.as_object().and_then(|o| o.get("type")) {
-        if let Some(type_str) = type_val.as_str() {
-            // Check if this is a reference to a named type
-            if let Some(named_type) = types.get(type_str) {
-                return infer_schema_type(named_type, types);
-            } else if type_str.contains('.') {
-                // Handle imported types like "prim.NonNegativeInteger"
-                // For now, infer based on common patterns
-                if type_str.contains("Integer") {
-                    return Some("integer".to_string());
-                } else if type_str.contains("Number") {
-                    return Some("number".to_string());
-                } else if type_str.contains("String") {
-                    return Some("string".to_string());
-                }
-                // Unknown imported type
-                return None;
-            } else {
-                // It's a built-in type like "string", "number", etc.
-                return Some(type_str.to_string());
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_free_var_paths(arg, bound, out);
             }
         }
-    }
-    if let Some(ref_val) = schema.as_object().and_then(|o| o.get("$ref")) {
-        if let Some(ref_str) = ref_val.as_str() {
-            // Handle explicit type references
-            if let Some(referenced) = types.get(ref_str) {
-                return infer_schema_type(referenced, types);
-            }
+        Expr::Lambda { param, body } => {
+            let mut inner = bound.to_vec();
+            inner.push(param);
+            collect_free_var_paths(body, &inner, out);
         }
-    }
-    None
-}
-
-fn is_numeric_type(type_name: &Option<String>) -> bool {
-    matches!(type_name.as_deref(), Some("number") | Some("integer"))
-}
-
-fn op_symbol(op: BinaryOp) -> &'static str {
-    match op {
-        BinaryOp::Add => "+",
-        BinaryOp::Sub => "-",
-        BinaryOp::Mul => "*",
-        BinaryOp::Div => "/",
-        BinaryOp::Mod => "%",
-        BinaryOp::Eq => "==",
-        BinaryOp::NotEq => "!=",
-        BinaryOp::Lt => "<",
-        BinaryOp::Lte => "<=",
-        BinaryOp::Gt => ">",
-        BinaryOp::Gte => ">=",
-        BinaryOp::And => "&&",
-        BinaryOp::Or => "||",
-    }
-}
-
-pub(crate) fn collect_var_paths(expr: &Expr, out: &mut Vec<Vec<String>>) {
-    match expr {
-        Expr::Var(path) => out.push(path.clone()),
-        Expr::Unary { expr, .. } => collect_var_paths(expr, out),
-        Expr::Binary { left, right, .. } => {
-            collect_var_paths(left, out);
-            collect_var_paths(right, out);
+        Expr::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => {
+            collect_free_var_paths(cond, bound, out);
+            collect_free_var_paths(then_branch, bound, out);
+            collect_free_var_paths(else_branch, bound, out);
         }
-        Expr::Call { args, .. } => {
-            for arg in args {
-                collect_var_paths(arg, out);
-            }
+        Expr::Let { name, value, body } => {
+            collect_free_var_paths(value, bound, out);
+            let mut inner = bound.to_vec();
+            inner.push(name);
+            collect_free_var_paths(body, &inner, out);
         }
-        Expr::Number(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
+        Expr::Number(_) | Expr::Quantity(_) | Expr::String(_) | Expr::Bool(_) | Expr::Null => {}
     }
 }
 
@@ -1465,10 +1417,7 @@ fn dereference_named_schema<'a>(
         if is_builtin_type_name(type_name) || type_name == "union" {
             return Some(node);
         }
-        let Some(next) = types.get(type_name) else {
-            return None;
-        };
-        node = next;
+        node = types.get(type_name)?;
         depth += 1;
     }
 }
@@ -1515,10 +1464,7 @@ pub fn resolve_type_schema(schema: &SchemaDoc, type_name: &str) -> Result<JsonVa
         return Ok(found.clone());
     }
 
-    if matches!(
-        type_name,
-        "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    ) {
+    if is_builtin_type_name(type_name) {
         return Ok(serde_json::json!({ "type": type_name }));
     }
 
@@ -1553,6 +1499,24 @@ pub fn validate_json_against_schema_with_types(
     let mut ctx = SchemaValidationContext {
         types,
         type_stack: Vec::new(),
+        conceal_values: false,
+    };
+    validate_json_against_schema_inner(value, schema, path, 0, &mut ctx)
+}
+
+/// Like [`validate_json_against_schema_with_types`], but messages leave out the
+/// checked value, for values that must not be shown:
+/// `minimum violation at $.PORT: value < 1`.
+pub fn validate_json_against_schema_concealed(
+    value: &JsonValue,
+    schema: &JsonValue,
+    path: &str,
+    types: &BTreeMap<String, JsonValue>,
+) -> Result<(), SyamlError> {
+    let mut ctx = SchemaValidationContext {
+        types,
+        type_stack: Vec::new(),
+        conceal_values: true,
     };
     validate_json_against_schema_inner(value, schema, path, 0, &mut ctx)
 }
@@ -1563,6 +1527,16 @@ fn validate_json_against_schema_inner(
     path: &str,
     depth: usize,
     ctx: &mut SchemaValidationContext<'_>,
+) -> Result<(), SyamlError> {
+    validate_json_against_schema_at(value, schema, path, depth, ctx).map_err(|e| e.at_path(path))
+}
+
+fn validate_json_against_schema_at(
+    value: &JsonValue,
+    schema: &JsonValue,
+    path: &str,
+    depth: usize,
+    ctx: &mut SchemaValidationContext<'_>,
 ) -> Result<(), SyamlError> {
     if depth > MAX_SCHEMA_VALIDATION_DEPTH {
         return Err(SyamlError::SchemaError(format!(
@@ -1583,7 +1557,13 @@ fn validate_json_against_schema_inner(
         if type_name == "union" {
             return validate_union_type(value, schema_obj, path, depth, ctx);
         }
-        if is_builtin_type_name(type_name) {
+        if let Some(parsed) = Quantity::from_json_as(type_name, value) {
+            let quantity = parsed.map_err(|reason| {
+                let reason = ctx.shown(reason, &format!("expected {type_name}"));
+                SyamlError::SchemaError(format!("type mismatch at {path}: {reason}"))
+            })?;
+            validate_quantity_keywords(quantity, type_name, schema_obj, path, ctx)?;
+        } else if is_builtin_type_name(type_name) {
             if !json_matches_type(value, type_name) {
                 return Err(SyamlError::SchemaError(format!(
                     "type mismatch at {path}: expected {type_name}, found {}",
@@ -1599,13 +1579,15 @@ fn validate_json_against_schema_inner(
         if let Some(options) = enum_value.as_array() {
             if !options.iter().any(|candidate| candidate == value) {
                 return Err(SyamlError::SchemaError(format!(
-                    "enum mismatch at {path}: value {value} not in enum set"
+                    "enum mismatch at {path}: value {}not in enum set",
+                    ctx.shown(format!("{value} "), "")
                 )));
             }
         } else if let Some(options) = enum_value.as_object() {
             if !options.values().any(|candidate| candidate == value) {
                 return Err(SyamlError::SchemaError(format!(
-                    "enum mismatch at {path}: value {value} not in enum set"
+                    "enum mismatch at {path}: value {}not in enum set",
+                    ctx.shown(format!("{value} "), "")
                 )));
             }
         } else {
@@ -1615,8 +1597,8 @@ fn validate_json_against_schema_inner(
         }
     }
 
-    validate_numeric_keywords(value, schema_obj, path)?;
-    validate_string_keywords(value, schema_obj, path)?;
+    validate_numeric_keywords(value, schema_obj, path, ctx)?;
+    validate_string_keywords(value, schema_obj, path, ctx)?;
     validate_object_keywords(value, schema_obj, path, depth, ctx)?;
     validate_array_keywords(value, schema_obj, path, depth, ctx)?;
 
@@ -1636,8 +1618,15 @@ fn validate_named_type_reference(
         ))
     })?;
 
-    if let Some(cycle_start) = ctx.type_stack.iter().position(|t| t == type_name) {
-        let mut cycle = ctx.type_stack[cycle_start..].to_vec();
+    if let Some(cycle_start) = ctx
+        .type_stack
+        .iter()
+        .position(|(t, at)| t == type_name && at == path)
+    {
+        let mut cycle: Vec<String> = ctx.type_stack[cycle_start..]
+            .iter()
+            .map(|(t, _)| t.clone())
+            .collect();
         cycle.push(type_name.to_string());
         return Err(SyamlError::SchemaError(format!(
             "cyclic type reference at {path}: {}",
@@ -1645,7 +1634,8 @@ fn validate_named_type_reference(
         )));
     }
 
-    ctx.type_stack.push(type_name.to_string());
+    ctx.type_stack
+        .push((type_name.to_string(), path.to_string()));
     let result = validate_json_against_schema_inner(value, referenced_schema, path, depth + 1, ctx);
     ctx.type_stack.pop();
     result
@@ -1715,7 +1705,7 @@ fn validate_union_type(
     for option_schema in &option_schemas {
         match validate_json_against_schema_inner(value, option_schema, path, depth + 1, ctx) {
             Ok(()) => return Ok(()),
-            Err(e) => errors.push(e.to_string()),
+            Err(e) => errors.extend(e.errors().iter().map(ToString::to_string)),
         }
     }
 
@@ -1725,10 +1715,45 @@ fn validate_union_type(
     )))
 }
 
+/// `minimum` / `maximum` bounds on `duration`, `timestamp` and `bytesize`
+/// values, written in the same spelling as the values.
+fn validate_quantity_keywords(
+    value: Quantity,
+    type_name: &str,
+    schema: &serde_json::Map<String, JsonValue>,
+    path: &str,
+    ctx: &SchemaValidationContext<'_>,
+) -> Result<(), SyamlError> {
+    for (keyword, violated) in [
+        ("minimum", std::cmp::Ordering::Less),
+        ("maximum", std::cmp::Ordering::Greater),
+    ] {
+        let Some(bound) = schema.get(keyword) else {
+            continue;
+        };
+        let bound = Quantity::from_json_as(type_name, bound)
+            .and_then(Result::ok)
+            .ok_or_else(|| {
+                SyamlError::SchemaError(format!(
+                    "{keyword} at {path} must be a {type_name}, found {bound}"
+                ))
+            })?;
+        if value.compare(&bound) == Some(violated) {
+            let symbol = if keyword == "minimum" { "<" } else { ">" };
+            return Err(SyamlError::SchemaError(format!(
+                "{keyword} violation at {path}: {} {symbol} {bound}",
+                ctx.shown(value, "value")
+            )));
+        }
+    }
+    Ok(())
+}
+
 fn validate_numeric_keywords(
     value: &JsonValue,
     schema: &serde_json::Map<String, JsonValue>,
     path: &str,
+    ctx: &SchemaValidationContext<'_>,
 ) -> Result<(), SyamlError> {
     let val = match value.as_f64() {
         Some(v) => v,
@@ -1741,7 +1766,8 @@ fn validate_numeric_keywords(
         })?;
         if val < min {
             return Err(SyamlError::SchemaError(format!(
-                "minimum violation at {path}: {val} < {min}"
+                "minimum violation at {path}: {} < {min}",
+                ctx.shown(val, "value")
             )));
         }
     }
@@ -1752,7 +1778,8 @@ fn validate_numeric_keywords(
         })?;
         if val > max {
             return Err(SyamlError::SchemaError(format!(
-                "maximum violation at {path}: {val} > {max}"
+                "maximum violation at {path}: {} > {max}",
+                ctx.shown(val, "value")
             )));
         }
     }
@@ -1763,7 +1790,8 @@ fn validate_numeric_keywords(
         })?;
         if val <= min {
             return Err(SyamlError::SchemaError(format!(
-                "exclusiveMinimum violation at {path}: {val} <= {min}"
+                "exclusiveMinimum violation at {path}: {} <= {min}",
+                ctx.shown(val, "value")
             )));
         }
     }
@@ -1774,7 +1802,8 @@ fn validate_numeric_keywords(
         })?;
         if val >= max {
             return Err(SyamlError::SchemaError(format!(
-                "exclusiveMaximum violation at {path}: {val} >= {max}"
+                "exclusiveMaximum violation at {path}: {} >= {max}",
+                ctx.shown(val, "value")
             )));
         }
     }
@@ -1786,6 +1815,7 @@ fn validate_string_keywords(
     value: &JsonValue,
     schema: &serde_json::Map<String, JsonValue>,
     path: &str,
+    ctx: &SchemaValidationContext<'_>,
 ) -> Result<(), SyamlError> {
     let s = match value.as_str() {
         Some(v) => v,
@@ -1799,7 +1829,7 @@ fn validate_string_keywords(
         if (s.chars().count() as u64) < min {
             return Err(SyamlError::SchemaError(format!(
                 "minLength violation at {path}: {} < {min}",
-                s.chars().count()
+                ctx.shown(s.chars().count(), "length")
             )));
         }
     }
@@ -1811,7 +1841,7 @@ fn validate_string_keywords(
         if (s.chars().count() as u64) > max {
             return Err(SyamlError::SchemaError(format!(
                 "maxLength violation at {path}: {} > {max}",
-                s.chars().count()
+                ctx.shown(s.chars().count(), "length")
             )));
         }
     }
@@ -1825,7 +1855,8 @@ fn validate_string_keywords(
         })?;
         if !re.is_match(s) {
             return Err(SyamlError::SchemaError(format!(
-                "pattern violation at {path}: '{s}' does not match '{pat}'"
+                "pattern violation at {path}: {} does not match '{pat}'",
+                ctx.shown(format!("'{s}'"), "value")
             )));
         }
     }
@@ -1846,14 +1877,19 @@ fn validate_object_keywords(
     };
 
     let explicit_required = parse_required_property_set(schema, path)?;
+    // Every missing property and bad child is reported, not just the first.
+    let mut errors = Vec::new();
 
     if let Some(required) = explicit_required.as_ref() {
-        for key in required {
-            if !obj.contains_key(key) {
-                return Err(SyamlError::SchemaError(format!(
-                    "required property missing at {path}: '{key}'"
-                )));
-            }
+        let mut missing: Vec<&String> = required
+            .iter()
+            .filter(|key| !obj.contains_key(key.as_str()))
+            .collect();
+        missing.sort();
+        for key in missing {
+            errors.push(SyamlError::SchemaError(format!(
+                "required property missing at {path}: '{key}'"
+            )));
         }
     }
 
@@ -1870,21 +1906,23 @@ fn validate_object_keywords(
                 None => !optional,
             };
 
-            if required && !obj.contains_key(k) {
-                return Err(SyamlError::SchemaError(format!(
+            if required && !obj.contains_key(k) && explicit_required.is_none() {
+                errors.push(SyamlError::SchemaError(format!(
                     "required property missing at {path}: '{k}'"
                 )));
             }
 
             if let Some(child_value) = obj.get(k) {
                 let child_path = format!("{}.{}", path, k);
-                validate_json_against_schema_inner(
+                if let Err(e) = validate_json_against_schema_inner(
                     child_value,
                     child_schema,
                     &child_path,
                     depth + 1,
                     ctx,
-                )?;
+                ) {
+                    errors.push(e);
+                }
             }
         }
     }
@@ -1902,17 +1940,19 @@ fn validate_object_keywords(
                 continue;
             }
             let child_path = format!("{}.{}", path, key);
-            validate_json_against_schema_inner(
+            if let Err(e) = validate_json_against_schema_inner(
                 child_value,
                 values_schema,
                 &child_path,
                 depth + 1,
                 ctx,
-            )?;
+            ) {
+                errors.push(e);
+            }
         }
     }
 
-    Ok(())
+    SyamlError::from_errors(errors)
 }
 
 fn parse_required_property_set(
@@ -1994,10 +2034,16 @@ fn validate_array_keywords(
     }
 
     if let Some(items_schema) = schema.get("items") {
+        let mut errors = Vec::new();
         for (idx, item) in arr.iter().enumerate() {
             let child_path = format!("{}[{}]", path, idx);
-            validate_json_against_schema_inner(item, items_schema, &child_path, depth + 1, ctx)?;
+            if let Err(e) =
+                validate_json_against_schema_inner(item, items_schema, &child_path, depth + 1, ctx)
+            {
+                errors.push(e);
+            }
         }
+        return SyamlError::from_errors(errors);
     }
 
     Ok(())
@@ -2020,7 +2066,7 @@ fn is_builtin_type_name(type_name: &str) -> bool {
     matches!(
         type_name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || QUANTITY_TYPE_NAMES.contains(&type_name)
 }
 
 fn json_type_name(value: &JsonValue) -> &'static str {
@@ -2351,29 +2397,25 @@ pub fn validate_mutability_keywords(schema: &JsonValue, path: &str) -> Result<()
         let mode = parse_mutability_mode(schema)?;
         let type_name = obj.get("type").and_then(JsonValue::as_str);
         match mode {
-            MutabilityMode::AppendOnly => {
-                if type_name != Some("array") {
-                    return Err(SyamlError::MutabilityError(format!(
-                        "mutability 'append_only' at {} requires type: array",
-                        path
-                    )));
-                }
+            MutabilityMode::AppendOnly if type_name != Some("array") => {
+                return Err(SyamlError::MutabilityError(format!(
+                    "mutability 'append_only' at {} requires type: array",
+                    path
+                )));
             }
-            MutabilityMode::MapPutOnly => {
-                if type_name != Some("object") {
-                    return Err(SyamlError::MutabilityError(format!(
-                        "mutability 'map_put_only' at {} requires type: object",
-                        path
-                    )));
-                }
+            MutabilityMode::MapPutOnly if type_name != Some("object") => {
+                return Err(SyamlError::MutabilityError(format!(
+                    "mutability 'map_put_only' at {} requires type: object",
+                    path
+                )));
             }
-            MutabilityMode::MonotoneIncrease => {
-                if !matches!(type_name, Some("integer") | Some("number")) {
-                    return Err(SyamlError::MutabilityError(format!(
-                        "mutability 'monotone_increase' at {} requires type: integer or number",
-                        path
-                    )));
-                }
+            MutabilityMode::MonotoneIncrease
+                if !matches!(type_name, Some("integer") | Some("number")) =>
+            {
+                return Err(SyamlError::MutabilityError(format!(
+                    "mutability 'monotone_increase' at {} requires type: integer or number",
+                    path
+                )));
             }
             _ => {}
         }
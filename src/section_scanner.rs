@@ -3,6 +3,7 @@
 use regex::Regex;
 
 use crate::error::SyamlError;
+use crate::source_map::LineIndex;
 
 #[derive(Debug, Clone)]
 /// A named document section extracted from the source text.
@@ -36,11 +37,14 @@ pub fn scan_sections(input: &str) -> Result<(String, Vec<Section>), SyamlError>
         SyamlError::MarkerError("document is empty; expected ---!syaml/v0".to_string())
     })?;
 
+    let index = LineIndex::new(input);
+
     if marker_line.trim() != MARKER {
         return Err(SyamlError::MarkerError(format!(
             "expected first non-empty line to be '{MARKER}', found '{}'",
             marker_line.trim()
-        )));
+        ))
+        .with_span(index.line_span(marker_line_idx + 1)));
     }
 
     let fence_re = Regex::new(r"^---([a-z_]+)\s*$").expect("valid regex");
@@ -72,7 +76,8 @@ pub fn scan_sections(input: &str) -> Result<(String, Vec<Section>), SyamlError>
             return Err(SyamlError::SectionError(format!(
                 "content before first section fence at line {}",
                 i + 1
-            )));
+            ))
+            .with_span(index.line_span(i + 1)));
         }
     }
 
@@ -85,28 +90,30 @@ pub fn scan_sections(input: &str) -> Result<(String, Vec<Section>), SyamlError>
         });
     }
 
-    validate_sections(&sections)?;
+    validate_sections(&sections)
+        .map_err(|(err, fence_line)| err.with_span(index.line_span(fence_line)))?;
     Ok(("v0".to_string(), sections))
 }
 
-fn validate_sections(sections: &[Section]) -> Result<(), SyamlError> {
+/// Checks section names and uniqueness, returning the offending fence line on error.
+fn validate_sections(sections: &[Section]) -> Result<(), (SyamlError, usize)> {
     let mut seen = std::collections::HashSet::new();
     for section in sections {
         if !matches!(
             section.name.as_str(),
-            "meta" | "schema" | "data" | "contracts" | "module"
+            "meta" | "schema" | "data" | "contracts" | "functions" | "module" | "overlay"
         ) {
-            return Err(SyamlError::SectionError(format!(
-                "unknown section '{}'",
-                section.name
-            )));
+            return Err((
+                SyamlError::SectionError(format!("unknown section '{}'", section.name)),
+                section.start_line,
+            ));
         }
 
         if !seen.insert(section.name.clone()) {
-            return Err(SyamlError::SectionError(format!(
-                "duplicate section '{}'",
-                section.name
-            )));
+            return Err((
+                SyamlError::SectionError(format!("duplicate section '{}'", section.name)),
+                section.start_line,
+            ));
         }
     }
 
@@ -0,0 +1,301 @@
+//! Sensitive values: secrets marked `sensitive: true` and everything derived
+//! from them.
+//!
+//! A value is sensitive when it comes from a `meta.env` binding declared
+//! `sensitive: true`, sits at a data path whose schema is `sensitive: true`,
+//! or is imported from a sensitive path of another document. Sensitivity
+//! follows values through imports, templates, `$.` references,
+//! interpolations and derived expressions: a value that read a sensitive
+//! value is sensitive too. Compiled output can be redacted, and error
+//! messages never print sensitive values.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value as JsonValue;
+
+use crate::error::SyamlError;
+use crate::path_is_within;
+use crate::provenance::Provenance;
+use crate::resolve::{get_json_path, node_var_paths, set_json_path};
+use crate::source_map::SourceSpan;
+
+/// Text that replaces sensitive values in redacted output and messages.
+pub const REDACTED: &str = "[redacted]";
+
+/// Bounds schema walks; recursive types nest as deep as their data.
+const MAX_SCHEMA_DEPTH: usize = 256;
+
+/// Returns `true` when `schema` is marked `sensitive: true`, directly or
+/// through the named type it refers to.
+pub fn schema_is_sensitive(schema: &JsonValue, types: &BTreeMap<String, JsonValue>) -> bool {
+    let mut current = schema;
+    for _ in 0..MAX_SCHEMA_DEPTH {
+        if is_marked(current) {
+            return true;
+        }
+        match current
+            .get("type")
+            .and_then(JsonValue::as_str)
+            .and_then(|name| types.get(name))
+        {
+            Some(named) => current = named,
+            None => return false,
+        }
+    }
+    false
+}
+
+/// Returns `true` when any schema node in `types` is marked sensitive.
+pub(crate) fn schema_marks_sensitive(types: &BTreeMap<String, JsonValue>) -> bool {
+    fn walk(value: &JsonValue) -> bool {
+        match value {
+            JsonValue::Object(map) => is_marked(value) || map.values().any(walk),
+            JsonValue::Array(items) => items.iter().any(walk),
+            _ => false,
+        }
+    }
+    types.values().any(walk)
+}
+
+fn is_marked(schema: &JsonValue) -> bool {
+    schema.get("sensitive").and_then(JsonValue::as_bool) == Some(true)
+}
+
+/// Data paths whose schema, reached through the type hints, is marked
+/// sensitive. Array items and map values are listed per element.
+pub(crate) fn schema_sensitive_paths(
+    data: &JsonValue,
+    type_hints: &BTreeMap<String, String>,
+    types: &BTreeMap<String, JsonValue>,
+) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    for (path, type_name) in type_hints {
+        let (Some(schema), Some(value)) = (types.get(type_name), get_json_path(data, path)) else {
+            continue;
+        };
+        collect_schema_paths(schema, value, path, types, 0, &mut out);
+    }
+    out
+}
+
+fn collect_schema_paths(
+    schema: &JsonValue,
+    value: &JsonValue,
+    path: &str,
+    types: &BTreeMap<String, JsonValue>,
+    depth: usize,
+    out: &mut BTreeSet<String>,
+) {
+    if depth > MAX_SCHEMA_DEPTH {
+        return;
+    }
+    if is_marked(schema) {
+        out.insert(path.to_string());
+        return;
+    }
+    let Some(schema_obj) = schema.as_object() else {
+        return;
+    };
+    if let Some(named) = schema_obj
+        .get("type")
+        .and_then(JsonValue::as_str)
+        .and_then(|name| types.get(name))
+    {
+        collect_schema_paths(named, value, path, types, depth + 1, out);
+    }
+
+    let properties = schema_obj.get("properties").and_then(JsonValue::as_object);
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                let child_schema = properties
+                    .and_then(|props| props.get(key))
+                    .or_else(|| schema_obj.get("values"));
+                if let Some(child_schema) = child_schema {
+                    let child_path = format!("{path}.{key}");
+                    collect_schema_paths(child_schema, child, &child_path, types, depth + 1, out);
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            if let Some(item_schema) = schema_obj.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    let item_path = format!("{path}[{i}]");
+                    collect_schema_paths(item_schema, item, &item_path, types, depth + 1, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Extends `sensitive` with every path whose recorded steps read a
+/// sensitive value, until nothing changes.
+///
+/// `sensitive` holds data paths (`$.a.b`), env symbols (`env.NAME`) and
+/// import paths (`alias.a.b`), spelled like [`ProvenanceStep`] reads. A read
+/// overlaps a sensitive path when either contains the other: reading
+/// `$.db` copies `$.db.password`.
+///
+/// [`ProvenanceStep`]: crate::provenance::ProvenanceStep
+pub(crate) fn propagate(provenance: &Provenance, sensitive: &mut BTreeSet<String>) {
+    let overlaps = |read: &str, sensitive: &BTreeSet<String>| {
+        sensitive
+            .iter()
+            .any(|path| path_is_within(read, path) || path_is_within(path, read))
+    };
+    loop {
+        let derived: Vec<String> = provenance
+            .recorded()
+            .filter(|(path, _)| !sensitive.iter().any(|s| path_is_within(path, s)))
+            .filter(|(_, steps)| {
+                steps
+                    .iter()
+                    .flat_map(|step| &step.reads)
+                    .any(|read| overlaps(read, sensitive))
+            })
+            .map(|(path, _)| path.to_string())
+            .collect();
+        if derived.is_empty() {
+            return;
+        }
+        sensitive.extend(derived);
+    }
+}
+
+/// The data paths among `sensitive` that exist in `data`, without those
+/// already covered by a sensitive ancestor.
+pub(crate) fn data_paths(sensitive: &BTreeSet<String>, data: &JsonValue) -> BTreeSet<String> {
+    let data_paths: Vec<&String> = sensitive
+        .iter()
+        .filter(|path| path.starts_with("$.") && get_json_path(data, path).is_some())
+        .collect();
+    data_paths
+        .iter()
+        .filter(|path| {
+            !data_paths
+                .iter()
+                .any(|other| other != *path && path_is_within(path, other))
+        })
+        .map(|path| (*path).clone())
+        .collect()
+}
+
+/// Replaces the value at each of `paths` in `data` with [`REDACTED`].
+pub(crate) fn redact(data: &mut JsonValue, paths: &BTreeSet<String>) {
+    for path in paths {
+        if get_json_path(data, path).is_some() {
+            let _ = set_json_path(data, path, JsonValue::String(REDACTED.to_string()));
+        }
+    }
+}
+
+/// Data paths of derived values (`=expr`, `${...}`) in `data` that read a
+/// `sensitive` path, directly or through another such value. Used before the
+/// expressions run, when no provenance records their reads yet.
+pub(crate) fn derived_readers(
+    data: &JsonValue,
+    imports: &BTreeMap<String, JsonValue>,
+    sensitive: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let mut nodes = Vec::new();
+    collect_derived_reads(data, "$", imports, &mut nodes);
+    let mut readers = BTreeSet::new();
+    loop {
+        let found: Vec<String> = nodes
+            .iter()
+            .filter(|(path, _)| !readers.contains(path))
+            .filter(|(_, reads)| {
+                reads.iter().any(|read| {
+                    sensitive
+                        .iter()
+                        .chain(&readers)
+                        .any(|path| path_is_within(read, path) || path_is_within(path, read))
+                })
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        if found.is_empty() {
+            return readers;
+        }
+        readers.extend(found);
+    }
+}
+
+/// `(path, reads)` of each derived value, reads spelled like
+/// [`ProvenanceStep`] reads.
+///
+/// [`ProvenanceStep`]: crate::provenance::ProvenanceStep
+fn collect_derived_reads(
+    value: &JsonValue,
+    path: &str,
+    imports: &BTreeMap<String, JsonValue>,
+    out: &mut Vec<(String, Vec<String>)>,
+) {
+    match value {
+        JsonValue::Object(map) => {
+            for (key, child) in map {
+                collect_derived_reads(child, &format!("{path}.{key}"), imports, out);
+            }
+        }
+        JsonValue::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_derived_reads(item, &format!("{path}[{i}]"), imports, out);
+            }
+        }
+        JsonValue::String(raw) if raw.trim().starts_with('=') || raw.contains("${") => {
+            let reads = node_var_paths(raw)
+                .into_iter()
+                .map(|var_path| {
+                    let joined = var_path.join(".");
+                    if var_path[0] == "env" || imports.contains_key(&var_path[0]) {
+                        joined
+                    } else {
+                        format!("$.{joined}")
+                    }
+                })
+                .collect();
+            out.push((path.to_string(), reads));
+        }
+        _ => {}
+    }
+}
+
+/// Fails when `sensitive` lists data paths: code generated with the data
+/// would carry their values in plain text.
+pub(crate) fn refuse_embedding(sensitive: &BTreeSet<String>) -> Result<(), SyamlError> {
+    if sensitive.is_empty() {
+        return Ok(());
+    }
+    let paths: Vec<&str> = sensitive.iter().map(String::as_str).collect();
+    Err(SyamlError::SerializationError(format!(
+        "refusing to write sensitive data into generated code ({}); redact it (--redact) or leave data out (--skip-data)",
+        paths.join(", ")
+    )))
+}
+
+/// Replaces each span's text in `source` with [`REDACTED`]. Spans index bytes
+/// of `source`; overlapping spans keep the first.
+pub(crate) fn mask_spans<'s>(
+    source: &str,
+    spans: impl IntoIterator<Item = &'s SourceSpan>,
+) -> String {
+    let mut ranges: Vec<(usize, usize)> = spans
+        .into_iter()
+        .map(|span| (span.start, span.end))
+        .filter(|(start, end)| start < end && *end <= source.len())
+        .collect();
+    ranges.sort_unstable();
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for (start, end) in ranges {
+        if start < last || !source.is_char_boundary(start) || !source.is_char_boundary(end) {
+            continue;
+        }
+        out.push_str(&source[last..start]);
+        out.push_str(REDACTED);
+        last = end;
+    }
+    out.push_str(&source[last..]);
+    out
+}
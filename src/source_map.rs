@@ -0,0 +1,268 @@
+//! Source locations for parsed documents and rustc-style snippet rendering.
+//!
+//! Parsing records where each data key/value came from. Errors that carry a
+//! normalized data path (`$.a.b[0]`) are later matched against this map so the
+//! CLI can point at the offending line and column.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::sensitive::REDACTED;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Location of a key or value in `.syaml` source text.
+pub struct SourceSpan {
+    /// Source file path, when the document was loaded from disk.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+    /// Byte offset of the span start in the source text.
+    pub start: usize,
+    /// Byte offset one past the span end in the source text.
+    pub end: usize,
+    /// Whether the spanned text is a sensitive value. Snippets show
+    /// `[redacted]` in its place.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sensitive: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Map from normalized data path to the span of its key or value.
+pub struct SourceMap {
+    entries: BTreeMap<String, SourceSpan>,
+}
+
+impl SourceMap {
+    /// Creates an empty source map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the span for `path`. The first span recorded for a path wins.
+    pub fn insert(&mut self, path: impl Into<String>, span: SourceSpan) {
+        self.entries.entry(path.into()).or_insert(span);
+    }
+
+    /// Returns the span recorded for exactly `path`.
+    pub fn get(&self, path: &str) -> Option<&SourceSpan> {
+        self.entries.get(path)
+    }
+
+    /// Returns the span for `path`, falling back to its nearest recorded ancestor.
+    pub fn lookup(&self, path: &str) -> Option<&SourceSpan> {
+        let mut candidate = Some(path.to_string());
+        while let Some(current) = candidate {
+            if let Some(span) = self.entries.get(&current) {
+                return Some(span);
+            }
+            candidate = parent_path(&current);
+        }
+        None
+    }
+
+    /// Iterates recorded `(path, span)` pairs in path order.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &SourceSpan)> {
+        self.entries.iter()
+    }
+
+    /// Number of recorded paths.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` when no paths are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Byte offsets of line starts in a source text.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds a line index over `text`.
+    pub fn new(text: &'a str) -> Self {
+        let mut starts = vec![0];
+        for (i, b) in text.bytes().enumerate() {
+            if b == b'\n' {
+                starts.push(i + 1);
+            }
+        }
+        Self { text, starts }
+    }
+
+    /// Returns the byte offset where 1-based `line` starts.
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.starts.get(line.checked_sub(1)?).copied()
+    }
+
+    /// Returns the text of 1-based `line` without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self.starts.get(line).copied().unwrap_or(self.text.len());
+        let raw = &self.text[start..end];
+        Some(raw.trim_end_matches(['\n', '\r']))
+    }
+
+    /// Builds a span on 1-based `line` covering byte columns `start..end` of that line.
+    ///
+    /// Columns are clamped to the line length and to character boundaries.
+    pub fn span(&self, line: usize, start: usize, end: usize) -> SourceSpan {
+        let Some(text) = self.line_text(line) else {
+            let offset = self.text.len();
+            return SourceSpan {
+                file: None,
+                line,
+                column: 1,
+                start: offset,
+                end: offset,
+                sensitive: false,
+            };
+        };
+        let line_start = self.starts[line - 1];
+        let start = floor_char_boundary(text, start.min(text.len()));
+        let end = floor_char_boundary(text, end.clamp(start, text.len()));
+        SourceSpan {
+            file: None,
+            line,
+            column: text[..start].chars().count() + 1,
+            start: line_start + start,
+            end: line_start + end,
+            sensitive: false,
+        }
+    }
+
+    /// Builds a span covering the non-blank content of 1-based `line`.
+    pub fn line_span(&self, line: usize) -> SourceSpan {
+        let text = self.line_text(line).unwrap_or("");
+        let start = text.len() - text.trim_start().len();
+        self.span(line, start, text.trim_end().len())
+    }
+}
+
+fn floor_char_boundary(text: &str, mut idx: usize) -> usize {
+    while idx > 0 && !text.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Renders `message` with a rustc-style snippet pointing at `span` in `source`.
+///
+/// The span's byte range is used to draw the caret underline; when the span is
+/// empty, a single caret is placed at its column. A sensitive span's text is
+/// shown as `[redacted]`.
+pub fn render_snippet(message: &str, span: &SourceSpan, source: &str) -> String {
+    let index = LineIndex::new(source);
+    let file = span.file.as_deref().unwrap_or("<input>");
+    let mut out = format!("error: {message}\n");
+    let gutter = span.line.to_string().len();
+    let pad = " ".repeat(gutter);
+    out.push_str(&format!("{pad}--> {file}:{}:{}\n", span.line, span.column));
+
+    let Some(line_text) = index.line_text(span.line) else {
+        return out.trim_end().to_string();
+    };
+    let line_start = index.starts[span.line - 1];
+    let caret_start = span.start.saturating_sub(line_start).min(line_text.len());
+    let caret_end = span
+        .end
+        .saturating_sub(line_start)
+        .clamp(caret_start, line_text.len());
+    let lead = floor_char_boundary(line_text, caret_start);
+    let tail = floor_char_boundary(line_text, caret_end);
+    let (line_text, spanned) = if span.sensitive {
+        let masked = format!("{}{REDACTED}{}", &line_text[..lead], &line_text[tail..]);
+        (std::borrow::Cow::Owned(masked), REDACTED)
+    } else {
+        (
+            std::borrow::Cow::Borrowed(line_text),
+            &line_text[lead..tail],
+        )
+    };
+    let lead_width = line_text[..lead].chars().count();
+    let caret_width = spanned.chars().count().max(1);
+
+    out.push_str(&format!("{pad} |\n"));
+    out.push_str(&format!("{} | {line_text}\n", span.line));
+    out.push_str(&format!(
+        "{pad} | {}{}",
+        " ".repeat(lead_width),
+        "^".repeat(caret_width)
+    ));
+    out
+}
+
+fn parent_path(path: &str) -> Option<String> {
+    if path == "$" {
+        return None;
+    }
+    let mut last_sep = None;
+    for (idx, ch) in path.char_indices() {
+        if (ch == '.' && idx > 1) || ch == '[' {
+            last_sep = Some(idx);
+        }
+    }
+    match last_sep {
+        Some(1) => Some("$".to_string()),
+        Some(idx) => Some(path[..idx].to_string()),
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_snippet, LineIndex, SourceMap};
+
+    #[test]
+    fn line_index_builds_spans_with_byte_offsets() {
+        let text = "a: 1\nport: 99999\n";
+        let index = LineIndex::new(text);
+        let span = index.span(2, 6, 11);
+        assert_eq!(span.line, 2);
+        assert_eq!(span.column, 7);
+        assert_eq!(&text[span.start..span.end], "99999");
+    }
+
+    #[test]
+    fn lookup_falls_back_to_ancestor_paths() {
+        let index = LineIndex::new("a:\n  b: 1\n");
+        let mut map = SourceMap::new();
+        map.insert("$.a", index.span(1, 0, 1));
+        assert_eq!(map.lookup("$.a.b[0]").unwrap().line, 1);
+        assert!(map.lookup("$.z").is_none());
+    }
+
+    #[test]
+    fn renders_caret_under_span() {
+        let text = "---!syaml/v0\n---data\nport: 99999\n";
+        let mut span = LineIndex::new(text).span(3, 6, 11);
+        span.file = Some("x.syaml".to_string());
+        let rendered = render_snippet("bad port", &span, text);
+        assert_eq!(
+            rendered,
+            "error: bad port\n --> x.syaml:3:7\n  |\n3 | port: 99999\n  |       ^^^^^"
+        );
+    }
+
+    #[test]
+    fn renders_sensitive_span_as_redacted() {
+        let text = "---!syaml/v0\n---data\ntoken: hunter2 # key\n";
+        let mut span = LineIndex::new(text).span(3, 7, 14);
+        span.sensitive = true;
+        let rendered = render_snippet("bad token", &span, text);
+        assert!(!rendered.contains("hunter2"), "{rendered}");
+        assert!(
+            rendered.ends_with("3 | token: [redacted] # key\n  |        ^^^^^^^^^^"),
+            "{rendered}"
+        );
+    }
+}
@@ -22,6 +22,72 @@ pub fn expand_data_templates(
     expand_value(data, &root_snapshot, imports, "$")
 }
 
+/// Expands template invocations in `data`, resolving local template paths
+/// against `root` rather than `data` itself. Overlay patches use this to
+/// invoke the templates of their base document.
+pub(crate) fn expand_data_templates_in(
+    data: &mut JsonValue,
+    root: &JsonValue,
+    imports: &HashMap<String, JsonValue>,
+) -> Result<(), SyamlError> {
+    expand_value(data, root, imports, "$")
+}
+
+/// Data paths of the locked fields that template invocations in `data` will
+/// produce, e.g. `$.api.name` when `api` invokes a template with `name!`.
+///
+/// Runs on data before expansion. Invocations that do not resolve are
+/// skipped; expansion reports them.
+pub(crate) fn locked_template_fields(
+    data: &JsonValue,
+    imports: &HashMap<String, JsonValue>,
+) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    collect_locked_fields(data, data, imports, "$", &mut out);
+    out
+}
+
+fn collect_locked_fields(
+    value: &JsonValue,
+    root: &JsonValue,
+    imports: &HashMap<String, JsonValue>,
+    path: &str,
+    out: &mut BTreeSet<String>,
+) {
+    match value {
+        JsonValue::Object(map) => {
+            let template_source = parse_template_invocation(map, path)
+                .ok()
+                .flatten()
+                .and_then(|(template_ref, _)| {
+                    resolve_template_path(root, imports, &template_ref, path).ok()
+                });
+            if let Some(source) = template_source {
+                for key in collect_locked_keys(source) {
+                    out.insert(format!("{path}.{key}"));
+                }
+                if let Some(fields) = source.as_object() {
+                    for (key, child) in fields {
+                        let key = key.strip_suffix('!').unwrap_or(key);
+                        collect_locked_fields(child, root, imports, &format!("{path}.{key}"), out);
+                    }
+                }
+            }
+            for (key, child) in map {
+                if parse_template_key(key).is_none() {
+                    collect_locked_fields(child, root, imports, &format!("{path}.{key}"), out);
+                }
+            }
+        }
+        JsonValue::Array(items) => {
+            for (idx, child) in items.iter().enumerate() {
+                collect_locked_fields(child, root, imports, &format!("{path}[{idx}]"), out);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn expand_value(
     value: &mut JsonValue,
     root: &JsonValue,
@@ -100,10 +166,13 @@ fn expand_value(
     }
 }
 
+/// Template reference plus its argument bindings.
+type TemplateInvocation = (String, HashMap<String, JsonValue>);
+
 fn parse_template_invocation(
     map: &JsonMap<String, JsonValue>,
     path: &str,
-) -> Result<Option<(String, HashMap<String, JsonValue>)>, SyamlError> {
+) -> Result<Option<TemplateInvocation>, SyamlError> {
     let mut invocation_key: Option<String> = None;
     let mut raw_key: Option<String> = None;
 
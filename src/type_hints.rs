@@ -13,12 +13,14 @@ use crate::error::SyamlError;
 const MAX_NORMALIZE_DEPTH: usize = 64;
 const MAX_TYPE_HINTS: usize = 10_000;
 
+/// `(normalized_data, hints_by_path, freeze_markers_by_path)` returned by
+/// [`normalize_data_with_hints`].
+pub type NormalizedData = (JsonValue, BTreeMap<String, String>, BTreeMap<String, bool>);
+
 /// Normalizes a data value and extracts type hints and freeze markers.
 ///
 /// Returns `(normalized_data, hints_by_path, freeze_markers_by_path)`.
-pub fn normalize_data_with_hints(
-    value: &JsonValue,
-) -> Result<(JsonValue, BTreeMap<String, String>, BTreeMap<String, bool>), SyamlError> {
+pub fn normalize_data_with_hints(value: &JsonValue) -> Result<NormalizedData, SyamlError> {
     let mut hints = BTreeMap::new();
     let mut freeze_markers = BTreeMap::new();
     let normalized = normalize_value(value, "$", &mut hints, &mut freeze_markers, 0)?;
@@ -35,14 +37,16 @@ fn normalize_value(
     if depth > MAX_NORMALIZE_DEPTH {
         return Err(SyamlError::TypeHintError(format!(
             "data nesting depth exceeds maximum ({MAX_NORMALIZE_DEPTH}) at {path}"
-        )));
+        ))
+        .at_path(path));
     }
 
     match value {
         JsonValue::Object(map) => {
             let mut out = JsonMap::new();
             for (key_str, v) in map {
-                let (canonical_key, hint) = split_key_and_hint(key_str)?;
+                let (canonical_key, hint) =
+                    split_key_and_hint(key_str).map_err(|e| e.at_path(path))?;
                 // Check for freeze marker (^ suffix) on the canonical key
                 let (canonical_key, frozen) = split_key_and_freeze(&canonical_key);
                 let child_path = format!("{}.{}", path, canonical_key);
@@ -50,7 +54,8 @@ fn normalize_value(
                     return Err(SyamlError::TypeHintError(format!(
                         "duplicate canonical key '{}' at {path}",
                         canonical_key
-                    )));
+                    ))
+                    .at_path(child_path));
                 }
 
                 if frozen {
@@ -91,7 +96,19 @@ fn normalize_value(
     }
 }
 
-fn split_key_and_hint(raw: &str) -> Result<(String, Option<String>), SyamlError> {
+/// Returns the canonical form of a raw data key, with any `<Type>` hint and
+/// `^` freeze suffix removed.
+///
+/// Keys with malformed hints are returned trimmed but otherwise unchanged.
+pub fn canonical_data_key(raw: &str) -> String {
+    let canonical = match split_key_and_hint(raw) {
+        Ok((key, _)) => key,
+        Err(_) => raw.trim().to_string(),
+    };
+    split_key_and_freeze(&canonical).0
+}
+
+pub(crate) fn split_key_and_hint(raw: &str) -> Result<(String, Option<String>), SyamlError> {
     let trimmed = raw.trim();
     if !trimmed.ends_with('>') {
         return Ok((trimmed.to_string(), None));
@@ -155,7 +172,7 @@ fn is_valid_type_segment(segment: &str) -> bool {
 mod tests {
     use serde_json::json;
 
-    use super::normalize_data_with_hints;
+    use super::{canonical_data_key, normalize_data_with_hints};
 
     #[test]
     fn extracts_type_hints() {
@@ -180,4 +197,11 @@ mod tests {
         // Key should be normalized to "name" without the ^
         assert!(data.as_object().unwrap().contains_key("name"));
     }
+
+    #[test]
+    fn canonical_data_key_strips_hint_and_freeze_marker() {
+        assert_eq!(canonical_data_key("port^ <shared.Port>"), "port");
+        assert_eq!(canonical_data_key("name^"), "name");
+        assert_eq!(canonical_data_key("plain"), "plain");
+    }
 }
@@ -1,14 +1,17 @@
 //! TypeScript type generation from named schema definitions.
 
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use serde_json::{Map as JsonMap, Value as JsonValue};
 
 use crate::ast::{ImportBinding, ParsedDocument};
+use crate::expr::parse_expression;
+use crate::expr::render::{render, Target};
 use crate::schema::parse_field_version_meta;
-use crate::{parse_document, parse_document_or_manifest, SyamlError};
+use crate::sensitive::{self, REDACTED};
+use crate::{parse_document, parse_document_or_manifest, path_is_within, SyamlError};
 
 const TYPESCRIPT_KEYWORDS: &[&str] = &[
     "break",
@@ -371,8 +374,9 @@ fn render_constraint_functions(
             let fn_name = format!("check{type_name}Constraint{index}");
             let escaped_path = escape_string(path);
             let escaped_expr = escape_string(expression);
+            let escaped_source = escape_string(&constraint_source(expression));
             out.push_str(&format!(
-                "export function {fn_name}(value: {type_name}): string | null {{\n  return runGeneratedConstraintCheck(value, \"{escaped_path}\", \"{escaped_expr}\");\n}}\n\n"
+                "export function {fn_name}(value: {type_name}): string | null {{\n  return runGeneratedConstraintCheck(value, \"{escaped_path}\", \"{escaped_expr}\", \"{escaped_source}\");\n}}\n\n"
             ));
             method_names.push(fn_name);
             index += 1;
@@ -393,6 +397,15 @@ fn render_constraint_functions(
     out
 }
 
+/// JavaScript source evaluated for a constraint expression.
+fn constraint_source(expression: &str) -> String {
+    let source = expression.trim().trim_start_matches('=').trim();
+    match parse_expression(source) {
+        Ok(ast) => render(&ast, Target::TypeScript, &|path: &[String]| path.join(".")),
+        Err(_) => source.to_string(),
+    }
+}
+
 fn render_constraint_runtime_helpers() -> String {
     concat!(
         "type GeneratedConstraintContext = Record<string, unknown>;\n",
@@ -438,6 +451,170 @@ fn render_constraint_runtime_helpers() -> String {
         "  return null;\n",
         "}\n",
         "\n",
+        "function expectString(name: string, value: unknown): string {\n",
+        "  if (typeof value !== \"string\") throw new Error(`${name}() expects a string argument`);\n",
+        "  return value;\n",
+        "}\n",
+        "\n",
+        "function expectIndex(name: string, value: unknown): number {\n",
+        "  if (typeof value !== \"number\" || !Number.isInteger(value) || value < 0) {\n",
+        "    throw new Error(`${name}() expects a non-negative integer argument`);\n",
+        "  }\n",
+        "  return value;\n",
+        "}\n",
+        "\n",
+        "const stringFunctions: Record<string, (...args: unknown[]) => unknown> = {\n",
+        "  lower: (s) => expectString(\"lower\", s).toLowerCase(),\n",
+        "  upper: (s) => expectString(\"upper\", s).toUpperCase(),\n",
+        "  trim: (s) => expectString(\"trim\", s).trim(),\n",
+        "  starts_with: (s, p) => expectString(\"starts_with\", s).startsWith(expectString(\"starts_with\", p)),\n",
+        "  ends_with: (s, p) => expectString(\"ends_with\", s).endsWith(expectString(\"ends_with\", p)),\n",
+        "  contains: (s, p) => expectString(\"contains\", s).includes(expectString(\"contains\", p)),\n",
+        "  replace: (s, from, to) => expectString(\"replace\", s).split(expectString(\"replace\", from)).join(expectString(\"replace\", to)),\n",
+        "  split: (s, sep) => expectString(\"split\", s).split(expectString(\"split\", sep)),\n",
+        "  join: (items, sep) => {\n",
+        "    if (!Array.isArray(items)) throw new Error(\"join() expects an array argument\");\n",
+        "    return items.map(String).join(expectString(\"join\", sep));\n",
+        "  },\n",
+        "  substr: (s, start, count) => {\n",
+        "    const chars = [...expectString(\"substr\", s)];\n",
+        "    const from = expectIndex(\"substr\", start);\n",
+        "    const to = count === undefined ? chars.length : from + expectIndex(\"substr\", count);\n",
+        "    return chars.slice(from, to).join(\"\");\n",
+        "  },\n",
+        "  pad_left: (s, width, fill = \" \") => {\n",
+        "    const text = expectString(\"pad_left\", s);\n",
+        "    const missing = expectIndex(\"pad_left\", width) - [...text].length;\n",
+        "    return missing > 0 ? expectString(\"pad_left\", fill).repeat(missing) + text : text;\n",
+        "  },\n",
+        "  format: (template, ...values) => {\n",
+        "    let next = 0;\n",
+        "    return expectString(\"format\", template).replace(/\\{\\{|\\}\\}|\\{(\\d*)\\}/g, (token, index) => {\n",
+        "      if (token === \"{{\") return \"{\";\n",
+        "      if (token === \"}}\") return \"}\";\n",
+        "      const value = values[index === \"\" ? next++ : Number(index)];\n",
+        "      if (value === undefined) throw new Error(\"format() placeholder has no argument\");\n",
+        "      return typeof value === \"string\" ? value : JSON.stringify(value);\n",
+        "    });\n",
+        "  },\n",
+        "  matches: (s, re) => new RegExp(expectString(\"matches\", re)).test(expectString(\"matches\", s)),\n",
+        "};\n",
+        "\n",
+        "type ConstraintLambda = (item: unknown) => unknown;\n",
+        "\n",
+        "function isJsonObject(value: unknown): value is Record<string, unknown> {\n",
+        "  return value !== null && typeof value === \"object\" && !Array.isArray(value);\n",
+        "}\n",
+        "\n",
+        "function expectItems(name: string, value: unknown): unknown[] {\n",
+        "  if (Array.isArray(value)) return value;\n",
+        "  if (isJsonObject(value)) return Object.keys(value).sort().map((key) => value[key]);\n",
+        "  throw new Error(`${name}() expects an array or object argument`);\n",
+        "}\n",
+        "\n",
+        "function expectArray(name: string, value: unknown): unknown[] {\n",
+        "  if (!Array.isArray(value)) throw new Error(`${name}() expects an array argument`);\n",
+        "  return value;\n",
+        "}\n",
+        "\n",
+        "function expectObject(name: string, value: unknown): Record<string, unknown> {\n",
+        "  if (!isJsonObject(value)) throw new Error(`${name}() expects an object argument`);\n",
+        "  return value;\n",
+        "}\n",
+        "\n",
+        "function expectLambda(name: string, value: unknown): ConstraintLambda {\n",
+        "  if (typeof value !== \"function\") throw new Error(`${name}() expects a lambda argument`);\n",
+        "  return value as ConstraintLambda;\n",
+        "}\n",
+        "\n",
+        "function optionalLambda(name: string, value: unknown): ConstraintLambda {\n",
+        "  return value === undefined ? (item) => item : expectLambda(name, value);\n",
+        "}\n",
+        "\n",
+        "function canonicalJson(value: unknown): string {\n",
+        "  return JSON.stringify(value, (_key, inner: unknown) =>\n",
+        "    isJsonObject(inner)\n",
+        "      ? Object.fromEntries(Object.keys(inner).sort().map((key) => [key, inner[key]]))\n",
+        "      : inner,\n",
+        "  );\n",
+        "}\n",
+        "\n",
+        "function compareSortKeys(a: unknown, b: unknown): number {\n",
+        "  const [x, y] = [a as number | string, b as number | string];\n",
+        "  return x < y ? -1 : x > y ? 1 : 0;\n",
+        "}\n",
+        "\n",
+        "const collectionFunctions: Record<string, (...args: unknown[]) => unknown> = {\n",
+        "  map: (items, f) => {\n",
+        "    const fn = expectLambda(\"map\", f);\n",
+        "    if (isJsonObject(items)) {\n",
+        "      return Object.fromEntries(Object.entries(items).map(([key, item]) => [key, fn(item)]));\n",
+        "    }\n",
+        "    return expectItems(\"map\", items).map((item) => fn(item));\n",
+        "  },\n",
+        "  filter: (items, f) => {\n",
+        "    const fn = expectLambda(\"filter\", f);\n",
+        "    if (isJsonObject(items)) {\n",
+        "      return Object.fromEntries(Object.entries(items).filter(([, item]) => fn(item) === true));\n",
+        "    }\n",
+        "    return expectItems(\"filter\", items).filter((item) => fn(item) === true);\n",
+        "  },\n",
+        "  all: (items, f) => {\n",
+        "    const fn = expectLambda(\"all\", f);\n",
+        "    return expectItems(\"all\", items).every((item) => fn(item) === true);\n",
+        "  },\n",
+        "  any: (items, f) => {\n",
+        "    const fn = expectLambda(\"any\", f);\n",
+        "    return expectItems(\"any\", items).some((item) => fn(item) === true);\n",
+        "  },\n",
+        "  sum: (items, f) => {\n",
+        "    const fn = optionalLambda(\"sum\", f);\n",
+        "    return expectItems(\"sum\", items).reduce<number>((total, item) => {\n",
+        "      const value = fn(item);\n",
+        "      if (typeof value !== \"number\") throw new Error(\"sum() expects numeric items\");\n",
+        "      return total + value;\n",
+        "    }, 0);\n",
+        "  },\n",
+        "  count: (items, f) => {\n",
+        "    const all = expectItems(\"count\", items);\n",
+        "    if (f === undefined) return all.length;\n",
+        "    const fn = expectLambda(\"count\", f);\n",
+        "    return all.filter((item) => fn(item) === true).length;\n",
+        "  },\n",
+        "  unique: (items, f) => {\n",
+        "    const fn = optionalLambda(\"unique\", f);\n",
+        "    const seen = new Set<string>();\n",
+        "    return expectArray(\"unique\", items).filter((item) => {\n",
+        "      const key = canonicalJson(fn(item));\n",
+        "      if (seen.has(key)) return false;\n",
+        "      seen.add(key);\n",
+        "      return true;\n",
+        "    });\n",
+        "  },\n",
+        "  sort: (items, f) => {\n",
+        "    const fn = optionalLambda(\"sort\", f);\n",
+        "    const keyed = expectArray(\"sort\", items).map((item) => [fn(item), item] as const);\n",
+        "    const kinds = new Set(keyed.map(([key]) => typeof key));\n",
+        "    if (kinds.size > 1 || (keyed.length > 0 && !kinds.has(\"number\") && !kinds.has(\"string\"))) {\n",
+        "      throw new Error(\"sort() keys must be all numbers or all strings\");\n",
+        "    }\n",
+        "    return keyed\n",
+        "      .sort(([a], [b]) => compareSortKeys(a, b))\n",
+        "      .map(([, item]) => item);\n",
+        "  },\n",
+        "  keys: (value) => Object.keys(expectObject(\"keys\", value)).sort(),\n",
+        "  values: (value) => expectItems(\"values\", expectObject(\"values\", value)),\n",
+        "  contains: (haystack, needle) => {\n",
+        "    if (typeof haystack === \"string\") return haystack.includes(expectString(\"contains\", needle));\n",
+        "    if (Array.isArray(haystack)) {\n",
+        "      const wanted = canonicalJson(needle);\n",
+        "      return haystack.some((item) => canonicalJson(item) === wanted);\n",
+        "    }\n",
+        "    const key = expectString(\"contains\", needle);\n",
+        "    return Object.prototype.hasOwnProperty.call(expectObject(\"contains\", haystack), key);\n",
+        "  },\n",
+        "};\n",
+        "\n",
         "function createConstraintContext(\n",
         "  data: unknown,\n",
         "  currentScope: unknown,\n",
@@ -458,6 +635,8 @@ fn render_constraint_runtime_helpers() -> String {
         "      if (key === \"round\") return Math.round;\n",
         "      if (key === \"len\") return len;\n",
         "      if (key === \"coalesce\") return coalesce;\n",
+        "      if (key in collectionFunctions) return collectionFunctions[key];\n",
+        "      if (key in stringFunctions) return stringFunctions[key];\n",
         "\n",
         "      const rootObject = data as Record<string, unknown> | null;\n",
         "      if (rootObject && typeof rootObject === \"object\" && key in rootObject) {\n",
@@ -495,6 +674,7 @@ fn render_constraint_runtime_helpers() -> String {
         "  value: unknown,\n",
         "  path: string,\n",
         "  expression: string,\n",
+        "  source: string,\n",
         "): string | null {\n",
         "  const normalizedPath = normalizeConstraintPath(path);\n",
         "  const currentValue = getConstraintPath(value, normalizedPath);\n",
@@ -504,7 +684,7 @@ fn render_constraint_runtime_helpers() -> String {
         "  const parentPath = parentConstraintPath(normalizedPath);\n",
         "  const currentScope = parentPath ? getConstraintPath(value, parentPath) : undefined;\n",
         "  try {\n",
-        "    const result = evaluateGeneratedConstraint(expression, value, currentScope, currentValue);\n",
+        "    const result = evaluateGeneratedConstraint(source, value, currentScope, currentValue);\n",
         "    if (result === true) return null;\n",
         "    return `constraint failed at '${normalizedPath}': '${expression}' evaluated to ${String(result)}`;\n",
         "  } catch (error) {\n",
@@ -561,9 +741,7 @@ fn collect_keyed_enum_members(
     if enum_map.is_empty() {
         return None;
     }
-    if schema_obj.get("type").and_then(JsonValue::as_str).is_none() {
-        return None;
-    }
+    schema_obj.get("type").and_then(JsonValue::as_str)?;
     let mut members: Vec<(String, JsonValue)> = enum_map
         .iter()
         .map(|(k, v)| (k.clone(), v.clone()))
@@ -794,6 +972,16 @@ fn ts_type_for_schema(schema: &JsonValue, state: &mut RenderState) -> String {
     "unknown".to_string()
 }
 
+/// TypeScript type for a built-in `duration`, `timestamp` or `bytesize`
+/// value: its JSON spelling (`"30s"`, an RFC 3339 string, `"512MiB"` or a
+/// byte count).
+fn ts_quantity_type(type_name: &str) -> &'static str {
+    match type_name {
+        "bytesize" => "string | number",
+        _ => "string",
+    }
+}
+
 fn ts_type_for_type_name(
     type_name: &str,
     schema_obj: &JsonMap<String, JsonValue>,
@@ -805,6 +993,7 @@ fn ts_type_for_type_name(
         "number" => "number".to_string(),
         "boolean" => "boolean".to_string(),
         "null" => "null".to_string(),
+        "duration" | "timestamp" | "bytesize" => ts_quantity_type(type_name).to_string(),
         "array" => {
             if let Some(items) = schema_obj.get("items") {
                 let item_type = ts_type_for_schema(items, state);
@@ -967,12 +1156,32 @@ fn escape_string(raw: &str) -> String {
 ///
 /// The compiled (resolved) `---data` section is emitted as `export const` statements
 /// after the type definitions, separated by a `// --- Data ---` comment.
+///
+/// Fails when the data holds sensitive values, which generated code would
+/// carry in plain text; see [`generate_typescript_types_and_redacted_data_from_path`].
 pub fn generate_typescript_types_and_data_from_path(
     path: impl AsRef<Path>,
     env_provider: &dyn crate::EnvProvider,
 ) -> Result<String, SyamlError> {
-    let path = path.as_ref();
+    generate_typescript_types_and_data(path.as_ref(), env_provider, false)
+}
+
+/// Like [`generate_typescript_types_and_data_from_path`], but redacts
+/// sensitive data instead of failing. A top-level string that is sensitive
+/// is emitted as `[redacted]`; any other top-level constant holding a
+/// sensitive value is left out, with a comment in its place.
+pub fn generate_typescript_types_and_redacted_data_from_path(
+    path: impl AsRef<Path>,
+    env_provider: &dyn crate::EnvProvider,
+) -> Result<String, SyamlError> {
+    generate_typescript_types_and_data(path.as_ref(), env_provider, true)
+}
 
+fn generate_typescript_types_and_data(
+    path: &Path,
+    env_provider: &dyn crate::EnvProvider,
+    redact: bool,
+) -> Result<String, SyamlError> {
     // Parse to get type hints (path → schema type name).
     let input = fs::read_to_string(path).map_err(|e| {
         SyamlError::ImportError(format!("failed to read '{}': {e}", path.display()))
@@ -987,12 +1196,20 @@ pub fn generate_typescript_types_and_data_from_path(
 
     // Compile to get resolved data values.
     let compiled = crate::compile_document_from_path_with_fetch(path, env_provider, None, false)?;
+    if !redact {
+        sensitive::refuse_embedding(&compiled.sensitive_paths)?;
+    }
 
     // Render types (existing logic).
     let types_output = render_typescript_types(&schemas);
 
     // Render data items.
-    let data_output = render_typescript_data(&compiled.value, &type_hints, &type_names);
+    let data_output = render_typescript_data(
+        &compiled.value,
+        &type_hints,
+        &type_names,
+        &compiled.sensitive_paths,
+    );
 
     let contracts_output = if let Some(ref func_doc) = parsed.contracts {
         crate::contracts::generate_typescript_function_stubs(func_doc, &schemas.types)
@@ -1018,6 +1235,7 @@ fn render_typescript_data(
     data: &JsonValue,
     type_hints: &BTreeMap<String, String>,
     type_names: &BTreeMap<String, String>,
+    sensitive: &BTreeSet<String>,
 ) -> String {
     let Some(obj) = data.as_object() else {
         return String::new();
@@ -1032,6 +1250,21 @@ fn render_typescript_data(
         let type_hint = type_hints.get(&hint_path).map(String::as_str);
         let ts_type = data_ts_type(type_hint, value, type_names);
         let var_name = to_camel_case_ts(key);
+        if sensitive
+            .iter()
+            .any(|path| path_is_within(path, &hint_path))
+        {
+            if sensitive.contains(&hint_path) && value.is_string() && ts_type == "string" {
+                out.push_str(&format!(
+                    "export const {var_name}: string = \"{REDACTED}\";\n\n"
+                ));
+            } else {
+                out.push_str(&format!(
+                    "// `{var_name}` is left out: it holds sensitive values.\n\n"
+                ));
+            }
+            continue;
+        }
         // JSON is valid TypeScript literal syntax.
         let json_str = serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".to_string());
         out.push_str(&format!(
@@ -1051,6 +1284,7 @@ fn data_ts_type(
             "string" => return "string".to_string(),
             "integer" | "number" => return "number".to_string(),
             "boolean" => return "boolean".to_string(),
+            "duration" | "timestamp" | "bytesize" => return ts_quantity_type(hint).to_string(),
             other => {
                 if let Some(mapped) = type_names.get(other) {
                     return mapped.clone();
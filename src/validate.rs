@@ -5,14 +5,15 @@ use std::collections::{BTreeMap, HashSet};
 use serde_json::json;
 use serde_json::Value as JsonValue;
 
-use crate::ast::SchemaDoc;
+use crate::ast::{PureFunctionDef, SchemaDoc, Warning};
 use crate::error::SyamlError;
 use crate::expr::eval::{evaluate, EvalContext, EvalError};
 use crate::expr::parse_expression;
 use crate::expr::parser::{BinaryOp, Expr};
 use crate::resolve::get_json_path;
 use crate::schema::{
-    parse_field_version_meta, resolve_type_schema, validate_json_against_schema_with_types,
+    parse_field_version_meta, resolve_type_schema, validate_json_against_schema_concealed,
+    validate_json_against_schema_with_types,
 };
 
 const MAX_CONSTRAINT_PATHS: usize = 2048;
@@ -27,25 +28,59 @@ const REL_ANY: u8 = REL_LT | REL_EQ | REL_GT;
 ///
 /// Each hint path must exist in `data`, and each referenced type must resolve
 /// either to a named type in `schema` or to a built-in primitive type.
+///
+/// Every hint is checked; all failures are returned together via
+/// [`SyamlError::from_errors`].
 pub fn validate_type_hints(
     data: &JsonValue,
     hints: &BTreeMap<String, String>,
     schema: &SchemaDoc,
 ) -> Result<(), SyamlError> {
+    validate_type_hints_concealing(data, hints, schema, &|_| false)
+}
+
+/// [`validate_type_hints`], leaving values out of the messages for hints
+/// whose path `is_sensitive`.
+pub(crate) fn validate_type_hints_concealing(
+    data: &JsonValue,
+    hints: &BTreeMap<String, String>,
+    schema: &SchemaDoc,
+    is_sensitive: &dyn Fn(&str) -> bool,
+) -> Result<(), SyamlError> {
+    let mut errors = Vec::new();
     for (path, type_name) in hints {
-        let _ = resolve_type_schema(schema, type_name)?;
-        let value = get_json_path(data, path).ok_or_else(|| {
-            SyamlError::TypeHintError(format!("type hint references missing path '{}'", path))
-        })?;
-        let hinted_schema = json!({ "type": type_name });
-        validate_json_against_schema_with_types(value, &hinted_schema, path, &schema.types)?;
+        if let Err(e) = validate_type_hint(data, path, type_name, schema, is_sensitive(path)) {
+            errors.push(e.at_path(path));
+        }
     }
 
     for (path, type_name) in hints {
-        validate_nested_hint_matches_parent_schema(path, type_name, hints, schema)?;
+        if let Err(e) = validate_nested_hint_matches_parent_schema(path, type_name, hints, schema) {
+            errors.push(e.at_path(path));
+        }
     }
 
-    Ok(())
+    SyamlError::from_errors(errors)
+}
+
+fn validate_type_hint(
+    data: &JsonValue,
+    path: &str,
+    type_name: &str,
+    schema: &SchemaDoc,
+    conceal: bool,
+) -> Result<(), SyamlError> {
+    let _ = resolve_type_schema(schema, type_name)?;
+    let value = get_json_path(data, path).ok_or_else(|| {
+        SyamlError::TypeHintError(format!("type hint references missing path '{}'", path))
+    })?;
+    let hinted_schema = json!({ "type": type_name });
+    let validate = if conceal {
+        validate_json_against_schema_concealed
+    } else {
+        validate_json_against_schema_with_types
+    };
+    validate(value, &hinted_schema, path, &schema.types)
 }
 
 fn validate_nested_hint_matches_parent_schema(
@@ -466,7 +501,7 @@ fn is_builtin_type_name(type_name: &str) -> bool {
     matches!(
         type_name,
         "string" | "integer" | "number" | "boolean" | "object" | "array" | "null"
-    )
+    ) || crate::quantity::QUANTITY_TYPE_NAMES.contains(&type_name)
 }
 
 /// Builds the full constraint set for a document from type-local constraints
@@ -513,15 +548,17 @@ pub fn validate_constraints(
     constraints: &BTreeMap<String, Vec<String>>,
 ) -> Result<(), SyamlError> {
     let imports = BTreeMap::new();
-    validate_constraints_with_imports(data, env, constraints, &imports)
+    validate_constraints_with_imports(data, env, constraints, &imports, &BTreeMap::new())
 }
 
-/// Evaluates schema constraints against data, environment, and imported namespaces.
+/// Evaluates schema constraints against data, environment, and imported namespaces,
+/// with `functions` callable by name.
 pub fn validate_constraints_with_imports(
     data: &JsonValue,
     env: &BTreeMap<String, JsonValue>,
     constraints: &BTreeMap<String, Vec<String>>,
     imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
 ) -> Result<(), SyamlError> {
     if constraints.len() > MAX_CONSTRAINT_PATHS {
         return Err(SyamlError::ConstraintError(format!(
@@ -530,6 +567,7 @@ pub fn validate_constraints_with_imports(
         )));
     }
 
+    let mut errors = Vec::new();
     for (path, expressions) in constraints {
         if expressions.len() > MAX_CONSTRAINTS_PER_PATH {
             return Err(SyamlError::ConstraintError(format!(
@@ -540,71 +578,104 @@ pub fn validate_constraints_with_imports(
         }
 
         let normalized_path = normalize_path(path);
-        let value = get_json_path(data, &normalized_path).ok_or_else(|| {
-            SyamlError::ConstraintError(format!(
-                "constraint path '{}' not found (normalized '{}')",
-                path, normalized_path
-            ))
-        })?;
-        let current_scope = parent_path(&normalized_path).and_then(|p| get_json_path(data, &p));
-        let mut parsed_expressions = Vec::with_capacity(expressions.len());
-
-        for expression in expressions {
-            let source = expression.trim().trim_start_matches('=').trim();
-            if source.len() > MAX_CONSTRAINT_EXPR_LEN {
-                return Err(SyamlError::ConstraintError(format!(
-                    "constraint expression at '{}' exceeds max length ({MAX_CONSTRAINT_EXPR_LEN})",
-                    normalized_path
-                )));
-            }
-            let ast = parse_expression(source)?;
-            parsed_expressions.push((expression.clone(), ast));
+        if let Err(e) = validate_constraints_at_path(
+            data,
+            env,
+            imports,
+            functions,
+            path,
+            &normalized_path,
+            expressions,
+        ) {
+            errors.push(e.at_path(&normalized_path));
         }
+    }
 
-        detect_impossible_constraints(&normalized_path, &parsed_expressions)?;
-
-        for (expression, ast) in &parsed_expressions {
-            let unresolved = HashSet::new();
-            let ctx = EvalContext {
-                data,
-                imports,
-                env,
-                unresolved_paths: &unresolved,
-                current_value: Some(value),
-                current_scope,
-                named_scopes: std::collections::BTreeMap::new(),
-            };
+    SyamlError::from_errors(errors)
+}
 
-            let result = evaluate(ast, &ctx).map_err(|eval_err| match eval_err {
-                EvalError::Fatal(SyamlError::ExpressionError(msg)) => {
-                    SyamlError::ConstraintError(format!(
-                        "constraint evaluation failed at '{}': {} (in expression '{}')",
-                        normalized_path, msg, expression
-                    ))
-                }
-                other => map_eval_error(other),
-            })?;
-            match result {
-                JsonValue::Bool(true) => {}
-                JsonValue::Bool(false) => {
-                    return Err(SyamlError::ConstraintError(format!(
-                        "constraint failed at '{}': '{}' evaluated to false",
-                        normalized_path, expression
-                    )));
-                }
-                other => {
-                    return Err(SyamlError::ConstraintError(format!(
-                        "constraint '{}' at '{}' must evaluate to boolean, got {}",
-                        expression,
-                        normalized_path,
-                        json_type_name(&other)
-                    )));
-                }
+fn validate_constraints_at_path(
+    data: &JsonValue,
+    env: &BTreeMap<String, JsonValue>,
+    imports: &BTreeMap<String, JsonValue>,
+    functions: &BTreeMap<String, PureFunctionDef>,
+    path: &str,
+    normalized_path: &str,
+    expressions: &[String],
+) -> Result<(), SyamlError> {
+    let value = get_json_path(data, normalized_path).ok_or_else(|| {
+        SyamlError::ConstraintError(format!(
+            "constraint path '{}' not found (normalized '{}')",
+            path, normalized_path
+        ))
+    })?;
+    let current_scope = parent_path(normalized_path).and_then(|p| get_json_path(data, &p));
+    let mut parsed_expressions = Vec::with_capacity(expressions.len());
+
+    for expression in expressions {
+        let source = expression.trim().trim_start_matches('=').trim();
+        if source.len() > MAX_CONSTRAINT_EXPR_LEN {
+            return Err(SyamlError::ConstraintError(format!(
+                "constraint expression at '{}' exceeds max length ({MAX_CONSTRAINT_EXPR_LEN})",
+                normalized_path
+            )));
+        }
+        let ast = parse_expression(source)?;
+        parsed_expressions.push((expression.clone(), ast));
+    }
+
+    detect_impossible_constraints(normalized_path, &parsed_expressions)?;
+
+    let mut errors = Vec::new();
+    let no_quantities = BTreeMap::new();
+    for (expression, ast) in &parsed_expressions {
+        let unresolved = HashSet::new();
+        let ctx = EvalContext {
+            data,
+            imports,
+            env,
+            unresolved_paths: &unresolved,
+            current_value: Some(value),
+            current_scope,
+            named_scopes: std::collections::BTreeMap::new(),
+            functions,
+            quantities: &no_quantities,
+        };
+
+        let result = match evaluate(ast, &ctx) {
+            Ok(result) => result,
+            Err(EvalError::Fatal(SyamlError::ExpressionError(msg))) => {
+                errors.push(SyamlError::ConstraintError(format!(
+                    "constraint evaluation failed at '{}': {} (in expression '{}')",
+                    normalized_path, msg, expression
+                )));
+                continue;
+            }
+            Err(other) => {
+                errors.push(map_eval_error(other));
+                continue;
+            }
+        };
+        match result {
+            JsonValue::Bool(true) => {}
+            JsonValue::Bool(false) => {
+                errors.push(SyamlError::ConstraintError(format!(
+                    "constraint failed at '{}': '{}' evaluated to false",
+                    normalized_path, expression
+                )));
+            }
+            other => {
+                errors.push(SyamlError::ConstraintError(format!(
+                    "constraint '{}' at '{}' must evaluate to boolean, got {}",
+                    expression,
+                    normalized_path,
+                    json_type_name(&other)
+                )));
             }
         }
     }
 
-    Ok(())
+    SyamlError::from_errors(errors)
 }
 
 fn detect_impossible_constraints(
@@ -775,13 +846,11 @@ fn normalize_numeric_comparison(
 ) -> Option<NumericComparison> {
     match (left, right) {
         (Expr::Var(var_path), Expr::Number(value)) => {
-            if relation_mask_for_op(op).is_none() {
-                return None;
-            }
+            relation_mask_for_op(op)?;
             Some(NumericComparison {
                 var: var_path.join("."),
                 op,
-                value: *value,
+                value: value.as_f64(),
             })
         }
         (Expr::Number(value), Expr::Var(var_path)) => {
@@ -789,7 +858,7 @@ fn normalize_numeric_comparison(
             Some(NumericComparison {
                 var: var_path.join("."),
                 op: flipped,
-                value: *value,
+                value: value.as_f64(),
             })
         }
         _ => None,
@@ -1023,9 +1092,7 @@ fn parent_path(path: &str) -> Option<String> {
 
     let mut last_sep = None;
     for (idx, ch) in path.char_indices() {
-        if ch == '.' && idx > 1 {
-            last_sep = Some(idx);
-        } else if ch == '[' {
+        if (ch == '.' && idx > 1) || ch == '[' {
             last_sep = Some(idx);
         }
     }
@@ -1049,18 +1116,20 @@ fn map_eval_error(err: EvalError) -> SyamlError {
 /// Validates data fields against schema versioning annotations.
 ///
 /// When `target_version` is `None`, all checks are skipped and no warnings are produced.
-/// Returns collected warnings (deprecation notices). Hard errors are returned as `SyamlError`.
+/// Returns collected warnings (deprecation notices). Hard errors are collected across all
+/// fields and returned together as a `SyamlError`.
 pub fn validate_versioned_fields(
     data: &JsonValue,
     hints: &BTreeMap<String, String>,
     schema: &SchemaDoc,
     target_version: Option<&semver::Version>,
-) -> Result<Vec<String>, SyamlError> {
+) -> Result<Vec<Warning>, SyamlError> {
     let Some(target) = target_version else {
         return Ok(Vec::new());
     };
 
     let mut warnings = Vec::new();
+    let mut errors = Vec::new();
 
     for (hint_path, type_name) in hints {
         let type_schema = match schema.types.get(type_name) {
@@ -1096,20 +1165,28 @@ pub fn validate_versioned_fields(
             // `since` check: field is present but target < since → error
             if let Some(ref since) = meta.since {
                 if field_present && target < since {
-                    return Err(SyamlError::VersionFieldError(format!(
-                        "field '{}' at '{}' is not available until version {} (target: {})",
-                        prop_name, hint_path, since, target
-                    )));
+                    errors.push(
+                        SyamlError::VersionFieldError(format!(
+                            "field '{}' at '{}' is not available until version {} (target: {})",
+                            prop_name, hint_path, since, target
+                        ))
+                        .at_path(format!("{hint_path}.{prop_name}")),
+                    );
+                    continue;
                 }
             }
 
             // `removed` check: field is present and target >= removed → error
             if let Some(ref removed) = meta.removed {
                 if field_present && target >= removed {
-                    return Err(SyamlError::VersionFieldError(format!(
-                        "field '{}' at '{}' was removed in version {} (target: {})",
-                        prop_name, hint_path, removed, target
-                    )));
+                    errors.push(
+                        SyamlError::VersionFieldError(format!(
+                            "field '{}' at '{}' was removed in version {} (target: {})",
+                            prop_name, hint_path, removed, target
+                        ))
+                        .at_path(format!("{hint_path}.{prop_name}")),
+                    );
+                    continue;
                 }
             }
 
@@ -1129,10 +1206,17 @@ pub fn validate_versioned_fields(
                     };
                     match dep_info.severity {
                         DeprecationSeverity::Error => {
-                            return Err(SyamlError::VersionFieldError(msg));
+                            errors.push(
+                                SyamlError::VersionFieldError(msg)
+                                    .at_path(format!("{hint_path}.{prop_name}")),
+                            );
                         }
                         DeprecationSeverity::Warning => {
-                            warnings.push(msg);
+                            warnings.push(Warning {
+                                message: msg,
+                                data_path: Some(format!("{hint_path}.{prop_name}")),
+                                span: None,
+                            });
                         }
                     }
                 }
@@ -1140,6 +1224,7 @@ pub fn validate_versioned_fields(
         }
     }
 
+    SyamlError::from_errors(errors)?;
     Ok(warnings)
 }
 
@@ -0,0 +1,315 @@
+//! File watching for the CLI `--watch` modes.
+//!
+//! [`watch_set`] lists every file a build depends on: the root document, its
+//! local import graph (as walked by [`collect_import_graph`]), the base
+//! documents of overlays, `@module` imports resolved through the project
+//! registry, and the `module.syaml` / `syaml.syaml` files that affect
+//! resolution. [`PollWatcher`] detects changes to those files
+//! by polling their modification time and size, which works on any filesystem
+//! without platform-specific notification APIs.
+//!
+//! [`watch_set`]: crate::watch::watch_set
+//! [`PollWatcher`]: crate::watch::PollWatcher
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::error::SyamlError;
+use crate::html_docs_gen::collect_import_graph;
+use crate::module::{
+    find_module_manifest, find_project_root, load_module_registry, resolve_module_import,
+    MANIFEST_FILENAME, REGISTRY_FILENAME,
+};
+use crate::parse_document_or_manifest;
+
+/// Default delay between polls.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Returns every file (and, for directory inputs, every directory) whose change
+/// should trigger a rebuild of `input`.
+///
+/// `input` may be a `.syaml` file, a `module.syaml` manifest, or a directory that
+/// is scanned recursively for `.syaml` files.
+pub fn watch_set(input: &Path) -> Result<BTreeSet<PathBuf>, SyamlError> {
+    let mut out = BTreeSet::new();
+    let mut queue: Vec<PathBuf> = Vec::new();
+
+    if input.is_dir() {
+        collect_dir(input, &mut out, &mut queue)?;
+    } else {
+        queue.push(canonical(input));
+        if input.file_name().and_then(|n| n.to_str()) == Some(MANIFEST_FILENAME) {
+            // Docs for a module cover every member file next to the manifest.
+            let dir = input.parent().unwrap_or(Path::new("."));
+            collect_dir(dir, &mut out, &mut queue)?;
+        }
+    }
+
+    let mut visited_roots = BTreeSet::new();
+    while let Some(root) = queue.pop() {
+        if !visited_roots.insert(root.clone()) {
+            continue;
+        }
+        out.insert(root.clone());
+        if !root.exists() {
+            continue;
+        }
+
+        for (path, content) in collect_import_graph(&root)? {
+            if let Some(manifest) = find_module_manifest(&path) {
+                queue.push(canonical(&manifest));
+            }
+            let registry_dir = path.parent().and_then(find_project_root);
+            if let Some(dir) = &registry_dir {
+                let registry = dir.join(REGISTRY_FILENAME);
+                if registry.exists() {
+                    out.insert(canonical(&registry));
+                }
+            }
+            for module_import in module_imports(&content, registry_dir.as_deref()) {
+                queue.push(module_import);
+            }
+            if let Some(base) = overlay_base(&path, &content) {
+                queue.push(base);
+            }
+            out.insert(path);
+        }
+    }
+
+    Ok(out)
+}
+
+/// The base document an overlay at `path` patches, if it is an overlay.
+fn overlay_base(path: &Path, content: &str) -> Option<PathBuf> {
+    let base = parse_document_or_manifest(content).ok()?.overlay?;
+    Some(canonical(&path.parent()?.join(base)))
+}
+
+/// Resolves the `@module` imports of a document to file paths.
+///
+/// Imports that cannot be resolved are skipped; the build reports them.
+fn module_imports(content: &str, project_root: Option<&Path>) -> Vec<PathBuf> {
+    let Ok(parsed) = parse_document_or_manifest(content) else {
+        return Vec::new();
+    };
+    let Some(meta) = parsed.meta else {
+        return Vec::new();
+    };
+    let at_imports: Vec<&str> = meta
+        .imports
+        .values()
+        .map(|binding| binding.path.as_str())
+        .filter(|path| path.starts_with('@'))
+        .collect();
+    if at_imports.is_empty() {
+        return Vec::new();
+    }
+    let Some(registry) = project_root.and_then(|root| load_module_registry(root).ok()) else {
+        return Vec::new();
+    };
+    at_imports
+        .into_iter()
+        .filter_map(|path| resolve_module_import(path, &registry).ok())
+        .map(|path| canonical(&path))
+        .collect()
+}
+
+fn collect_dir(
+    dir: &Path,
+    out: &mut BTreeSet<PathBuf>,
+    queue: &mut Vec<PathBuf>,
+) -> Result<(), SyamlError> {
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        // Directory mtimes change when files are added or removed.
+        out.insert(canonical(&dir));
+        let entries = fs::read_dir(&dir).map_err(|e| {
+            SyamlError::ImportError(format!("failed to read directory '{}': {e}", dir.display()))
+        })?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().and_then(|e| e.to_str()) == Some("syaml") {
+                queue.push(canonical(&path));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Observed state of a watched path; `None` when the path does not exist.
+type FileStamp = Option<(Option<SystemTime>, u64)>;
+
+fn stamp(path: &Path) -> FileStamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+/// Polling change detector over a set of paths.
+#[derive(Debug)]
+pub struct PollWatcher {
+    interval: Duration,
+    stamps: BTreeMap<PathBuf, FileStamp>,
+}
+
+impl PollWatcher {
+    /// Creates a watcher with no paths that polls every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            stamps: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the watched paths and records their current state as the baseline.
+    pub fn set_paths(&mut self, paths: impl IntoIterator<Item = PathBuf>) {
+        self.stamps = paths
+            .into_iter()
+            .map(|path| {
+                let current = stamp(&path);
+                (path, current)
+            })
+            .collect();
+    }
+
+    /// Number of watched paths.
+    pub fn len(&self) -> usize {
+        self.stamps.len()
+    }
+
+    /// Returns `true` when no paths are watched.
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    /// Returns the paths that changed since the baseline (or the previous poll)
+    /// and updates the baseline.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, previous) in self.stamps.iter_mut() {
+            let current = stamp(path);
+            if current != *previous {
+                *previous = current;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+
+    /// Blocks until at least one watched path changes and returns the changed paths.
+    ///
+    /// After the first change is seen, waits one more interval so that editors
+    /// writing several files (or writing a file in steps) trigger a single rebuild.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        loop {
+            thread::sleep(self.interval);
+            let mut changed = self.poll();
+            if changed.is_empty() {
+                continue;
+            }
+            thread::sleep(self.interval);
+            for path in self.poll() {
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+            return changed;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{watch_set, PollWatcher};
+
+    fn temp_dir(prefix: &str) -> PathBuf {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "super_yaml_{}_{}_{}",
+            prefix,
+            std::process::id(),
+            stamp
+        ));
+        fs::create_dir_all(&path).expect("create temp dir");
+        fs::canonicalize(path).expect("canonicalize temp dir")
+    }
+
+    #[test]
+    fn watch_set_includes_imports_modules_and_registry() {
+        let dir = temp_dir("watch_set");
+        fs::write(
+            dir.join("syaml.syaml"),
+            "---!syaml/v0\n---data\nmodules:\n  lib: ./lib\n",
+        )
+        .unwrap();
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(
+            dir.join("lib/module.syaml"),
+            "---!syaml/v0\n---module\nname: lib\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("lib/types.syaml"),
+            "---!syaml/v0\n---schema\nPort: integer\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("shared.syaml"),
+            "---!syaml/v0\n---data\nport: 80\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("root.syaml"),
+            "---!syaml/v0\n---meta\nimports:\n  shared: ./shared.syaml\n  lib: \"@lib/types\"\n---data\nx: 1\n",
+        )
+        .unwrap();
+
+        let set = watch_set(&dir.join("root.syaml")).unwrap();
+        for file in [
+            "root.syaml",
+            "shared.syaml",
+            "syaml.syaml",
+            "lib/types.syaml",
+            "lib/module.syaml",
+        ] {
+            assert!(set.contains(&dir.join(file)), "missing {file}: {set:?}");
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn poll_reports_modified_and_deleted_files() {
+        let dir = temp_dir("watch_poll");
+        let file = dir.join("a.syaml");
+        fs::write(&file, "---!syaml/v0\n---data\nx: 1\n").unwrap();
+
+        let mut watcher = PollWatcher::new(Duration::from_millis(10));
+        watcher.set_paths([file.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::write(&file, "---!syaml/v0\n---data\nx: 12\n").unwrap();
+        assert_eq!(watcher.poll(), vec![file.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(watcher.wait(), vec![file]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
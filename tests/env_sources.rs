@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use super_yaml::{
+    compile_document, compile_document_from_path, MapEnvProvider, ProcessEnvProvider,
+};
+
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(prefix: &str) -> Self {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "super_yaml_env_sources_{}_{}_{}",
+            prefix,
+            std::process::id(),
+            stamp
+        ));
+        fs::create_dir_all(path.join("secrets")).expect("create temp dir");
+        Self { path }
+    }
+
+    fn write(&self, file: &str, content: &str) {
+        fs::write(self.path.join(file), content).expect("write temp file");
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+const SOURCES: &str = r#"
+---!syaml/v0
+---meta
+env:
+  DB_PASSWORD:
+    from: file
+    path: secrets/db_password
+  REPLICAS:
+    from: file
+    path: secrets/replicas
+  TLS_CERT:
+    from: literal_file
+    path: secrets/tls.pem
+  API_TOKEN:
+    from: dotenv
+    file: .env
+    key: API_TOKEN
+  PORT:
+    from: dotenv
+    file: .env
+    key: PORT
+  REGION:
+    from: dotenv
+    file: .env
+    key: REGION
+    default: eu-west-1
+---schema
+{}
+---data
+db_password: ${env.DB_PASSWORD}
+replicas: ${env.REPLICAS}
+cert: ${env.TLS_CERT}
+token: ${env.API_TOKEN}
+port: ${env.PORT}
+region: ${env.REGION}
+"#;
+
+#[test]
+fn file_literal_file_and_dotenv_sources_resolve_relative_to_document() {
+    let dir = TempDir::new("sources");
+    dir.write("config.syaml", SOURCES);
+    dir.write("secrets/db_password", "hunter2\n");
+    dir.write("secrets/replicas", "3\n");
+    dir.write(
+        "secrets/tls.pem",
+        "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n",
+    );
+    dir.write(
+        ".env",
+        "# local overrides\nexport API_TOKEN=\"abc\\\"123\"\nPORT=8080 # web\n",
+    );
+
+    let value = compile_document_from_path(dir.path.join("config.syaml"), &ProcessEnvProvider)
+        .expect("compile")
+        .value;
+    assert_eq!(value["db_password"], json!("hunter2"));
+    assert_eq!(value["replicas"], json!(3));
+    assert_eq!(
+        value["cert"],
+        json!("-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n")
+    );
+    assert_eq!(value["token"], json!("abc\"123"));
+    assert_eq!(value["port"], json!(8080));
+    assert_eq!(value["region"], json!("eu-west-1"));
+}
+
+#[test]
+fn missing_files_are_reported_like_missing_variables() {
+    let dir = TempDir::new("missing");
+    dir.write("config.syaml", SOURCES);
+    dir.write("secrets/replicas", "3");
+    dir.write(".env", "API_TOKEN=abc\nPORT=8080\n");
+
+    let err =
+        compile_document_from_path(dir.path.join("config.syaml"), &ProcessEnvProvider).unwrap_err();
+    assert_eq!(err.code(), "env_error");
+    assert!(
+        err.to_string()
+            .contains("missing required file secrets/db_password for symbol 'DB_PASSWORD'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn file_access_is_denied_unless_the_provider_serves_it() {
+    let doc = r#"
+---!syaml/v0
+---meta
+env:
+  TOKEN:
+    from: dotenv
+    file: app.env
+    key: TOKEN
+    required: false
+  NOTE:
+    from: file
+    path: note.txt
+    trim: false
+---schema
+{}
+---data
+token: ${env.TOKEN}
+note: ${env.NOTE}
+"#;
+    // Documents compiled from a string read relative paths from the current directory.
+    let cwd = std::env::current_dir().expect("cwd");
+    let env = MapEnvProvider::new(HashMap::new()).with_file(cwd.join("note.txt"), " 5 \n");
+    let value = compile_document(doc, &env).expect("compile").value;
+    assert_eq!(value["token"], json!(null));
+    assert_eq!(value["note"], json!(" 5 \n"));
+}
+
+#[test]
+fn invalid_sources_and_dotenv_lines_are_rejected() {
+    let unsupported = r#"
+---!syaml/v0
+---meta
+env:
+  TOKEN:
+    from: vault
+    key: TOKEN
+---schema
+{}
+---data
+token: ${env.TOKEN}
+"#;
+    let err = compile_document(unsupported, &MapEnvProvider::new(HashMap::new())).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unsupported from='vault'; expected env, file, literal_file or dotenv"),
+        "{err}"
+    );
+    let command = unsupported.replace(
+        "from: vault\n    key: TOKEN",
+        "from: command\n    run: pass show token",
+    );
+    let err = compile_document(&command, &MapEnvProvider::new(HashMap::new())).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("unsupported from='command': documents cannot run programs"),
+        "{err}"
+    );
+
+    let dotenv = unsupported.replace(
+        "from: vault\n    key: TOKEN",
+        "from: dotenv\n    file: app.env\n    key: TOKEN",
+    );
+    let cwd = std::env::current_dir().expect("cwd");
+    let env = MapEnvProvider::new(HashMap::new()).with_file(cwd.join("app.env"), "TOKEN abc\n");
+    let err = compile_document(&dotenv, &env).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("dotenv file 'app.env': line 1: expected KEY=value"),
+        "{err}"
+    );
+}
@@ -22,6 +22,8 @@ fn eval_with(
         current_value,
         current_scope: None,
         named_scopes: BTreeMap::new(),
+        functions: &BTreeMap::new(),
+        quantities: &BTreeMap::new(),
     };
     evaluate(&expr, &ctx)
 }
@@ -224,3 +226,416 @@ fn evaluation_reports_function_errors() {
         other => panic!("expected fatal error, got {other:?}"),
     }
 }
+
+#[test]
+fn evaluates_string_functions() {
+    let data = json!({"name": "  Api-Gateway  ", "host": "db.internal", "tags": ["a", 1, true]});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(eval("lower(trim(name))"), json!("api-gateway"));
+    assert_eq!(eval("upper(\"abc\")"), json!("ABC"));
+    assert_eq!(eval("starts_with(host, \"db.\")"), json!(true));
+    assert_eq!(eval("ends_with(host, \".com\")"), json!(false));
+    assert_eq!(eval("contains(host, \"intern\")"), json!(true));
+    assert_eq!(eval("replace(host, \".\", \"-\")"), json!("db-internal"));
+    assert_eq!(eval("split(\"a,b,,c\", \",\")"), json!(["a", "b", "", "c"]));
+    assert_eq!(eval("join(tags, \"/\")"), json!("a/1/true"));
+    assert_eq!(eval("substr(\"héllo\", 1, 3)"), json!("éll"));
+    assert_eq!(eval("substr(\"hello\", 3)"), json!("lo"));
+    assert_eq!(eval("substr(\"hi\", 5, 2)"), json!(""));
+    assert_eq!(eval("pad_left(\"7\", 3, \"0\")"), json!("007"));
+    assert_eq!(eval("pad_left(\"1234\", 3)"), json!("1234"));
+    assert_eq!(
+        eval("format(\"{}:{} ({0}) {{x}}\", host, 5432)"),
+        json!("db.internal:5432 (db.internal) {x}")
+    );
+    assert_eq!(eval("matches(host, \"^[a-z]+\\.internal$\")"), json!(true));
+    assert_eq!(eval("matches(\"v12\", \"^v\\d+$\")"), json!(true));
+}
+
+#[test]
+fn string_functions_report_type_and_argument_errors() {
+    let data = json!({"port": 80, "name": "svc"});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let message = |src: &str| match eval_with(src, &data, &env, &unresolved, None).unwrap_err() {
+        EvalError::Fatal(e) => e.to_string(),
+        other => panic!("expected fatal error, got {other:?}"),
+    };
+
+    assert!(
+        message("lower(port)").contains("lower() expects argument 1 to be a string, got number")
+    );
+    assert!(message("starts_with(name, 1)").contains("argument 2 to be a string"));
+    assert!(message("join(name, \",\")").contains("join() expects argument 1 to be an array"));
+    assert!(message("substr(name, -1)").contains("a non-negative integer"));
+    assert!(message("pad_left(name, 5, \"ab\")").contains("single character"));
+    assert!(message("format(\"{} {}\", name)").contains("placeholder 1 has no argument"));
+    assert!(message("matches(name, \"(\")").contains("invalid regex"));
+    assert!(message("split(name, \"\")").contains("separator must not be empty"));
+    assert!(message("pad_left(name, 100000000)").contains("max string length"));
+}
+
+#[test]
+fn evaluates_indexing_and_collection_functions() {
+    let data = json!({
+        "replicas": [{"name": "a", "port": 8080}, {"name": "b", "port": 8081}, {"name": "c", "port": 8080}],
+        "limits": {"cpu": 2, "memory": 4},
+        "weights": [50, 30, 20],
+        "tags": ["web", "api", "web"]
+    });
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(eval("replicas[1].port"), json!(8081));
+    assert_eq!(eval("replicas[len(replicas) - 1][\"name\"]"), json!("c"));
+    assert_eq!(eval("limits[\"cpu\"] + limits.memory"), json!(6));
+    assert_eq!(eval("split(\"a.b\", \".\")[1]"), json!("b"));
+    assert_eq!(
+        eval("map(replicas, r => r.port)"),
+        json!([8080, 8081, 8080])
+    );
+    assert_eq!(
+        eval("map(filter(replicas, r => r.port == 8080), r => r.name)"),
+        json!(["a", "c"])
+    );
+    assert_eq!(
+        eval("map(limits, v => v * 2)"),
+        json!({"cpu": 4, "memory": 8})
+    );
+    assert_eq!(eval("filter(limits, v => v > 2)"), json!({"memory": 4}));
+    assert_eq!(eval("all(weights, w => w > 0)"), json!(true));
+    assert_eq!(eval("any(replicas, r => r.name == \"z\")"), json!(false));
+    assert_eq!(eval("sum(weights)"), json!(100));
+    assert_eq!(eval("sum(replicas, r => r.port - 8080)"), json!(1));
+    assert_eq!(eval("count(replicas, r => r.port == 8080)"), json!(2));
+    assert_eq!(eval("count(limits)"), json!(2));
+    assert_eq!(eval("unique(tags)"), json!(["web", "api"]));
+    assert_eq!(eval("len(unique(replicas, r => r.port))"), json!(2));
+    assert_eq!(eval("sort(weights)"), json!([20, 30, 50]));
+    assert_eq!(
+        eval("map(sort(replicas, r => r.name), r => r.name)"),
+        json!(["a", "b", "c"])
+    );
+    assert_eq!(eval("keys(limits)"), json!(["cpu", "memory"]));
+    assert_eq!(eval("values(limits)"), json!([2, 4]));
+    assert_eq!(eval("contains(tags, \"api\")"), json!(true));
+    assert_eq!(eval("contains(replicas, replicas[0])"), json!(true));
+    assert_eq!(eval("contains(limits, \"disk\")"), json!(false));
+    assert_eq!(eval("contains(\"web\", \"e\")"), json!(true));
+    assert_eq!(
+        eval("all(replicas, r => any(weights, w => w > r.port - 8060))"),
+        json!(true)
+    );
+}
+
+#[test]
+fn indexing_and_lambdas_report_errors() {
+    let data = json!({"items": [1, 2], "map": {"a": 1}, "mixed": [1, "a"]});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let message = |src: &str| match eval_with(src, &data, &env, &unresolved, None).unwrap_err() {
+        EvalError::Fatal(e) => e.to_string(),
+        other => panic!("expected fatal error, got {other:?}"),
+    };
+
+    assert!(message("items[2]").contains("index 2 out of bounds for array of length 2"));
+    assert!(message("items[-1]").contains("non-negative integer"));
+    assert!(message("items[\"a\"]").contains("array index must be a number"));
+    assert!(message("map[\"b\"]").contains("key 'b' not found"));
+    assert!(message("map.a[0]").contains("cannot index into number"));
+    assert!(message("map(items, 1)").contains("argument 2 to be a lambda"));
+    assert!(message("filter(items)").contains("filter expects 2 arguments"));
+    assert!(message("len(x => x)").contains("only allowed as arguments"));
+    assert!(message("map(items, x => x.port)").contains("path 'x.port' not found"));
+    assert!(message("sort(mixed)").contains("all numbers or all strings"));
+    assert!(message("keys(items)").contains("keys() expects argument 1 to be an object"));
+}
+
+#[test]
+fn indexing_waits_for_unresolved_elements() {
+    let data = json!({"items": ["=1 + 1", 3]});
+    let env = BTreeMap::new();
+    let unresolved: HashSet<String> = ["$.items[0]".to_string()].into_iter().collect();
+
+    let err = eval_with("items[1]", &data, &env, &unresolved, None).unwrap_err();
+    assert!(matches!(err, EvalError::Unresolved(path) if path == "$.items"));
+}
+
+#[test]
+fn evaluates_conditionals_and_let_bindings() {
+    let data = json!({"env_name": "prod", "replicas": 3, "items": [1, 2]});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(
+        eval("if env_name == \"prod\" then replicas * 2 else 1"),
+        json!(6)
+    );
+    assert_eq!(eval("replicas > 5 ? \"large\" : \"small\""), json!("small"));
+    assert_eq!(
+        eval("replicas == 1 ? \"one\" : replicas == 2 ? \"two\" : \"many\""),
+        json!("many")
+    );
+    assert_eq!(eval("let x = replicas + 1 in x * x"), json!(16));
+    assert_eq!(
+        eval("let base = 10, total = base * replicas in total + base"),
+        json!(40)
+    );
+    assert_eq!(eval("let replicas = 1 in replicas"), json!(1));
+    assert_eq!(
+        eval("map(items, i => let d = i * 10 in if d > 10 then d else 0)"),
+        json!([0, 20])
+    );
+    // The branch not taken is never evaluated.
+    assert_eq!(eval("if true then 1 else 1 / 0"), json!(1));
+    assert_eq!(eval("false ? missing.path : len(items)"), json!(2));
+}
+
+#[test]
+fn conditionals_wait_only_for_the_branch_taken() {
+    let data = json!({"flag": true, "pending": "=1"});
+    let env = BTreeMap::new();
+    let unresolved: HashSet<String> = ["$.pending".to_string()].into_iter().collect();
+
+    let out = eval_with("flag ? 1 : pending", &data, &env, &unresolved, None).unwrap();
+    assert_eq!(out, json!(1));
+    let err = eval_with("flag ? pending : 1", &data, &env, &unresolved, None).unwrap_err();
+    assert!(matches!(err, EvalError::Unresolved(_)));
+}
+
+#[test]
+fn conditionals_and_let_report_errors() {
+    let message = |src: &str| parse_expression(src).unwrap_err().to_string();
+    assert!(message("if a then b").contains("expected 'else'"));
+    assert!(message("if a b else c").contains("expected 'then'"));
+    assert!(message("a ? b").contains("expected ':'"));
+    assert!(message("let x 1 in x").contains("expected '=' after let binding name"));
+    assert!(message("let x = 1 x").contains("expected 'in'"));
+    assert!(message("let in = 1 in 2").contains("expected a name after 'let'"));
+    assert!(message("then + 1").contains("unexpected keyword 'then'"));
+
+    let data = json!({"n": 1});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let err = eval_with("if n then 1 else 2", &data, &env, &unresolved, None).unwrap_err();
+    match err {
+        EvalError::Fatal(e) => assert!(e.to_string().contains("expected boolean, got number")),
+        other => panic!("expected fatal error, got {other:?}"),
+    }
+}
+
+#[test]
+fn evaluates_null_safe_access_and_defaults() {
+    let data = json!({
+        "server": {"host": "api", "tls": null, "ports": [80]},
+        "limits": {"timeout": "30s"},
+    });
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(eval("server?.tls?.port"), json!(null));
+    assert_eq!(eval("server?.proxy?.port"), json!(null));
+    assert_eq!(eval("server?.tls?.port ?? 443"), json!(443));
+    assert_eq!(eval("server?.host ?? \"localhost\""), json!("api"));
+    assert_eq!(eval("server.ports?.[3] ?? server.ports?.[0]"), json!(80));
+    assert_eq!(eval("null ?? null ?? 1"), json!(1));
+    assert_eq!(eval("false ?? true"), json!(false));
+    assert_eq!(eval("(server?.tls ?? 1) + 1"), json!(2));
+    assert_eq!(eval("(limits?.timeout ?? 1m) * 2"), json!("1m"));
+    // The default is only evaluated when it is needed.
+    assert_eq!(eval("server.host ?? missing.path"), json!("api"));
+
+    let expr = parse_expression("a?.b?.[i] ?? (c ? d : e)").unwrap();
+    assert_eq!(expr.to_string(), "a?.b?.[i] ?? (if c then d else e)");
+}
+
+#[test]
+fn missing_paths_are_reported_as_not_found() {
+    let data = json!({"server": {"host": "api", "tls": null}, "pending": "=1"});
+    let env = BTreeMap::new();
+    let unresolved: HashSet<String> = ["$.pending".to_string()].into_iter().collect();
+    let message = |src: &str| match eval_with(src, &data, &env, &unresolved, None).unwrap_err() {
+        EvalError::Fatal(e) => e.to_string(),
+        other => panic!("expected fatal error, got {other:?}"),
+    };
+
+    assert!(message("server.port").contains("path 'server.port' not found"));
+    assert!(message("server.tls.port").contains("path 'server.tls.port' not found"));
+    assert!(message("server?.tls.port").contains("path 'server.tls.port' not found"));
+    assert!(message("missing?.port").contains("unknown reference 'missing.port'"));
+    assert!(message("server.host?.port").contains("path 'server.host.port' not found"));
+
+    let err = eval_with("pending?.x ?? 1", &data, &env, &unresolved, None).unwrap_err();
+    assert!(matches!(err, EvalError::Unresolved(_)));
+
+    let expr = parse_expression("input.port").unwrap();
+    let imports = BTreeMap::new();
+    let ctx = EvalContext {
+        data: &data,
+        imports: &imports,
+        env: &env,
+        unresolved_paths: &unresolved,
+        current_value: None,
+        current_scope: None,
+        named_scopes: BTreeMap::from([("input".to_string(), json!({"host": "api"}))]),
+        functions: &BTreeMap::new(),
+        quantities: &BTreeMap::new(),
+    };
+    match evaluate(&expr, &ctx).unwrap_err() {
+        EvalError::Fatal(e) => assert!(e.to_string().contains("path 'input.port' not found")),
+        other => panic!("expected fatal error, got {other:?}"),
+    }
+}
+
+#[test]
+fn arithmetic_is_exact_for_decimals_and_large_integers() {
+    let data = json!({
+        "subtotal": 19.99,
+        "tax_rate": 0.0825,
+        "snowflake": 9007199254740993u64,
+        "max": i64::MAX,
+        "prices": [0.1, 0.2, 0.3],
+    });
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(eval("0.1 + 0.2"), json!(0.3));
+    assert_eq!(eval("0.1 + 0.2 == 0.3"), json!(true));
+    assert_eq!(eval("subtotal * 3"), json!(59.97));
+    assert_eq!(eval("subtotal * tax_rate"), json!(1.649175));
+    assert_eq!(eval("snowflake + 2"), json!(9007199254740995u64));
+    assert_eq!(eval("snowflake % 10"), json!(3));
+    // Intermediates may exceed 64 bits as long as the result fits.
+    assert_eq!(eval("max * 4 / 4"), json!(i64::MAX));
+    assert_eq!(eval("round(2.5)"), json!(3));
+    assert_eq!(eval("floor(-0.5)"), json!(-1));
+    assert_eq!(eval("max(1, 2.5, 2)"), json!(2.5));
+    assert_eq!(eval("sum(prices)"), json!(0.6));
+    assert_eq!(eval("7 / 2"), json!(3.5));
+    assert_eq!(eval("1.50 * 2"), json!(3));
+    assert!(eval("1.50 * 2").is_i64());
+}
+
+#[test]
+fn arithmetic_reports_overflow() {
+    let data = json!({"max": i64::MAX, "half": 0.5});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let message = |src: &str| match eval_with(src, &data, &env, &unresolved, None).unwrap_err() {
+        EvalError::Fatal(e) => e.to_string(),
+        other => panic!("expected fatal error, got {other:?}"),
+    };
+
+    assert!(message("max * 4").contains("does not fit in a 64-bit integer"));
+    assert!(message("max * max * max").contains("numeric overflow in '*'"));
+    assert!(message("half % 2").contains("expected integer"));
+    assert!(message("half / 0").contains("division by zero"));
+}
+
+#[test]
+fn evaluates_duration_timestamp_and_bytesize_arithmetic() {
+    let data = json!({
+        "timeout": "90s",
+        "start": "2024-05-01T12:00:00+02:00",
+        "memory": "512MiB",
+    });
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let eval = |src: &str| eval_with(src, &data, &env, &unresolved, None).unwrap();
+
+    assert_eq!(eval("30s + 1m"), json!("1m30s"));
+    assert_eq!(eval("duration(timeout) * 2"), json!("3m"));
+    assert_eq!(eval("duration(timeout) / 30s"), json!(3));
+    assert_eq!(eval("timeout > 1m"), json!(true));
+    assert_eq!(eval("seconds(timeout)"), json!(90));
+    assert_eq!(eval("milliseconds(250ms + 1s)"), json!(1250));
+    assert_eq!(
+        eval("timestamp(start) + 36h"),
+        json!("2024-05-02T22:00:00Z")
+    );
+    assert_eq!(
+        eval(r#"timestamp(start) - timestamp("2024-05-01T00:00:00Z")"#),
+        json!("10h")
+    );
+    assert_eq!(eval("unix_seconds(timestamp(start))"), json!(1714557600));
+    assert_eq!(eval("bytesize(memory) * 4"), json!("2GiB"));
+    assert_eq!(eval("bytes(1KB + 24B)"), json!(1024));
+    assert_eq!(eval("min(5m, 90s, 2m)"), json!("1m30s"));
+}
+
+#[test]
+fn quantity_arithmetic_rejects_mismatched_kinds() {
+    let data = json!({"name": "api"});
+    let env = BTreeMap::new();
+    let unresolved = HashSet::new();
+    let message = |src: &str| match eval_with(src, &data, &env, &unresolved, None).unwrap_err() {
+        EvalError::Fatal(e) => e.to_string(),
+        other => panic!("expected fatal error, got {other:?}"),
+    };
+
+    assert!(message("30s + 1MiB").contains("cannot apply '+'"));
+    assert!(message("duration(name)").contains("duration"));
+    assert!(message(r#"timestamp("9999-12-31T23:59:59Z") + 1d"#).contains("timestamp out of range"));
+    assert!(
+        message(r#"timestamp("0000-01-01T00:00:00Z") - 1ns"#).contains("timestamp out of range")
+    );
+    assert!(message(r#"timestamp("9999-12-31T23:30:00-01:00")"#).contains("out of range"));
+    assert!(parse_expression("5parsecs").is_err());
+}
+
+#[test]
+fn infers_expression_types_from_data_and_hints() {
+    use super_yaml::expr::typecheck::{Type, TypeEnv};
+
+    let types = BTreeMap::new();
+    let data = json!({
+        "replicas": 3,
+        "ratio": 0.5,
+        "name": "api",
+        "timeout": "90s",
+        "ports": [80, 443],
+        "doubled": "=replicas * 2",
+    });
+    let hints = BTreeMap::from([("$.timeout".to_string(), "duration".to_string())]);
+    let env = TypeEnv::new(&types).with_data(&data, &hints);
+    let infer = |src: &str| {
+        let mut issues = Vec::new();
+        let ty = env.infer(&parse_expression(src).unwrap(), &mut issues);
+        (ty, issues)
+    };
+
+    assert_eq!(infer("doubled + 1").0, Type::Integer);
+    assert_eq!(infer("replicas / 2").0, Type::Number);
+    assert_eq!(infer("timeout * 2").0, Type::Quantity("duration"));
+    assert_eq!(infer("timeout > 1m").0, Type::Boolean);
+    assert_eq!(infer("name + replicas").0, Type::String);
+    assert_eq!(infer("map(ports, p => p * ratio)").0.to_string(), "array");
+    assert_eq!(infer("let n = len(name) in n > 2").0, Type::Boolean);
+
+    let (_, issues) = infer("timeout + 1MiB");
+    assert_eq!(
+        issues,
+        vec!["cannot apply '+' to duration and bytesize in 'timeout + 1MiB'".to_string()]
+    );
+    let (_, issues) = infer("filter(ports, p => p + 1)");
+    assert!(issues[0].contains("filter() lambda must return boolean, got integer"));
+    let (_, issues) = infer("ports[\"a\"] + replica");
+    assert_eq!(issues.len(), 2, "{issues:?}");
+    assert!(issues[0].contains("array index must be a number, got string"));
+    assert!(issues[1].contains("unknown reference 'replica'"));
+
+    assert_eq!(infer("ports?.[0] ?? 0").0, Type::Integer);
+    assert_eq!(
+        infer("(name?.missing ?? 1) + 1").1,
+        vec!["cannot index into string in 'name?.missing'".to_string()]
+    );
+    let (_, issues) = infer("replicas ?? \"none\"");
+    assert!(issues[0].contains("cannot apply '??' to integer and string"));
+}
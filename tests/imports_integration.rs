@@ -7,7 +7,10 @@ use serde_json::json;
 
 use ed25519_dalek::{Signer, SigningKey};
 
-use super_yaml::{compile_document_from_path, verify, MapEnvProvider};
+use super_yaml::provenance::StepKind;
+use super_yaml::{
+    compile_document_from_path, compile_document_with_provenance_from_path, verify, MapEnvProvider,
+};
 
 fn env_provider(vars: &[(&str, &str)]) -> MapEnvProvider {
     let mut map = HashMap::new();
@@ -110,6 +113,55 @@ service <RootService>:
     assert!(compiled.get("shared").is_none());
 }
 
+#[test]
+fn env_bindings_can_be_typed_with_imported_types() {
+    let dir = TempDir::new("imports_typed_env");
+    dir.write(
+        "shared.syaml",
+        r#"
+---!syaml/v0
+---schema
+Port:
+  type: integer
+  minimum: 1
+  maximum: 65535
+---data
+{}
+"#,
+    );
+    dir.write(
+        "root.syaml",
+        r#"
+---!syaml/v0
+---meta
+imports:
+  shared: ./shared.syaml
+env:
+  PORT:
+    key: PORT
+    type: shared.Port
+---schema
+{}
+---data
+port: =env.PORT
+"#,
+    );
+
+    let path = dir.file_path("root.syaml");
+    let compiled = compile_document_from_path(&path, &env_provider(&[("PORT", "8080")]))
+        .expect("compile")
+        .value;
+    assert_eq!(compiled["port"], json!(8080));
+
+    let err = compile_document_from_path(&path, &env_provider(&[("PORT", "0")]))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("env 'PORT' for symbol 'PORT' does not match its type: minimum violation"),
+        "{err}"
+    );
+}
+
 #[test]
 fn import_alias_can_be_explicitly_extracted_into_output() {
     let dir = TempDir::new("imports_data_extract");
@@ -1457,7 +1509,7 @@ imports:
 "#,
     );
 
-    let err = compile_document_from_path(&dir.file_path("root.syaml"), &env_provider(&[]))
+    let err = compile_document_from_path(dir.file_path("root.syaml"), &env_provider(&[]))
         .unwrap_err()
         .to_string();
     assert!(
@@ -1508,7 +1560,7 @@ Config:
 "#,
     );
 
-    let err = compile_document_from_path(&dir.file_path("root.syaml"), &env_provider(&[]))
+    let err = compile_document_from_path(dir.file_path("root.syaml"), &env_provider(&[]))
         .unwrap_err()
         .to_string();
     assert!(err.contains("shared.Port"), "unexpected error: {err}");
@@ -1551,7 +1603,7 @@ port: "=shared.default_port"
 "#,
     );
 
-    let err = compile_document_from_path(&dir.file_path("root.syaml"), &env_provider(&[]))
+    let err = compile_document_from_path(dir.file_path("root.syaml"), &env_provider(&[]))
         .unwrap_err()
         .to_string();
     assert!(
@@ -1559,3 +1611,174 @@ port: "=shared.default_port"
         "unexpected error: {err}"
     );
 }
+
+#[test]
+fn imported_functions_are_called_through_the_alias() {
+    let dir = TempDir::new("imported_functions");
+
+    dir.write(
+        "capacity.syaml",
+        r#"
+---!syaml/v0
+---schema
+Size:
+  type: object
+  properties:
+    cores: integer
+    memory_gb: number
+---functions
+capacity:
+  params:
+    - cores <integer>
+    - memory_gb <number>
+  returns: integer
+  body: floor(min(cores * 250, memory_gb * 100))
+sized:
+  params: [size <Size>]
+  body: capacity(size.cores, size.memory_gb)
+"#,
+    );
+    dir.write(
+        "root.syaml",
+        r#"
+---!syaml/v0
+---meta
+imports:
+  cap:
+    path: ./capacity.syaml
+---data
+worker <cap.Size>:
+  cores: 4
+  memory_gb: 2
+direct: "=cap.capacity(worker.cores, worker.memory_gb)"
+nested: "=cap.sized(worker)"
+"#,
+    );
+
+    let compiled = compile(&dir.file_path("root.syaml"));
+    assert_eq!(compiled["direct"], json!(200));
+    assert_eq!(compiled["nested"], json!(200));
+
+    // Unqualified names do not leak out of the import.
+    let root = fs::read_to_string(dir.file_path("root.syaml")).unwrap();
+    dir.write("bare.syaml", &root.replace("cap.sized(", "sized("));
+    let err = compile_document_from_path(dir.file_path("bare.syaml"), &env_provider(&[]))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("unknown function 'sized'"), "{err}");
+
+    dir.write(
+        "no_functions.syaml",
+        &root.replace(
+            "path: ./capacity.syaml",
+            "path: ./capacity.syaml\n    sections: [schema, data]",
+        ),
+    );
+    let err = compile_document_from_path(dir.file_path("no_functions.syaml"), &env_provider(&[]))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("unknown function 'cap.capacity'")
+            && err.contains("add 'functions' to sections"),
+        "{err}"
+    );
+}
+
+#[test]
+fn provenance_records_each_step_and_explains_the_chain() {
+    let dir = TempDir::new("provenance");
+    dir.write(
+        "shared.syaml",
+        r#"
+---!syaml/v0
+---data
+defaults:
+  port: 8080
+"#,
+    );
+    dir.write(
+        "root.syaml",
+        r#"
+---!syaml/v0
+---meta
+env:
+  REGION:
+    from: env
+    key: APP_REGION
+    default: eu
+imports:
+  shared: ./shared.syaml
+---schema
+Color:
+  type: object
+  properties:
+    red: integer
+    green: integer
+  constructors:
+    rg: { regex: '^rg\((?<red>\d+),(?<green>\d+)\)$' }
+---data
+_templates:
+  service:
+    name: "{{NAME}}"
+    port: "{{PORT:9000}}"
+api:
+  {{_templates.service}}:
+    NAME: api
+base_port: shared.defaults.port
+port_ref: $.base_port
+host: =api.name + "." + env.REGION
+url: "http://${host}:${port_ref + 1}"
+accent <Color>: "rg(1,2)"
+"#,
+    );
+
+    let (compiled, provenance) =
+        compile_document_with_provenance_from_path(dir.file_path("root.syaml"), &env_provider(&[]))
+            .expect("compile");
+    assert_eq!(compiled.value["url"], json!("http://api.eu:8081"));
+
+    let kinds = |path: &str| {
+        provenance
+            .steps(path)
+            .iter()
+            .map(|step| step.kind)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(kinds("$.base_port"), vec![StepKind::Import]);
+    assert_eq!(kinds("$.port_ref"), vec![StepKind::Reference]);
+    assert_eq!(kinds("$.host"), vec![StepKind::Expression]);
+    assert_eq!(kinds("$.url"), vec![StepKind::Interpolation]);
+    assert_eq!(kinds("$.accent"), vec![StepKind::Constructor]);
+    assert_eq!(kinds("$.api"), vec![StepKind::Template]);
+    assert_eq!(
+        provenance.steps("$.host")[0].reads,
+        vec!["$.api.name".to_string(), "env.REGION".to_string()]
+    );
+
+    let text = provenance.explain("url").expect("explain");
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(
+        lines[0].starts_with("url = \"http://api.eu:8081\" ("),
+        "{text}"
+    );
+    assert!(lines[0].ends_with("root.syaml:30:6)"), "{text}");
+    for expected in [
+        "  interpolation http://${host}:${port_ref + 1} -> \"http://api.eu:8081\"",
+        "      expression =api.name + \".\" + env.REGION -> \"api.eu\"",
+        "          template {{_templates.service}} with {\"NAME\":\"api\"} -> {\"name\":\"api\",\"port\":9000}",
+        "        env.REGION = \"eu\" (default; APP_REGION is not set)",
+        "      reference $.base_port -> 8080",
+        "          import shared.defaults.port -> 8080",
+        "            shared.defaults.port = 8080 (import 'shared' from ./shared.syaml)",
+    ] {
+        assert!(lines.contains(&expected), "missing {expected:?} in\n{text}");
+    }
+
+    let err = provenance.explain("missing").unwrap_err();
+    assert_eq!(err.code(), "path_not_found");
+    assert!(
+        err.to_string()
+            .contains("path 'missing' not found in compiled data"),
+        "{err}"
+    );
+}
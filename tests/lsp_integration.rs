@@ -0,0 +1,345 @@
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value as JsonValue};
+
+use super_yaml::lsp::{path_to_uri, read_message, serve, write_message, LspServer};
+
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(prefix: &str) -> Self {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "super_yaml_{}_{}_{}",
+            prefix,
+            std::process::id(),
+            stamp
+        ));
+        fs::create_dir_all(&path).expect("create temp dir");
+        Self {
+            path: fs::canonicalize(&path).expect("canonicalize temp dir"),
+        }
+    }
+
+    fn write(&self, file: &str, content: &str) {
+        fs::write(self.path.join(file), content).expect("write temp file");
+    }
+
+    fn uri(&self, file: &str) -> String {
+        path_to_uri(&self.path.join(file))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+const SHARED: &str = r#"---!syaml/v0
+---schema
+Port:
+  type: integer
+  minimum: 1
+  maximum: 65535
+---data
+default_port: 8080
+"#;
+
+const ROOT: &str = r#"---!syaml/v0
+---meta
+imports:
+  shared: ./shared.syaml
+---schema
+Service:
+  type: object
+  properties:
+    host: string
+    port:
+      type: shared.Port
+---data
+service <Service>:
+  host: localhost
+  port: 99999
+backup: "${service.host}"
+"#;
+
+fn start(dir: &TempDir) -> LspServer {
+    let mut server = LspServer::new();
+    server.handle(json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": { "rootUri": path_to_uri(&dir.path) },
+    }));
+    server
+}
+
+fn open(server: &mut LspServer, uri: &str, text: &str) -> Vec<JsonValue> {
+    server.handle(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": { "uri": uri, "languageId": "syaml", "version": 1, "text": text },
+        },
+    }))
+}
+
+fn request(server: &mut LspServer, method: &str, params: JsonValue) -> JsonValue {
+    let responses = server.handle(json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    }));
+    assert_eq!(responses.len(), 1, "expected one response to {method}");
+    responses.into_iter().next().unwrap()
+}
+
+fn at(uri: &str, line: usize, character: usize) -> JsonValue {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character },
+    })
+}
+
+fn workspace() -> (TempDir, LspServer, String) {
+    let dir = TempDir::new("lsp");
+    dir.write("shared.syaml", SHARED);
+    dir.write("root.syaml", ROOT);
+    let mut server = start(&dir);
+    let uri = dir.uri("root.syaml");
+    open(&mut server, &uri, ROOT);
+    (dir, server, uri)
+}
+
+#[test]
+fn initialize_advertises_capabilities() {
+    let dir = TempDir::new("lsp_init");
+    let mut server = LspServer::new();
+    let response = request(
+        &mut server,
+        "initialize",
+        json!({ "rootUri": path_to_uri(&dir.path) }),
+    );
+    let capabilities = &response["result"]["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], json!(true));
+    assert_eq!(capabilities["definitionProvider"], json!(true));
+    assert_eq!(capabilities["renameProvider"], json!(true));
+    assert!(capabilities["completionProvider"].is_object());
+}
+
+#[test]
+fn open_publishes_located_diagnostics() {
+    let dir = TempDir::new("lsp_diag");
+    dir.write("shared.syaml", SHARED);
+    dir.write("root.syaml", ROOT);
+    let mut server = start(&dir);
+    let uri = dir.uri("root.syaml");
+    let messages = open(&mut server, &uri, ROOT);
+
+    assert_eq!(messages.len(), 1);
+    let params = &messages[0]["params"];
+    assert_eq!(messages[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(params["uri"], json!(uri));
+    let diagnostics = params["diagnostics"].as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0]["severity"], json!(1));
+    assert_eq!(diagnostics[0]["range"]["start"]["line"], json!(14));
+
+    let fixed = ROOT.replace("99999", "8080");
+    let messages = server.handle(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": fixed }],
+        },
+    }));
+    assert_eq!(messages[0]["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn hover_shows_resolved_schema_type() {
+    let (_dir, mut server, uri) = workspace();
+
+    let on_hint = request(&mut server, "textDocument/hover", at(&uri, 12, 11));
+    let value = on_hint["result"]["contents"]["value"].as_str().unwrap();
+    assert!(value.contains("type `Service`"), "{value}");
+    assert!(value.contains("\"properties\""), "{value}");
+
+    let on_key = request(&mut server, "textDocument/hover", at(&uri, 14, 3));
+    let value = on_key["result"]["contents"]["value"].as_str().unwrap();
+    assert!(value.contains("`$.service.port`"), "{value}");
+    assert!(value.contains("65535"), "{value}");
+}
+
+#[test]
+fn definition_follows_local_imported_and_data_references() {
+    let (dir, mut server, uri) = workspace();
+
+    let local = request(&mut server, "textDocument/definition", at(&uri, 12, 11));
+    assert_eq!(local["result"]["uri"], json!(uri));
+    assert_eq!(local["result"]["range"]["start"]["line"], json!(5));
+
+    let imported = request(&mut server, "textDocument/definition", at(&uri, 10, 21));
+    assert_eq!(imported["result"]["uri"], json!(dir.uri("shared.syaml")));
+    assert_eq!(imported["result"]["range"]["start"]["line"], json!(2));
+
+    let text = ROOT.replace("\"${service.host}\"", "$.service.host");
+    open(&mut server, &uri, &text);
+    let data = request(&mut server, "textDocument/definition", at(&uri, 15, 12));
+    assert_eq!(data["result"]["range"]["start"]["line"], json!(13));
+}
+
+#[test]
+fn completion_offers_types_and_missing_properties() {
+    let (_dir, mut server, uri) = workspace();
+
+    let text = ROOT.replace(
+        "backup: \"${service.host}\"",
+        "other <Se: 1\nnext <Service>:\n  host: a\n  ",
+    );
+    open(&mut server, &uri, &text);
+
+    let types = request(&mut server, "textDocument/completion", at(&uri, 15, 9));
+    let labels: Vec<&str> = types["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|item| item["label"].as_str())
+        .collect();
+    assert!(labels.contains(&"Service"), "{labels:?}");
+    assert!(labels.contains(&"shared.Port"), "{labels:?}");
+    assert!(labels.contains(&"string"), "{labels:?}");
+
+    let properties = request(&mut server, "textDocument/completion", at(&uri, 18, 2));
+    let labels: Vec<&str> = properties["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|item| item["label"].as_str())
+        .collect();
+    assert_eq!(labels, vec!["port"]);
+}
+
+#[test]
+fn rename_updates_type_across_importing_files() {
+    let (dir, mut server, _uri) = workspace();
+    let shared_uri = dir.uri("shared.syaml");
+    open(&mut server, &shared_uri, SHARED);
+
+    let response = request(
+        &mut server,
+        "textDocument/rename",
+        json!({
+            "textDocument": { "uri": shared_uri },
+            "position": { "line": 2, "character": 1 },
+            "newName": "PortNumber",
+        }),
+    );
+    let changes = response["result"]["changes"].as_object().unwrap();
+
+    let shared_edits = changes[&shared_uri].as_array().unwrap();
+    assert_eq!(shared_edits.len(), 1);
+    assert_eq!(shared_edits[0]["range"]["start"]["line"], json!(2));
+
+    let root_edits = changes[&dir.uri("root.syaml")].as_array().unwrap();
+    assert_eq!(root_edits.len(), 1);
+    assert_eq!(root_edits[0]["newText"], json!("PortNumber"));
+    assert_eq!(root_edits[0]["range"]["start"]["line"], json!(10));
+    assert_eq!(root_edits[0]["range"]["start"]["character"], json!(19));
+}
+
+#[test]
+fn rename_rejects_invalid_names() {
+    let (_dir, mut server, uri) = workspace();
+    let response = request(
+        &mut server,
+        "textDocument/rename",
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 5, "character": 1 },
+            "newName": "not valid",
+        }),
+    );
+    assert!(response["error"].is_object());
+}
+
+#[test]
+fn shutdown_then_exit() {
+    let mut server = LspServer::new();
+    let response = request(&mut server, "shutdown", JsonValue::Null);
+    assert_eq!(response["result"], JsonValue::Null);
+    assert!(!server.exit_requested());
+    server.handle(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert!(server.exit_requested());
+}
+
+#[test]
+fn diagnostics_read_imports_from_open_buffers() {
+    let (dir, mut server, root) = workspace();
+    let shared = dir.uri("shared.syaml");
+
+    // The unsaved buffer allows the port, so the importer has no error.
+    let messages = open(&mut server, &shared, &SHARED.replace("65535", "100000"));
+    let root_diagnostics = messages
+        .iter()
+        .find(|m| m["params"]["uri"] == json!(root))
+        .unwrap();
+    assert_eq!(root_diagnostics["params"]["diagnostics"], json!([]));
+
+    // Editing the import republishes the importer.
+    let messages = server.handle(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didChange",
+        "params": {
+            "textDocument": { "uri": shared, "version": 2 },
+            "contentChanges": [{ "text": SHARED }],
+        },
+    }));
+    assert_eq!(messages.len(), 2, "{messages:?}");
+    assert_eq!(messages[1]["params"]["uri"], json!(root));
+    assert_eq!(
+        messages[1]["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn serve_answers_malformed_messages_and_continues() {
+    let mut input = b"Content-Length: 9\r\n\r\n{not json".to_vec();
+    write_message(&mut input, &json!([1, 2])).unwrap();
+    write_message(
+        &mut input,
+        &json!({ "jsonrpc": "2.0", "id": 7, "method": "shutdown" }),
+    )
+    .unwrap();
+    write_message(&mut input, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+
+    let mut output = Vec::new();
+    serve(&mut Cursor::new(input), &mut output).unwrap();
+    let mut reader = Cursor::new(output);
+    let parse_error = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(parse_error["error"]["code"], json!(-32700));
+    assert_eq!(parse_error["id"], JsonValue::Null);
+    let invalid = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(invalid["error"]["code"], json!(-32600));
+    let shutdown = read_message(&mut reader).unwrap().unwrap();
+    assert_eq!(shutdown["id"], json!(7));
+    assert_eq!(shutdown["result"], JsonValue::Null);
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
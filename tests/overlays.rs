@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use super_yaml::overlay::{profile_path, resolve_profile};
+use super_yaml::overrides::parse_override;
+use super_yaml::{
+    compile_document, compile_document_from_path, compile_document_from_path_with_options,
+    compile_document_with_provenance_from_path, parse_document, CompileOptions, MapEnvProvider,
+    ProcessEnvProvider, SyamlError,
+};
+
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(prefix: &str) -> Self {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "super_yaml_overlays_{}_{}_{}",
+            prefix,
+            std::process::id(),
+            stamp
+        ));
+        fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+
+    fn write(&self, file: &str, content: &str) {
+        fs::write(self.path.join(file), content).expect("write temp file");
+    }
+
+    fn compile(&self, file: &str, profile: Option<&str>) -> Result<serde_json::Value, SyamlError> {
+        let options = CompileOptions {
+            profile: profile.map(str::to_string),
+            ..CompileOptions::default()
+        };
+        compile_document_from_path_with_options(self.path.join(file), &ProcessEnvProvider, &options)
+            .map(|compiled| compiled.value)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+const BASE: &str = r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name: string
+    replicas:
+      type: integer
+      minimum: 1
+    hosts:
+      type: array
+      items: string
+    debug: boolean?
+Port:
+  type: integer
+  maximum: 65535
+---data
+_templates:
+  sidecar:
+    image: "{{IMAGE}}"
+    port: 9000
+  api:
+    name!: "{{NAME}}"
+    replicas: 1
+    hosts: []
+region^: eu
+service <Service>:
+  name: billing
+  replicas: 1
+  hosts: [a.internal]
+  debug: true
+api <Service>:
+  {{_templates.api}}:
+    NAME: api
+pods: =service.replicas * 2
+host_count: =len(service.hosts)
+"#;
+
+#[test]
+fn profile_overlays_merge_append_and_delete() {
+    let dir = TempDir::new("profile");
+    dir.write("service.syaml", BASE);
+    dir.write(
+        "service.prod.syaml",
+        r#"---!syaml/v0
+---meta
+overlay: ./service.syaml
+---data
+service:
+  replicas: =2 + 3
+  hosts+: [b.internal]
+  debug-: ~
+metrics_port <Port>: 9100
+sidecar:
+  {{_templates.sidecar}}:
+    IMAGE: envoy
+"#,
+    );
+
+    let base = dir.compile("service.syaml", None).unwrap();
+    assert_eq!(base["pods"], json!(2));
+    assert_eq!(base["service"]["debug"], json!(true));
+
+    let prod = dir.compile("service.syaml", Some("prod")).unwrap();
+    assert_eq!(
+        prod["service"],
+        json!({"name": "billing", "replicas": 5, "hosts": ["a.internal", "b.internal"]})
+    );
+    assert_eq!(prod["pods"], json!(10));
+    assert_eq!(prod["host_count"], json!(2));
+    assert_eq!(prod["metrics_port"], json!(9100));
+    assert_eq!(prod["sidecar"], json!({"image": "envoy", "port": 9000}));
+    assert!(prod.get("_templates").is_none());
+
+    // Compiling the overlay file directly gives the same result.
+    let direct =
+        compile_document_from_path(dir.path.join("service.prod.syaml"), &ProcessEnvProvider)
+            .unwrap()
+            .value;
+    assert_eq!(direct, prod);
+}
+
+#[test]
+fn overlay_sections_chain_and_precede_overrides() {
+    let dir = TempDir::new("chain");
+    dir.write("service.syaml", BASE);
+    dir.write(
+        "service.prod.syaml",
+        "---!syaml/v0\n---meta\noverlay: ./service.syaml\n---data\nservice:\n  replicas: 4\n  hosts+: [b.internal]\n",
+    );
+    dir.write(
+        "service.prod-eu.syaml",
+        r#"---!syaml/v0
+---overlay
+base: ./service.prod.syaml
+data:
+  service:
+    hosts: [eu.internal]
+  region_label: "${service.name}-eu"
+"#,
+    );
+    let parsed =
+        parse_document(&fs::read_to_string(dir.path.join("service.prod-eu.syaml")).unwrap())
+            .unwrap();
+    assert_eq!(parsed.overlay.as_deref(), Some("./service.prod.syaml"));
+
+    let value = dir.compile("service.syaml", Some("prod-eu")).unwrap();
+    assert_eq!(value["service"]["replicas"], json!(4));
+    assert_eq!(value["service"]["hosts"], json!(["eu.internal"]));
+    assert_eq!(value["region_label"], json!("billing-eu"));
+
+    let options = CompileOptions {
+        profile: Some("prod".to_string()),
+        overrides: vec![parse_override("service.replicas=9").unwrap()],
+        ..CompileOptions::default()
+    };
+    let value = compile_document_from_path_with_options(
+        dir.path.join("service.syaml"),
+        &ProcessEnvProvider,
+        &options,
+    )
+    .unwrap()
+    .value;
+    assert_eq!(value["service"]["replicas"], json!(9));
+    assert_eq!(value["pods"], json!(18));
+
+    assert_eq!(
+        resolve_profile(&dir.path.join("service.syaml"), "prod-eu").unwrap(),
+        dir.path.join("service.prod-eu.syaml")
+    );
+    assert_eq!(
+        profile_path(&dir.path.join("service.syaml"), "qa"),
+        dir.path.join("service.qa.syaml")
+    );
+}
+
+#[test]
+fn overlays_are_validated_and_cannot_patch_frozen_or_locked_keys() {
+    let dir = TempDir::new("protected");
+    dir.write("service.syaml", BASE);
+    // Errors point at the patch in the overlay, whose data starts on line 5.
+    let cases = [
+        (
+            "region: us\n",
+            "cannot patch 'region': it is frozen with '^'",
+            5,
+        ),
+        (
+            "api:\n  name: other\n",
+            "cannot patch 'api.name': it is a locked template field",
+            6,
+        ),
+        ("service:\n  replicas: 0\n", "minimum", 6),
+        ("service <Service>:\n  replicas: 0\n", "minimum", 6),
+        ("metrics_port <Port>: 70000\n", "maximum", 5),
+        ("service:\n  hosts+: [b, 7]\n", "type mismatch", 6),
+    ];
+    for (patch, expected, line) in cases {
+        dir.write(
+            "service.bad.syaml",
+            &format!("---!syaml/v0\n---meta\noverlay: ./service.syaml\n---data\n{patch}"),
+        );
+        let err = dir.compile("service.syaml", Some("bad")).unwrap_err();
+        assert!(err.to_string().contains(expected), "{patch}: {err}");
+        let span = err.span().expect("located error");
+        assert!(
+            span.file.as_deref().unwrap().ends_with("service.bad.syaml"),
+            "{patch}: {span:?}"
+        );
+        assert_eq!(span.line, line, "{patch}: {span:?}");
+    }
+}
+
+#[test]
+fn invalid_overlays_and_profiles_are_rejected() {
+    let dir = TempDir::new("invalid");
+    dir.write("service.syaml", BASE);
+    dir.write("other.syaml", "---!syaml/v0\n---data\nx: 1\n");
+    let cases = [
+        (
+            "service:\n  name+: [x]\n",
+            "cannot patch 'service.name': there is no list to append to",
+        ),
+        (
+            "service:\n  hosts+: b\n",
+            "'hosts+' appends a list, found string",
+        ),
+        (
+            "service:\n  debug-: false\n",
+            "'debug-' deletes 'debug'; its value must be ~",
+        ),
+        (
+            "missing-: ~\n",
+            "cannot patch 'missing': there is no key to delete",
+        ),
+        (
+            "service <string>: billing\n",
+            "cannot patch 'service': its type hint <string> differs from the base hint <Service>",
+        ),
+    ];
+    for (patch, expected) in cases {
+        dir.write(
+            "service.bad.syaml",
+            &format!("---!syaml/v0\n---meta\noverlay: ./service.syaml\n---data\n{patch}"),
+        );
+        let err = dir.compile("service.syaml", Some("bad")).unwrap_err();
+        assert_eq!(err.code(), "overlay_error", "{err}");
+        assert!(err.to_string().contains(expected), "{patch}: {err}");
+    }
+
+    let documents = [
+        (
+            "---!syaml/v0\n---meta\noverlay: ./service.syaml\n---schema\nPort: integer\n---data\nx: 1\n",
+            "an overlay cannot declare a schema section",
+        ),
+        (
+            "---!syaml/v0\n---meta\noverlay: ./service.syaml\n---overlay\nbase: ./service.syaml\n",
+            "not both",
+        ),
+        (
+            "---!syaml/v0\n---overlay\nbase: ./service.syaml\n---data\nx: 1\n",
+            "cannot also have a ---data section",
+        ),
+        (
+            "---!syaml/v0\n---meta\noverlay: ./missing.syaml\n---data\nx: 1\n",
+            "overlay base './missing.syaml' not found",
+        ),
+    ];
+    for (document, expected) in documents {
+        dir.write("service.bad.syaml", document);
+        let err = dir.compile("service.bad.syaml", None).unwrap_err();
+        assert!(err.to_string().contains(expected), "{document}: {err}");
+    }
+
+    let err = dir.compile("service.syaml", Some("qa")).unwrap_err();
+    assert!(err.to_string().contains("profile 'qa' not found"), "{err}");
+    let err = dir.compile("service.syaml", Some("../x")).unwrap_err();
+    assert!(err.to_string().contains("invalid profile name"), "{err}");
+    dir.write(
+        "service.other.syaml",
+        "---!syaml/v0\n---meta\noverlay: ./other.syaml\n---data\nx: 2\n",
+    );
+    let err = dir.compile("service.syaml", Some("other")).unwrap_err();
+    assert!(err.to_string().contains("is not an overlay of"), "{err}");
+
+    let options = CompileOptions {
+        profile: Some("prod".to_string()),
+        ..CompileOptions::default()
+    };
+    let err = super_yaml::compile_document_with_options(
+        "---!syaml/v0\n---data\nx: 1\n",
+        &MapEnvProvider::new(HashMap::new()),
+        &options,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("needs a file path"), "{err}");
+    assert!(compile_document("---!syaml/v0\n---data\nx: 1\n", &ProcessEnvProvider).is_ok());
+}
+
+/// `path` named relative to the working directory.
+fn relative_to_cwd(path: &Path) -> PathBuf {
+    let cwd = fs::canonicalize(std::env::current_dir().expect("cwd")).expect("canonical cwd");
+    let mut relative = PathBuf::new();
+    for _ in cwd.components().skip(1) {
+        relative.push("..");
+    }
+    relative.extend(
+        fs::canonicalize(path)
+            .expect("canonical path")
+            .components()
+            .skip(1),
+    );
+    relative
+}
+
+#[test]
+fn explain_names_overlay_and_base_files_alike() {
+    let dir = TempDir::new("explain");
+    dir.write("service.syaml", BASE);
+    dir.write(
+        "service.prod.syaml",
+        "---!syaml/v0\n---meta\noverlay: ./service.syaml\n---data\nservice:\n  replicas: 4\n",
+    );
+
+    let relative = relative_to_cwd(&dir.path);
+    let (_, provenance) = compile_document_with_provenance_from_path(
+        relative.join("service.prod.syaml"),
+        &ProcessEnvProvider,
+    )
+    .unwrap();
+    let text = provenance.explain("pods").unwrap();
+    let base = format!("{}:", relative.join("service.syaml").display());
+    let overlay = format!("{}:", relative.join("service.prod.syaml").display());
+    assert!(text.contains(&format!("pods = 8 ({base}")), "{text}");
+    assert!(
+        text.contains(&format!("service.replicas = 4 ({overlay}")),
+        "{text}"
+    );
+
+    let absolute = dir.path.canonicalize().unwrap();
+    let (_, provenance) = compile_document_with_provenance_from_path(
+        absolute.join("service.prod.syaml"),
+        &ProcessEnvProvider,
+    )
+    .unwrap();
+    let text = provenance.explain("pods").unwrap();
+    let overlay = format!("{}:", absolute.join("service.prod.syaml").display());
+    assert!(text.contains(&overlay), "{text}");
+}
@@ -227,3 +227,38 @@ fn generate_proto_types_from_versioned_fields_example() {
         "deprecated fields should have [deprecated = true]:\n{out}"
     );
 }
+
+#[test]
+fn generate_proto_types_maps_quantity_types() {
+    let doc = r#"---!syaml/v0
+---schema
+Job:
+  type: object
+  properties:
+    timeout:
+      type: duration
+      field_number: 1
+    started_at:
+      type: timestamp
+      field_number: 2
+    memory:
+      type: bytesize
+      field_number: 3
+---data
+"#;
+    let out = proto(doc);
+    assert!(
+        out.contains("import \"google/protobuf/duration.proto\";")
+            && out.contains("import \"google/protobuf/timestamp.proto\";"),
+        "missing well-known type imports:\n{out}"
+    );
+    assert!(
+        out.contains("google.protobuf.Duration timeout = 1;"),
+        "{out}"
+    );
+    assert!(
+        out.contains("google.protobuf.Timestamp started_at = 2;"),
+        "{out}"
+    );
+    assert!(out.contains("uint64 memory = 3;"), "{out}");
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value as JsonValue};
+
+use super_yaml::schema::validate_schema_type_references;
+use super_yaml::{
+    compile_document, compile_document_to_json_schema, from_json_schema, generate_rust_types,
+    generate_typescript_types, parse_document, MapEnvProvider,
+};
+
+fn env() -> MapEnvProvider {
+    MapEnvProvider::new(HashMap::new())
+}
+
+const TREES: &str = r#"---!syaml/v0
+---schema
+MenuItem:
+  type: object
+  properties:
+    label: string
+    children:
+      type: array
+      items: MenuItem
+      optional: true
+Rule:
+  type: object
+  properties:
+    match: string
+    next: Rule?
+Expr:
+  type: union
+  options:
+    - integer
+    - BinOp
+BinOp:
+  type: object
+  properties:
+    op: string
+    args:
+      type: array
+      items: Expr
+Team:
+  type: object
+  properties:
+    name: string
+    lead: Person
+    teams:
+      type: object
+      values: Team
+Person:
+  type: object
+  properties:
+    name: string
+    team: Team?
+---data
+menu <MenuItem>:
+  label: root
+  children:
+    -
+      label: file
+      children:
+        -
+          label: open
+rule <Rule>:
+  match: /api
+  next:
+    match: /api/v1
+expr <Expr>:
+  op: add
+  args: [1, {op: mul, args: [2, 3]}]
+org <Team>:
+  name: eng
+  lead:
+    name: ada
+  teams:
+    infra:
+      name: infra
+      lead:
+        name: bob
+      teams: {}
+depth: =len(menu.children)
+"#;
+
+#[test]
+fn recursive_types_validate_nested_values() {
+    let compiled = compile_document(TREES, &env()).unwrap();
+    assert_eq!(
+        compiled.value["menu"]["children"][0]["children"][0],
+        json!({"label": "open"})
+    );
+    assert_eq!(compiled.value["rule"]["next"], json!({"match": "/api/v1"}));
+    assert_eq!(
+        compiled.value["org"]["teams"]["infra"]["lead"]["name"],
+        json!("bob")
+    );
+    assert_eq!(compiled.value["depth"], json!(1));
+
+    let cases = [
+        (
+            "          label: open\n",
+            "          label: 7\n",
+            "type mismatch at $.menu.children[0].children[0].label",
+        ),
+        (
+            "    match: /api/v1\n",
+            "    match: /api/v1\n    next:\n      next: {}\n",
+            "required property missing at $.rule.next.next",
+        ),
+        (
+            "args: [2, 3]",
+            "args: [2, x]",
+            "union mismatch at $.expr.args[1].args[1]",
+        ),
+    ];
+    for (from, to, expected) in cases {
+        let err = compile_document(&TREES.replacen(from, to, 1), &env()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{to}: {err}");
+    }
+}
+
+#[test]
+fn deeply_nested_recursive_values_validate() {
+    let mut doc = String::from(
+        "---!syaml/v0\n---schema\nNode:\n  type: object\n  properties:\n    name: string\n    child: Node?\n---data\nroot <Node>:\n",
+    );
+    let mut indent = String::from("  ");
+    for level in 0..50 {
+        doc.push_str(&format!("{indent}name: n{level}\n{indent}child:\n"));
+        indent.push_str("  ");
+    }
+    doc.push_str(&format!("{indent}name: leaf\n"));
+    compile_document(&doc, &env()).unwrap();
+
+    let err = compile_document(&doc.replace("name: leaf", "name: [leaf]"), &env()).unwrap_err();
+    assert!(err.to_string().contains("type mismatch"), "{err}");
+}
+
+#[test]
+fn recursive_types_without_a_finite_value_are_rejected() {
+    let cases = [
+        (
+            "Node:\n  type: object\n  properties:\n    next: Node\n",
+            "recursive type 'Node' at schema.Node has no finite value",
+        ),
+        (
+            "Holder:\n  type: object\n  properties:\n    a: A\nA: B\nB:\n  type: A\n",
+            "recursive type 'A' at schema.A has no finite value",
+        ),
+        (
+            "Node:\n  type: object\n  properties:\n    children:\n      type: array\n      items: Node\n      minItems: 1\n",
+            "recursive type 'Node'",
+        ),
+        (
+            "Loop:\n  type: union\n  options: [Loop]\n",
+            "recursive type 'Loop'",
+        ),
+    ];
+    for (schema, expected) in cases {
+        let doc = format!("---!syaml/v0\n---schema\n{schema}---data\n{{}}\n");
+        let err = compile_document(&doc, &env()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{schema}: {err}");
+    }
+
+    let parsed = parse_document(TREES).unwrap();
+    validate_schema_type_references(&parsed.schema.types).unwrap();
+}
+
+#[test]
+fn json_schema_export_uses_refs_for_recursion() {
+    let exported: JsonValue =
+        serde_json::from_str(&compile_document_to_json_schema(TREES, &env(), false).unwrap())
+            .unwrap();
+    let defs = &exported["$defs"];
+    assert_eq!(
+        defs["MenuItem"]["properties"]["children"]["items"],
+        json!({"$ref": "#/$defs/MenuItem"})
+    );
+    assert_eq!(
+        defs["Rule"]["properties"]["next"],
+        json!({"$ref": "#/$defs/Rule"})
+    );
+    assert_eq!(
+        defs["Team"]["properties"]["teams"]["additionalProperties"],
+        json!({"$ref": "#/$defs/Team"})
+    );
+
+    // The exported schema imports back to the same recursive types.
+    let imported = from_json_schema(&exported.to_string()).unwrap();
+    let doc = TREES.replacen(
+        &TREES[TREES.find("---schema").unwrap()..TREES.find("---data").unwrap()],
+        &imported[imported.find("---schema").unwrap()..],
+        1,
+    );
+    assert_eq!(
+        compile_document(&doc, &env()).unwrap().value,
+        compile_document(TREES, &env()).unwrap().value
+    );
+}
+
+#[test]
+fn rust_codegen_boxes_recursive_fields() {
+    let rust = generate_rust_types(TREES).unwrap();
+    assert!(rust.contains("pub next: Option<Box<Rule>>,"), "{rust}");
+    assert!(
+        rust.contains("pub children: Option<Vec<MenuItem>>,"),
+        "{rust}"
+    );
+    assert!(rust.contains("pub args: Vec<Expr>,"), "{rust}");
+    assert!(rust.contains("    BinOp(BinOp),"), "{rust}");
+    assert!(
+        rust.contains("pub teams: std::collections::BTreeMap<String, Team>,"),
+        "{rust}"
+    );
+    // Team and Person hold each other inline, so both sides are boxed.
+    assert!(rust.contains("pub lead: Box<Person>,"), "{rust}");
+    assert!(rust.contains("pub team: Option<Box<Team>>,"), "{rust}");
+}
+
+#[test]
+fn typescript_codegen_emits_recursive_interfaces() {
+    let ts = generate_typescript_types(TREES).unwrap();
+    assert!(
+        ts.contains(
+            "export interface MenuItem {\n  children?: Array<MenuItem>;\n  label: string;\n}"
+        ),
+        "{ts}"
+    );
+    assert!(ts.contains("  next?: Rule;\n"), "{ts}");
+    assert!(ts.contains("export type Expr = number | BinOp;"), "{ts}");
+    assert!(ts.contains("  teams: Record<string, Team>;\n"), "{ts}");
+}
@@ -2,9 +2,9 @@ use std::collections::{BTreeMap, HashMap};
 
 use serde_json::{json, Map as JsonMap, Value as JsonValue};
 
-use super_yaml::ast::{EnvBinding, Meta};
+use super_yaml::ast::{EnvBinding, EnvSource, Meta};
 use super_yaml::resolve::{
-    get_json_path, resolve_env_bindings, resolve_expressions, MapEnvProvider,
+    get_json_path, query_json_path, resolve_env_bindings, resolve_expressions, MapEnvProvider,
 };
 
 fn env_provider(vars: &[(&str, &str)]) -> MapEnvProvider {
@@ -24,6 +24,10 @@ fn resolve_env_bindings_uses_env_defaults_and_null_for_optional() {
             key: "NUM_KEY".to_string(),
             required: true,
             default: None,
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
     env_defs.insert(
@@ -32,6 +36,10 @@ fn resolve_env_bindings_uses_env_defaults_and_null_for_optional() {
             key: "FLAG_KEY".to_string(),
             required: true,
             default: None,
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
     env_defs.insert(
@@ -40,6 +48,10 @@ fn resolve_env_bindings_uses_env_defaults_and_null_for_optional() {
             key: "MISSING".to_string(),
             required: true,
             default: Some(json!("fallback")),
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
     env_defs.insert(
@@ -48,6 +60,10 @@ fn resolve_env_bindings_uses_env_defaults_and_null_for_optional() {
             key: "NOPE".to_string(),
             required: false,
             default: None,
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
 
@@ -77,6 +93,10 @@ fn resolve_env_bindings_errors_for_missing_required_without_default() {
             key: "DB_HOST".to_string(),
             required: true,
             default: None,
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
 
@@ -106,6 +126,10 @@ fn resolve_env_bindings_redacts_raw_values_in_parse_errors() {
             key: "BAD_ENV".to_string(),
             required: true,
             default: None,
+            source: EnvSource::Env,
+            type_ref: None,
+            parse: None,
+            sensitive: false,
         },
     );
 
@@ -151,7 +175,30 @@ fn resolve_expressions_detects_cycles() {
     let env = BTreeMap::new();
 
     let err = resolve_expressions(&mut data, &env).unwrap_err();
-    assert!(err.to_string().contains("possible dependency cycle"));
+    assert!(err
+        .to_string()
+        .contains("derived values form a dependency cycle: a -> b -> a"));
+}
+
+#[test]
+fn resolve_expressions_reports_only_the_cycle() {
+    let mut data = json!({
+        "alpha": "=beta + 1",
+        "beta": "=gamma * 2",
+        "gamma": "=delta + alpha",
+        "delta": "=1",
+        "tail": "=alpha + delta"
+    });
+    let env = BTreeMap::new();
+
+    let err = resolve_expressions(&mut data, &env)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("dependency cycle: alpha -> beta -> gamma -> alpha"),
+        "{err}"
+    );
+    assert!(!err.contains("delta") && !err.contains("tail"), "{err}");
 }
 
 #[test]
@@ -198,6 +245,49 @@ fn get_json_path_supports_root_object_and_arrays() {
     assert!(get_json_path(&data, "name").is_none());
 }
 
+#[test]
+fn query_json_path_expands_wildcards() {
+    let data = json!({
+        "services": {
+            "web": {"port": 80},
+            "db": {"port": 5432},
+            "cache": {}
+        },
+        "items": [{"id": 1}, {"id": 2}]
+    });
+
+    let found = |path: &str| {
+        query_json_path(&data, path)
+            .unwrap()
+            .into_iter()
+            .map(|(at, value)| (at, value.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        found("$.services.*.port"),
+        vec![
+            ("$.services.db.port".to_string(), json!(5432)),
+            ("$.services.web.port".to_string(), json!(80)),
+        ]
+    );
+    assert_eq!(
+        found("$.items[*].id"),
+        vec![
+            ("$.items[0].id".to_string(), json!(1)),
+            ("$.items[1].id".to_string(), json!(2)),
+        ]
+    );
+    assert_eq!(
+        found("$.items[1]"),
+        vec![("$.items[1]".to_string(), json!({"id": 2}))]
+    );
+    assert_eq!(found("$"), vec![("$".to_string(), data.clone())]);
+    assert!(found("$.missing.*").is_empty());
+    assert!(found("$.items.*").is_empty());
+    assert!(query_json_path(&data, "$.items[x]").is_err());
+    assert!(get_json_path(&data, "$.items[*]").is_none());
+}
+
 #[test]
 fn resolve_expressions_updates_nested_array_paths() {
     let mut data = json!({
@@ -208,8 +298,8 @@ fn resolve_expressions_updates_nested_array_paths() {
     });
     let env = BTreeMap::new();
 
-    let err = resolve_expressions(&mut data, &env).unwrap_err();
-    assert!(err.to_string().contains("unexpected character '['"));
+    resolve_expressions(&mut data, &env).unwrap();
+    assert_eq!(data["nested"][0]["v"], json!(3));
 }
 
 #[test]
@@ -244,18 +334,44 @@ fn resolve_expressions_supports_multi_pass_dependencies() {
 }
 
 #[test]
-fn resolve_expressions_rejects_excessive_expression_nodes() {
+fn resolve_expressions_evaluates_long_dependency_chains_once() {
+    // Each value reads the one after it, so document order is the worst order.
+    let count = 5000;
     let mut map = JsonMap::new();
-    for i in 0..1030 {
-        map.insert(format!("k{i}"), JsonValue::String("=1".to_string()));
+    for i in 0..count {
+        let value = if i + 1 == count {
+            "=1".to_string()
+        } else {
+            format!("=k{} + 1", i + 1)
+        };
+        map.insert(format!("k{i}"), JsonValue::String(value));
     }
     let mut data = JsonValue::Object(map);
     let env = BTreeMap::new();
 
-    let err = resolve_expressions(&mut data, &env).unwrap_err();
-    assert!(err
-        .to_string()
-        .contains("too many derived expressions/interpolations"));
+    resolve_expressions(&mut data, &env).unwrap();
+    assert_eq!(data["k0"], json!(count));
+}
+
+#[test]
+fn compile_type_checks_long_dependency_chains() {
+    let count = 20_000;
+    let mut doc = String::from("---!syaml/v0\n---data\n");
+    for i in 0..count - 1 {
+        doc.push_str(&format!("k{i}: \"=k{} + 1\"\n", i + 1));
+    }
+    doc.push_str(&format!("k{}: 1\n", count - 1));
+
+    let compiled = super_yaml::compile_document(&doc, &env_provider(&[])).unwrap();
+    let json: JsonValue = serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["k0"], json!(count));
+
+    let cyclic = doc.replace(
+        &format!("k{}: 1", count - 1),
+        &format!("k{}: \"=k0\"", count - 1),
+    );
+    let err = super_yaml::compile_document(&cyclic, &env_provider(&[])).unwrap_err();
+    assert!(err.to_string().contains("dependency cycle"), "{err}");
 }
 
 #[test]
@@ -1,8 +1,12 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use super_yaml::{generate_rust_types, generate_rust_types_from_path};
+use super_yaml::{
+    generate_rust_types, generate_rust_types_and_data_from_path, generate_rust_types_from_path,
+    MapEnvProvider,
+};
 
 struct TempDir {
     path: PathBuf,
@@ -41,6 +45,42 @@ impl Drop for TempDir {
     }
 }
 
+/// Builds `rust` with a `main` body in a crate that depends on this one,
+/// runs it, and returns its stdout.
+fn compile_and_run(rust: &str, main_body: &str) -> String {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let project = Path::new(env!("CARGO_TARGET_TMPDIR")).join("rust_codegen_check");
+    fs::create_dir_all(project.join("src")).expect("create check crate");
+    fs::write(
+        project.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"rust_codegen_check\"\nversion = \"0.0.0\"\nedition = \"2021\"\n\n[workspace]\n\n[dependencies]\nsuper_yaml = {{ path = {:?} }}\nserde = {{ version = \"1\", features = [\"derive\"] }}\n",
+            manifest_dir.display().to_string()
+        ),
+    )
+    .expect("write manifest");
+    if let Ok(lock) = fs::read(manifest_dir.join("Cargo.lock")) {
+        fs::write(project.join("Cargo.lock"), lock).expect("write lockfile");
+    }
+    fs::write(
+        project.join("src/main.rs"),
+        format!("#![allow(dead_code)]\n{rust}\nfn main() {{\n{main_body}\n}}\n"),
+    )
+    .expect("write main.rs");
+
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["run", "--quiet", "--offline", "--manifest-path"])
+        .arg(project.join("Cargo.toml"))
+        .output()
+        .expect("run cargo");
+    assert!(
+        output.status.success(),
+        "generated code failed to build or run:\n{}\n{rust}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("utf-8 output")
+}
+
 #[test]
 fn generate_rust_types_from_inline_document() {
     let input = r#"
@@ -241,3 +281,187 @@ x: 1
     assert!(rendered.contains("pub fn value(&self) -> Timezone"));
     assert!(rendered.contains("pub fn timezone_value(key: TimezoneKey) -> Timezone"));
 }
+
+#[test]
+fn contract_strict_conditions_translate_conditionals_and_let() {
+    let dir = TempDir::new("strict_conditionals");
+    dir.write(
+        "root.syaml",
+        r#"
+---!syaml/v0
+---schema
+Limit: integer
+---data
+limit <Limit>: 10
+---contracts
+Scale:
+  inputs:
+    replicas:
+      type: integer
+    burst:
+      type: boolean
+  output:
+    type: integer
+  permissions:
+    data:
+      read:
+        - "$.limit"
+  specification:
+    preconditions:
+      strict:
+        - "input.replicas <= (if input.burst then data.limit * 2 else data.limit)"
+    postconditions:
+      strict:
+        - "let floor = input.burst ? 2 : 1 in output >= floor"
+"#,
+    );
+
+    let rendered = generate_rust_types_and_data_from_path(
+        dir.file_path("root.syaml"),
+        &MapEnvProvider::new(Default::default()),
+    )
+    .unwrap();
+
+    assert!(
+        rendered.contains("if !(replicas <= (if burst { data_limit * 2 } else { data_limit })) {"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains("if !({ let floor = if burst { 2 } else { 1 }; output >= floor }) {"),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn generate_rust_types_maps_quantity_types() {
+    let input = r#"---!syaml/v0
+---schema
+Job:
+  type: object
+  properties:
+    timeout: duration
+    started_at: timestamp
+    memory:
+      type: bytesize
+      optional: true
+---data
+"#;
+    let rendered = generate_rust_types(input).unwrap();
+    assert!(rendered.contains("pub timeout: super_yaml::quantity::Duration,"));
+    assert!(rendered.contains("pub started_at: super_yaml::quantity::Timestamp,"));
+    assert!(rendered.contains("Option<super_yaml::quantity::ByteSize>"));
+}
+
+#[test]
+fn generate_rust_data_boxes_recursive_fields() {
+    let dir = TempDir::new("recursive_data");
+    dir.write(
+        "rules.syaml",
+        r#"---!syaml/v0
+---schema
+Rule:
+  type: object
+  properties:
+    match: string
+    next: Rule?
+Team:
+  type: object
+  properties:
+    name: string
+    lead: Person
+Person:
+  type: object
+  properties:
+    name: string
+    team: Team?
+---data
+rule <Rule>:
+  match: /api
+  next:
+    match: /api/v1
+    next:
+      match: /api/v1/users
+team <Team>:
+  name: eng
+  lead:
+    name: ada
+"#,
+    );
+
+    let rendered = generate_rust_types_and_data_from_path(
+        dir.file_path("rules.syaml"),
+        &MapEnvProvider::new(Default::default()),
+    )
+    .unwrap();
+
+    assert!(
+        rendered.contains("pub next: Option<Box<Rule>>,"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains(
+            "next: Some(Box::new(Rule { match_: \"/api/v1\".to_string(), next: Some(Box::new(Rule { match_: \"/api/v1/users\".to_string(), next: None })) })),"
+        ),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains("lead: Box::new(Person { name: \"ada\".to_string(), team: None }),"),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn generate_rust_data_builds_quantity_values() {
+    let dir = TempDir::new("quantity_data");
+    dir.write(
+        "job.syaml",
+        r#"---!syaml/v0
+---schema
+Timeout: duration
+Job:
+  type: object
+  properties:
+    timeout: Timeout
+    started_at: timestamp
+    memory:
+      type: bytesize
+      optional: true
+    sizes:
+      type: array
+      items: bytesize
+---data
+job <Job>:
+  timeout: 30s
+  started_at: 2024-01-02T03:04:05Z
+  memory: 512MiB
+  sizes: [1KiB, 2048]
+grace <duration>: 5m
+retry <Timeout>: 1m30s
+"#,
+    );
+
+    let rendered = generate_rust_types_and_data_from_path(
+        dir.file_path("job.syaml"),
+        &MapEnvProvider::new(Default::default()),
+    )
+    .unwrap();
+    assert!(
+        rendered.contains("timeout: \"30s\".parse::<super_yaml::quantity::Duration>().unwrap(),"),
+        "{rendered}"
+    );
+    assert!(
+        rendered.contains("super_yaml::quantity::ByteSize::from_bytes(2048)"),
+        "{rendered}"
+    );
+
+    let stdout = compile_and_run(
+        &rendered,
+        r#"    let job = job();
+    println!("{} {} {:?} {:?}", job.timeout, job.started_at, job.memory.map(|m| m.to_string()), job.sizes.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    println!("{} {}", grace(), retry());"#,
+    );
+    assert_eq!(
+        stdout,
+        "30s 2024-01-02T03:04:05Z Some(\"512MiB\") [\"1KiB\", \"2KiB\"]\n5m 1m30s\n"
+    );
+}
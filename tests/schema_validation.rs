@@ -454,6 +454,66 @@ fn validate_json_against_schema_catches_object_array_enum_violations() {
     assert!(err.to_string().contains("enum mismatch"));
 }
 
+#[test]
+fn schema_validation_reports_every_bad_sibling() {
+    let input = r#"
+---!syaml/v0
+---schema
+Svc:
+  type: object
+  properties:
+    port: integer
+    name: string
+    tags:
+      type: array
+      items: string
+    host: string
+    limits:
+      type: object
+      values: integer
+---data
+c <Svc>:
+  port: "x"
+  name: 5
+  tags: [1, ok, 2]
+  limits:
+    cpu: 2
+    mem: lots
+"#;
+    let env = MapEnvProvider::new(Default::default());
+    let err = compile_document(input, &env).unwrap_err();
+    let found: Vec<(Option<&str>, String)> = err
+        .errors()
+        .iter()
+        .map(|e| (e.data_path(), e.to_string()))
+        .collect();
+    let paths: Vec<Option<&str>> = found.iter().map(|(path, _)| *path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            Some("$.c"),
+            Some("$.c.limits.mem"),
+            Some("$.c.name"),
+            Some("$.c.port"),
+            Some("$.c.tags[0]"),
+            Some("$.c.tags[2]"),
+        ],
+        "{err}"
+    );
+    assert!(
+        found[0]
+            .1
+            .contains("required property missing at $.c: 'host'"),
+        "{err}"
+    );
+    assert!(
+        found[3]
+            .1
+            .contains("type mismatch at $.c.port: expected integer, found string"),
+        "{err}"
+    );
+}
+
 #[test]
 fn validate_json_against_schema_reports_invalid_pattern() {
     let err = validate_json_against_schema(
@@ -2119,11 +2179,14 @@ item <Item>:
 "#;
     let compiled = compile_document(doc, &no_env()).unwrap();
     assert_eq!(compiled.warnings.len(), 1);
+    let warning = &compiled.warnings[0];
     assert!(
-        compiled.warnings[0].contains("deprecated"),
+        warning.message.contains("deprecated"),
         "expected deprecation warning: {:?}",
         compiled.warnings
     );
+    assert_eq!(warning.data_path.as_deref(), Some("$.item.old_field"));
+    assert_eq!(warning.span.as_ref().map(|span| span.line), Some(17));
 }
 
 #[test]
@@ -2441,3 +2504,620 @@ fn validate_schema_type_references_rejects_as_string_unknown_property() {
         err
     );
 }
+
+#[test]
+fn compile_reports_every_independent_validation_failure() {
+    let doc = r#"
+---!syaml/v0
+---schema
+Port:
+  type: integer
+  maximum: 65535
+Cfg:
+  type: object
+  properties:
+    replicas:
+      type: integer
+      constraints: "value >= 2"
+    workers:
+      type: integer
+      constraints: "value <= 8"
+---data
+a <Port>: 99999
+b <Port>: 70000
+cfg <Cfg>:
+  replicas: 1
+  workers: 10
+"#;
+    let err = compile_document(doc, &no_env()).unwrap_err();
+    let paths: Vec<&str> = err
+        .errors()
+        .into_iter()
+        .filter_map(|e| e.data_path())
+        .collect();
+    assert_eq!(
+        paths,
+        vec!["$.a", "$.b", "$.cfg.replicas", "$.cfg.workers"],
+        "unexpected errors: {err}"
+    );
+}
+
+#[test]
+fn compile_skips_constraints_on_values_that_failed_type_checks() {
+    let doc = r#"
+---!syaml/v0
+---schema
+Cfg:
+  type: object
+  properties:
+    replicas:
+      type: integer
+      constraints: "value >= 2"
+---data
+cfg <Cfg>:
+  replicas: many
+"#;
+    let err = compile_document(doc, &no_env()).unwrap_err();
+    assert_eq!(err.errors().len(), 1, "unexpected errors: {err}");
+    assert!(
+        err.to_string().contains("type mismatch"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn string_functions_work_in_derived_values_and_constraints() {
+    let doc = r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name:
+      type: string
+      constraints: 'matches(value, "^[a-z][a-z0-9-]*$")'
+    image: string
+  constraints:
+    - 'starts_with(image, "registry.local/")'
+---data
+raw_name: "  Billing-API "
+svc <Service>:
+  name: "=lower(trim(raw_name))"
+  image: '=format("registry.local/{}:{}", lower(trim(raw_name)), pad_left("7", 3, "0"))'
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["svc"]["name"], "billing-api");
+    assert_eq!(json["svc"]["image"], "registry.local/billing-api:007");
+
+    let err = compile_document(
+        &doc.replace("lower(trim(raw_name))\"", "trim(raw_name)\""),
+        &no_env(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("matches(value"), "{err}");
+}
+
+#[test]
+fn collection_functions_work_in_derived_values_and_constraints() {
+    let doc = r#"---!syaml/v0
+---schema
+Replica:
+  type: object
+  properties:
+    name: string
+    port: integer
+Deployment:
+  type: object
+  properties:
+    replicas:
+      type: array
+      items: Replica
+    weights:
+      type: array
+      items: integer
+  constraints:
+    - 'len(unique(map(replicas, r => r.port))) == len(replicas)'
+    - 'sum(weights) == 100'
+    - 'replicas[0].port > 1024'
+---data
+base_port: 8080
+deploy <Deployment>:
+  replicas:
+    -
+      name: a
+      port: =base_port
+    -
+      name: b
+      port: =base_port + 1
+  weights: [60, 40]
+first_name: =deploy.replicas[0].name
+ports: '=map(deploy.replicas, r => r.port)'
+busy: '=count(deploy.replicas, r => r.port > 8080)'
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["first_name"], "a");
+    assert_eq!(json["ports"], serde_json::json!([8080, 8081]));
+    assert_eq!(json["busy"], 1);
+
+    let err =
+        compile_document(&doc.replace("=base_port + 1", "=base_port"), &no_env()).unwrap_err();
+    assert!(err.to_string().contains("len(unique("), "{err}");
+
+    let err = compile_document(&doc.replace("[60, 40]", "[60, 30]"), &no_env()).unwrap_err();
+    assert!(err.to_string().contains("sum(weights)"), "{err}");
+}
+
+#[test]
+fn conditionals_and_let_work_in_data_interpolation_and_constraints() {
+    let doc = r#"---!syaml/v0
+---schema
+Pool:
+  type: object
+  properties:
+    tier: string
+    size: integer
+  constraints:
+    - 'if tier == "prod" then size >= 3 else size >= 1'
+    - 'let cap = tier == "prod" ? 10 : 4 in size <= cap'
+---data
+tier: prod
+pool <Pool>:
+  tier: =tier
+  size: '=let base = 2 in tier == "prod" ? base * 2 : base'
+label: '${tier == "prod" ? "live" : "test"}-${if pool.size > 3 then "big" else "small"}'
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["pool"]["size"], 4);
+    assert_eq!(json["label"], "live-big");
+
+    let err = compile_document(&doc.replace("base * 2", "base * 6"), &no_env()).unwrap_err();
+    assert!(err.to_string().contains("size <= cap"), "{err}");
+}
+
+#[test]
+fn quantity_types_validate_and_bound_values() {
+    let doc = r#"---!syaml/v0
+---schema
+Timeout:
+  type: duration
+  minimum: 1s
+  maximum: 1h
+Service:
+  type: object
+  properties:
+    timeout: Timeout
+    memory: bytesize
+    deployed_at: timestamp
+  constraints:
+    - "timeout * 2 <= 1h"
+---data
+base <duration>: 1h30m
+retry <duration>: "=base / 4"
+svc <Service>:
+  timeout: 30s
+  memory: 1GB
+  deployed_at: "2024-02-29T23:59:59.250+01:00"
+raw <bytesize>: 1048576
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["retry"], "22m30s");
+    assert_eq!(json["svc"]["timeout"], "30s");
+    assert_eq!(json["raw"], 1048576);
+
+    let err = compile_document(&doc.replace("timeout: 30s", "timeout: 2h"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("maximum violation"), "{err}");
+
+    let err = compile_document(&doc.replace("memory: 1GB", "memory: lots"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid byte size 'lots'"), "{err}");
+}
+
+#[test]
+fn hinted_quantity_values_are_quantity_operands() {
+    let doc = r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    timeout: duration
+---data
+t <duration>: 90s
+t2 <duration>: 1h30m
+at <timestamp>: "2024-01-01T00:00:00Z"
+size <bytesize>: 1KB
+svc <Service>:
+  timeout: 1m30s
+sum <duration>: "=t + t2"
+later <timestamp>: "=at + t"
+doubled <bytesize>: "=size + size"
+same: "=t == svc.timeout"
+differ: "=t != t2"
+longer: "=t2 > t"
+label: "wait ${t}"
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["sum"], "1h31m30s");
+    assert_eq!(json["later"], "2024-01-01T00:01:30Z");
+    assert_eq!(json["doubled"], "2KB");
+    assert_eq!(json["same"], true);
+    assert_eq!(json["differ"], true);
+    assert_eq!(json["longer"], true);
+    assert_eq!(json["label"], "wait 90s");
+
+    let err = compile_document(&doc.replace("=t + t2", "=t + size"), &no_env()).unwrap_err();
+    assert!(err.to_string().contains("cannot apply '+'"), "{err}");
+}
+
+#[test]
+fn compile_type_checks_derived_values_before_evaluating_them() {
+    let doc = r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name: string
+    replicas: integer
+    timeout: duration
+---data
+worker_threads: 4
+svc <Service>:
+  name: api
+  replicas: 2
+  timeout: 30s
+total: "=svc.replicas * worker_thread"
+label: "=svc.name * 2"
+port <integer>: '=svc.name + "-x"'
+grace <duration>: "=svc.timeout * 2"
+unused: "=if false then svc.name > 1 else 0"
+"#;
+    let err = compile_document(doc, &no_env()).unwrap_err();
+    let errors: Vec<(String, String)> = err
+        .errors()
+        .iter()
+        .map(|e| (e.data_path().unwrap_or("").to_string(), e.to_string()))
+        .collect();
+    assert_eq!(errors.len(), 4, "{err}");
+    let message = |path: &str| {
+        errors
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, m)| m.as_str())
+            .unwrap_or_else(|| panic!("no error at {path}: {err}"))
+    };
+    assert!(message("$.total").contains("unknown reference 'worker_thread'"));
+    assert!(message("$.label").contains("cannot apply '*' to string and integer in 'svc.name * 2'"));
+    assert!(message("$.port").contains("does not match type hint 'integer'"));
+    // Checked even though the branch is never taken.
+    assert!(message("$.unused").contains("cannot apply '>' to string and integer"));
+
+    let fixed = doc
+        .replace("worker_thread\"", "worker_threads\"")
+        .replace("svc.name * 2", "svc.name + 2")
+        .replace("port <integer>", "port <string>")
+        .replace("svc.name > 1", "svc.replicas > 1");
+    let compiled = compile_document(&fixed, &no_env()).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&compiled.to_json_string(false).unwrap()).unwrap();
+    assert_eq!(json["total"], 8);
+    assert_eq!(json["grace"], "1m");
+}
+
+#[test]
+fn compile_reports_type_errors_with_validation_failures() {
+    let doc = r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name: string
+    replicas:
+      type: integer
+      minimum: 1
+---data
+svc <Service>:
+  name: api
+  replicas: 0
+port <integer>: '=svc.name + "-x"'
+"#;
+    let err = compile_document(doc, &no_env()).unwrap_err();
+    let errors: Vec<(Option<&str>, String)> = err
+        .errors()
+        .iter()
+        .map(|e| (e.data_path(), e.to_string()))
+        .collect();
+    assert_eq!(errors.len(), 2, "{err}");
+    assert_eq!(errors[0].0, Some("$.port"), "{err}");
+    assert!(
+        errors[0].1.contains("does not match type hint 'integer'"),
+        "{err}"
+    );
+    assert_eq!(errors[1].0, Some("$.svc.replicas"), "{err}");
+    assert!(errors[1].1.contains("minimum violation"), "{err}");
+
+    // A value that cannot be evaluated either is reported once, by its type.
+    let err =
+        compile_document(&doc.replace("svc.name + \"-x\"", "svc.name * 2"), &no_env()).unwrap_err();
+    assert_eq!(err.errors().len(), 1, "{err}");
+    assert!(
+        err.to_string()
+            .contains("cannot apply '*' to string and integer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn type_local_constraints_are_type_checked() {
+    let err = compile_document(
+        r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name: string
+  constraints:
+    - 'name > "abc"'
+---data
+{}
+"#,
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.contains("cannot apply '>' to string and string in 'name > \"abc\"'"),
+        "{err}"
+    );
+
+    let err = compile_document(
+        r#"---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    tags:
+      type: array
+      items: string
+  constraints:
+    - "len(tags)"
+---data
+{}
+"#,
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.contains("must evaluate to boolean, got integer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn contract_strict_conditions_are_type_checked() {
+    let doc = r#"---!syaml/v0
+---data
+level: beginner
+---contracts
+Advance:
+  inputs:
+    new_level: string
+  output:
+    type: integer
+  permissions:
+    data:
+      read:
+        - "$.level"
+  specification:
+    preconditions:
+      strict:
+        - "input.new_level != data.level"
+    postconditions:
+      strict:
+        - "output >= 0"
+"#;
+    compile_document(doc, &no_env()).unwrap();
+
+    let err = compile_document(
+        &doc.replace(
+            "input.new_level != data.level",
+            "input.new_level > data.level",
+        ),
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.contains("cannot apply '>' to string and string"),
+        "{err}"
+    );
+
+    let err = compile_document(&doc.replace("output >= 0", "output + 1"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("strict postcondition 'output + 1': must evaluate to boolean, got integer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn compile_calls_user_functions_from_data_and_constraints() {
+    let doc = r#"---!syaml/v0
+---schema
+Worker:
+  type: object
+  properties:
+    cores: integer
+    memory_gb: number
+  constraints:
+    - "capacity(value.cores, value.memory_gb) >= 100"
+---functions
+capacity:
+  description: Requests one worker can serve.
+  params:
+    - cores <integer>
+    - memory_gb <number>
+  returns: integer
+  body: floor(min(cores * 250, memory_gb * 100))
+fleet:
+  params: [workers <array>]
+  body: sum(workers, w => capacity(w.cores, w.memory_gb))
+---data
+small <Worker>:
+  cores: 2
+  memory_gb: 4
+pool: [{cores: 2, memory_gb: 4}, {cores: 8, memory_gb: 32}]
+small_capacity: "=capacity(small.cores, small.memory_gb)"
+total: "=fleet(pool)"
+label: "serves ${capacity(1, 1)} rps"
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    assert_eq!(compiled.value["small_capacity"], json!(400));
+    assert_eq!(compiled.value["total"], json!(2400));
+    assert_eq!(compiled.value["label"], json!("serves 100 rps"));
+
+    let err = compile_document(&doc.replace("memory_gb: 4", "memory_gb: 0.5"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("'capacity(value.cores, value.memory_gb) >= 100' evaluated to false"),
+        "{err}"
+    );
+}
+
+#[test]
+fn compile_rejects_recursive_functions() {
+    let doc = r#"---!syaml/v0
+---functions
+countdown:
+  params: [n <integer>]
+  body: "n <= 0 ? 0 : step(n)"
+step:
+  params: [n <integer>]
+  body: countdown(n - 1)
+---data
+x: "=countdown(3)"
+"#;
+    let err = compile_document(doc, &no_env()).unwrap_err().to_string();
+    assert!(
+        err.contains("recursive call chain countdown -> step -> countdown"),
+        "{err}"
+    );
+
+    let direct = doc.replace("step(n)", "countdown(n - 1)");
+    let err = compile_document(&direct, &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("recursive call chain countdown -> countdown"),
+        "{err}"
+    );
+}
+
+#[test]
+fn function_bodies_and_calls_are_type_checked() {
+    let doc = r#"---!syaml/v0
+---functions
+scaled:
+  params:
+    - name <string>
+    - factor <integer>
+  returns: integer
+  body: factor * 2
+---data
+replicas: 3
+ok: '=scaled("api", replicas)'
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    assert_eq!(compiled.value["ok"], json!(6));
+
+    // The body sees only its parameters: no data, env or imports.
+    let err = compile_document(&doc.replace("factor * 2", "factor * replicas"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("functions.scaled.body: unknown reference 'replicas'"),
+        "{err}"
+    );
+
+    let err = compile_document(&doc.replace("factor * 2", "name + factor"), &no_env())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("result of type string does not match return type 'integer'"),
+        "{err}"
+    );
+
+    let err = compile_document(
+        &doc.replace(r#"scaled("api", replicas)"#, "scaled(replicas, replicas)"),
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(
+        err.contains("scaled() expects argument 1 ('name') to be string, got integer"),
+        "{err}"
+    );
+
+    let err = compile_document(
+        &doc.replace(r#"scaled("api", replicas)"#, "scaled(replicas)"),
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("scaled expects 2 arguments, got 1"), "{err}");
+}
+
+#[test]
+fn optional_fields_are_read_with_null_safe_access() {
+    let doc = r#"---!syaml/v0
+---schema
+Tls:
+  type: object
+  properties:
+    cert: string
+    port: integer?
+Server:
+  type: object
+  properties:
+    host: string
+    tls: Tls?
+    url: string?
+  constraints:
+    - "(value?.tls?.port ?? 443) > 0"
+---data
+server <Server>:
+  host: example.com
+  url: =server.host + ":" + (server?.tls?.port ?? 443)
+"#;
+    let compiled = compile_document(doc, &no_env()).unwrap();
+    assert_eq!(compiled.value["server"]["url"], json!("example.com:443"));
+
+    let with_tls = doc.replace(
+        "  host: example.com\n",
+        "  host: example.com\n  tls:\n    cert: a.pem\n    port: 8443\n",
+    );
+    let compiled = compile_document(&with_tls, &no_env()).unwrap();
+    assert_eq!(compiled.value["server"]["url"], json!("example.com:8443"));
+
+    let err = compile_document(
+        &doc.replace("server?.tls?.port", "server.tls.port"),
+        &no_env(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("path 'server.tls.port' not found"), "{err}");
+    assert!(!err.contains("cycle"), "{err}");
+}
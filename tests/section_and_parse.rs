@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use super_yaml::parse_document;
 use super_yaml::section_scanner::scan_sections;
-use super_yaml::{validate_document, MapEnvProvider};
+use super_yaml::{compile_document, validate_document, MapEnvProvider};
 
 fn env_provider(vars: &[(&str, &str)]) -> MapEnvProvider {
     let mut map = HashMap::new();
@@ -126,6 +126,53 @@ Compute:
     assert!(contracts.functions.contains_key("Compute"));
 }
 
+#[test]
+fn parse_document_parses_functions_section() {
+    let input = r#"
+---!syaml/v0
+---functions
+capacity:
+  params:
+    - cores <integer>
+    - memory_gb <number>
+  returns: integer
+  body: "=cores * 250"
+"#;
+
+    let parsed = parse_document(input).unwrap();
+    let functions = parsed.functions.expect("functions");
+    let capacity = &functions.functions["capacity"];
+    let params: Vec<(&str, &serde_json::Value)> = capacity
+        .params
+        .iter()
+        .map(|p| (p.name.as_str(), &p.type_ref))
+        .collect();
+    assert_eq!(
+        params,
+        vec![
+            ("cores", &serde_json::json!("integer")),
+            ("memory_gb", &serde_json::json!("number"))
+        ]
+    );
+    assert_eq!(capacity.body, "cores * 250");
+
+    for (from, to, message) in [
+        ("capacity:", "len:", "invalid function name 'len'"),
+        (
+            "- cores <integer>",
+            "- cores",
+            "invalid parameter \"cores\"",
+        ),
+        ("returns:", "result:", "unknown key 'result'"),
+        ("=cores * 250", "=cores *", "invalid expression"),
+    ] {
+        let err = parse_document(&input.replace(from, to))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(message), "{err}");
+    }
+}
+
 #[test]
 fn parse_document_validates_meta_env_shape() {
     let input = r#"
@@ -224,8 +271,8 @@ fn parse_document_rejects_unsupported_env_binding_source() {
 ---meta
 env:
   TOKEN:
-    from: file
-    key: TOKEN_FILE
+    from: vault
+    key: TOKEN
 ---schema
 {}
 ---data
@@ -233,7 +280,7 @@ name: ok
 "#;
 
     let err = parse_document(input).unwrap_err();
-    assert!(err.to_string().contains("unsupported from='file'"));
+    assert!(err.to_string().contains("unsupported from='vault'"));
 }
 
 #[test]
@@ -368,3 +415,74 @@ fn parse_document_handles_trailing_newlines() {
     assert_eq!(parsed.version, "v0");
     assert_eq!(parsed.data.value["name"], "x");
 }
+
+#[test]
+fn parse_errors_point_at_document_line() {
+    let input = "---!syaml/v0\n---data\na: 1\nb: [1, 2\n";
+    let err = parse_document(input).unwrap_err();
+    let span = err.span().expect("parse error should carry a span");
+    assert_eq!(span.line, 4);
+    assert_eq!(span.column, 1);
+    assert!(err
+        .to_string()
+        .contains("yaml parse error in section 'data'"));
+}
+
+#[test]
+fn validation_errors_point_at_offending_value() {
+    let input = r#"---!syaml/v0
+---schema
+Port:
+  type: integer
+  maximum: 65535
+---data
+name: svc
+port <Port>: 99999
+"#;
+    let err = compile_document(input, &env_provider(&[])).unwrap_err();
+    assert_eq!(err.data_path(), Some("$.port"));
+    let span = err.span().expect("validation error should carry a span");
+    assert_eq!(span.line, 8);
+    assert_eq!(&input[span.start..span.end], "99999");
+}
+
+#[test]
+fn anchors_aliases_and_merge_keys_compile_with_type_hints() {
+    let input = r#"---!syaml/v0
+---schema
+Port:
+  type: integer
+  maximum: 65535
+---data
+defaults: &defaults
+  port <Port>: 8080
+  replicas: 2
+api:
+  <<: *defaults
+  replicas: 4
+worker:
+  <<: *defaults
+"#;
+    let compiled = compile_document(input, &env_provider(&[])).unwrap();
+    let json = compiled.to_json_string(false).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        value["api"],
+        serde_json::json!({"port": 8080, "replicas": 4})
+    );
+    assert_eq!(
+        value["worker"],
+        serde_json::json!({"port": 8080, "replicas": 2})
+    );
+
+    let invalid = input.replace("8080", "99999");
+    let err = compile_document(&invalid, &env_provider(&[])).unwrap_err();
+    assert!(err.to_string().contains("65535"), "{err}");
+}
+
+#[test]
+fn aliases_do_not_cross_sections() {
+    let input = "---!syaml/v0\n---schema\nPort: &port integer\n---data\nx: *port\n";
+    let err = parse_document(input).unwrap_err();
+    assert!(err.to_string().contains("unknown alias '*port'"), "{err}");
+}
@@ -0,0 +1,470 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use super_yaml::ast::CompiledDocument;
+use super_yaml::contracts::{contracts_to_json, mark_sensitive_parameters};
+use super_yaml::sensitive::REDACTED;
+use super_yaml::source_map::render_snippet;
+use super_yaml::{
+    compile_document, compile_document_from_path, compile_document_with_provenance_from_path,
+    generate_html_docs, generate_rust_types, generate_rust_types_and_data_from_path,
+    generate_rust_types_and_redacted_data_from_path, generate_typescript_types_and_data_from_path,
+    generate_typescript_types_and_redacted_data_from_path, parse_document, MapEnvProvider,
+    ProcessEnvProvider, SyamlError,
+};
+
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(prefix: &str) -> Self {
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "super_yaml_sensitive_{}_{}_{}",
+            prefix,
+            std::process::id(),
+            stamp
+        ));
+        fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+
+    fn write(&self, file: &str, content: &str) {
+        fs::write(self.path.join(file), content).expect("write temp file");
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.path.exists() {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+fn compile_with_env(input: &str, vars: &[(&str, &str)]) -> Result<CompiledDocument, SyamlError> {
+    let env = MapEnvProvider::new(
+        vars.iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect::<HashMap<_, _>>(),
+    );
+    compile_document(input, &env)
+}
+
+fn paths(list: &[&str]) -> BTreeSet<String> {
+    list.iter().map(|p| p.to_string()).collect()
+}
+
+const SERVICE: &str = r#"
+---!syaml/v0
+---meta
+env:
+  DB_PASSWORD:
+    key: DB_PASSWORD
+    sensitive: true
+  DB_HOST:
+    key: DB_HOST
+---schema
+Credentials:
+  type: object
+  properties:
+    user: string
+    token:
+      type: string
+      sensitive: true
+ApiKey:
+  type: string
+  sensitive: true
+ApiKeys:
+  type: array
+  items: ApiKey
+---data
+db:
+  host: ${env.DB_HOST}
+  password: =env.DB_PASSWORD
+  url: "postgres://app:${env.DB_PASSWORD}@${env.DB_HOST}/app"
+password_copy: $.db.password
+db_copy: $.db
+host_len: =len(db.host)
+creds <Credentials>:
+  user: app
+  token: t0ken-value
+token_banner: "token=${creds.token}"
+api_keys <ApiKeys>: [k3y-one, k3y-two]
+"#;
+
+#[test]
+fn sensitivity_propagates_from_env_and_schema_markers() {
+    let compiled = compile_with_env(
+        SERVICE,
+        &[("DB_PASSWORD", "hunter22"), ("DB_HOST", "db.internal")],
+    )
+    .unwrap();
+    assert_eq!(
+        compiled.sensitive_paths,
+        paths(&[
+            "$.api_keys[0]",
+            "$.api_keys[1]",
+            "$.creds.token",
+            "$.db.password",
+            "$.db.url",
+            "$.db_copy",
+            "$.password_copy",
+            "$.token_banner",
+        ])
+    );
+    // Derived from non-secret values only.
+    assert_eq!(compiled.value["host_len"], json!(11));
+    assert_eq!(compiled.value["db"]["password"], json!("hunter22"));
+}
+
+#[test]
+fn redact_replaces_sensitive_values() {
+    let mut compiled = compile_with_env(
+        SERVICE,
+        &[("DB_PASSWORD", "hunter22"), ("DB_HOST", "db.internal")],
+    )
+    .unwrap();
+    compiled.redact();
+    assert_eq!(compiled.value["db"]["host"], json!("db.internal"));
+    assert_eq!(compiled.value["db"]["password"], json!(REDACTED));
+    assert_eq!(compiled.value["db_copy"], json!(REDACTED));
+    assert_eq!(compiled.value["creds"]["user"], json!("app"));
+    assert_eq!(compiled.value["creds"]["token"], json!(REDACTED));
+    let json = compiled.to_json_string(false).unwrap();
+    assert!(
+        !json.contains("hunter22") && !json.contains("t0ken-value"),
+        "{json}"
+    );
+}
+
+#[test]
+fn error_messages_mask_sensitive_values() {
+    let input = r#"
+---!syaml/v0
+---meta
+env:
+  API_TOKEN:
+    key: API_TOKEN
+    sensitive: true
+---schema
+Level:
+  enum: [low, high]
+Credentials:
+  type: object
+  properties:
+    token:
+      type: string
+      sensitive: true
+---data
+level <Level>: =env.API_TOKEN
+creds <Credentials>:
+  token: t0ken-value
+token_level <Level>: $.creds.token
+"#;
+    let err = compile_with_env(input, &[("API_TOKEN", "s3cr3t-token")]).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.contains("enum mismatch at $.level: value not in enum set"),
+        "{message}"
+    );
+    assert!(
+        message.contains("enum mismatch at $.token_level: value not in enum set"),
+        "{message}"
+    );
+    assert!(
+        !message.contains("s3cr3t-token") && !message.contains("t0ken-value"),
+        "{message}"
+    );
+
+    // Failures before validation hide the whole message of a value that
+    // reads a secret.
+    let derived = input.replace("level <Level>: =env.API_TOKEN", "level: =env.API_TOKEN * 2");
+    let message = compile_with_env(&derived, &[("API_TOKEN", "s3cr3t-token")])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        message.contains("details hidden because the value at $.level is sensitive"),
+        "{message}"
+    );
+    assert!(!message.contains("s3cr3t-token"), "{message}");
+}
+
+#[test]
+fn short_secrets_leave_unrelated_messages_alone() {
+    let input = r#"
+---!syaml/v0
+---meta
+env:
+  PIN:
+    key: PIN
+    sensitive: true
+---schema
+Port:
+  type: integer
+  minimum: 2
+Ports:
+  type: array
+  items: Port
+---data
+pin: =env.PIN
+ports <Ports>: [80, 1]
+"#;
+    let message = compile_with_env(input, &[("PIN", "1")])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        message.contains("minimum violation at $.ports[1]: 1 < 2"),
+        "{message}"
+    );
+    assert!(!message.contains(REDACTED), "{message}");
+}
+
+#[test]
+fn error_snippets_mask_sensitive_source_text() {
+    let input = r#"---!syaml/v0
+---schema
+Credentials:
+  type: object
+  properties:
+    token:
+      type: string
+      sensitive: true
+      maxLength: 4
+---data
+creds <Credentials>:
+  token: t0ken-value # rotated
+"#;
+    let err = compile_document(input, &MapEnvProvider::new(HashMap::new())).unwrap_err();
+    let span = err.span().expect("located");
+    assert!(span.sensitive);
+    let rendered = render_snippet(&err.to_string(), span, input);
+    assert!(!rendered.contains("t0ken-value"), "{rendered}");
+    assert!(
+        rendered.contains("12 |   token: [redacted] # rotated"),
+        "{rendered}"
+    );
+}
+
+#[test]
+fn sensitive_paths_follow_imported_values() {
+    let dir = TempDir::new("imports");
+    dir.write(
+        "shared.syaml",
+        r#"---!syaml/v0
+---meta
+env:
+  SHARED_SECRET:
+    key: SUPER_YAML_SENSITIVE_TEST_UNSET
+    default: shared-s3cret
+    sensitive: true
+---schema
+{}
+---data
+secret: =env.SHARED_SECRET
+region: eu
+"#,
+    );
+    dir.write(
+        "main.syaml",
+        r#"---!syaml/v0
+---meta
+imports:
+  shared: ./shared.syaml
+---schema
+{}
+---data
+copied: shared.secret
+derived: "${shared.secret}-x"
+region: =shared.region
+"#,
+    );
+    let compiled =
+        compile_document_from_path(dir.path.join("main.syaml"), &ProcessEnvProvider).unwrap();
+    assert_eq!(compiled.sensitive_paths, paths(&["$.copied", "$.derived"]));
+    assert_eq!(compiled.value["region"], json!("eu"));
+}
+
+#[test]
+fn explain_redacts_sensitive_values() {
+    let dir = TempDir::new("explain");
+    dir.write("service.syaml", SERVICE);
+    let env = MapEnvProvider::new(HashMap::from([
+        ("DB_PASSWORD".to_string(), "hunter22".to_string()),
+        ("DB_HOST".to_string(), "db.internal".to_string()),
+    ]));
+    let (_, provenance) =
+        compile_document_with_provenance_from_path(dir.path.join("service.syaml"), &env).unwrap();
+
+    for path in ["db.url", "password_copy", "db_copy", "token_banner"] {
+        let text = provenance.explain(path).unwrap();
+        assert!(!text.contains("hunter22"), "{text}");
+        assert!(!text.contains("t0ken-value"), "{text}");
+        assert!(text.contains(REDACTED), "{text}");
+    }
+    let text = provenance.explain("db.url").unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("db.url = \"[redacted]\" ("), "{text}");
+    assert!(
+        lines.contains(&"    env.DB_PASSWORD = \"[redacted]\" (environment variable DB_PASSWORD)"),
+        "{text}"
+    );
+    assert!(
+        lines.contains(&"    env.DB_HOST = \"db.internal\" (environment variable DB_HOST)"),
+        "{text}"
+    );
+    let text = provenance.explain("db_copy").unwrap();
+    assert!(text.starts_with("db_copy = \"[redacted]\""), "{text}");
+    assert!(
+        text.contains("\"host\":\"db.internal\",\"password\":\"[redacted]\""),
+        "{text}"
+    );
+
+    let text = provenance.explain("host_len").unwrap();
+    assert!(!text.contains(REDACTED), "{text}");
+}
+
+#[test]
+fn generated_docs_and_rust_types_hide_secrets() {
+    let input = r#"
+---!syaml/v0
+---meta
+env:
+  DB_PASSWORD:
+    key: DB_PASSWORD
+    default: dev-passw0rd
+    sensitive: true
+---schema
+Credentials:
+  type: object
+  properties:
+    user: string
+    token:
+      type: string
+      sensitive: true
+---data
+creds <Credentials>:
+  user: app
+  token: t0ken-value
+"#;
+    let html = generate_html_docs(input).unwrap();
+    assert!(!html.contains("dev-passw0rd"), "env default leaked");
+    assert!(!html.contains("t0ken-value"), "sensitive data leaked");
+    assert!(html.contains("sensitive-badge"));
+
+    let rust = generate_rust_types(input).unwrap();
+    assert!(rust.contains("pub token: Secret<String>"), "{rust}");
+    assert!(rust.contains("pub struct Secret<T>(pub T);"), "{rust}");
+    assert!(
+        rust.contains("f.write_str(\"Secret([redacted])\")"),
+        "{rust}"
+    );
+
+    let parsed = parse_document(input).unwrap();
+    let binding = &parsed.meta.unwrap().env["DB_PASSWORD"];
+    assert!(binding.sensitive);
+}
+
+#[test]
+fn generated_data_refuses_or_redacts_secrets() {
+    let dir = TempDir::new("codegen_data");
+    dir.write("service.syaml", SERVICE);
+    let file = dir.path.join("service.syaml");
+    let env = MapEnvProvider::new(HashMap::from([
+        ("DB_PASSWORD".to_string(), "hunter22".to_string()),
+        ("DB_HOST".to_string(), "db.internal".to_string()),
+    ]));
+
+    for err in [
+        generate_rust_types_and_data_from_path(&file, &env).unwrap_err(),
+        generate_typescript_types_and_data_from_path(&file, &env).unwrap_err(),
+    ] {
+        let message = err.to_string();
+        assert!(
+            message.contains("refusing to write sensitive data into generated code"),
+            "{message}"
+        );
+        assert!(message.contains("$.password_copy"), "{message}");
+        assert!(!message.contains("hunter22"), "{message}");
+    }
+
+    let rust = generate_rust_types_and_redacted_data_from_path(&file, &env).unwrap();
+    let ts = generate_typescript_types_and_redacted_data_from_path(&file, &env).unwrap();
+    for out in [&rust, &ts] {
+        for secret in ["hunter22", "t0ken-value", "k3y-one"] {
+            assert!(!out.contains(secret), "{secret} leaked:\n{out}");
+        }
+    }
+    assert!(
+        rust.contains(
+            "pub fn password_copy() -> Secret<String> {\n    Secret(\"[redacted]\".to_string())\n}"
+        ),
+        "{rust}"
+    );
+    assert!(
+        rust.contains("// `db` is left out: it holds sensitive values."),
+        "{rust}"
+    );
+    assert!(
+        rust.contains("// `creds` is left out: it holds sensitive values."),
+        "{rust}"
+    );
+    assert!(rust.contains("pub const HOST_LEN: i64 = 11;"), "{rust}");
+    assert_eq!(rust.matches("pub struct Secret<T>").count(), 1, "{rust}");
+
+    assert!(
+        ts.contains("export const passwordCopy: string = \"[redacted]\";"),
+        "{ts}"
+    );
+    assert!(
+        ts.contains("// `apiKeys` is left out: it holds sensitive values."),
+        "{ts}"
+    );
+    assert!(ts.contains("export const hostLen: number = 11;"), "{ts}");
+}
+
+#[test]
+fn contracts_mark_sensitive_parameters_by_flag_and_type() {
+    let input = r#"
+---!syaml/v0
+---schema
+Token:
+  type: string
+  sensitive: true
+---data
+{}
+---contracts
+login:
+  inputs:
+    user: string
+    token: Token
+    otp:
+      type: string
+      sensitive: true
+"#;
+    let parsed = parse_document(input).unwrap();
+    let mut contracts = parsed.contracts.unwrap();
+    mark_sensitive_parameters(&mut contracts, &parsed.schema.types);
+    let inputs = &contracts.functions["login"].inputs;
+    assert!(!inputs["user"].sensitive);
+    assert!(inputs["token"].sensitive);
+    assert!(inputs["otp"].sensitive);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&contracts_to_json(&contracts, false).unwrap()).unwrap();
+    assert_eq!(
+        json["functions"]["login"]["inputs"]["token"]["sensitive"],
+        json!(true)
+    );
+    assert!(json["functions"]["login"]["inputs"]["user"]
+        .get("sensitive")
+        .is_none());
+}
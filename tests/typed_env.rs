@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use super_yaml::{compile_document, MapEnvProvider, SyamlError};
+
+fn compile_with_env(input: &str, vars: &[(&str, &str)]) -> Result<serde_json::Value, SyamlError> {
+    let env = MapEnvProvider::new(
+        vars.iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect::<HashMap<_, _>>(),
+    );
+    compile_document(input, &env).map(|compiled| compiled.value)
+}
+
+const TYPED: &str = r#"
+---!syaml/v0
+---meta
+env:
+  PORT:
+    key: PORT
+    type: Port
+  ZIP:
+    key: ZIP
+    type: string
+  DEBUG:
+    key: DEBUG
+    type: boolean
+    default: false
+  HOSTS:
+    key: HOSTS
+    type:
+      type: array
+      items: string
+    parse: csv
+  WEIGHTS:
+    key: WEIGHTS
+    type:
+      type: array
+      items: integer
+    parse: csv
+    default: []
+  DB:
+    key: DB
+    type: Database
+    parse: json
+    required: false
+---schema
+Port:
+  type: integer
+  minimum: 1
+  maximum: 65535
+Database:
+  type: object
+  properties:
+    host: string
+    pool: integer
+---data
+port: =env.PORT
+zip: ${env.ZIP}
+debug: =env.DEBUG
+hosts: =env.HOSTS
+weights: =env.WEIGHTS
+db: =env.DB
+"#;
+
+#[test]
+fn typed_bindings_parse_by_type_and_mode() {
+    let value = compile_with_env(
+        TYPED,
+        &[
+            ("PORT", "8080"),
+            ("ZIP", "01234"),
+            ("HOSTS", "a.example, b.example"),
+            ("WEIGHTS", "1,2,3"),
+            ("DB", r#"{"host": "db", "pool": 5}"#),
+        ],
+    )
+    .unwrap();
+    assert_eq!(value["port"], json!(8080));
+    assert_eq!(value["zip"], json!("01234"));
+    assert_eq!(value["debug"], json!(false));
+    assert_eq!(value["hosts"], json!(["a.example", "b.example"]));
+    assert_eq!(value["weights"], json!([1, 2, 3]));
+    assert_eq!(value["db"], json!({"host": "db", "pool": 5}));
+}
+
+#[test]
+fn typed_bindings_are_validated_with_the_full_message() {
+    let base = [("ZIP", "01234"), ("HOSTS", "a"), ("PORT", "8080")];
+    let cases = [
+        (("PORT", "70000"), "env 'PORT' for symbol 'PORT' does not match its type: maximum violation at $.PORT: 70000 > 65535"),
+        (("DEBUG", "yes-please"), "env 'DEBUG' for symbol 'DEBUG' does not match its type: type mismatch at $.DEBUG: expected boolean, found string"),
+        (("DB", r#"{"host": "db.internal"}"#), "env 'DB' for symbol 'DB' does not match its type: required property missing at $.DB: 'pool'"),
+        (("DB", "{oops"), "failed to parse env 'DB': invalid JSON"),
+        (("WEIGHTS", "1,heavy"), "env 'WEIGHTS' for symbol 'WEIGHTS' does not match its type: type mismatch at $.WEIGHTS[1]: expected integer, found string"),
+    ];
+    for ((key, raw), expected) in cases {
+        let mut vars = base.to_vec();
+        vars.retain(|(k, _)| *k != key);
+        vars.push((key, raw));
+        let err = compile_with_env(TYPED, &vars).unwrap_err();
+        assert_eq!(err.code(), "env_error", "{err}");
+        assert_eq!(err.to_string(), format!("environment error: {expected}"));
+    }
+}
+
+#[test]
+fn sensitive_typed_bindings_are_validated_without_echoing_values() {
+    let sensitive =
+        ["PORT", "DEBUG", "DB", "WEIGHTS"]
+            .iter()
+            .fold(TYPED.to_string(), |doc, key| {
+                doc.replace(
+                    &format!("    key: {key}\n"),
+                    &format!("    key: {key}\n    sensitive: true\n"),
+                )
+            });
+    let base = [("ZIP", "01234"), ("HOSTS", "a"), ("PORT", "8080")];
+    let cases = [
+        (("PORT", "70000"), "env 'PORT' for symbol 'PORT' does not match its type: maximum violation at $.PORT: value > 65535"),
+        (("DEBUG", "yes-please"), "env 'DEBUG' for symbol 'DEBUG' does not match its type: type mismatch at $.DEBUG: expected boolean, found string"),
+        (("DB", r#"{"host": "s3cr3t-db"}"#), "env 'DB' for symbol 'DB' does not match its type: required property missing at $.DB: 'pool'"),
+        (("DB", "{s3cr3t"), "failed to parse env 'DB': invalid JSON"),
+        (("WEIGHTS", "1,s3cr3t"), "env 'WEIGHTS' for symbol 'WEIGHTS' does not match its type: type mismatch at $.WEIGHTS[1]: expected integer, found string"),
+    ];
+    for ((key, raw), expected) in cases {
+        let mut vars = base.to_vec();
+        vars.retain(|(k, _)| *k != key);
+        vars.push((key, raw));
+        let err = compile_with_env(&sensitive, &vars).unwrap_err();
+        assert_eq!(err.code(), "env_error", "{err}");
+        let message = err.to_string();
+        assert_eq!(message, format!("environment error: {expected}"));
+        assert!(
+            !message.contains("s3cr3t") && !message.contains(raw),
+            "{message}"
+        );
+    }
+}
+
+#[test]
+fn typed_bindings_type_env_references_in_expressions() {
+    let input = r#"
+---!syaml/v0
+---meta
+env:
+  PORT:
+    key: PORT
+    type: integer
+---schema
+{}
+---data
+label <string>: =env.PORT
+"#;
+    let err = compile_with_env(input, &[("PORT", "8080")])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("result of type integer does not match type hint 'string'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn typed_bindings_reject_unknown_types_and_parse_modes() {
+    let unknown = r#"
+---!syaml/v0
+---meta
+env:
+  PORT:
+    key: PORT
+    type: Prot
+---schema
+{}
+---data
+port: =env.PORT
+"#;
+    let err = compile_with_env(unknown, &[]).unwrap_err().to_string();
+    assert!(err.contains("meta.env.PORT: unknown type 'Prot'"), "{err}");
+
+    let bad_mode = unknown.replace("type: Prot", "parse: yaml");
+    let err = compile_with_env(&bad_mode, &[]).unwrap_err().to_string();
+    assert!(
+        err.contains("meta.env.PORT.parse must be one of scalar, string, json or csv"),
+        "{err}"
+    );
+}
@@ -286,3 +286,31 @@ x: 1
     assert!(rendered.contains("export const TimezoneMembers"));
     assert!(rendered.contains("export function getTimezone(key: TimezoneKey): Timezone"));
 }
+
+#[test]
+fn constraint_checks_evaluate_translated_conditionals() {
+    let input = r#"
+---!syaml/v0
+---schema
+Pool:
+  type: object
+  properties:
+    tier: string
+    size: integer
+  constraints:
+    - 'if tier == "prod" then size >= 3 else size >= 1'
+    - 'let cap = size * 2 in cap <= 20'
+---data
+example: 1
+"#;
+
+    let rendered = generate_typescript_types(input).unwrap();
+
+    assert!(rendered.contains(
+        "\"if tier == \\\"prod\\\" then size >= 3 else size >= 1\", \"(tier == \\\"prod\\\") ? (size >= 3) : (size >= 1)\");"
+    ), "{rendered}");
+    assert!(
+        rendered.contains("\"((cap) => cap <= 20)(size * 2)\");"),
+        "{rendered}"
+    );
+}
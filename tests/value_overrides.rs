@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use super_yaml::overrides::parse_override;
+use super_yaml::{compile_document_with_options, CompileOptions, MapEnvProvider, SyamlError};
+
+const SERVICE: &str = r#"
+---!syaml/v0
+---schema
+Service:
+  type: object
+  properties:
+    name: string
+    replicas:
+      type: integer
+      minimum: 1
+---data
+_templates:
+  svc:
+    name!: "{{NAME}}"
+    replicas: 1
+service <Service>:
+  {{_templates.svc}}:
+    NAME: api
+capacity: =service.replicas * 100
+replicas_ref: $.service.replicas
+region^: eu
+banner: "${service.name} in ${region}"
+tls:
+  cert: none
+"#;
+
+fn compile_with(overrides: &[&str]) -> Result<serde_json::Value, SyamlError> {
+    let options = CompileOptions {
+        overrides: overrides
+            .iter()
+            .map(|spec| parse_override(spec).expect("valid override"))
+            .collect(),
+        ..CompileOptions::default()
+    };
+    compile_document_with_options(SERVICE, &MapEnvProvider::new(HashMap::new()), &options)
+        .map(|compiled| compiled.value)
+}
+
+#[test]
+fn parse_override_reads_values_like_inline_data() {
+    assert_eq!(
+        parse_override("service.replicas=5").unwrap(),
+        ("$.service.replicas".to_string(), json!(5))
+    );
+    assert_eq!(
+        parse_override("$.flags=[a, true]").unwrap(),
+        ("$.flags".to_string(), json!(["a", true]))
+    );
+    assert_eq!(
+        parse_override("url=http://x?a=b").unwrap(),
+        ("$.url".to_string(), json!("http://x?a=b"))
+    );
+    assert_eq!(parse_override("name=").unwrap().1, json!(""));
+
+    for bad in ["replicas", "=5", "items[x]=1", "services.*.port=1"] {
+        let err = parse_override(bad).unwrap_err();
+        assert!(matches!(err, SyamlError::OverrideError(_)), "{bad}: {err}");
+    }
+}
+
+#[test]
+fn overrides_apply_before_derived_values_resolve() {
+    let value = compile_with(&["service.replicas=5", "tls.cert=pem", "extra=true"]).unwrap();
+    assert_eq!(value["service"]["replicas"], json!(5));
+    assert_eq!(value["capacity"], json!(500));
+    assert_eq!(value["replicas_ref"], json!(5));
+    assert_eq!(value["tls"]["cert"], json!("pem"));
+    assert_eq!(value["extra"], json!(true));
+}
+
+#[test]
+fn override_values_are_literal() {
+    let value = compile_with(&["banner=${region}", "tls.cert==1 + 1", "extra=$.region"]).unwrap();
+    assert_eq!(value["banner"], json!("${region}"));
+    assert_eq!(value["tls"]["cert"], json!("=1 + 1"));
+    assert_eq!(value["extra"], json!("$.region"));
+}
+
+#[test]
+fn overrides_are_validated_by_type_hints_and_constraints() {
+    let err = compile_with(&["service.replicas=0"])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("minimum violation at $.service.replicas"),
+        "{err}"
+    );
+
+    let err = compile_with(&["service.replicas=2.5"])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("type mismatch at $.service.replicas: expected integer"),
+        "{err}"
+    );
+}
+
+#[test]
+fn override_errors_name_the_override_instead_of_the_source() {
+    let err = compile_with(&["service.replicas=0"]).unwrap_err();
+    assert!(err.span().is_none(), "{err:?}");
+    assert!(
+        err.to_string()
+            .ends_with("(set by --set service.replicas=0)"),
+        "{err}"
+    );
+
+    // The type is checked before `capacity` reads the value.
+    let err = compile_with(&["service.replicas=abc"]).unwrap_err();
+    assert_eq!(err.data_path(), Some("$.service.replicas"));
+    assert!(err.span().is_none(), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        "schema error: type mismatch at $.service.replicas: expected integer, found string (set by --set service.replicas=abc)"
+    );
+}
+
+#[test]
+fn overrides_reject_frozen_keys_locked_fields_and_missing_parents() {
+    let err = compile_with(&["region=us"]).unwrap_err();
+    assert_eq!(err.code(), "override_error");
+    assert!(
+        err.to_string()
+            .contains("cannot override 'region': it is frozen with '^'"),
+        "{err}"
+    );
+
+    let err = compile_with(&["service.name=web"]).unwrap_err().to_string();
+    assert!(err.contains("it is a locked template field"), "{err}");
+
+    let err = compile_with(&["service={name: web, replicas: 2}"])
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("cannot override 'service': 'service.name' is a locked template field"),
+        "{err}"
+    );
+
+    let err = compile_with(&["missing.port=1"]).unwrap_err().to_string();
+    assert!(
+        err.contains("cannot override 'missing.port': the path does not exist in data"),
+        "{err}"
+    );
+}
@@ -318,61 +318,174 @@ class SyamlValidator {
       return;
     }
 
-    if (result.ok) {
+    if (result.records.length === 0) {
       this.diagnostics.delete(uri);
       return;
     }
 
-    const range = diagnosticRange(document, result.message);
-    const diagnostic = new vscode.Diagnostic(
-      range,
-      result.message,
-      vscode.DiagnosticSeverity.Error
-    );
-    diagnostic.source = "super-yaml";
-    this.diagnostics.set(uri, [diagnostic]);
+    const diagnostics = result.records.map((record) => {
+      // Records located in another file (an import) are shown on this
+      // document, with the other location attached as related information.
+      const foreign = record.file !== undefined;
+      const diagnostic = new vscode.Diagnostic(
+        record.span && !foreign
+          ? spanRange(document, record.span)
+          : diagnosticRange(document, record.message),
+        foreign ? `${record.message} (in ${record.file})` : record.message,
+        record.severity === "warning"
+          ? vscode.DiagnosticSeverity.Warning
+          : vscode.DiagnosticSeverity.Error
+      );
+      diagnostic.source = "super-yaml";
+      if (record.code) {
+        diagnostic.code = record.code;
+      }
+      if (foreign && record.file && record.span) {
+        const position = new vscode.Position(
+          Math.max(record.span.line - 1, 0),
+          Math.max(record.span.column - 1, 0)
+        );
+        diagnostic.relatedInformation = [
+          new vscode.DiagnosticRelatedInformation(
+            new vscode.Location(vscode.Uri.file(record.file), position),
+            record.message
+          )
+        ];
+      }
+      return diagnostic;
+    });
+    this.diagnostics.set(uri, diagnostics);
   }
 
   private async runValidation(
     document: vscode.TextDocument
-  ): Promise<{ ok: true } | { ok: false; message: string }> {
+  ): Promise<{ records: DiagnosticRecord[] }> {
     let parser: ParserCommand;
     try {
       parser = await resolveParserCommand(document, this.extensionPath);
     } catch {
       return {
-        ok: false,
-        message: "Cannot run SYAML parser. Set syaml.parser.path or install super-yaml."
+        records: [
+          errorRecord("Cannot run SYAML parser. Set syaml.parser.path or install super-yaml.")
+        ]
       };
     }
 
     return withInputFile(document, async (inputPath) => {
-      const args = [...parser.argPrefix, "validate", inputPath];
+      const args = [
+        ...parser.argPrefix,
+        "validate",
+        inputPath,
+        "--message-format",
+        "json"
+      ];
       try {
-        await execFileAsync(parser.command, args, {
+        const { stderr } = await execFileAsync(parser.command, args, {
           cwd: parser.cwd,
           timeout: 15000,
           maxBuffer: 1024 * 1024
         });
-        return { ok: true };
+        return { records: parseDiagnosticRecords(String(stderr ?? ""), inputPath) };
       } catch (error) {
         const execError = error as ExecError;
         if (execError.code === "ENOENT") {
           return {
-            ok: false,
-            message:
-              "Cannot run SYAML parser. Set syaml.parser.path or install super-yaml."
+            records: [
+              errorRecord(
+                "Cannot run SYAML parser. Set syaml.parser.path or install super-yaml."
+              )
+            ]
           };
         }
 
         const output = normalizeExecOutput(execError);
-        const message = extractDiagnosticMessage(output);
-        return { ok: false, message };
+        const records = parseDiagnosticRecords(output, inputPath);
+        if (records.length > 0) {
+          return { records };
+        }
+        // Older parsers (or usage errors) print plain text.
+        return { records: [errorRecord(extractDiagnosticMessage(output))] };
       }
     });
   }
 }
 
+/** One line of `super-yaml validate --message-format json` output. */
+interface DiagnosticRecord {
+  code?: string;
+  severity: "error" | "warning";
+  message: string;
+  /** Set only when the record refers to a file other than the validated one. */
+  file?: string;
+  span?: { line: number; column: number; start: number; end: number };
+  data_path?: string | null;
+}
+
+function errorRecord(message: string): DiagnosticRecord {
+  return { severity: "error", message };
+}
+
+function parseDiagnosticRecords(output: string, inputPath: string): DiagnosticRecord[] {
+  const records: DiagnosticRecord[] = [];
+  for (const line of output.split(/\r?\n/)) {
+    const trimmed = line.trim();
+    if (!trimmed.startsWith("{")) {
+      continue;
+    }
+    try {
+      const parsed = JSON.parse(trimmed) as Partial<DiagnosticRecord>;
+      if (typeof parsed.message === "string") {
+        records.push({
+          code: parsed.code,
+          severity: parsed.severity === "warning" ? "warning" : "error",
+          message: parsed.message,
+          file:
+            typeof parsed.file === "string" && !isSameFile(parsed.file, inputPath)
+              ? parsed.file
+              : undefined,
+          span: parsed.span ?? undefined,
+          data_path: parsed.data_path
+        });
+      }
+    } catch {
+      // Not a diagnostic record.
+    }
+  }
+  return records;
+}
+
+function isSameFile(a: string, b: string): boolean {
+  if (path.resolve(a) === path.resolve(b)) {
+    return true;
+  }
+  try {
+    return fsSync.realpathSync(a) === fsSync.realpathSync(b);
+  } catch {
+    return false;
+  }
+}
+
+/**
+ * Converts a span's UTF-8 byte offsets into a range of the document, whose
+ * positions count UTF-16 code units.
+ */
+function spanRange(
+  document: vscode.TextDocument,
+  span: NonNullable<DiagnosticRecord["span"]>
+): vscode.Range {
+  const bytes = Buffer.from(document.getText(), "utf8");
+  const toPosition = (offset: number): vscode.Position =>
+    document.positionAt(
+      bytes.subarray(0, Math.min(Math.max(offset, 0), bytes.length)).toString("utf8").length
+    );
+  const start = toPosition(span.start);
+  const end = toPosition(Math.max(span.end, span.start));
+  if (end.isEqual(start)) {
+    return new vscode.Range(start, document.validatePosition(start.translate(0, 1)));
+  }
+  return new vscode.Range(start, end);
+}
+
 class SyamlSemanticTokensProvider
   implements vscode.DocumentSemanticTokensProvider
 {